@@ -0,0 +1,278 @@
+# English messages. Placeables like { $path } are filled in by the caller.
+
+warning = Warning:{" "}
+error = Error:{" "}
+not-initialized = Refrs not initialized. To initialize, run: { $command }
+already-initialized = Refrs already initialized. Use option -f to reinitialize
+initializing = Initializing...
+no-project-selected = No project selected.
+select-project-hint = To select a project use: { $command }
+path-missing = Failed because path '{ $path }' is not existing.
+
+no-projects = No projects found.
+projects-header = # Projects
+name = Name
+absolute-path = Absolute Path
+url = URL
+untitled = Untitled
+ref-authors = Authors
+ref-editors = Editors
+ref-venue = Published in
+ref-year = Date
+ref-publisher = Publisher
+ref-tags = Tags
+ref-cited = Cited by
+ref-cited-count = { $count } (as of { $checked })
+ref-abstract = Abstract
+ref-note = Note
+ref-attachments = Attachments
+ref-file = File
+no-projects-to-select = No projects available to select.
+select-workspace = Select a workspace to set as current
+workspace-set = Current workspace set to: { $name } ({ $path })
+workspace-not-registered = { $path } is not a registered workspace. Clone one with: refrs clone <path> <url>
+workspace-path-needed = Not a terminal, so give the workspace: { $command }
+select-workspace-to-remove = Select a workspace to remove
+confirm-delete-workspace = Delete { $path } and everything in it?
+workspace-removed = Removed workspace { $path }
+workspace-files-deleted = Deleted { $path }
+workspace-name-empty = A workspace name can't be empty.
+workspace-name-taken = Another workspace is already named { $name }.
+workspace-renamed = Renamed workspace { $old } to { $new }
+no-current-project = No current project is set.
+current-project = Current project: { $name } ({ $path })
+updating-branch = Updating branch { $branch }
+branch-created = Created and switched to branch { $branch }
+branch-switched = Switched to branch { $branch }
+remote-only = (remote)
+would-pull = Would pull { $count } commit(s) from { $remote }:
+would-push = Would push { $count } commit(s) to { $remote }:
+would-import = Would create { $count } file(s):
+dry-run-done = Dry run: nothing was written.
+dry-run-unsupported = --dry-run is only supported by { $commands }
+json-unsupported = --json is only supported by { $commands }
+
+key-format-set = Citation key format set to: { $format }
+key-format = Citation key format: { $format }
+keys-unchanged = Every entry already has its citation key.
+keys-assigned = Assigned citation keys to { $count } entries.
+language-set = Language set to: { $language }
+language = Language: { $language }
+language-from-locale = from the locale
+config-set = { $name } set to { $value }
+config-unset = { $name } unset
+config-overridden = The environment variable { $var } overrides this setting.
+config-from-env = (from { $var })
+
+nothing-to-remind = Nothing to remind you of.
+reading-for-days = Reading for { $days } days:
+unread-high-priority = Unread, high priority:
+
+selftest-lossy = { $lossy } of { $checked } entries lost data in conversion
+selftest-ok = All { $checked } entries round-trip without loss.
+
+lint-ok = No problems found.
+lint-issues = { $count } problem(s) found
+lint-fixed = Fixed { $count } file(s).
+lint-fixable = fixable
+lint-fix-hint = Run refrs lint --fix to repair { $count } of them.
+lint-unreadable = not valid RIS: { $error }
+lint-empty-file = file has no entries
+lint-missing = missing { $tag }
+lint-malformed-doi = malformed DOI: { $value }
+lint-malformed-issn = malformed ISSN: { $value }
+lint-duplicate-key = citation key { $key } is also used by { $others }
+
+lookup-searching = Searching Crossref for { $query }
+lookup-none = Crossref found no matches.
+lookup-prompt = Import which?
+lookup-pick-hint = Run refrs lookup in a terminal to pick one to import.
+
+citations-count = cited { $count } times
+citations-unknown = Neither Semantic Scholar nor OpenAlex knows
+citations-none = No citation counts to update.
+citations-updated = Updated the citation counts of { $count } reference(s).
+
+import-line = Line { $number }: { $line }
+import-unexpected-end = Unexpected end of BibTeX.
+import-translation-empty = The translation server found nothing
+import-meta-tags = Reading the page's meta tags instead
+import-unreadable = Could not read
+import-nothing = Nothing to import.
+import-fetching-arxiv = Fetching arXiv record { $id }
+import-fetching-pubmed = Fetching PubMed record { $id }
+import-fetching = Fetching { $url }
+import-fetching-doi = Fetching DOI { $id }
+import-reading = Reading { $path }
+import-choose-source = Choose a source: { $sources }
+import-from-file = { $imported } of { $count } from { $path }
+import-done = Imported { $count } reference(s).
+import-confidence-high = high confidence
+import-confidence-low = low confidence, use --format to override
+import-detected = Detected { $format } ({ $confidence })
+import-none-found = No references found.
+import-unparsable = Could not parse { $format }:
+import-unrecognized = Did not recognize the text format. Recognized formats: { $formats }. Use { $option } for others.
+import-merge-skipped = Merge { $count } skipped duplicate(s) into the existing entries?
+import-duplicate-of = { $key } (duplicate of { $id })
+import-select = Select the references to import (space to toggle, enter to confirm)
+import-edit-keys = Edit citation keys?
+import-citation-key = Citation key
+merged = Merged { $from } into { $into }
+column-type = Type
+column-author = Author
+column-year = Year
+column-title = Title
+column-duplicate-of = Duplicate of
+
+export-no-folder = No { $folder } folder found.
+export-duplicate-key = Duplicate key { $key } of { $id } exported as { $exported }
+export-group = { $count } entries in { $path } ({ $group })
+export-done-split = { $count } entries exported to { $files } files
+export-done = { $count } entries exported to { $path }
+export-no-output = Give an output file, or --split-by together with --out-dir.
+export-sort-set = Export order set to: { $sort }
+export-sort = Export order: { $sort }
+
+dedupe-none = No duplicates found.
+dedupe-found = Found { $count } possible duplicate pair(s).
+dedupe-none-shared = No references shared between projects.
+dedupe-found-shared = Found { $count } reference(s) present in several projects.
+dedupe-action = What should be done?
+dedupe-consolidate = Consolidate: give every copy the same fields
+dedupe-link = Link: note in each copy where the others are
+dedupe-skip = Skip
+dedupe-consolidated = Consolidated { $count } copies
+dedupe-linked = Linked { $count } copies
+dedupe-merge = Merge { $from } and { $into }?
+dedupe-keep-which = Keep which { $tag }?
+dedupe-changes = Changes to { $label }:
+column-field = Field
+
+daemon-already-running = Daemon already running (pid { $pid }).
+daemon-started = Daemon started (pid { $pid }), syncing every { $interval } seconds.
+daemon-stopping = Asked the daemon (pid { $pid }) to stop.
+daemon-not-running = The daemon is not running.
+daemon-not-running-sync-hint = The daemon is not running. Use `refrs update` to sync the current project.
+daemon-never-started = The daemon has never been started.
+daemon-running = Running (pid { $pid }), up for { $uptime }, syncing every { $interval } seconds.
+daemon-stopped = Not running.
+daemon-watched-header = # Watched folders
+daemon-imported-header = # Recently imported
+daemon-ago = { $duration } ago
+daemon-never = never
+daemon-last-sync = last sync { $time }
+daemon-watching = Watching { $folder } for .bib and .ris files.
+daemon-not-watched = { $folder } is not being watched.
+daemon-unwatched = Stopped watching { $folder }.
+
+edit-unchanged = No changes.
+edit-restored = The original file has been restored.
+edit-emptied = All entries were removed from the file.
+
+search-none = No references match '{ $query }'.
+search-found = { $count } reference(s) found.
+search-references = Search references
+list-none = No matching references.
+list-count = { $count } reference(s).
+column-id = Id
+column-cited = Cited
+no-reference-with-id = No reference with id '{ $id }'.
+no-references = No references in the current project.
+no-field-changes = No field changes.
+note = Note:{" "}
+
+remove-and = and
+remove-confirm = Remove { $id }?
+remove-done = Removed { $id }
+tag-none-removed = { $id } has none of these tags.
+tag-none-added = { $id } already has these tags.
+tag-none-yet = No tags yet. Add one with: { $command }
+render-done = Bibliography with { $count } entries written to { $path }
+attach-already = { $file } is already attached to { $id }.
+archive-nothing = Nothing to archive.
+archive-no-url = { $id } has no URL to archive.
+archive-archiving = Archiving
+cite-no-match = No reference matches '{ $query }'.
+cite-copied = Copied
+cite-several = Several references match
+graph-fetching = Fetching citations from Semantic Scholar...
+graph-done = Graph with { $nodes } nodes and { $edges } edges written to { $path }
+coauthors-done = Co-author network with { $nodes } authors and { $edges } edges written to { $path }
+notes-skipped = { $path } has no refrs marker, leaving it alone.
+notes-done = Notes in { $vault }: { $created } created, { $updated } updated, { $unchanged } unchanged.
+pandoc-key-not-found = citation key not found: { $key }
+tui-needs-terminal = refrs tui needs a terminal. Use refrs list instead.
+tui-header = refrs  { $shown } of { $total } references
+tui-search-hint = Press / to search
+tui-help = j/k move  / search  o open  c \cite  C [@]  t tag  d delete  q quit
+tui-tag-prompt = Add or remove tag on { $id }:
+tui-delete-confirm = Delete { $id }? [y/N]
+tui-tagged = Tagged { $id }: { $tag }
+tui-untagged = Untagged { $id }: { $tag }
+
+auth-project-cleared = Project credentials cleared; using the global ones.
+auth-cleared = Credentials cleared.
+auth-project-credentials = Project credentials
+auth-credentials = Credentials
+auth-project-own = The current project has its own: { $credentials }
+auth-set = Credentials set to: { $credentials }
+
+open-no-attachment = { $id } has no attachment.
+open-no-link = { $id } has neither a DOI nor a URL.
+open-no-pdf = { $id } has no PDF. Attach one with: { $command }
+open-opening = Opening
+open-with-none = No handlers configured, everything opens with the system default.
+open-with-cleared = { $kind } opens with the system default again.
+open-with = { $kind } opens with: { $command }
+open-with-default = { $kind } opens with the system default.
+openurl-none-hint = No library resolver configured. Set one with: { $command }
+openurl-cleared = Library resolver cleared.
+openurl-set = Library resolver set to: { $url }
+openurl = Library resolver: { $url }
+openurl-none = No library resolver configured.
+
+styles-installed = Installed style { $name }
+styles-locale-installed = Installed locale { $code }
+styles-header = # Styles
+styles-none = No styles installed. Install one with: { $command }
+styles-locales-header = # Locales
+styles-no-locales = No locales installed.
+styles-removed = Removed style { $name }
+styles-unknown = No installed style named '{ $name }'.
+
+update-conflict = Conflict in { $path }
+update-keep-remote = Keep the remote version
+update-delete-remote = Delete it, as the remote side did
+update-keep-local = Keep the local version
+update-delete-local = Delete it, as the local side did
+update-abort = Stop, leaving the project as it was
+update-resolve = Resolve how?
+
+venues-nothing = No venues to complete.
+venues-not-found = No Crossref journal found for { $query }
+venues-alias-added = Added alias { $alias } = { $canonical }
+venues-no-aliases = No aliases yet. Add some with --add or in { $file }.
+venues-normalized = All venues are already normalized.
+venues-completed = Completed { $count } entries.
+venues-normalized-count = Normalized { $count } entries.
+
+serve-api-running = API running on { $url }
+serve-send-header = Send the header: { $header }
+serve-running = Server running on { $url }
+serve-no-browser = Failed to open browser. Please visit { $url } manually.
+
+git-cloning = Cloning:
+git-absolute-path = Absolute path:
+git-receiving = Receiving objects: { $received }/{ $total }
+git-cloned = Repository cloned successfully!
+git-pulling = Pulling with rebase in:
+git-nothing-to-pull = { $branch } is not on { $remote } yet; nothing to pull.
+git-rebased = Rebase completed successfully!
+git-pushing = Pushing changes in:
+git-pushed = Push completed successfully!
+git-staging = Staging all changes in:
+git-staged = All changes staged successfully!
+git-staged-only = Staged changes without committing (auto_commit = false):
+git-committing = Committing changes with message:
+git-committed = Commit completed successfully!
@@ -0,0 +1,278 @@
+# Norske meldinger (bokmål).
+
+warning = Advarsel:{" "}
+error = Feil:{" "}
+not-initialized = Refrs er ikke initialisert. Kjør { $command } for å initialisere.
+already-initialized = Refrs er allerede initialisert. Bruk -f for å initialisere på nytt
+initializing = Initialiserer...
+no-project-selected = Ingen prosjekt er valgt.
+select-project-hint = Velg et prosjekt med: { $command }
+path-missing = Mislyktes fordi stien '{ $path }' ikke finnes.
+
+no-projects = Fant ingen prosjekter.
+projects-header = # Prosjekter
+name = Navn
+absolute-path = Absolutt sti
+url = URL
+untitled = Uten tittel
+ref-authors = Forfattere
+ref-editors = Redaktører
+ref-venue = Publisert i
+ref-year = Dato
+ref-publisher = Forlag
+ref-tags = Stikkord
+ref-cited = Sitert av
+ref-cited-count = { $count } (per { $checked })
+ref-abstract = Sammendrag
+ref-note = Merknad
+ref-attachments = Vedlegg
+ref-file = Fil
+no-projects-to-select = Ingen prosjekter å velge mellom.
+select-workspace = Velg arbeidsområdet som skal være gjeldende
+workspace-set = Gjeldende arbeidsområde er satt til: { $name } ({ $path })
+workspace-not-registered = { $path } er ikke et registrert arbeidsområde. Klon et med: refrs clone <sti> <url>
+workspace-path-needed = Ikke en terminal, så oppgi arbeidsområdet: { $command }
+select-workspace-to-remove = Velg arbeidsområdet som skal fjernes
+confirm-delete-workspace = Slette { $path } og alt i den?
+workspace-removed = Fjernet arbeidsområdet { $path }
+workspace-files-deleted = Slettet { $path }
+workspace-name-empty = Et arbeidsområde kan ikke ha et tomt navn.
+workspace-name-taken = Et annet arbeidsområde heter allerede { $name }.
+workspace-renamed = Endret navn på arbeidsområdet { $old } til { $new }
+no-current-project = Ingen gjeldende prosjekt er satt.
+current-project = Gjeldende prosjekt: { $name } ({ $path })
+updating-branch = Oppdaterer grenen { $branch }
+branch-created = Opprettet og byttet til grenen { $branch }
+branch-switched = Byttet til grenen { $branch }
+remote-only = (fjernlager)
+would-pull = Ville hentet { $count } innsjekking(er) fra { $remote }:
+would-push = Ville sendt { $count } innsjekking(er) til { $remote }:
+would-import = Ville opprettet { $count } fil(er):
+dry-run-done = Prøvekjøring: ingenting ble skrevet.
+dry-run-unsupported = --dry-run støttes bare av { $commands }
+json-unsupported = --json støttes bare av { $commands }
+
+key-format-set = Format for siteringsnøkler er satt til: { $format }
+key-format = Format for siteringsnøkler: { $format }
+keys-unchanged = Alle oppføringer har allerede siteringsnøkkel.
+keys-assigned = Tildelte siteringsnøkler til { $count } oppføringer.
+language-set = Språk er satt til: { $language }
+language = Språk: { $language }
+language-from-locale = fra systemets språkinnstilling
+config-set = { $name } er satt til { $value }
+config-unset = { $name } er fjernet
+config-overridden = Miljøvariabelen { $var } overstyrer denne innstillingen.
+config-from-env = (fra { $var })
+
+nothing-to-remind = Ingenting å minne deg på.
+reading-for-days = Lest på i { $days } dager:
+unread-high-priority = Ulest, høy prioritet:
+
+selftest-lossy = { $lossy } av { $checked } oppføringer mistet data i konverteringen
+selftest-ok = Alle { $checked } oppføringer konverteres fram og tilbake uten tap.
+
+lint-ok = Fant ingen problemer.
+lint-issues = Fant { $count } problem(er)
+lint-fixed = Reparerte { $count } fil(er).
+lint-fixable = kan repareres
+lint-fix-hint = Kjør refrs lint --fix for å reparere { $count } av dem.
+lint-unreadable = ikke gyldig RIS: { $error }
+lint-empty-file = filen har ingen oppføringer
+lint-missing = mangler { $tag }
+lint-malformed-doi = ugyldig DOI: { $value }
+lint-malformed-issn = ugyldig ISSN: { $value }
+lint-duplicate-key = siteringsnøkkelen { $key } brukes også av { $others }
+
+lookup-searching = Søker i Crossref etter { $query }
+lookup-none = Crossref fant ingen treff.
+lookup-prompt = Hvilken skal importeres?
+lookup-pick-hint = Kjør refrs lookup i en terminal for å velge en å importere.
+
+citations-count = sitert { $count } ganger
+citations-unknown = Verken Semantic Scholar eller OpenAlex kjenner
+citations-none = Ingen siteringstall å oppdatere.
+citations-updated = Oppdaterte siteringstallene til { $count } referanse(r).
+
+import-line = Linje { $number }: { $line }
+import-unexpected-end = BibTeX-teksten sluttet uventet.
+import-translation-empty = Oversettelsesserveren fant ingenting
+import-meta-tags = Leser sidens metadata i stedet
+import-unreadable = Kunne ikke lese
+import-nothing = Ingenting å importere.
+import-fetching-arxiv = Henter arXiv-oppføringen { $id }
+import-fetching-pubmed = Henter PubMed-oppføringen { $id }
+import-fetching = Henter { $url }
+import-fetching-doi = Henter DOI { $id }
+import-reading = Leser { $path }
+import-choose-source = Velg en kilde: { $sources }
+import-from-file = { $imported } av { $count } fra { $path }
+import-done = Importerte { $count } referanse(r).
+import-confidence-high = høy sikkerhet
+import-confidence-low = lav sikkerhet, bruk --format for å overstyre
+import-detected = Gjenkjente { $format } ({ $confidence })
+import-none-found = Fant ingen referanser.
+import-unparsable = Kunne ikke lese { $format }:
+import-unrecognized = Kjente ikke igjen tekstformatet. Gjenkjente formater: { $formats }. Bruk { $option } for andre.
+import-merge-skipped = Slå sammen { $count } hoppet over duplikat(er) med de eksisterende oppføringene?
+import-duplicate-of = { $key } (duplikat av { $id })
+import-select = Velg referansene som skal importeres (mellomrom for å velge, enter for å bekrefte)
+import-edit-keys = Endre siteringsnøkler?
+import-citation-key = Siteringsnøkkel
+merged = Slo sammen { $from } med { $into }
+column-type = Type
+column-author = Forfatter
+column-year = År
+column-title = Tittel
+column-duplicate-of = Duplikat av
+
+export-no-folder = Fant ingen { $folder }-mappe.
+export-duplicate-key = Nøkkelen { $key } til { $id } er allerede brukt, eksportert som { $exported }
+export-group = { $count } oppføringer i { $path } ({ $group })
+export-done-split = { $count } oppføringer eksportert til { $files } filer
+export-done = { $count } oppføringer eksportert til { $path }
+export-no-output = Oppgi en utdatafil, eller --split-by sammen med --out-dir.
+export-sort-set = Eksportrekkefølge satt til: { $sort }
+export-sort = Eksportrekkefølge: { $sort }
+
+dedupe-none = Fant ingen duplikater.
+dedupe-found = Fant { $count } mulige duplikatpar.
+dedupe-none-shared = Ingen referanser er felles for flere prosjekter.
+dedupe-found-shared = Fant { $count } referanse(r) som finnes i flere prosjekter.
+dedupe-action = Hva skal gjøres?
+dedupe-consolidate = Samordne: gi alle kopiene de samme feltene
+dedupe-link = Lenke: noter i hver kopi hvor de andre er
+dedupe-skip = Hopp over
+dedupe-consolidated = Samordnet { $count } kopier
+dedupe-linked = Lenket { $count } kopier
+dedupe-merge = Slå sammen { $from } og { $into }?
+dedupe-keep-which = Hvilken { $tag } skal beholdes?
+dedupe-changes = Endringer i { $label }:
+column-field = Felt
+
+daemon-already-running = Bakgrunnstjenesten kjører allerede (pid { $pid }).
+daemon-started = Bakgrunnstjenesten startet (pid { $pid }), synkroniserer hvert { $interval }. sekund.
+daemon-stopping = Ba bakgrunnstjenesten (pid { $pid }) om å stoppe.
+daemon-not-running = Bakgrunnstjenesten kjører ikke.
+daemon-not-running-sync-hint = Bakgrunnstjenesten kjører ikke. Bruk `refrs update` for å synkronisere det gjeldende prosjektet.
+daemon-never-started = Bakgrunnstjenesten har aldri vært startet.
+daemon-running = Kjører (pid { $pid }), oppe i { $uptime }, synkroniserer hvert { $interval }. sekund.
+daemon-stopped = Kjører ikke.
+daemon-watched-header = # Overvåkede mapper
+daemon-imported-header = # Nylig importert
+daemon-ago = for { $duration } siden
+daemon-never = aldri
+daemon-last-sync = sist synkronisert { $time }
+daemon-watching = Overvåker { $folder } for .bib- og .ris-filer.
+daemon-not-watched = { $folder } overvåkes ikke.
+daemon-unwatched = Sluttet å overvåke { $folder }.
+
+edit-unchanged = Ingen endringer.
+edit-restored = Den opprinnelige filen er gjenopprettet.
+edit-emptied = Alle oppføringene ble fjernet fra filen.
+
+search-none = Ingen referanser samsvarer med '{ $query }'.
+search-found = Fant { $count } referanse(r).
+search-references = Søk i referanser
+list-none = Ingen samsvarende referanser.
+list-count = { $count } referanse(r).
+column-id = Id
+column-cited = Sitert
+no-reference-with-id = Ingen referanse med id '{ $id }'.
+no-references = Det gjeldende prosjektet har ingen referanser.
+no-field-changes = Ingen feltendringer.
+note = Merk:{" "}
+
+remove-and = og
+remove-confirm = Fjerne { $id }?
+remove-done = Fjernet { $id }
+tag-none-removed = { $id } har ingen av disse emneordene.
+tag-none-added = { $id } har allerede disse emneordene.
+tag-none-yet = Ingen emneord ennå. Legg til et med: { $command }
+render-done = Litteraturliste med { $count } oppføringer skrevet til { $path }
+attach-already = { $file } er allerede vedlagt { $id }.
+archive-nothing = Ingenting å arkivere.
+archive-no-url = { $id } har ingen URL å arkivere.
+archive-archiving = Arkiverer
+cite-no-match = Ingen referanse samsvarer med '{ $query }'.
+cite-copied = Kopierte
+cite-several = Flere referanser samsvarer
+graph-fetching = Henter siteringer fra Semantic Scholar...
+graph-done = Graf med { $nodes } noder og { $edges } kanter skrevet til { $path }
+coauthors-done = Medforfatternettverk med { $nodes } forfattere og { $edges } kanter skrevet til { $path }
+notes-skipped = { $path } har ingen refrs-markør og blir latt i fred.
+notes-done = Notater i { $vault }: { $created } opprettet, { $updated } oppdatert, { $unchanged } uendret.
+pandoc-key-not-found = fant ikke siteringsnøkkelen: { $key }
+tui-needs-terminal = refrs tui trenger en terminal. Bruk refrs list i stedet.
+tui-header = refrs  { $shown } av { $total } referanser
+tui-search-hint = Trykk / for å søke
+tui-help = j/k flytt  / søk  o åpne  c \cite  C [@]  t emneord  d slett  q avslutt
+tui-tag-prompt = Legg til eller fjern emneord på { $id }:
+tui-delete-confirm = Slette { $id }? [y/N]
+tui-tagged = La til emneord på { $id }: { $tag }
+tui-untagged = Fjernet emneord fra { $id }: { $tag }
+
+auth-project-cleared = Prosjektets påloggingsdetaljer er fjernet; bruker de globale.
+auth-cleared = Påloggingsdetaljene er fjernet.
+auth-project-credentials = Prosjektets påloggingsdetaljer
+auth-credentials = Påloggingsdetaljer
+auth-project-own = Det gjeldende prosjektet har egne: { $credentials }
+auth-set = Påloggingsdetaljer satt til: { $credentials }
+
+open-no-attachment = { $id } har ikke noe vedlegg.
+open-no-link = { $id } har verken DOI eller URL.
+open-no-pdf = { $id } har ingen PDF. Legg ved en med: { $command }
+open-opening = Åpner
+open-with-none = Ingen programmer er satt opp, alt åpnes med systemets standard.
+open-with-cleared = { $kind } åpnes med systemets standard igjen.
+open-with = { $kind } åpnes med: { $command }
+open-with-default = { $kind } åpnes med systemets standard.
+openurl-none-hint = Ingen bibliotekstjeneste er satt opp. Sett en med: { $command }
+openurl-cleared = Bibliotekstjenesten er fjernet.
+openurl-set = Bibliotekstjeneste satt til: { $url }
+openurl = Bibliotekstjeneste: { $url }
+openurl-none = Ingen bibliotekstjeneste er satt opp.
+
+styles-installed = Installerte stilen { $name }
+styles-locale-installed = Installerte språkfilen { $code }
+styles-header = # Stiler
+styles-none = Ingen stiler er installert. Installer en med: { $command }
+styles-locales-header = # Språkfiler
+styles-no-locales = Ingen språkfiler er installert.
+styles-removed = Fjernet stilen { $name }
+styles-unknown = Ingen installert stil heter '{ $name }'.
+
+update-conflict = Konflikt i { $path }
+update-keep-remote = Behold den eksterne versjonen
+update-delete-remote = Slett den, slik den eksterne siden gjorde
+update-keep-local = Behold den lokale versjonen
+update-delete-local = Slett den, slik den lokale siden gjorde
+update-abort = Stopp, og la prosjektet være som det var
+update-resolve = Hvordan skal konflikten løses?
+
+venues-nothing = Ingen publiseringskanaler å fullføre.
+venues-not-found = Fant ingen tidsskrift i Crossref for { $query }
+venues-alias-added = La til aliaset { $alias } = { $canonical }
+venues-no-aliases = Ingen aliaser ennå. Legg til med --add eller i { $file }.
+venues-normalized = Alle publiseringskanaler er allerede normalisert.
+venues-completed = Fullførte { $count } oppføringer.
+venues-normalized-count = Normaliserte { $count } oppføringer.
+
+serve-api-running = API-et kjører på { $url }
+serve-send-header = Send headeren: { $header }
+serve-running = Serveren kjører på { $url }
+serve-no-browser = Kunne ikke åpne nettleseren. Gå til { $url } selv.
+
+git-cloning = Kloner:
+git-absolute-path = Absolutt sti:
+git-receiving = Mottar objekter: { $received }/{ $total }
+git-cloned = Repositoriet ble klonet!
+git-pulling = Henter med rebase i:
+git-nothing-to-pull = { $branch } finnes ikke på { $remote } ennå; ingenting å hente.
+git-rebased = Rebase fullført!
+git-pushing = Sender endringer i:
+git-pushed = Endringene er sendt!
+git-staging = Legger til alle endringer i:
+git-staged = Alle endringer er lagt til!
+git-staged-only = La til endringene uten å committe (auto_commit = false):
+git-committing = Committer endringene med meldingen:
+git-committed = Commit fullført!
@@ -0,0 +1,155 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::serve::AppData;
+use crate::repo;
+use crate::services::encoding;
+use crate::services::library::{self, LibraryEntry};
+use crate::services::merge;
+use crate::services::serialization::{self, ParseResult};
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ApiError>)>;
+
+#[derive(Serialize)]
+pub struct ApiError {
+    error: String,
+}
+
+/// A reference as JSON, also printed by `--json`.
+#[derive(Serialize)]
+pub struct ApiReference {
+    id: String,
+    #[serde(rename = "type")]
+    ty: String,
+    fields: HashMap<String, Vec<String>>,
+}
+
+impl From<LibraryEntry> for ApiReference {
+    fn from(entry: LibraryEntry) -> Self {
+        Self {
+            id: entry.id,
+            ty: entry.entry.ty.to_str().to_string(),
+            fields: entry.entry.fields,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ImportResult {
+    imported: Vec<String>,
+    /// Ids of existing references the skipped entries duplicate.
+    duplicates: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct SyncResult {
+    synced: bool,
+}
+
+/// JSON API routes, mounted under `/api`.
+pub fn router() -> Router<AppData> {
+    Router::new()
+        .route("/references", get(list_handler).post(import_handler))
+        .route("/references/:id", get(reference_handler))
+        .route("/sync", post(sync_handler))
+}
+
+fn error(status: StatusCode, message: impl ToString) -> (StatusCode, Json<ApiError>) {
+    (
+        status,
+        Json(ApiError {
+            error: message.to_string(),
+        }),
+    )
+}
+
+fn internal(err: anyhow::Error) -> (StatusCode, Json<ApiError>) {
+    error(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err))
+}
+
+/// GET /api/references
+async fn list_handler(State(app_data): State<AppData>) -> ApiResult<Vec<ApiReference>> {
+    let entries = library::load_entries(&app_data.project_path).map_err(internal)?;
+    Ok(Json(entries.into_iter().map(ApiReference::from).collect()))
+}
+
+/// GET /api/references/:id
+async fn reference_handler(
+    State(app_data): State<AppData>,
+    Path(id): Path<String>,
+) -> ApiResult<ApiReference> {
+    let entries = library::load_entries(&app_data.project_path).map_err(internal)?;
+    match entries.into_iter().find(|e| e.id == id) {
+        Some(entry) => Ok(Json(entry.into())),
+        None => Err(error(StatusCode::NOT_FOUND, format!("No reference {}", id))),
+    }
+}
+
+/// POST /api/references
+/// Imports the BibTeX or RIS request body. Entries that duplicate existing
+/// references are skipped and reported.
+async fn import_handler(State(app_data): State<AppData>, body: Bytes) -> ApiResult<ImportResult> {
+    let (body, encoding) = encoding::decode(&body);
+    let entries = match serialization::parse(&body) {
+        ParseResult::Parsed { entries, .. } => entries,
+        ParseResult::BibtexError { error: err } => {
+            return Err(error(StatusCode::BAD_REQUEST, format!("Invalid BibTeX (read as {}): {}", encoding, err)))
+        }
+        ParseResult::Error { format, error: err } => {
+            return Err(error(StatusCode::BAD_REQUEST, format!("Invalid {} (read as {}): {:#}", format, encoding, err)))
+        }
+        ParseResult::UnrecognizedFormat => {
+            return Err(error(
+                StatusCode::BAD_REQUEST,
+                "Unrecognized format, expected BibTeX, RIS, CSL-JSON, Better BibTeX JSON or RefWorks",
+            ))
+        }
+    };
+
+    // Importing commits, which blocks on git.
+    let (imported, duplicates) = blocking(move || {
+        let pending =
+            serialization::prepare_import(entries, &app_data.project_path, app_data.key_format, &app_data.key_pattern)?;
+        let duplicates: Vec<String> = pending.iter().filter_map(|p| p.duplicate_of.clone()).collect();
+        let imported = serialization::commit_import(&pending, &app_data.project_path, app_data.key_format)?;
+        Ok((imported, duplicates))
+    })
+    .await?;
+
+    Ok(Json(ImportResult {
+        imported: imported
+            .iter()
+            .map(|name| name.trim_end_matches(".ris").to_string())
+            .collect(),
+        duplicates,
+    }))
+}
+
+/// POST /api/sync
+/// Pulls and pushes the project repository.
+async fn sync_handler(State(app_data): State<AppData>) -> ApiResult<SyncResult> {
+    blocking(move || {
+        repo::pull_rebase(&app_data.project_path, &app_data.git_auth, merge::resolve_unattended)
+            .and_then(|_| repo::push(&app_data.project_path, &app_data.git_auth))
+    })
+    .await?;
+    Ok(Json(SyncResult { synced: true }))
+}
+
+/// Runs `work` on the blocking thread pool, so git and the file system don't
+/// stall the server's other requests.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> Result<T, (StatusCode, Json<ApiError>)> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|err| internal(err.into()))?
+        .map_err(internal)
+}
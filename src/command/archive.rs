@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::model::reference::ATTACHMENTS_FOLDER;
+use crate::repo;
+use crate::services::archive::{self, ARCHIVE_TAG};
+use crate::services::library::{self, LibraryEntry};
+use crate::state::AppState;
+use crate::util::{print_entry_diff, print_no_project_selected, print_not_initialized, resolve_reference};
+
+pub fn handle_archive(state: &AppState, id: Option<&str>, all: bool, snapshot: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+
+    let targets: Vec<&LibraryEntry> = if all {
+        // Only URL-only references are at risk of link rot.
+        entries
+            .iter()
+            .filter(|e| e.entry.get_field("UR").is_some() && e.entry.get_field("DO").is_none())
+            .filter(|e| {
+                let tag = if snapshot { "L1" } else { ARCHIVE_TAG };
+                e.entry.get_field(tag).is_none()
+            })
+            .collect()
+    } else {
+        match resolve_reference(&entries, id)? {
+            Some(reference) => vec![reference],
+            None => return Ok(()),
+        }
+    };
+
+    if targets.is_empty() {
+        println!("{}", t!("archive-nothing").blue().bold());
+        return Ok(());
+    }
+
+    let mut archived = Vec::new();
+    for reference in targets {
+        match archive_reference(&state.current_project, reference, snapshot) {
+            Ok(true) => archived.push(reference.id.clone()),
+            Ok(false) => {}
+            Err(err) => println!("{}{}: {:#}", t!("error").red().bold(), reference.id, err),
+        }
+    }
+
+    if !archived.is_empty() {
+        repo::add_all(&state.current_project)?;
+        repo::commit(
+            &state.current_project,
+            &format!("Archived {}", archived.join(", ")),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Archives a single reference and updates its file. Returns whether anything changed.
+fn archive_reference(project_path: &str, reference: &LibraryEntry, snapshot: bool) -> Result<bool> {
+    let Some(url) = reference.entry.get_field("UR") else {
+        println!("{}", t!("archive-no-url", id = reference.id.bold()));
+        return Ok(false);
+    };
+
+    println!("{} {}", t!("archive-archiving").green().bold(), url.underline());
+
+    let mut updated = reference.entry.clone();
+    if snapshot {
+        let html = archive::fetch_snapshot(url)?;
+        let relative_path = format!("{}/{}-snapshot.html", ATTACHMENTS_FOLDER, reference.id);
+        let folder = Path::new(project_path).join(ATTACHMENTS_FOLDER);
+        fs::create_dir_all(&folder)?;
+        fs::write(Path::new(project_path).join(&relative_path), html)?;
+
+        let attachments = updated.fields.entry("L1".to_string()).or_default();
+        if !attachments.contains(&relative_path) {
+            attachments.push(relative_path);
+        }
+    } else {
+        let archive_url = archive::wayback_save(url)?;
+        updated
+            .fields
+            .insert(ARCHIVE_TAG.to_string(), vec![archive_url]);
+    }
+
+    print_entry_diff(&reference.entry, &updated);
+    library::replace_entry(reference, Some(&updated))?;
+    Ok(true)
+}
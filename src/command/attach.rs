@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use crate::model::reference::{Reference, ATTACHMENTS_FOLDER, ATTACHMENT_TAG};
+use crate::repo;
+use crate::model::ris::RisEntry;
+use crate::services::library::{self, LibraryEntry};
+use crate::state::AppState;
+use crate::util::{print_entry_diff, print_no_project_selected, print_not_initialized, resolve_reference};
+
+/// Copies a file into the project's attachments folder, links it to the
+/// reference and commits both.
+pub fn handle_attach(state: &AppState, id: &str, file: &str) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let source = Path::new(file);
+    if !source.is_file() {
+        bail!("No such file: {}", file);
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let Some(entry) = resolve_reference(&entries, Some(id))? else {
+        return Ok(());
+    };
+    let extension = source
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_else(|| "bin".to_string());
+    let Some((relative_path, updated)) =
+        store_attachment(&state.current_project, entry, &fs::read(source)?, &extension)?
+    else {
+        repo::status(format!("{}{}", t!("note").blue().bold(), t!("attach-already", file = file, id = entry.id.bold())));
+        return Ok(());
+    };
+    print_entry_diff(&entry.entry, &updated);
+
+    repo::add_all(&state.current_project)?;
+    repo::commit(
+        &state.current_project,
+        &format!("Attached {} to {}", relative_path, entry.id),
+    )?;
+
+    Ok(())
+}
+
+/// Writes `content` to the attachments folder and links it in the entry's
+/// `.ris` file, leaving its other entries as they are, without committing. Returns the attachment's path and the
+/// updated entry, or `None` when the same content is already attached.
+pub fn store_attachment(
+    project_path: &str,
+    entry: &LibraryEntry,
+    content: &[u8],
+    extension: &str,
+) -> Result<Option<(String, RisEntry)>> {
+    let reference = Reference::new(&entry.id, &entry.path.to_string_lossy(), &entry.entry);
+    let project = Path::new(project_path);
+    let already_attached = reference
+        .attachments
+        .iter()
+        .any(|path| fs::read(project.join(path)).is_ok_and(|existing| existing == content));
+    if already_attached {
+        return Ok(None);
+    }
+
+    let relative_path = reference.attachment_path(extension, |path| project.join(path).exists());
+    fs::create_dir_all(project.join(ATTACHMENTS_FOLDER))?;
+    fs::write(project.join(&relative_path), content)?;
+
+    let mut updated = entry.entry.clone();
+    updated
+        .fields
+        .entry(ATTACHMENT_TAG.to_string())
+        .or_default()
+        .push(relative_path.clone());
+    library::replace_entry(entry, Some(&updated))?;
+
+    Ok(Some((relative_path, updated)))
+}
@@ -0,0 +1,87 @@
+use std::fs;
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use crate::repo::GitAuth;
+use crate::state::{save_state, AppState};
+use crate::util::{print_no_project_selected, print_not_initialized};
+
+fn describe(auth: &GitAuth) -> String {
+    if auth.is_empty() {
+        return "SSH agent and git credential helpers".to_string();
+    }
+    let mut parts = Vec::new();
+    if let Some(key) = &auth.ssh_key {
+        parts.push(format!("SSH key {}", key));
+    }
+    if let Some(var) = &auth.token_env {
+        parts.push(format!("token from ${}", var));
+    }
+    if let Some(username) = &auth.username {
+        parts.push(format!("user {}", username));
+    }
+    parts.join(", ")
+}
+
+/// Shows or changes the git credentials, globally or for the current
+/// project with `project`. Given fields replace the configured ones.
+pub fn handle_auth(state: &mut AppState, mut changes: GitAuth, project: bool, clear: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if project && state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    if let Some(key) = &changes.ssh_key {
+        // Stored absolute, since refrs may run from any directory.
+        let Ok(path) = fs::canonicalize(key) else {
+            bail!("No such SSH key: {}", key);
+        };
+        changes.ssh_key = Some(path.to_string_lossy().to_string());
+    }
+
+    let current_project = state.current_project.clone();
+    let auth = if project {
+        let Some(entry) = state.projects.iter_mut().find(|p| p.absolute_path == current_project) else {
+            bail!("{} is not a cloned project", current_project);
+        };
+        if clear {
+            entry.git_auth = None;
+            save_state(state)?;
+            println!("{}", t!("auth-project-cleared"));
+            return Ok(());
+        }
+        entry.git_auth.get_or_insert_with(|| state.git_auth.clone())
+    } else {
+        if clear {
+            state.git_auth = GitAuth::default();
+            save_state(state)?;
+            println!("{}", t!("auth-cleared"));
+            return Ok(());
+        }
+        &mut state.git_auth
+    };
+
+    if changes.is_empty() {
+        let scope = if project { t!("auth-project-credentials") } else { t!("auth-credentials") };
+        println!("{}: {}", scope, describe(auth).bold());
+        if !project && state.git_auth_for(&current_project) != &state.git_auth {
+            println!("{}", t!("auth-project-own", credentials = describe(state.git_auth_for(&current_project))));
+        }
+        return Ok(());
+    }
+
+    auth.ssh_key = changes.ssh_key.or(auth.ssh_key.take());
+    auth.token_env = changes.token_env.or(auth.token_env.take());
+    auth.username = changes.username.or(auth.username.take());
+    let description = describe(auth);
+    save_state(state)?;
+    println!("{}", t!("auth-set", credentials = description.bold()));
+
+    Ok(())
+}
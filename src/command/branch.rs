@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::repo;
+use crate::state::AppState;
+use crate::util::{print_no_project_selected, print_not_initialized};
+
+/// The current project, if refrs is initialized and one is selected.
+fn project(state: &AppState) -> Option<&str> {
+    if !state.initialized {
+        print_not_initialized();
+        return None;
+    }
+
+    if state.current_project.is_empty() || !Path::new(&state.current_project).exists() {
+        print_no_project_selected();
+        return None;
+    }
+
+    Some(&state.current_project)
+}
+
+pub fn handle_create(state: &AppState, name: &str) -> Result<()> {
+    let Some(project) = project(state) else {
+        return Ok(());
+    };
+
+    repo::create_branch(project, name)?;
+    println!("{}", t!("branch-created", branch = name.bold()));
+    Ok(())
+}
+
+pub fn handle_switch(state: &AppState, name: &str) -> Result<()> {
+    let Some(project) = project(state) else {
+        return Ok(());
+    };
+
+    repo::switch_branch(project, name)?;
+    println!("{}", t!("branch-switched", branch = name.bold()));
+    Ok(())
+}
+
+pub fn handle_list(state: &AppState) -> Result<()> {
+    let Some(project) = project(state) else {
+        return Ok(());
+    };
+
+    for branch in repo::branches(project)? {
+        if branch.current {
+            println!("* {}", branch.name.green().bold());
+        } else if branch.remote_only {
+            println!("  {} {}", branch.name, t!("remote-only").dimmed());
+        } else {
+            println!("  {}", branch.name);
+        }
+    }
+    Ok(())
+}
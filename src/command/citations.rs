@@ -0,0 +1,70 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::model::date::Date;
+use crate::repo;
+use crate::services::citations::{self, CitationCount, CITATIONS_TAG};
+use crate::services::{doi, library};
+use crate::state::AppState;
+use crate::util::{print_no_project_selected, print_not_initialized};
+
+/// Looks up how often each entry with a DOI has been cited and stores the
+/// count with today's date. Counts younger than a month are kept unless
+/// `refresh` is given.
+pub fn handle_citations(state: &AppState, refresh: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let today = Date::today();
+    let entries = library::load_entries(&state.current_project)?;
+    let mut updated = Vec::new();
+
+    for reference in &entries {
+        let Some(found) = reference.entry.get_field("DO").and_then(|value| doi::find(value)) else {
+            continue;
+        };
+        let current = CitationCount::of(&reference.entry);
+        if !refresh && current.is_some_and(|current| !current.is_stale(&today)) {
+            continue;
+        }
+
+        let count = match citations::fetch(&found) {
+            Ok(Some(count)) => count,
+            Ok(None) => {
+                println!("{} {}", t!("citations-unknown").yellow(), reference.id);
+                continue;
+            }
+            Err(err) => {
+                println!("{}{:#}", t!("error").red().bold(), err);
+                continue;
+            }
+        };
+        let count = CitationCount { count, checked: today };
+        println!("{} {}", reference.id.bold(), t!("citations-count", count = count.count));
+
+        let mut entry = reference.entry.clone();
+        entry.fields.insert(CITATIONS_TAG.to_string(), vec![count.to_string()]);
+        library::replace_entry(reference, Some(&entry))?;
+        updated.push(reference.id.clone());
+    }
+
+    if updated.is_empty() {
+        println!("{}", t!("citations-none").blue().bold());
+        return Ok(());
+    }
+
+    repo::add_all(&state.current_project)?;
+    repo::commit(
+        &state.current_project,
+        &format!("Updated citation counts of {}", updated.join(", ")),
+    )?;
+    println!("{}", t!("citations-updated", count = updated.len()).green().bold());
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use anyhow::Result;
+use arboard::Clipboard;
+use clap::ValueEnum;
+use colored::Colorize;
+
+use crate::services::library::{self, LibraryEntry};
+use crate::state::AppState;
+use crate::util::{describe_entry, print_no_project_selected, print_not_initialized, resolve_reference};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CiteFormat {
+    /// `\cite{key}`
+    Latex,
+    /// `[@key]`
+    Pandoc,
+}
+
+/// Copies a citation of the reference best matching `query` to the
+/// clipboard. Several matches are narrowed down in a fuzzy picker.
+pub fn handle_cite(state: &AppState, query: Option<&str>, format: CiteFormat) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let reference = match query {
+        Some(query) => match library::fuzzy_find(&entries, query).as_slice() {
+            [] => {
+                println!("{}{}", t!("error").red().bold(), t!("cite-no-match", query = query));
+                return Ok(());
+            }
+            [only] => *only,
+            candidates => match pick(candidates, query)? {
+                Some(reference) => reference,
+                None => return Ok(()),
+            },
+        },
+        None => match resolve_reference(&entries, None)? {
+            Some(reference) => reference,
+            None => return Ok(()),
+        },
+    };
+
+    let citation = citation(reference, format);
+    let mut clipboard = Clipboard::new()?;
+    clipboard.set_text(citation.clone())?;
+
+    println!("{} {}  {}", t!("cite-copied").green().bold(), citation, describe_entry(&reference.entry).dimmed());
+    Ok(())
+}
+
+/// The reference's citation in `format`, e.g. `\cite{doe2020}`.
+pub fn citation(reference: &LibraryEntry, format: CiteFormat) -> String {
+    match format {
+        CiteFormat::Latex => format!("\\cite{{{}}}", reference.key()),
+        CiteFormat::Pandoc => format!("[@{}]", reference.key()),
+    }
+}
+
+fn pick<'a>(candidates: &[&'a LibraryEntry], query: &str) -> Result<Option<&'a LibraryEntry>> {
+    let items: Vec<String> = candidates
+        .iter()
+        .map(|e| format!("{}  {}", e.key(), describe_entry(&e.entry)))
+        .collect();
+
+    let selected = dialoguer::FuzzySelect::new()
+        .with_prompt(t!("cite-several"))
+        .with_initial_text(query)
+        .items(&items)
+        .default(0)
+        .max_length(15)
+        .interact_opt()?;
+
+    Ok(selected.map(|i| candidates[i]))
+}
@@ -4,18 +4,26 @@ use crate::state::Project;
 use crate::repo;
 use crate::util::print_not_initialized;
 
-pub fn handle_clone(state: &mut AppState, relative_path: &str, url: &str) -> Result<()> {
+pub fn handle_clone(
+    state: &mut AppState,
+    relative_path: &str,
+    url: &str,
+    depth: Option<u32>,
+    branch: Option<&str>,
+) -> Result<()> {
     if !state.initialized {
         print_not_initialized();
         return Ok(());
     }
 
-    let absolute_path = repo::clone_repo(relative_path, url)?;
+    let absolute_path = repo::clone_repo(relative_path, url, &state.git_auth, depth, branch)?;
     state.projects.push(Project {
+        name: None,
         absolute_path,
         url: url.to_string(),
+        git_auth: None,
     });
 
-    save_state(&state)?;
+    save_state(state)?;
     Ok(())
 }
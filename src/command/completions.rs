@@ -0,0 +1,64 @@
+use std::io;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::Shells;
+
+use crate::services::library;
+use crate::state;
+use crate::util::describe_entry;
+
+/// Environment variable the completion scripts set when they call refrs
+/// back for candidates.
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+/// Prints the completion script for `shell`. Instead of listing every
+/// command and option, it calls `refrs` while completing, which also offers
+/// workspace names and the citation keys of the current project.
+pub fn handle_completions(shell: CompletionShell) -> Result<()> {
+    let name = shell.to_possible_value().expect("no skipped variants").get_name().to_string();
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&name)
+        .expect("clap_complete supports every CompletionShell");
+    completer.write_registration(COMPLETE_VAR, "refrs", "refrs", "refrs", &mut io::stdout())?;
+    Ok(())
+}
+
+/// Names of the registered projects, for `workspace set` and friends.
+pub fn workspace_names() -> Vec<CompletionCandidate> {
+    let Ok(state) = state::load_state() else {
+        return Vec::new();
+    };
+    state
+        .projects
+        .iter()
+        .map(|project| CompletionCandidate::new(project.name()).help(Some(project.absolute_path.clone().into())))
+        .collect()
+}
+
+/// Citation keys of the current project, described like in pickers.
+pub fn reference_keys() -> Vec<CompletionCandidate> {
+    let Ok(state) = state::load_state() else {
+        return Vec::new();
+    };
+    if state.current_project.is_empty() {
+        return Vec::new();
+    }
+    let Ok(entries) = library::load_entries(&state.current_project) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .map(|e| CompletionCandidate::new(&e.id).help(Some(describe_entry(&e.entry).into())))
+        .collect()
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::config::{self, UserConfig, USER_SETTINGS};
+
+/// Prints a setting, with environment overrides applied.
+pub fn handle_get(name: &str) -> Result<()> {
+    if let Some(value) = config::load_user_config()?.with_env()?.get(name)? {
+        println!("{}", value);
+    }
+    Ok(())
+}
+
+/// Stores a setting in the user's config file. An empty value unsets it.
+pub fn handle_set(name: &str, value: &str) -> Result<()> {
+    // Only this setting is checked and written, so a broken file can be
+    // repaired one setting at a time.
+    let mut user_config = UserConfig::default();
+    user_config.set(name, value)?;
+    config::save_user_setting(&user_config, name)?;
+
+    match user_config.get(name)? {
+        Some(value) => println!("{}", t!("config-set", name = name.bold(), value = value)),
+        None => println!("{}", t!("config-unset", name = name.bold())),
+    }
+    if let Some((_, var)) = USER_SETTINGS.iter().find(|(setting, _)| *setting == name) {
+        if std::env::var_os(var).is_some() {
+            println!("{}", t!("config-overridden", var = var).yellow());
+        }
+    }
+    Ok(())
+}
+
+/// Prints every setting, marking the ones an environment variable overrides.
+pub fn handle_list() -> Result<()> {
+    let user_config = config::load_user_config()?.with_env()?;
+    for (name, var) in USER_SETTINGS {
+        let value = user_config.get(name)?.unwrap_or_else(|| "-".dimmed().to_string());
+        if std::env::var_os(var).is_some() {
+            println!("{:<16} {}  {}", name, value, t!("config-from-env", var = var).dimmed());
+        } else {
+            println!("{:<16} {}", name, value);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,222 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::services::daemon::{self, DaemonStatus};
+use crate::services::ipc::{self, Request, Response};
+use crate::state::{save_state, AppState};
+use crate::util::{print_json, print_not_initialized};
+
+/// Starts `refrs daemon run` as a detached background process.
+pub fn handle_start(state: &AppState, interval: u64) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if let Some(status) = daemon::load_status()? {
+        if status.is_running() {
+            println!("{}", t!("daemon-already-running", pid = status.pid));
+            return Ok(());
+        }
+    }
+
+    let child = Command::new(std::env::current_exe()?)
+        .args(["daemon", "run", "--interval", &interval.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    println!("{}", t!("daemon-started", pid = child.id(), interval = interval).green().bold());
+    Ok(())
+}
+
+/// Runs the daemon loop in the foreground.
+pub fn handle_run(state: &AppState, interval: u64) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    daemon::run(Duration::from_secs(interval))
+}
+
+pub fn handle_stop() -> Result<()> {
+    if let Some(response) = ipc::send(&Request::Stop)? {
+        return print_response(&response);
+    }
+
+    // Fall back to the stop file for daemons without a control socket.
+    match daemon::load_status()? {
+        Some(status) if status.is_running() => {
+            daemon::request_stop()?;
+            println!("{}", t!("daemon-stopping", pid = status.pid));
+        }
+        _ => println!("{}", t!("daemon-not-running")),
+    }
+    Ok(())
+}
+
+/// Runs a sync cycle in the running daemon right away.
+pub fn handle_sync() -> Result<()> {
+    match ipc::send(&Request::Sync)? {
+        Some(response) => print_response(&response),
+        None => {
+            println!("{}", t!("daemon-not-running-sync-hint"));
+            Ok(())
+        }
+    }
+}
+
+/// Hands a file to the running daemon to import into the current project.
+pub fn handle_import(file: &str) -> Result<()> {
+    let path = std::env::current_dir()?.join(file);
+    let request = Request::Import {
+        path: path.to_string_lossy().to_string(),
+    };
+    match ipc::send(&request)? {
+        Some(response) => print_response(&response),
+        None => {
+            println!("{}", t!("daemon-not-running"));
+            Ok(())
+        }
+    }
+}
+
+fn print_response(response: &Response) -> Result<()> {
+    if response.ok {
+        if !response.message.is_empty() {
+            println!("{}", response.message.green().bold());
+        }
+        Ok(())
+    } else {
+        anyhow::bail!("{}", response.message)
+    }
+}
+
+/// The daemon status as JSON, for `--json`.
+#[derive(Serialize)]
+struct StatusJson<'a> {
+    running: bool,
+    #[serde(flatten)]
+    status: Option<&'a DaemonStatus>,
+    watch_folders: &'a [String],
+}
+
+pub fn handle_status(state: &AppState, json: bool) -> Result<()> {
+    // Ask the daemon first so the status is current, not from the last heartbeat.
+    let live = ipc::send(&Request::Status)?.and_then(|response| response.status);
+    let status = match live {
+        Some(status) => Some(status),
+        None => daemon::load_status()?,
+    };
+    if json {
+        return print_json(&StatusJson {
+            running: status.as_ref().is_some_and(DaemonStatus::is_running),
+            status: status.as_ref(),
+            watch_folders: &state.watch_folders,
+        });
+    }
+    let Some(status) = status else {
+        println!("{}", t!("daemon-never-started"));
+        return Ok(());
+    };
+
+    if status.is_running() {
+        let uptime = format_duration(daemon::now().saturating_sub(status.started_at));
+        println!(
+            "{}",
+            t!("daemon-running", pid = status.pid, uptime = uptime, interval = status.interval_secs).green().bold()
+        );
+    } else {
+        println!("{}", t!("daemon-stopped").yellow().bold());
+    }
+
+    print_projects(&status);
+
+    if !state.watch_folders.is_empty() {
+        println!("{}", t!("daemon-watched-header").green().bold());
+        for folder in &state.watch_folders {
+            println!("{}", folder);
+        }
+    }
+
+    if !status.imported.is_empty() {
+        println!("{}", t!("daemon-imported-header").green().bold());
+        for file in &status.imported {
+            println!("{}", file);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_projects(status: &DaemonStatus) {
+    if status.projects.is_empty() {
+        return;
+    }
+
+    println!("{}", t!("projects-header").green().bold());
+    for project in &status.projects {
+        let last_sync = match project.last_sync {
+            Some(time) => t!("daemon-ago", duration = format_duration(daemon::now().saturating_sub(time))),
+            None => t!("daemon-never"),
+        };
+        println!("{:<50} {}", project.path, t!("daemon-last-sync", time = last_sync));
+        if let Some(error) = &project.last_error {
+            println!("  {}", error.red());
+        }
+    }
+}
+
+fn format_duration(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h {}m", s / 3600, (s % 3600) / 60),
+        s => format!("{}d {}h", s / 86400, (s % 86400) / 3600),
+    }
+}
+
+pub fn handle_watch(state: &mut AppState, folder: &str) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    let absolute = std::env::current_dir()?.join(Path::new(folder));
+    let absolute = absolute.to_string_lossy().to_string();
+    if !state.watch_folders.contains(&absolute) {
+        state.watch_folders.push(absolute.clone());
+        save_state(state)?;
+    }
+    println!("{}", t!("daemon-watching", folder = absolute.bold()));
+    Ok(())
+}
+
+pub fn handle_unwatch(state: &mut AppState, folder: &str) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    let absolute = std::env::current_dir()?.join(Path::new(folder));
+    let absolute = absolute.to_string_lossy().to_string();
+    let before = state.watch_folders.len();
+    state
+        .watch_folders
+        .retain(|f| f != folder && *f != absolute);
+
+    if state.watch_folders.len() == before {
+        println!("{}", t!("daemon-not-watched", folder = folder));
+    } else {
+        save_state(state)?;
+        println!("{}", t!("daemon-unwatched", folder = folder));
+    }
+    Ok(())
+}
@@ -0,0 +1,263 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::model::ris::RisEntry;
+use crate::repo;
+use crate::services::library::{self, LibraryEntry, LINK_TAG};
+use crate::services::merge::{self, Side};
+use crate::state::AppState;
+use crate::util::{describe_entry, print_entry_diff, print_no_project_selected, print_not_initialized, truncate};
+
+pub fn handle_dedupe(state: &AppState) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let pairs = library::duplicate_pairs(&entries);
+    if pairs.is_empty() {
+        println!("{}", t!("dedupe-none").green().bold());
+        return Ok(());
+    }
+
+    println!("{}", t!("dedupe-found", count = pairs.len()));
+
+    // The library is read again after every merge, so later pairs see the
+    // merged entry. Pairs the user declined are remembered by content.
+    let mut declined = HashSet::new();
+    loop {
+        let entries = library::load_entries(&state.current_project)?;
+        let pair = library::duplicate_pairs(&entries).into_iter().find(|&(i, j)| {
+            !declined.contains(&(entries[i].entry.to_string(), entries[j].entry.to_string()))
+        });
+        let Some((i, j)) = pair else {
+            break;
+        };
+        let (left, right) = (&entries[i], &entries[j]);
+
+        println!();
+        match resolve_conflicts(&left.id, &left.entry, &right.id, &right.entry)? {
+            Some(merged) => {
+                merge::save_merged(&state.current_project, left, Some(right), &merged)?;
+                println!("{}", t!("merged", from = right.id, into = left.id).green().bold());
+            }
+            None => {
+                declined.insert((left.entry.to_string(), right.entry.to_string()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds works present in more than one registered project and offers to
+/// make the copies identical or to link them to each other.
+pub fn handle_dedupe_across_projects(state: &AppState) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    let paths: Vec<&str> = state.projects.iter().map(|p| p.absolute_path.as_str()).collect();
+    let projects = paths
+        .iter()
+        .map(|path| library::load_entries(path))
+        .collect::<Result<Vec<_>>>()?;
+
+    let groups = library::cross_project_groups(&projects);
+    if groups.is_empty() {
+        println!("{}", t!("dedupe-none-shared").green().bold());
+        return Ok(());
+    }
+
+    println!("{}", t!("dedupe-found-shared", count = groups.len()));
+
+    for group in groups {
+        println!();
+        for &(p, e) in &group {
+            let entry = &projects[p][e];
+            println!("{} {}", project_name(paths[p]).bold(), entry.id.cyan());
+        }
+        println!("{}", describe_entry(&projects[group[0].0][group[0].1].entry).dimmed());
+
+        let action = dialoguer::Select::new()
+            .with_prompt(t!("dedupe-action"))
+            .items(&[t!("dedupe-consolidate"), t!("dedupe-link"), t!("dedupe-skip")])
+            .default(2)
+            .interact()?;
+
+        match action {
+            0 => consolidate(&paths, &projects, &group)?,
+            1 => link(&paths, &projects, &group)?,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn project_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn consolidate(
+    paths: &[&str],
+    projects: &[Vec<LibraryEntry>],
+    group: &[(usize, usize)],
+) -> Result<()> {
+    let (first_project, first) = (group[0].0, &projects[group[0].0][group[0].1]);
+    let first_label = format!("{}/{}", project_name(paths[first_project]), first.id);
+
+    let mut merged = first.entry.clone();
+    for &(p, e) in &group[1..] {
+        let other = &projects[p][e];
+        let label = format!("{}/{}", project_name(paths[p]), other.id);
+
+        let mut choices = HashMap::new();
+        for conflict in merge::conflicts(&merged, &other.entry) {
+            let items = [
+                format!("{}: {}", first_label, conflict.left.join("; ")),
+                format!("{}: {}", label, conflict.right.join("; ")),
+            ];
+            let selected = dialoguer::Select::new()
+                .with_prompt(t!("dedupe-keep-which", tag = conflict.tag))
+                .items(&items)
+                .default(0)
+                .interact()?;
+            let side = if selected == 0 { Side::Left } else { Side::Right };
+            choices.insert(conflict.tag, side);
+        }
+        merged = merge::merge(&merged, &other.entry, &choices);
+    }
+
+    for &(p, e) in group {
+        let entry = &projects[p][e];
+        let mut updated = merged.clone();
+        // Links point elsewhere from each copy, so they are not shared.
+        updated.fields.remove(LINK_TAG);
+        if let Some(links) = entry.entry.fields.get(LINK_TAG) {
+            updated.fields.insert(LINK_TAG.to_string(), links.clone());
+        }
+        save_if_changed(paths[p], entry, &updated, "Consolidated")?;
+    }
+
+    println!("{}", t!("dedupe-consolidated", count = group.len()).green().bold());
+    Ok(())
+}
+
+fn link(paths: &[&str], projects: &[Vec<LibraryEntry>], group: &[(usize, usize)]) -> Result<()> {
+    for &(p, e) in group {
+        let entry = &projects[p][e];
+        let mut updated = entry.entry.clone();
+        let links = updated.fields.entry(LINK_TAG.to_string()).or_default();
+        for &(other_p, other_e) in group.iter().filter(|&&member| member != (p, e)) {
+            let link = format!("{}/{}", project_name(paths[other_p]), projects[other_p][other_e].id);
+            if !links.contains(&link) {
+                links.push(link);
+            }
+        }
+        save_if_changed(paths[p], entry, &updated, "Linked")?;
+    }
+
+    println!("{}", t!("dedupe-linked", count = group.len()).green().bold());
+    Ok(())
+}
+
+fn save_if_changed(
+    project_path: &str,
+    entry: &LibraryEntry,
+    updated: &RisEntry,
+    verb: &str,
+) -> Result<()> {
+    if updated.to_string() == entry.entry.to_string() {
+        return Ok(());
+    }
+    library::replace_entry(entry, Some(updated))?;
+    repo::add_all(project_path)?;
+    repo::commit(
+        project_path,
+        &format!("{} {} with other projects", verb, entry.id),
+    )?;
+    Ok(())
+}
+
+/// Shows both entries side by side and asks which value to keep for every
+/// conflicting field. Returns `None` if the user chooses not to merge.
+pub fn resolve_conflicts(
+    left_label: &str,
+    left: &RisEntry,
+    right_label: &str,
+    right: &RisEntry,
+) -> Result<Option<RisEntry>> {
+    print_comparison(left_label, left, right_label, right);
+
+    let merge_them = dialoguer::Confirm::new()
+        .with_prompt(t!("dedupe-merge", from = right_label, into = left_label))
+        .default(false)
+        .interact()?;
+    if !merge_them {
+        return Ok(None);
+    }
+
+    let mut choices = HashMap::new();
+    for conflict in merge::conflicts(left, right) {
+        let items = [
+            format!("{}: {}", left_label, conflict.left.join("; ")),
+            format!("{}: {}", right_label, conflict.right.join("; ")),
+        ];
+        let selected = dialoguer::Select::new()
+            .with_prompt(t!("dedupe-keep-which", tag = conflict.tag))
+            .items(&items)
+            .default(0)
+            .interact()?;
+        let side = if selected == 0 { Side::Left } else { Side::Right };
+        choices.insert(conflict.tag, side);
+    }
+
+    let merged = merge::merge(left, right, &choices);
+    println!("{}", t!("dedupe-changes", label = left_label.bold()));
+    print_entry_diff(left, &merged);
+
+    Ok(Some(merged))
+}
+
+/// Prints a field-by-field table of both entries, highlighting conflicts.
+fn print_comparison(left_label: &str, left: &RisEntry, right_label: &str, right: &RisEntry) {
+    println!(
+        "{:<5} {:<38} {:<38}",
+        t!("column-field").underline(),
+        truncate(left_label, 38).underline(),
+        truncate(right_label, 38).underline()
+    );
+
+    for tag in merge::all_tags(left, right) {
+        let l = merge::values(left, &tag);
+        let r = merge::values(right, &tag);
+        let row = format!(
+            "{:<5} {:<38} {:<38}",
+            tag,
+            truncate(&l.join("; "), 38),
+            truncate(&r.join("; "), 38)
+        );
+
+        if l == r {
+            println!("{}", row.dimmed());
+        } else if l.is_empty() || r.is_empty() {
+            println!("{}", row);
+        } else {
+            println!("{}", row.yellow().bold());
+        }
+    }
+}
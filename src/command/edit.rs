@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Context, Result};
+use colored::Colorize;
+
+use crate::model::ris::{parse_ris, RisEntry};
+use crate::repo;
+use crate::services::serialization::KEY_TAG;
+use crate::services::{encoding, library};
+use crate::state::AppState;
+use crate::util::{print_entry_diff, print_no_project_selected, print_not_initialized, resolve_reference};
+
+pub fn handle_edit(state: &AppState, id: Option<&str>, editor: Option<&str>) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let Some(reference) = resolve_reference(&entries, id)? else {
+        return Ok(());
+    };
+
+    // The bytes are kept to restore the file in its own encoding.
+    let original = fs::read(&reference.path)?;
+    let (before, _) = encoding::decode(&original);
+
+    let editor = editor
+        .map(str::to_string)
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .unwrap_or_else(|| "vi".to_string());
+    // Editors are often configured with arguments, like `code --wait`, and
+    // may live in a path with spaces.
+    let words = shell_words::split(&editor).with_context(|| format!("Could not split the editor {:?}", editor))?;
+    let Some((program, args)) = words.split_first() else {
+        bail!("The editor command is empty");
+    };
+    let status = Command::new(program)
+        .args(args)
+        .arg(&reference.path)
+        .status()
+        .with_context(|| format!("Failed to launch editor '{}'", editor))?;
+    if !status.success() {
+        return Err(anyhow!("Editor exited with status {}", status));
+    }
+
+    let (after, _) = encoding::read_text(&reference.path)?;
+    if after == before {
+        println!("{}", t!("edit-unchanged").blue().bold());
+        return Ok(());
+    }
+
+    // Refuse to commit a file refrs can no longer read.
+    let edited = match parse_ris(&after) {
+        Ok(edited) => edited,
+        Err(err) => {
+            fs::write(&reference.path, original)?;
+            println!("{}{}", t!("error").red().bold(), err);
+            println!("{}", t!("edit-restored"));
+            return Ok(());
+        }
+    };
+
+    if edited.is_empty() {
+        println!("{}", t!("edit-emptied").yellow());
+    } else {
+        print_changes(&parse_ris(&before).unwrap_or_default(), &edited);
+    }
+
+    repo::add_all(&state.current_project)?;
+    repo::commit(&state.current_project, &format!("Edited {}.ris", reference.id))?;
+
+    Ok(())
+}
+
+/// Prints the diff of every entry the edit changed, headed by its key when
+/// the file holds several. Entries are paired by position while the file
+/// keeps its number of entries, else by citation key.
+fn print_changes(original: &[RisEntry], edited: &[RisEntry]) {
+    let key = |entry: &RisEntry| entry.get_field(KEY_TAG).cloned();
+    let by_key = |entry: &RisEntry, others: &[RisEntry]| {
+        let wanted = key(entry)?;
+        others.iter().position(|other| key(other).as_ref() == Some(&wanted))
+    };
+    let empty = |entry: &RisEntry| RisEntry { ty: entry.ty.clone(), fields: HashMap::new() };
+    let several = original.len() > 1 || edited.len() > 1;
+    let heading = |entry: &RisEntry, n: usize| {
+        if several {
+            println!("{}", key(entry).unwrap_or_else(|| format!("#{}", n + 1)).bold());
+        }
+    };
+
+    let same_count = original.len() == edited.len();
+    for (i, new) in edited.iter().enumerate() {
+        let old = if same_count { Some(i) } else { by_key(new, original) }.map(|i| &original[i]);
+        if old.is_some_and(|old| old.to_string() == new.to_string()) {
+            continue;
+        }
+        heading(new, i);
+        print_entry_diff(old.unwrap_or(&empty(new)), new);
+    }
+    if !same_count {
+        for (i, old) in original.iter().enumerate() {
+            if by_key(old, edited).is_none() {
+                heading(old, i);
+                print_entry_diff(old, &empty(old));
+            }
+        }
+    }
+}
@@ -1,13 +1,23 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
-use crate::model::ris::{self, ris_entry_to_bibtex_string};
-use crate::services::serialization;
-use crate::state::AppState;
-use crate::util::print_not_initialized;
-use anyhow::Result;
+use crate::config;
+use crate::model::ris::RisEntry;
+use crate::repo;
+use crate::command::dedupe;
+use crate::services::citekey::KeyFormat;
+use crate::services::serialization::{self, Confidence, ImportFormat, ParseResult, PendingImport, KEY_TAG};
+use crate::services::export::{self, ExportFormat, ExportSort, SplitBy};
+use crate::services::query::Query;
+use crate::services::{arxiv, doi, encoding, html, jabref, library, merge, pdf, pubmed, translation_server, webpage};
+use crate::state::{save_state, AppState};
+use crate::util::{print_json, print_no_project_selected, print_not_initialized, truncate};
+use anyhow::{bail, Result};
 use arboard::Clipboard;
+use clap::ValueEnum;
 use colored::Colorize;
+use serde::Serialize;
 
 fn print_problematic_line(text: &str, start: usize, end: usize) {
     let lines: Vec<&str> = text.lines().collect();
@@ -19,7 +29,7 @@ fn print_problematic_line(text: &str, start: usize, end: usize) {
 
         if start >= line_start && start < line_end {
             // Print the problematic line
-            println!("Line {}: {}", line_number + 1, line);
+            repo::status(t!("import-line", number = line_number + 1, line = line));
 
             // Calculate the offset of the problem in the line
             let indicator_start = start - line_start;
@@ -30,62 +40,412 @@ fn print_problematic_line(text: &str, start: usize, end: usize) {
             indicator.push_str(&" ".repeat(indicator_start));
             indicator.push_str(&"-".repeat(indicator_end - indicator_start));
             indicator.push('^');
-            println!("{}{}", " ".repeat(6), indicator); // Align with "Line X: "
+            let prefix = t!("import-line", number = line_number + 1, line = "");
+            repo::status(format!("{}{}", " ".repeat(prefix.chars().count()), indicator)); // Align with "Line X: "
             return;
         }
 
         char_count += line.len() + 1; // Include the newline character
     }
 
-    println!("Unexpected end of bibtex.");
+    repo::status(t!("import-unexpected-end"));
 }
 
-pub fn handle_import(state: &AppState, from_clipboard: bool) -> Result<()> {
+/// Reads citation text from the clipboard. Browsers often only offer useful
+/// content as HTML, so that flavour is tried when the plain text is not a
+/// recognizable BibTeX or RIS export.
+fn read_clipboard() -> Result<String> {
+    let mut clipboard = Clipboard::new()?;
+    let text = clipboard.get_text().unwrap_or_default();
+    if is_recognized(&text) {
+        return Ok(text);
+    }
+
+    let candidates = [clipboard.get().html().ok(), Some(text.clone())];
+    for html in candidates.into_iter().flatten() {
+        let extracted = html::extract_citation(&html::to_text(&html));
+        if is_recognized(&extracted) {
+            return Ok(extracted);
+        }
+    }
+
+    Ok(text)
+}
+
+/// Whether `text` is in a structured format. Plain-text citations only count
+/// when nothing better was copied, so they are left for the final fallback.
+fn is_recognized(text: &str) -> bool {
+    matches!(
+        serialization::parse(text),
+        ParseResult::Parsed { format, .. } if !matches!(format, ImportFormat::Text)
+    )
+}
+
+/// Entries for a web page: from the translation server when one is set up,
+/// else, or when it finds nothing, from the page's meta tags.
+fn fetch_url(url: &str) -> Result<Vec<RisEntry>> {
+    if let Some(endpoint) = translation_server::endpoint() {
+        match translation_server::translate(endpoint, url) {
+            Ok(entries) if !entries.is_empty() => return Ok(entries),
+            Ok(_) => repo::status(format!("{}{}", t!("warning").bold().yellow(), t!("import-translation-empty"))),
+            Err(error) => repo::status(format!("{}{:#}", t!("warning").bold().yellow(), error)),
+        }
+        repo::status(t!("import-meta-tags").dimmed());
+    }
+    Ok(vec![webpage::fetch(url)?])
+}
+
+/// Where `refrs import` reads references from.
+pub enum ImportSource<'a> {
+    Clipboard,
+    /// An arXiv id or URL
+    Arxiv(&'a str),
+    /// A PubMed id
+    Pubmed(&'a str),
+    /// `.bib`, `.ris` or other reference files
+    Files(&'a [String]),
+    /// A publisher landing page or other page with citation meta tags
+    Url(&'a str),
+    /// A paper whose metadata is looked up and which is then attached
+    Pdf(&'a str),
+    /// A DOI, whose metadata is fetched from doi.org
+    Doi(&'a str),
+}
+
+pub fn handle_import(
+    state: &AppState,
+    source: Option<ImportSource>,
+    yes: bool,
+    key_format: Option<KeyFormat>,
+    format: Option<ImportFormat>,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
     if !state.initialized {
         print_not_initialized();
         return Ok(());
     }
 
     if state.projects.is_empty() {
-        println!("{}", "No project selected.".blue().bold());
+        print_no_project_selected();
         return Ok(());
     }
 
-    let text: String;
-    if from_clipboard {
-        let mut clipboard = Clipboard::new()?;
-        text = clipboard.get_text()?;
-    } else {
+    // Number of entries read from each file, to report results per file.
+    let mut per_file: Vec<(&str, usize)> = Vec::new();
+
+    let entries = match &source {
+        Some(ImportSource::Files(paths)) => {
+            let mut entries = Vec::new();
+            for path in paths.iter() {
+                repo::status(path.bold());
+                let read = match encoding::read_text(Path::new(path)) {
+                    Ok((text, _)) => parse_text(&text, format),
+                    Err(error) => {
+                        repo::status(format!("{} {:#}", t!("import-unreadable").red().bold(), error));
+                        None
+                    }
+                };
+                let read = read.unwrap_or_default();
+                per_file.push((path, read.len()));
+                entries.extend(read);
+            }
+            if entries.is_empty() {
+                repo::status(t!("import-nothing").yellow().bold());
+                return Ok(());
+            }
+            entries
+        }
+        Some(ImportSource::Clipboard) => {
+            let text = encoding::normalize(&read_clipboard()?);
+            match parse_text(&text, format) {
+                Some(entries) => entries,
+                None => return Ok(()),
+            }
+        }
+        Some(ImportSource::Arxiv(id)) => {
+            repo::status(t!("import-fetching-arxiv", id = arxiv::normalize_id(id).bold()).blue());
+            vec![arxiv::fetch(id)?]
+        }
+        Some(ImportSource::Pubmed(pmid)) => {
+            repo::status(t!("import-fetching-pubmed", id = pmid.bold()).blue());
+            vec![pubmed::fetch(pmid)?]
+        }
+        Some(ImportSource::Url(url)) => {
+            repo::status(t!("import-fetching", url = url.bold()).blue());
+            fetch_url(url)?
+        }
+        Some(ImportSource::Doi(id)) => {
+            repo::status(t!("import-fetching-doi", id = id.bold()).blue());
+            vec![doi::fetch(id)?]
+        }
+        Some(ImportSource::Pdf(path)) => {
+            repo::status(t!("import-reading", path = path.bold()).blue());
+            let metadata = pdf::read(Path::new(path))?;
+            vec![pdf::to_entry(&metadata)?]
+        }
+        None => {
+            let sources = [
+                "refrs import <file>...",
+                "refrs import --clipboard",
+                "refrs import --url <page>",
+                "refrs import --pdf <file>",
+                "refrs import --arxiv <id>",
+                "refrs import --pmid <id>",
+            ];
+            let sources: Vec<String> = sources.iter().map(|source| source.bold().to_string()).collect();
+            repo::status(format!("{}{}", t!("warning").bold().yellow(), t!("import-choose-source", sources = sources.join(", "))));
+            return Ok(());
+        }
+    };
+
+    let config = config::load(&state.current_project)?;
+    let (default_format, key_pattern) = config.key_scheme(state);
+    let key_format = key_format.unwrap_or(default_format);
+    let mut pending = serialization::prepare_import(entries, &state.current_project, key_format, key_pattern)?;
+    if json {
+        // There is no preview to choose from, so import like --yes.
+        let imported = if dry_run {
+            serialization::planned_file_names(&pending, &state.current_project, key_format)?
+        } else if pending.iter().any(|p| p.selected) {
+            serialization::commit_import(&pending, &state.current_project, key_format)?
+        } else {
+            Vec::new()
+        };
+        if let (Some(ImportSource::Pdf(path)), Some(file_name), false) = (&source, imported.first(), dry_run) {
+            crate::command::attach::handle_attach(state, file_name.trim_end_matches(".ris"), path)?;
+        }
+        return print_json(&ImportReport {
+            imported: imported.iter().map(|name| name.trim_end_matches(".ris").to_string()).collect(),
+            duplicates: pending.iter().filter(|p| !p.selected).filter_map(|p| p.duplicate_of.clone()).collect(),
+            dry_run,
+        });
+    }
+    print_import_preview(&pending);
+
+    if dry_run {
+        let file_names = serialization::planned_file_names(&pending, &state.current_project, key_format)?;
+        println!("{}", t!("would-import", count = file_names.len()).bold());
+        for (file_name, import) in file_names.iter().zip(pending.iter().filter(|p| p.selected)) {
+            let mut entry = import.entry.clone();
+            entry.fields.insert(KEY_TAG.to_string(), vec![file_name.trim_end_matches(".ris").to_string()]);
+            println!("\n{}", file_name.bold());
+            println!("{}", entry.to_string().dimmed());
+        }
+        println!("\n{}", t!("dry-run-done").yellow());
+        return Ok(());
+    }
+
+    if !yes {
+        select_imports(&mut pending)?;
+        merge_skipped_duplicates(&pending, &state.current_project)?;
+    }
+
+    if !pending.iter().any(|p| p.selected) {
+        println!("{}", t!("import-nothing").yellow().bold());
+        return Ok(());
+    }
+
+    let file_names = serialization::commit_import(&pending, &state.current_project, key_format)?;
+    if per_file.len() > 1 {
+        let mut rest = pending.as_slice();
+        for (path, count) in per_file {
+            let (from_file, remaining) = rest.split_at(count);
+            rest = remaining;
+            let imported = from_file.iter().filter(|p| p.selected).count();
+            println!("{}", t!("import-from-file", imported = format!("{:>4}", imported), count = format!("{:<4}", count), path = path));
+        }
+    }
+    println!("{}", t!("import-done", count = file_names.len()).green().bold());
+
+    if let (Some(ImportSource::Pdf(path)), Some(file_name)) = (&source, file_names.first()) {
+        crate::command::attach::handle_attach(state, file_name.trim_end_matches(".ris"), path)?;
+    }
+
+    Ok(())
+}
+
+/// What an import did, printed by `--json`.
+#[derive(Serialize)]
+struct ImportReport {
+    /// Keys of the new references, or with `--dry-run` the keys they would get
+    imported: Vec<String>,
+    /// Ids of existing references that skipped entries duplicate
+    duplicates: Vec<String>,
+    dry_run: bool,
+}
+
+/// Parses pasted text, detecting the format unless one is given. Problems
+/// are reported and yield `None`.
+fn parse_text(text: &str, format: Option<ImportFormat>) -> Option<Vec<RisEntry>> {
+    let result = match format {
+        Some(format) => serialization::parse_as(text, format),
+        None => {
+            let (result, confidence) = serialization::detect(text);
+            if let ParseResult::Parsed { format, .. } = &result {
+                let confidence = match confidence {
+                    Confidence::High => t!("import-confidence-high").green(),
+                    Confidence::Low => t!("import-confidence-low").yellow(),
+                };
+                repo::status(t!("import-detected", format = format.to_string().bold(), confidence = confidence));
+            }
+            result
+        }
+    };
+
+    match result {
+        ParseResult::Parsed { entries, .. } if entries.is_empty() => {
+            repo::status(t!("import-none-found").yellow().bold());
+            None
+        }
+        ParseResult::Parsed { entries, .. } => Some(entries),
+        ParseResult::BibtexError { error } => {
+            print_problematic_line(text, error.span.start, error.span.end);
+            None
+        }
+        ParseResult::Error { format, error } => {
+            repo::status(format!("{} {:#}", t!("import-unparsable", format = format).red().bold(), error));
+            None
+        }
+        ParseResult::UnrecognizedFormat => {
+            let formats: Vec<String> = ["BibTeX", "RIS", "CSL-JSON", "RefWorks"].iter().map(|f| f.bold().to_string()).collect();
+            repo::status(t!("import-unrecognized", formats = formats.join(", "), option = "--format endnote".bold()));
+            None
+        }
+    }
+}
+
+/// Prints the entries about to be imported as a table.
+fn print_import_preview(pending: &[PendingImport]) {
+    println!(
+        "{:<3} {:<8} {:<20} {:<6} {:<40} {}",
+        "#".underline(),
+        t!("column-type").underline(),
+        t!("column-author").underline(),
+        t!("column-year").underline(),
+        t!("column-title").underline(),
+        t!("column-duplicate-of").underline()
+    );
+
+    for (i, import) in pending.iter().enumerate() {
+        let entry = &import.entry;
+        let author = entry.get_field("AU").map(String::as_str).unwrap_or("-");
+        let year = entry.get_field("PY").map(String::as_str).unwrap_or("-");
+        let title = entry.get_field("TI").map(String::as_str).unwrap_or("-");
+        let duplicate = match &import.duplicate_of {
+            Some(id) => id.as_str().red().to_string(),
+            None => String::new(),
+        };
+
         println!(
-            "{}: Currenlty only clipboard is supported. Use: {}",
-            "Warning".bold().yellow(),
-            "refrs import --clipboard".bold()
+            "{:<3} {:<8} {:<20} {:<6} {:<40} {}",
+            i + 1,
+            entry.ty.to_str(),
+            truncate(author, 20),
+            truncate(year, 6),
+            truncate(title, 40),
+            duplicate
         );
+    }
+}
+
+/// Offers to merge skipped duplicates into the entries they collide with.
+fn merge_skipped_duplicates(pending: &[PendingImport], project_path: &str) -> Result<()> {
+    let existing = library::load_entries(project_path)?;
+    let collisions: Vec<_> = pending
+        .iter()
+        .filter(|p| !p.selected)
+        .filter_map(|p| {
+            let id = p.duplicate_of.as_ref()?;
+            library::find_entry(&existing, id).map(|e| (p, e))
+        })
+        .collect();
+
+    if collisions.is_empty() {
         return Ok(());
     }
 
-    match serialization::import(&text, &state.current_project)? {
-        serialization::ImportResult::BibtexImported => {}
-        serialization::ImportResult::BibtexError { error } => {
-            print_problematic_line(&text, error.span.start, error.span.end);
-        }
-        serialization::ImportResult::RisImported => {}
-        serialization::ImportResult::RisError { error } => {
-            println!("{}", error);
+    let merge_them = dialoguer::Confirm::new()
+        .with_prompt(t!("import-merge-skipped", count = collisions.len()))
+        .default(false)
+        .interact()?;
+    if !merge_them {
+        return Ok(());
+    }
+
+    for (import, existing) in collisions {
+        println!();
+        if let Some(merged) =
+            dedupe::resolve_conflicts(&existing.id, &existing.entry, &import.key, &import.entry)?
+        {
+            merge::save_merged(project_path, existing, None, &merged)?;
+            println!("{}", t!("merged", from = import.key, into = existing.id).green().bold());
         }
-        serialization::ImportResult::UnrecognizedFormat => {
-            println!(
-                "Did not recognize text format. Supported formats: {}, {}",
-                "BibTex".bold(),
-                "RIS".bold()
-            );
+    }
+
+    Ok(())
+}
+
+/// Lets the user deselect entries and edit the citation keys of the remaining ones.
+fn select_imports(pending: &mut [PendingImport]) -> Result<()> {
+    let items: Vec<String> = pending
+        .iter()
+        .map(|p| match &p.duplicate_of {
+            Some(id) => t!("import-duplicate-of", key = p.key, id = id),
+            None => p.key.clone(),
+        })
+        .collect();
+    let defaults: Vec<bool> = pending.iter().map(|p| p.selected).collect();
+
+    let chosen = dialoguer::MultiSelect::new()
+        .with_prompt(t!("import-select"))
+        .items(&items)
+        .defaults(&defaults)
+        .interact()?;
+
+    for (i, import) in pending.iter_mut().enumerate() {
+        import.selected = chosen.contains(&i);
+    }
+
+    if chosen.is_empty() {
+        return Ok(());
+    }
+
+    let edit_keys = dialoguer::Confirm::new()
+        .with_prompt(t!("import-edit-keys"))
+        .default(false)
+        .interact()?;
+
+    if edit_keys {
+        for import in pending.iter_mut().filter(|p| p.selected) {
+            import.key = dialoguer::Input::new()
+                .with_prompt(t!("import-citation-key"))
+                .with_initial_text(import.key.clone())
+                .interact_text()?;
         }
     }
 
     Ok(())
 }
 
-pub fn handle_export(state: &AppState, file_name: &String) -> Result<()> {
+/// Options of `refrs export` besides where to write to.
+pub struct ExportOptions<'a> {
+    pub format: ExportFormat,
+    pub jabref_groups: bool,
+    pub since: Option<&'a str>,
+    pub sort: Option<ExportSort>,
+    pub split_by: Option<SplitBy>,
+    /// Only the entries matching this; its order is not used.
+    pub query: Query,
+}
+
+/// Exports to `file_name`, or with `split_by` to one file per group in `out_dir`.
+pub fn handle_export(
+    state: &AppState,
+    file_name: Option<&str>,
+    out_dir: Option<&str>,
+    options: ExportOptions,
+) -> Result<()> {
     // Ensure the state is initialized
     if !state.initialized {
         print_not_initialized();
@@ -94,62 +454,122 @@ pub fn handle_export(state: &AppState, file_name: &String) -> Result<()> {
 
     // Check if a project is selected
     if state.projects.is_empty() {
-        println!("{}", "No project selected.".blue().bold());
+        print_no_project_selected();
         return Ok(());
     }
 
+    if options.jabref_groups && options.format != ExportFormat::Bibtex {
+        bail!("--jabref-groups only applies to BibTeX exports");
+    }
+
     let project_path = &state.current_project;
-    let ris_folder = "ris_files";
+    let ris_folder = &library::ris_folder(project_path)?;
     let ris_folder_path = Path::new(project_path).join(ris_folder);
 
-    // Ensure the ris_files folder exists
     if !ris_folder_path.exists() {
-        println!("{}", "No ris_files folder found.".red().bold());
+        println!("{}", t!("export-no-folder", folder = ris_folder).red().bold());
         return Ok(());
     }
 
-    // Collect all .ris files in the folder
-    let mut bibtex_entries = String::new();
-
-    for entry in fs::read_dir(&ris_folder_path)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        // Process only .ris files
-        if let Some(extension) = path.extension() {
-            if extension == "ris" {
-                // Read the .ris file
-                let content = fs::read_to_string(&path)?;
-
-                // Parse the RIS content
-                match ris::parse_ris(&content) {
-                    Ok(entries) => {
-                        for ris_entry in entries {
-                            // Generate a unique entry key based on the file name
-                            let entry_key = path
-                                .file_stem()
-                                .and_then(|os_str| os_str.to_str())
-                                .unwrap_or("unknown");
-
-                            // Convert RIS entry to BibTeX
-                            let bibtex_entry = ris_entry_to_bibtex_string(&ris_entry, entry_key);
-                            bibtex_entries.push_str(&bibtex_entry);
-                            bibtex_entries.push('\n'); // Add a newline between entries
-                        }
-                    }
-                    Err(err) => {
-                        eprintln!("Error parsing RIS file {}: {}", path.display(), err);
-                    }
-                }
+    let mut entries = library::load_entries(project_path)?;
+    entries.retain(|entry| options.query.matches(&entry.entry));
+
+    if let Some(since) = options.since {
+        let changed: HashSet<String> = repo::changed_files_since(project_path, since, ris_folder)?
+            .iter()
+            .filter_map(|file| Path::new(file).file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+            .collect();
+        entries.retain(|entry| changed.contains(&entry.id));
+    }
+
+    // Entries are exported under their pinned citation key, while git knows
+    // them by file name.
+    let mut exported: Vec<(String, RisEntry)> = entries
+        .iter()
+        .map(|entry| (entry.key().to_string(), entry.entry.clone()))
+        .collect();
+    for (i, key) in export::unique_keys(&mut exported) {
+        println!(
+            "{}",
+            t!("export-duplicate-key", key = key.bold(), id = entries[i].id, exported = exported[i].0.bold()).yellow()
+        );
+    }
+
+    // A Markdown reference list reads best by author, whatever the default.
+    let sort = options.sort.unwrap_or(match options.format {
+        ExportFormat::Markdown => ExportSort::Author,
+        _ => state.export_sort,
+    });
+    let added = if sort == ExportSort::Added {
+        let times = repo::added_times(project_path, ris_folder)?;
+        entries
+            .iter()
+            .zip(&exported)
+            .filter_map(|(entry, (key, _))| Some((key.clone(), *times.get(&entry.id)?)))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    export::sort(&mut exported, sort, &added);
+
+    match (options.split_by, out_dir, file_name) {
+        (Some(split_by), Some(out_dir), _) => {
+            fs::create_dir_all(out_dir)?;
+            let groups = export::split(&exported, split_by);
+            let stems = export::file_stems(groups.iter().map(|(group, _)| group.as_str()));
+            for ((group, entries), stem) in groups.iter().zip(stems) {
+                let file = format!("{}.{}", stem, options.format.extension());
+                let path = Path::new(out_dir).join(file);
+                write_export(&path, entries, &options)?;
+                println!("{}", t!("export-group", count = format!("{:>4}", entries.len()), path = path.display(), group = group));
             }
+            println!("{}", t!("export-done-split", count = exported.len(), files = groups.len()));
+        }
+        (None, _, Some(file_name)) => {
+            let output_path = Path::new(file_name);
+            write_export(output_path, &exported, &options)?;
+            println!("{}", t!("export-done", count = exported.len(), path = output_path.display()));
         }
+        _ => println!("{}", t!("export-no-output").red().bold()),
     }
 
-    // Write the concatenated BibTeX entries to the specified file
-    let output_path = Path::new(file_name);
-    fs::write(output_path, bibtex_entries)?;
+    Ok(())
+}
+
+fn write_export(path: &Path, entries: &[(String, RisEntry)], options: &ExportOptions) -> Result<()> {
+    let mut output = export::render(options.format, entries);
+    if options.jabref_groups && options.format == ExportFormat::Bibtex {
+        let refs: Vec<_> = entries.iter().map(|(_, entry)| entry).collect();
+        output.push('\n');
+        output.push_str(&jabref::groups_comment(&refs));
+        output.push('\n');
+    }
+    fs::write(path, output)?;
+    Ok(())
+}
+
+/// Shows or sets the default entry order of `refrs export`.
+pub fn handle_export_sort(state: &mut AppState, sort: Option<ExportSort>) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
 
-    println!("BibTeX entries exported to {}", output_path.display());
+    let name = |sort: ExportSort| {
+        sort.to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default()
+    };
+
+    match sort {
+        Some(sort) => {
+            state.export_sort = sort;
+            save_state(state)?;
+            println!("{}", t!("export-sort-set", sort = name(sort).bold()));
+        }
+        None => println!("{}", t!("export-sort", sort = name(state.export_sort).bold())),
+    }
 
     Ok(())
 }
@@ -0,0 +1,97 @@
+use std::fs;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::Colorize;
+
+use crate::services::graph::{self, Graph};
+use crate::services::library;
+use crate::state::AppState;
+use crate::util::{print_no_project_selected, print_not_initialized};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    Dot,
+    Graphml,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CoauthorFormat {
+    Dot,
+    Csv,
+}
+
+pub fn handle_graph(
+    state: &AppState,
+    format: GraphFormat,
+    output: Option<&str>,
+    semantic_scholar: bool,
+) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let mut graph = Graph {
+        nodes: graph::reference_nodes(&entries),
+        edges: graph::coauthor_edges(&entries),
+    };
+    if semantic_scholar {
+        eprintln!("{}", t!("graph-fetching").blue());
+        graph.edges.extend(graph::citation_edges(&entries));
+    }
+
+    let rendered = match format {
+        GraphFormat::Dot => graph::to_dot(&graph),
+        GraphFormat::Graphml => graph::to_graphml(&graph),
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)?;
+            println!("{}", t!("graph-done", nodes = graph.nodes.len(), edges = graph.edges.len(), path = path));
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(())
+}
+
+pub fn handle_coauthors(state: &AppState, format: CoauthorFormat, output: Option<&str>) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let graph = Graph {
+        nodes: graph::author_nodes(&entries),
+        edges: graph::coauthorship_edges(&entries),
+    };
+
+    let rendered = match format {
+        CoauthorFormat::Dot => graph::to_dot(&graph),
+        CoauthorFormat::Csv => graph::to_csv(&graph),
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)?;
+            println!("{}", t!("coauthors-done", nodes = graph.nodes.len(), edges = graph.edges.len(), path = path));
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(())
+}
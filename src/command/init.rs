@@ -4,16 +4,11 @@ use colored::*;
 
 pub fn handle_init(state: &mut AppState, force: bool) -> Result<()> {
     if state.initialized && !force {
-        println!(
-            "{}",
-            "Refrs already initialized. Use option -f to reinitialize"
-                .yellow()
-                .bold()
-        );
+        println!("{}", t!("already-initialized").yellow().bold());
         return Ok(());
     }
 
-    println!("{}", "Initializing...".blue().bold());
+    println!("{}", t!("initializing").blue().bold());
     *state = AppState::default();
     state.initialized = true;
     save_state(state)?;
@@ -0,0 +1,113 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::Colorize;
+
+use crate::config;
+use crate::repo;
+use crate::services::citekey::{self, KeyFormat};
+use crate::services::library;
+use crate::services::serialization::{self, KEY_TAG};
+use crate::state::{save_state, AppState};
+use crate::util::{print_no_project_selected, print_not_initialized};
+
+/// Shows or sets the key format. A pattern implies `KeyFormat::Pattern`.
+pub fn handle_key_format(state: &mut AppState, format: Option<KeyFormat>, pattern: Option<&str>) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    let name = |state: &AppState| {
+        let name = state
+            .key_format
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        match state.key_format {
+            KeyFormat::Pattern => format!("{} {}", name, state.key_pattern()),
+            _ => name,
+        }
+    };
+
+    if let Some(pattern) = pattern {
+        citekey::validate_pattern(pattern)?;
+        state.key_pattern = Some(pattern.to_string());
+    }
+
+    match format.or(pattern.map(|_| KeyFormat::Pattern)) {
+        Some(format) => {
+            state.key_format = format;
+            save_state(state)?;
+            println!("{}", t!("key-format-set", format = name(state).bold()));
+        }
+        None => println!("{}", t!("key-format", format = name(state).bold())),
+    }
+
+    Ok(())
+}
+
+/// Pins a citation key in the `ID` tag of every entry that lacks one, or
+/// recomputes all keys with `regenerate`, and commits the changed files.
+pub fn handle_assign_keys(state: &AppState, regenerate: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let config = config::load(&state.current_project)?;
+    let (key_format, key_pattern) = config.key_scheme(state);
+    let keys: HashMap<usize, String> =
+        serialization::assign_keys(&entries, key_format, key_pattern, regenerate)
+            .into_iter()
+            .filter(|(i, key)| entries[*i].entry.get_field(KEY_TAG).map(|k| k.trim()) != Some(key.as_str()))
+            .collect();
+
+    // Files holding several entries are rewritten in full.
+    let mut written = HashSet::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let Some(key) = keys.get(&i) else {
+            continue;
+        };
+        println!("{} {} {}", entry.key().dimmed(), "->".dimmed(), key.bold());
+        if !written.insert(&entry.path) {
+            continue;
+        }
+        let content: Vec<String> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| other.path == entry.path)
+            .map(|(j, other)| {
+                let mut updated = other.entry.clone();
+                if let Some(key) = keys.get(&j) {
+                    updated.fields.insert(KEY_TAG.to_string(), vec![key.clone()]);
+                }
+                updated.to_string()
+            })
+            .collect();
+        fs::write(&entry.path, content.join("\n"))?;
+    }
+    let changed = keys.len();
+
+    if changed == 0 {
+        println!("{}", t!("keys-unchanged"));
+        return Ok(());
+    }
+
+    repo::add_all(&state.current_project)?;
+    repo::commit(
+        &state.current_project,
+        &format!("Assigned citation keys to {} entries", changed),
+    )?;
+    println!("{}", t!("keys-assigned", count = changed));
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::i18n::Language;
+use crate::state::{save_state, AppState};
+use crate::util::print_not_initialized;
+
+/// Shows or sets the language refrs prints its messages in.
+pub fn handle_language(state: &mut AppState, language: Option<Language>) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    match language {
+        Some(language) => {
+            state.language = Some(language);
+            save_state(state)?;
+            println!("{}", t!("language-set", language = language.to_string().bold()));
+        }
+        None => {
+            let language = state.language.map(|l| l.to_string()).unwrap_or_else(|| t!("language-from-locale"));
+            println!("{}", t!("language", language = language.bold()));
+        }
+    }
+
+    Ok(())
+}
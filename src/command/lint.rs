@@ -0,0 +1,104 @@
+use std::collections::BTreeSet;
+use std::fs;
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use crate::model::ris::parse_ris;
+use crate::repo;
+use crate::services::encoding;
+use crate::services::lint::{self, Issue, Problem};
+use crate::state::AppState;
+use crate::util::{print_no_project_selected, print_not_initialized};
+
+/// Checks every entry of the current project and lists the problems found.
+/// With `fix`, repairs what it can first and commits the changes. Fails when
+/// problems remain, so it can run in CI.
+pub fn handle_lint(state: &AppState, fix: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let mut issues = lint::check(&state.current_project)?;
+    if fix {
+        let fixed = fix_issues(&issues)?;
+        if !fixed.is_empty() {
+            repo::add_all(&state.current_project)?;
+            repo::commit(
+                &state.current_project,
+                &format!("Fixed lint issues in {}", fixed.iter().cloned().collect::<Vec<_>>().join(", ")),
+            )?;
+            println!("{}", t!("lint-fixed", count = fixed.len()).green().bold());
+            issues = lint::check(&state.current_project)?;
+        }
+    }
+
+    if issues.is_empty() {
+        println!("{}", t!("lint-ok").green().bold());
+        return Ok(());
+    }
+
+    let mut last_id = None;
+    for issue in &issues {
+        if last_id != Some(&issue.id) {
+            println!("{}", issue.id.bold());
+            last_id = Some(&issue.id);
+        }
+        let hint = if issue.problem.fixable() { format!(" ({})", t!("lint-fixable")) } else { String::new() };
+        println!("  {}{}", describe(&issue.problem).yellow(), hint.dimmed());
+    }
+
+    let fixable = issues.iter().filter(|issue| issue.problem.fixable()).count();
+    if fixable > 0 {
+        println!("{}", t!("lint-fix-hint", count = fixable));
+    }
+    bail!(t!("lint-issues", count = issues.len()));
+}
+
+/// Removes empty files and rewrites files with repairable entries. Returns
+/// the ids of the files changed.
+fn fix_issues(issues: &[Issue]) -> Result<BTreeSet<String>> {
+    let mut fixed = BTreeSet::new();
+    for issue in issues.iter().filter(|issue| issue.problem.fixable()) {
+        if fixed.contains(&issue.id) {
+            continue;
+        }
+        if issue.problem == Problem::EmptyFile {
+            fs::remove_file(&issue.path)?;
+            fixed.insert(issue.id.clone());
+            continue;
+        }
+
+        let (content, _) = encoding::read_text(&issue.path)?;
+        let mut entries = parse_ris(&content)?;
+        let mut changed = false;
+        for entry in &mut entries {
+            changed |= lint::fix(entry);
+        }
+        if changed {
+            let content: Vec<String> = entries.iter().map(|entry| entry.to_string()).collect();
+            fs::write(&issue.path, content.join("\n"))?;
+            fixed.insert(issue.id.clone());
+        }
+    }
+    Ok(fixed)
+}
+
+fn describe(problem: &Problem) -> String {
+    match problem {
+        Problem::Unreadable(error) => t!("lint-unreadable", error = error),
+        Problem::EmptyFile => t!("lint-empty-file"),
+        Problem::Missing(tag) => t!("lint-missing", tag = tag),
+        Problem::MalformedDoi(value) => t!("lint-malformed-doi", value = value),
+        Problem::MalformedIssn(value) => t!("lint-malformed-issn", value = value),
+        Problem::DuplicateKey { key, others } => {
+            t!("lint-duplicate-key", key = key, others = others.join(", "))
+        }
+    }
+}
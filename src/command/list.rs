@@ -0,0 +1,65 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::command::api::ApiReference;
+use crate::services::citations::CitationCount;
+use crate::services::library;
+use crate::services::query::{ListSort, Query};
+use crate::state::AppState;
+use crate::util::{print_json, print_no_project_selected, print_not_initialized, truncate};
+
+/// Prints the references of the current project matching `query` as a
+/// table, or with `json` as an array of references.
+pub fn handle_list(state: &AppState, query: &Query, json: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = query.run(library::load_entries(&state.current_project)?);
+    if json {
+        let references: Vec<ApiReference> = entries.into_iter().map(ApiReference::from).collect();
+        return print_json(&references);
+    }
+
+    if entries.is_empty() {
+        println!("{}", t!("list-none").blue().bold());
+        return Ok(());
+    }
+
+    // Sorted by citations, the counts are shown too.
+    let cited = query.sort == ListSort::Citations;
+    println!(
+        "{:<30} {:<8} {:<20} {:<6} {}{}",
+        t!("column-id").underline(),
+        t!("column-type").underline(),
+        t!("column-author").underline(),
+        t!("column-year").underline(),
+        if cited { format!("{:<7}", t!("column-cited").underline()) } else { String::new() },
+        t!("column-title").underline()
+    );
+    for e in &entries {
+        let entry = &e.entry;
+        let author = entry.get_field("AU").map(String::as_str).unwrap_or("-");
+        let year = entry.get_field("PY").map(String::as_str).unwrap_or("-");
+        let title = entry.get_field("TI").map(String::as_str).unwrap_or("-");
+        let count = CitationCount::of(entry).map(|count| count.count.to_string());
+        println!(
+            "{:<30} {:<8} {:<20} {:<6} {}{}",
+            truncate(&e.id, 30),
+            entry.ty.to_str(),
+            truncate(author, 20),
+            truncate(year, 6),
+            if cited { format!("{:<7}", count.as_deref().unwrap_or("-")) } else { String::new() },
+            truncate(title, 50)
+        );
+    }
+    println!("{}", t!("list-count", count = entries.len()));
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::io::IsTerminal;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::command::files::{self, ImportSource};
+use crate::services::crossref::{self, Work};
+use crate::state::AppState;
+use crate::util::{print_no_project_selected, print_not_initialized};
+
+/// How many of Crossref's matches to offer.
+const MATCHES: usize = 10;
+
+/// Searches Crossref for `query`, usually a remembered title, lets the user
+/// pick one of the matches and imports it.
+pub fn handle_lookup(state: &AppState, query: &str) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    println!("{}", t!("lookup-searching", query = query.bold()).blue());
+    let works = crossref::search_works(query, MATCHES)?;
+    if works.is_empty() {
+        println!("{}", t!("lookup-none").yellow().bold());
+        return Ok(());
+    }
+
+    let items: Vec<String> = works.iter().map(describe).collect();
+    if !std::io::stdin().is_terminal() {
+        for (work, item) in works.iter().zip(&items) {
+            println!("{}  {}", work.doi.bold(), item);
+        }
+        println!("{}", t!("lookup-pick-hint"));
+        return Ok(());
+    }
+
+    let selected = dialoguer::Select::new()
+        .with_prompt(t!("lookup-prompt"))
+        .items(&items)
+        .default(0)
+        .interact_opt()?;
+    let Some(selected) = selected else {
+        return Ok(());
+    };
+    files::handle_import(state, Some(ImportSource::Doi(&works[selected].doi)), true, None, None, false, false)
+}
+
+/// `Title — Author, Author et al. (1992) Journal`
+fn describe(work: &Work) -> String {
+    let title = work.title.first().map(String::as_str).unwrap_or("Untitled");
+    let authors = work.authors();
+    let mut line = match authors.as_slice() {
+        [] => title.to_string(),
+        [first] => format!("{} — {}", title, first),
+        [first, second] => format!("{} — {}, {}", title, first, second),
+        [first, second, ..] => format!("{} — {}, {} et al.", title, first, second),
+    };
+    if let Some(year) = work.year() {
+        line.push_str(&format!(" ({})", year));
+    }
+    if let Some(container) = work.container_title.first() {
+        line.push_str(&format!(" {}", container.dimmed()));
+    }
+    line
+}
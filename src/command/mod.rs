@@ -5,3 +5,33 @@ pub mod workspace;
 pub mod update;
 pub mod files;
 pub mod serve;
+pub mod api;
+pub mod dedupe;
+pub mod open;
+pub mod cite;
+pub mod edit;
+pub mod archive;
+pub mod graph;
+pub mod keys;
+pub mod language;
+pub mod notes;
+pub mod styles;
+pub mod daemon;
+pub mod pandoc;
+pub mod remind;
+pub mod selftest;
+pub mod venues;
+pub mod search;
+pub mod list;
+pub mod remove;
+pub mod tag;
+pub mod attach;
+pub mod render;
+pub mod auth;
+pub mod branch;
+pub mod tui;
+pub mod config;
+pub mod completions;
+pub mod lint;
+pub mod lookup;
+pub mod citations;
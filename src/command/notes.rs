@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::services::library;
+use crate::services::notes::{self, NoteStatus};
+use crate::state::AppState;
+use crate::util::{print_no_project_selected, print_not_initialized};
+
+pub fn handle_generate_obsidian(state: &AppState, vault_dir: &str, template: Option<&str>) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let template = match template {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("Failed to read template {}", path))?,
+        None => notes::DEFAULT_OBSIDIAN_TEMPLATE.to_string(),
+    };
+
+    let vault = Path::new(vault_dir);
+    fs::create_dir_all(vault)?;
+
+    let (mut created, mut updated, mut unchanged) = (0, 0, 0);
+    for reference in library::load_entries(&state.current_project)? {
+        let note = notes::render_template(&template, &reference.id, &reference.entry);
+        let path = vault.join(format!("{}.md", reference.id));
+
+        match notes::sync_note(&path, &note)? {
+            NoteStatus::Created => created += 1,
+            NoteStatus::Updated => updated += 1,
+            NoteStatus::Unchanged => unchanged += 1,
+            NoteStatus::Skipped => {
+                println!("{}{}", t!("warning").yellow().bold(), t!("notes-skipped", path = path.display()))
+            }
+        }
+    }
+
+    println!(
+        "{}",
+        t!("notes-done", vault = vault.display(), created = created, updated = updated, unchanged = unchanged)
+    );
+
+    Ok(())
+}
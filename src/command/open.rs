@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::model::reference::Reference;
+use crate::services::open_with::{self, Target};
+use crate::services::{library, openurl};
+use crate::state::{save_state, AppState};
+use crate::util::{print_no_project_selected, print_not_initialized, resolve_reference};
+
+pub fn handle_open(state: &AppState, id: Option<&str>, library: bool, attachment: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let Some(reference) = resolve_reference(&entries, id)? else {
+        return Ok(());
+    };
+
+    if library {
+        let Some(resolver) = &state.openurl_resolver else {
+            println!("{}", t!("openurl-none-hint", command = "refrs openurl <base-url>".bold()));
+            return Ok(());
+        };
+        let link = Target::Library(openurl::build_link(resolver, &reference.entry));
+        return open_target(state, &link);
+    }
+
+    if attachment {
+        let Some(file) = reference.entry.get_field("L1") else {
+            println!("{}", t!("open-no-attachment", id = reference.id.bold()));
+            return Ok(());
+        };
+        let file = Target::File(Path::new(&state.current_project).join(file));
+        return open_target(state, &file);
+    }
+
+    match open_with::link_target(&reference.entry) {
+        Some(link) => open_target(state, &link),
+        None => {
+            println!("{}", t!("open-no-link", id = reference.id.bold()));
+            Ok(())
+        }
+    }
+}
+
+/// Opens the PDF attached to a reference with `refrs attach`.
+pub fn handle_open_pdf(state: &AppState, id: Option<&str>) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let Some(entry) = resolve_reference(&entries, id)? else {
+        return Ok(());
+    };
+
+    let reference = Reference::new(&entry.id, &entry.path.to_string_lossy(), &entry.entry);
+    let Some(pdf) = reference.pdf() else {
+        let command = format!("refrs attach {} <file.pdf>", reference.id);
+        println!("{}", t!("open-no-pdf", id = reference.id.bold(), command = command.bold()));
+        return Ok(());
+    };
+    open_target(state, &Target::File(Path::new(&state.current_project).join(pdf)))
+}
+
+fn open_target(state: &AppState, target: &Target) -> Result<()> {
+    println!("{} {}", t!("open-opening").green().bold(), target.location().underline());
+    open_with::open(target, &state.open_with)
+}
+
+/// Shows, sets or removes the command used to open a kind of link or attachment.
+pub fn handle_open_with(
+    state: &mut AppState,
+    kind: Option<&str>,
+    command: Option<&str>,
+    clear: bool,
+) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    let Some(kind) = kind.map(|k| k.trim().trim_start_matches('.').to_lowercase()) else {
+        if state.open_with.is_empty() {
+            println!("{}", t!("open-with-none"));
+        }
+        for (kind, command) in &state.open_with {
+            println!("{:<10} {}", kind.bold(), command);
+        }
+        return Ok(());
+    };
+
+    if clear {
+        state.open_with.remove(&kind);
+        save_state(state)?;
+        println!("{}", t!("open-with-cleared", kind = kind.bold()));
+    } else if let Some(command) = command {
+        state.open_with.insert(kind.clone(), command.trim().to_string());
+        save_state(state)?;
+        println!("{}", t!("open-with", kind = kind.bold(), command = command.bold()));
+    } else {
+        match state.open_with.get(&kind) {
+            Some(command) => println!("{}", t!("open-with", kind = kind.bold(), command = command.bold())),
+            None => println!("{}", t!("open-with-default", kind = kind.bold())),
+        }
+    }
+
+    Ok(())
+}
+
+/// Shows or sets the OpenURL resolver used for "find at my library" links.
+pub fn handle_openurl(state: &mut AppState, base_url: Option<&str>, clear: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if clear {
+        state.openurl_resolver = None;
+        save_state(state)?;
+        println!("{}", t!("openurl-cleared"));
+    } else if let Some(base_url) = base_url {
+        state.openurl_resolver = Some(base_url.trim().to_string());
+        save_state(state)?;
+        println!("{}", t!("openurl-set", url = base_url.bold()));
+    } else {
+        match &state.openurl_resolver {
+            Some(resolver) => println!("{}", t!("openurl", url = resolver.bold())),
+            None => println!("{}", t!("openurl-none")),
+        }
+    }
+
+    Ok(())
+}
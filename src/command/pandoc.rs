@@ -0,0 +1,34 @@
+use std::io::{Read, Write};
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+
+use crate::services::{library, pandoc};
+use crate::state::AppState;
+
+/// Pandoc JSON filter: reads a document on stdin, resolves `@key` citations
+/// against the current project and writes the document back to stdout.
+/// Nothing but the document may go to stdout, so problems are reported on
+/// stderr or as errors.
+pub fn handle_citeproc_filter(state: &AppState) -> Result<()> {
+    if !state.initialized || state.current_project.is_empty() {
+        bail!("refrs has no project selected; run `refrs workspace set` first");
+    }
+
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    let mut document: serde_json::Value =
+        serde_json::from_str(&input).context("Expected a Pandoc JSON document on stdin")?;
+
+    let options = pandoc::BibliographyOptions::from_meta(&document["meta"])?;
+    let entries = library::load_entries(&state.current_project)?;
+    let missing = pandoc::resolve_citations(&mut document, &entries, &options);
+    for key in missing {
+        eprintln!("{}{}", t!("warning").yellow().bold(), t!("pandoc-key-not-found", key = key));
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer(&mut stdout, &document)?;
+    stdout.flush()?;
+    Ok(())
+}
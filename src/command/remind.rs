@@ -0,0 +1,61 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::repo;
+use crate::services::daemon;
+use crate::services::library;
+use crate::services::remind::{self, Reminder};
+use crate::state::AppState;
+use crate::util::{print_no_project_selected, print_not_initialized};
+
+/// Lists entries marked reading for more than `days` days and unread
+/// high-priority entries. With `cron`, prints plain lines and nothing at all
+/// when there is nothing to report, so cron only mails when needed.
+pub fn handle_remind(state: &AppState, days: i64, cron: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let marked_line = format!("KW  - {}", remind::READING);
+    let marked = repo::last_changed_times(&state.current_project, &library::ris_folder(&state.current_project)?, &marked_line)?;
+    let reminders = remind::reminders(&entries, &marked, daemon::now() as i64, days);
+
+    if reminders.is_empty() {
+        if !cron {
+            println!("{}", t!("nothing-to-remind").green());
+        }
+        return Ok(());
+    }
+
+    for reminder in &reminders {
+        match (reminder, cron) {
+            (Reminder::StaleReading { id, title, days }, false) => println!(
+                "{} {} {}",
+                t!("reading-for-days", days = days).yellow().bold(),
+                id.bold(),
+                title
+            ),
+            (Reminder::StaleReading { id, title, days }, true) => {
+                println!("{} {} {}", t!("reading-for-days", days = days), id, title)
+            }
+            (Reminder::UnreadPriority { id, title }, false) => println!(
+                "{} {} {}",
+                t!("unread-high-priority").red().bold(),
+                id.bold(),
+                title
+            ),
+            (Reminder::UnreadPriority { id, title }, true) => {
+                println!("{} {} {}", t!("unread-high-priority"), id, title)
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::{Component, Path};
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::repo;
+use crate::services::library::{self, LibraryEntry};
+use crate::state::AppState;
+use crate::util::{describe_entry, print_no_project_selected, print_not_initialized, resolve_reference};
+
+/// Removes a reference from its `.ris` file, deleting the file once it holds
+/// no other entries, and the attachments no other reference uses, and
+/// commits the deletion. `id` may also be the file name.
+pub fn handle_remove(state: &AppState, id: Option<&str>, yes: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    // Accept `ris_files/doe_2020.ris` as well as `doe_2020`.
+    let id = id.map(|id| {
+        Path::new(id)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(id)
+    });
+
+    let entries = library::load_entries(&state.current_project)?;
+    let Some(reference) = resolve_reference(&entries, id)? else {
+        return Ok(());
+    };
+
+    let attachments = removable_attachments(&entries, &[reference]);
+    println!("{}  {}", reference.id.bold(), describe_entry(&reference.entry));
+    for attachment in &attachments {
+        println!("  {} {}", t!("remove-and").dimmed(), attachment);
+    }
+
+    if !yes {
+        let remove = dialoguer::Confirm::new()
+            .with_prompt(t!("remove-confirm", id = reference.id))
+            .default(false)
+            .interact()?;
+        if !remove {
+            return Ok(());
+        }
+    }
+
+    remove_reference(&state.current_project, &entries, reference)?;
+    println!("{}", t!("remove-done", id = reference.id).green().bold());
+
+    Ok(())
+}
+
+/// Removes the reference and its unshared attachments, and commits.
+pub fn remove_reference(project_path: &str, entries: &[LibraryEntry], reference: &LibraryEntry) -> Result<()> {
+    delete_entries(project_path, entries, &[reference])?;
+    repo::add_all(project_path)?;
+    repo::commit(project_path, &format!("Removed {}.ris", reference.id))
+}
+
+/// Deletes several references in one commit.
+pub fn remove_references(project_path: &str, entries: &[LibraryEntry], references: &[&LibraryEntry]) -> Result<()> {
+    delete_entries(project_path, entries, references)?;
+    let names: Vec<String> = references.iter().map(|r| format!("{}.ris", r.id)).collect();
+    repo::add_all(project_path)?;
+    repo::commit(project_path, &format!("Removed {}", names.join(", ")))
+}
+
+/// Takes the references out of their files, which are deleted once empty,
+/// then deletes the attachments only they used.
+fn delete_entries(project_path: &str, entries: &[LibraryEntry], references: &[&LibraryEntry]) -> Result<()> {
+    for reference in references {
+        library::replace_entry(reference, None)?;
+    }
+    for attachment in removable_attachments(entries, references) {
+        let path = Path::new(project_path).join(attachment);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Attachments (`L1`) of the references inside the project that no other
+/// reference points to, including ones in the same file. Files elsewhere on
+/// disk are never deleted.
+fn removable_attachments<'a>(entries: &[LibraryEntry], references: &[&'a LibraryEntry]) -> Vec<&'a String> {
+    let removed = |e: &LibraryEntry| references.iter().any(|reference| std::ptr::eq(*reference, e));
+    let used_elsewhere = |file: &String| {
+        entries
+            .iter()
+            .filter(|e| !removed(e))
+            .any(|e| e.entry.fields.get("L1").is_some_and(|files| files.contains(file)))
+    };
+    let inside_project = |file: &String| {
+        Path::new(file)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    };
+
+    let mut files: Vec<&String> = references
+        .iter()
+        .flat_map(|reference| reference.entry.fields.get("L1").into_iter().flatten())
+        .filter(|file| inside_project(file) && !used_elsewhere(file))
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
@@ -0,0 +1,38 @@
+use std::fs;
+
+use anyhow::Result;
+
+use crate::services::library;
+use crate::services::render::{self, RenderFormat};
+use crate::state::AppState;
+use crate::util::{print_no_project_selected, print_not_initialized};
+
+/// Formats the current project as a bibliography in a CSL style.
+pub fn handle_render(state: &AppState, style: &str, format: RenderFormat, output: Option<&str>) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let style = render::load_style(style)?;
+    let entries: Vec<_> = library::load_entries(&state.current_project)?
+        .into_iter()
+        .map(|entry| (entry.key().to_string(), entry.entry))
+        .collect();
+    let rendered = render::render(&style, &entries, format)?;
+
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)?;
+            println!("{}", t!("render-done", count = entries.len(), path = path));
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(())
+}
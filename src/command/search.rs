@@ -0,0 +1,180 @@
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::command::api::ApiReference;
+use crate::model::reference::ATTACHMENT_TAG;
+use crate::services::fulltext::{self, FulltextIndex};
+use crate::services::library::{self, LibraryEntry};
+use crate::services::pdf;
+use crate::services::search::{self, Hit};
+use crate::state::AppState;
+use crate::util::{describe_entry, print_json, print_no_project_selected, print_not_initialized};
+
+/// Characters of a long field such as the abstract shown around a match.
+const EXCERPT_WIDTH: usize = 100;
+
+/// Characters of an attachment's text kept around a match, for the JSON.
+const FULLTEXT_EXCERPT_WIDTH: usize = 300;
+
+/// A found reference as JSON.
+#[derive(Serialize)]
+struct SearchResult<'a> {
+    project: &'a str,
+    #[serde(flatten)]
+    reference: ApiReference,
+    hits: Vec<HitJson>,
+}
+
+#[derive(Serialize)]
+struct HitJson {
+    field: &'static str,
+    value: String,
+    /// Byte ranges of the matched terms in `value`
+    matches: Vec<Range<usize>>,
+}
+
+/// Lists references whose title, authors, abstract or keywords contain every
+/// word of `query`, in the current project or, with `all`, in every project.
+/// With `fulltext`, also those whose attached PDFs contain every word.
+pub fn handle_search(state: &AppState, query: &str, all: bool, fulltext: bool, json: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    let projects: Vec<&str> = if all {
+        state.projects.iter().map(|p| p.absolute_path.as_str()).collect()
+    } else if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    } else {
+        vec![state.current_project.as_str()]
+    };
+
+    let terms = search::terms(query);
+    if json {
+        let mut results = Vec::new();
+        for project in projects {
+            for (entry, hits) in find(project, &terms, fulltext)? {
+                let hits = hits
+                    .into_iter()
+                    .map(|hit| HitJson {
+                        field: hit.label,
+                        value: hit.value,
+                        matches: hit.ranges,
+                    })
+                    .collect();
+                results.push(SearchResult {
+                    project,
+                    reference: entry.into(),
+                    hits,
+                });
+            }
+        }
+        return print_json(&results);
+    }
+
+    let mut found = 0;
+    for project in projects {
+        let mut header_printed = false;
+        for (entry, hits) in find(project, &terms, fulltext)? {
+            if all && !header_printed {
+                println!("{}", project.green().bold());
+                header_printed = true;
+            }
+            found += 1;
+            println!("{}  {}", entry.id.bold(), describe_entry(&entry.entry).dimmed());
+            for hit in &hits {
+                print_hit(hit);
+            }
+        }
+    }
+
+    if found == 0 {
+        println!("{}", t!("search-none", query = query).yellow().bold());
+    } else {
+        println!("{}", t!("search-found", count = found));
+    }
+    Ok(())
+}
+
+/// The entries of a project matching every term, with where they match.
+fn find(project: &str, terms: &[String], fulltext: bool) -> Result<Vec<(LibraryEntry, Vec<Hit>)>> {
+    let entries = library::load_entries(project)?;
+    let index = if fulltext { Some(update_index(project, &entries)?) } else { None };
+    let in_text = index.as_ref().map(|index| index.search(terms)).unwrap_or_default();
+
+    let mut found = Vec::new();
+    for entry in entries {
+        let hits = search::search(&entry.entry, terms);
+        let text_hits: Vec<Hit> = attachments(&entry)
+            .filter(|path| in_text.contains(*path))
+            .filter_map(|path| text_hit(project, index.as_ref()?, path, terms))
+            .collect();
+        if hits.is_none() && text_hits.is_empty() {
+            continue;
+        }
+        let mut hits = hits.unwrap_or_default();
+        hits.extend(text_hits);
+        found.push((entry, hits));
+    }
+    Ok(found)
+}
+
+/// Brings the project's full-text index up to date with its attached PDFs.
+fn update_index(project: &str, entries: &[LibraryEntry]) -> Result<FulltextIndex> {
+    let pdfs: Vec<String> = entries.iter().flat_map(attachments).cloned().collect();
+
+    let path = fulltext::index_path(project);
+    let mut index = FulltextIndex::load(&path);
+    if index.update(project, &pdfs) > 0 {
+        index.save(&path)?;
+    }
+    Ok(index)
+}
+
+/// The attached PDFs of an entry.
+fn attachments(entry: &LibraryEntry) -> impl Iterator<Item = &String> {
+    entry
+        .entry
+        .fields
+        .get(ATTACHMENT_TAG)
+        .into_iter()
+        .flatten()
+        .filter(|path| path.to_lowercase().ends_with(".pdf"))
+}
+
+/// The text of an attachment around the first match of the terms, as the
+/// index keeps it.
+fn text_hit(project: &str, index: &FulltextIndex, path: &str, terms: &[String]) -> Option<Hit> {
+    let text = match index.text(path) {
+        Some(text) => text,
+        None => pdf::read_text(&Path::new(project).join(path)).ok()?.split_whitespace().collect::<Vec<_>>().join(" "),
+    };
+    let ranges: Vec<Range<usize>> = terms.iter().flat_map(|term| search::find_ranges(&text, term)).collect();
+    let hit = Hit { label: "text", value: text, ranges: search::merge(ranges) };
+    let (value, ranges) = hit.excerpt(FULLTEXT_EXCERPT_WIDTH);
+    Some(Hit { label: hit.label, value, ranges })
+}
+
+fn print_hit(hit: &Hit) {
+    let (text, ranges) = hit.excerpt(EXCERPT_WIDTH);
+    println!("    {:<9} {}", format!("{}:", hit.label).dimmed(), highlight(&text, &ranges));
+}
+
+/// The text with the given byte ranges in bold yellow.
+fn highlight(text: &str, ranges: &[Range<usize>]) -> String {
+    let mut result = String::new();
+    let mut last = 0;
+    for range in ranges {
+        result.push_str(&text[last..range.start]);
+        result.push_str(&text[range.clone()].yellow().bold().to_string());
+        last = range.end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
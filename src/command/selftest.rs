@@ -0,0 +1,55 @@
+use anyhow::{bail, Result};
+use biblatex::Bibliography;
+use colored::Colorize;
+
+use crate::model::ris::parse_ris;
+use crate::services::library;
+use crate::services::roundtrip::{self, Loss};
+use crate::state::AppState;
+
+/// Round-trips the built-in corpus and every entry of the current project
+/// through BibTeX and RIS, and reports each field that does not survive.
+pub fn handle_selftest(state: &AppState) -> Result<()> {
+    let mut checked = 0;
+    let mut lossy: Vec<(String, Vec<Loss>)> = Vec::new();
+    let mut record = |name: String, losses: Vec<Loss>| {
+        checked += 1;
+        if !losses.is_empty() {
+            lossy.push((name, losses));
+        }
+    };
+
+    for (i, entry) in parse_ris(roundtrip::CORPUS_RIS)?.iter().enumerate() {
+        record(format!("corpus RIS #{}", i + 1), roundtrip::ris_round_trip(entry)?);
+    }
+    let corpus = Bibliography::parse(roundtrip::CORPUS_BIBTEX)
+        .map_err(|e| anyhow::anyhow!("Invalid BibTeX corpus: {}", e))?;
+    for entry in corpus.iter() {
+        record(format!("corpus BibTeX {}", entry.key), roundtrip::bibtex_round_trip(entry)?);
+    }
+
+    if state.initialized && !state.current_project.is_empty() {
+        for entry in library::load_entries(&state.current_project)? {
+            record(entry.id.clone(), roundtrip::ris_round_trip(&entry.entry)?);
+        }
+    }
+
+    for (name, losses) in &lossy {
+        println!("{}", name.bold());
+        for loss in losses {
+            println!(
+                "  {} {}: {:?} became {:?}",
+                loss.path.dimmed(),
+                loss.field.yellow(),
+                loss.before,
+                loss.after
+            );
+        }
+    }
+
+    if !lossy.is_empty() {
+        bail!(t!("selftest-lossy", lossy = lossy.len(), checked = checked));
+    }
+    println!("{}", t!("selftest-ok", checked = checked).green().bold());
+    Ok(())
+}
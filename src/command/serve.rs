@@ -1,41 +1,160 @@
+use super::{api, attach, remove};
 use crate::{
-    services::serialization,
+    config,
+    model::ris::{self, ReferenceType, RisEntry},
+    repo::{self, GitAuth},
+    services::{
+        citekey::KeyFormat,
+        diff,
+        export::{self, ExportFormat},
+        graph,
+        library::{self, LibraryEntry},
+        merge::{self, Side},
+        open_with,
+        openurl,
+        pdf,
+        query::{self, ListSort},
+        rate_limit::RateLimiter,
+        search,
+        serialization,
+        tags,
+    },
     state::AppState,
-    util::{print_not_initialized, read_ris_files_from_dir},
+    util::{print_no_project_selected, print_not_initialized},
 };
 use anyhow::Result;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::{Html, IntoResponse},
+    body::Bytes,
+    extract::{ConnectInfo, DefaultBodyLimit, Path, Query, State},
+    http::{header, Method, Request, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Redirect, Response,
+    },
     routing::{get, post},
-    Form, Router,
+    Form, Json, Router,
 };
-use colored::Colorize;
+use clap::ValueEnum;
+use futures_util::Stream;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
 
 /// Shared state for all handlers.
 /// You can store additional fields as needed.
 #[derive(Clone)]
-struct AppData {
-    project_path: String,
+pub(crate) struct AppData {
+    pub(crate) project_path: String,
+    pub(crate) key_format: KeyFormat,
+    pub(crate) key_pattern: String,
+    pub(crate) openurl_resolver: Option<String>,
+    pub(crate) open_with: BTreeMap<String, String>,
+    pub(crate) git_auth: GitAuth,
+    /// Fingerprint of the library's files, updated by [`watch_library`]
+    pub(crate) library_changes: watch::Receiver<u64>,
 }
 
+#[derive(Deserialize)]
+struct IndexQuery {
+    tag: Option<String>,
+    q: Option<String>,
+    page: Option<usize>,
+    per_page: Option<usize>,
+    /// `author`, `title`, `year` or `id`
+    sort: Option<String>,
+    /// `asc` or `desc`
+    order: Option<String>,
+}
+
+impl IndexQuery {
+    /// The requested order; unknown values fall back to the id order.
+    fn sort_order(&self) -> (ListSort, bool) {
+        let sort = self
+            .sort
+            .as_deref()
+            .and_then(|sort| ListSort::from_str(sort, true).ok())
+            .unwrap_or_default();
+        (sort, self.order.as_deref() == Some("desc"))
+    }
+
+    /// The query string for another page of the same listing.
+    fn page_link(&self, page: usize, per_page: usize) -> String {
+        let (sort, descending) = self.sort_order();
+        self.link(sort, descending, page, per_page)
+    }
+
+    /// The query string for the same filters in another order or page.
+    fn link(&self, sort: ListSort, descending: bool, page: usize, per_page: usize) -> String {
+        let mut link = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(tag) = &self.tag {
+            link.append_pair("tag", tag);
+        }
+        if let Some(q) = self.q.as_deref().filter(|q| !q.trim().is_empty()) {
+            link.append_pair("q", q);
+        }
+        if sort != ListSort::default() {
+            link.append_pair("sort", &sort_name(sort));
+        }
+        if descending {
+            link.append_pair("order", "desc");
+        }
+        if page > 1 {
+            link.append_pair("page", &page.to_string());
+        }
+        if per_page != DEFAULT_PER_PAGE {
+            link.append_pair("per_page", &per_page.to_string());
+        }
+        format!("/?{}", link.finish())
+    }
+}
+
+fn sort_name(sort: ListSort) -> String {
+    sort.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default()
+}
+
+/// Rows shown per page of the index unless `?per_page=` says otherwise.
+const DEFAULT_PER_PAGE: usize = 50;
+const MAX_PER_PAGE: usize = 500;
+
+/// Fields the search box on the index looks in.
+const INDEX_SEARCH_FIELDS: [&str; 4] = ["AU", "TI", "PY", "KW"];
+
 /// GET /
 /// Show the list of references from ris_folder, with an "Edit" button for each item,
-/// plus "Upload" and "Update" buttons at the top.
+/// plus "Upload" and "Update" buttons at the top. `?tag=<tag>` shows only entries with that tag,
+/// and `?q=<terms>` only those with every term in their author, title, year or keywords.
+/// The table is split into pages of `?per_page=` rows; `?page=` starts at 1.
+/// `?sort=author|title|year&order=desc` orders it before paging.
 async fn index_handler(
     State(app_data): State<AppData>,
+    Query(filter): Query<IndexQuery>,
 ) -> Result<Html<String>, (StatusCode, String)> {
-    let ris_entries = read_ris_files_from_dir(&format!("{}/ris_files", app_data.project_path))
-        .unwrap_or_default();
+    let library_entries = library::load_entries(&app_data.project_path).unwrap_or_default();
+    let query = filter.q.as_deref().unwrap_or("").trim();
+    let terms = search::terms(query);
+    let mut shown: Vec<&LibraryEntry> = library_entries
+        .iter()
+        .filter(|reference| filter.tag.as_ref().is_none_or(|tag| tags::has_tag(&reference.entry, tag)))
+        .filter(|reference| search::matches_all(&reference.entry, &terms, &INDEX_SEARCH_FIELDS))
+        .collect();
+    let (sort, descending) = filter.sort_order();
+    query::sort_entries(&mut shown, sort, descending);
+    let per_page = filter.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+    let pages = shown.len().div_ceil(per_page).max(1);
+    let page = filter.page.unwrap_or(1).clamp(1, pages);
+    let matching = shown.len();
 
     // Start building the HTML.
     // This page has:
     // 1) "Upload File" button that goes to /upload
     // 2) "Update" button that sends POST to /update
-    // 3) Table of references with "Edit" button linking to /edit/<some_id>
+    // 3) Table of references with "Edit" button linking to /edit/<id>
 
     let mut html = String::new();
     html.push_str(
@@ -44,7 +163,12 @@ async fn index_handler(
         <head>
             <meta charset="UTF-8">
             <title>RIS / BibTeX Viewer</title>
-  <script src="https://cdn.tailwindcss.com"></script>
+  <link rel="stylesheet" href="/static/style.css" />
+  "#,
+    );
+    html.push_str(COPY_BIBTEX_SCRIPT);
+    html.push_str(
+        r#"
         </head>
         <body class="bg-gray-900 text-gray-100 min-h-screen">
             <header class="p-4 bg-gray-800 shadow-md mb-6">
@@ -53,6 +177,8 @@ async fn index_handler(
                 <div class="flex justify-center gap-4">
                     <a href="/add" class="bg-orange-600 hover:bg-orange-700 text-white py-2 px-4 rounded">Add RIS/BibTeX</a>
                     <a href="/upload" class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded">Upload File</a>
+                    <a href="/dedupe" class="bg-yellow-600 hover:bg-yellow-700 text-white py-2 px-4 rounded">Duplicates</a>
+                    <a href="/graph" class="bg-teal-600 hover:bg-teal-700 text-white py-2 px-4 rounded">Graph</a>
                     <form action="/update" method="post">
                         <button type="submit" class="bg-green-600 hover:bg-green-700 text-white py-2 px-4 rounded">
                             Update
@@ -64,49 +190,160 @@ async fn index_handler(
             <main class="max-w-6xl mx-auto px-4">
                 <section class="mb-6">
                     <h2 class="text-xl font-semibold border-b border-gray-700 pb-2 mb-4">RIS File Table</h2>
+    "#,
+    );
+
+    let mut hidden_inputs = filter
+        .tag
+        .as_ref()
+        .map(|tag| format!(r#"<input type="hidden" name="tag" value="{}" />"#, html_escape(tag)))
+        .unwrap_or_default();
+    if sort != ListSort::default() {
+        hidden_inputs.push_str(&format!(r#"<input type="hidden" name="sort" value="{}" />"#, sort_name(sort)));
+    }
+    if descending {
+        hidden_inputs.push_str(r#"<input type="hidden" name="order" value="desc" />"#);
+    }
+    if per_page != DEFAULT_PER_PAGE {
+        hidden_inputs.push_str(&format!(r#"<input type="hidden" name="per_page" value="{per_page}" />"#));
+    }
+    html.push_str(&format!(
+        r#"
+                    <form action="/" method="get" class="flex gap-2 mb-4">
+                        {hidden_inputs}
+                        <input type="search" name="q" value="{query}" placeholder="Search author, title, year or keyword" class="flex-grow text-gray-200 bg-gray-700 p-2 rounded" />
+                        <button type="submit" class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded">Search</button>
+                    </form>
+        "#,
+        query = html_escape(query),
+    ));
+    html.push_str(
+        r#"
+                    <form id="bulk" method="post" class="flex gap-2 mb-4">
+                        <button type="submit" name="format" value="bibtex" formaction="/bulk/export" class="bg-gray-700 hover:bg-gray-600 text-white py-1 px-3 rounded">Export selected as BibTeX</button>
+                        <button type="submit" name="format" value="ris" formaction="/bulk/export" class="bg-gray-700 hover:bg-gray-600 text-white py-1 px-3 rounded">Export selected as RIS</button>
+                        <button type="submit" formaction="/bulk/delete" onclick="return confirm('Remove the selected references and their attachments?')" class="bg-red-800 text-white py-1 px-3 rounded">Delete selected</button>
+                    </form>
+        "#,
+    );
+    if !terms.is_empty() {
+        html.push_str(&format!(
+            r#"<p class="mb-4 text-gray-400">{} of {} references match <a href="/" class="text-blue-400 hover:underline ml-2">Clear</a></p>"#,
+            matching,
+            library_entries.len()
+        ));
+    }
+
+    if let Some(tag) = &filter.tag {
+        html.push_str(&format!(
+            r#"<p class="mb-4 text-gray-400">Tagged <span class="bg-indigo-700 text-white px-2 py-0.5 rounded">{}</span> <a href="/" class="text-blue-400 hover:underline ml-2">Show all</a></p>"#,
+            html_escape(tag)
+        ));
+    }
+
+    let sortable_header = |label: &str, column: ListSort| {
+        let active = sort == column;
+        let descending_link = active && !descending;
+        let arrow = match (active, descending) {
+            (false, _) => "",
+            (true, false) => " ▲",
+            (true, true) => " ▼",
+        };
+        format!(
+            r#"<th class="px-4 py-3 text-left font-medium uppercase tracking-wider text-gray-200"><a href="{}" class="hover:underline">{label}{arrow}</a></th>"#,
+            html_escape(&filter.link(column, descending_link, 1, per_page))
+        )
+    };
+    let author_header = sortable_header("Author", ListSort::Author);
+    let title_header = sortable_header("Title", ListSort::Title);
+    let year_header = sortable_header("Year", ListSort::Year);
+    html.push_str(&format!(
+        r#"
                     <div class="overflow-x-auto rounded-lg shadow-lg">
                         <table class="min-w-full border-collapse">
                             <thead class="bg-gray-800 border-b border-gray-700">
                                 <tr>
-                                    <th class="px-4 py-3 text-left font-medium uppercase tracking-wider text-gray-200">Author</th>
-                                    <th class="px-4 py-3 text-left font-medium uppercase tracking-wider text-gray-200">Title</th>
-                                    <th class="px-4 py-3 text-left font-medium uppercase tracking-wider text-gray-200">Year</th>
+                                    <th class="px-4 py-3 text-left"><input type="checkbox" title="Select all" onclick="document.querySelectorAll('input[name=ids]').forEach(box => box.checked = this.checked)" /></th>
+                                    {author_header}
+                                    {title_header}
+                                    {year_header}
+                                    <th class="px-4 py-3 text-left font-medium uppercase tracking-wider text-gray-200">Tags</th>
                                     <th class="px-4 py-3 text-left font-medium uppercase tracking-wider text-gray-200">Actions</th>
                                 </tr>
                             </thead>
                             <tbody>
     "#
-    );
+    ));
+
+    // Populate the table rows, linking to each entry by its file stem.
+    for library_entry in shown.into_iter().skip((page - 1) * per_page).take(per_page) {
+        let entry = &library_entry.entry;
+        let id = path_encode(&library_entry.id);
+        let edit_id = path_encode(&edit_id(&library_entries, library_entry));
+        let raw_id = html_escape(&library_entry.id);
+        let tag_links: String = tags::tags(entry)
+            .iter()
+            .map(|tag| {
+                format!(
+                    r#"<a href="/?tag={}" class="inline-block bg-indigo-700 hover:bg-indigo-600 text-white text-xs px-2 py-0.5 rounded mr-1 mb-1">{}</a>"#,
+                    query_encode(tag.trim()),
+                    html_escape(tag.trim())
+                )
+            })
+            .collect();
+        let author = html_escape(
+            &entry
+                .fields
+                .get("AU")
+                .map(|authors| authors.join(", "))
+                .unwrap_or_else(|| "Unknown".to_string()),
+        );
+        let title = html_escape(
+            &entry
+                .fields
+                .get("TI")
+                .and_then(|titles| titles.first().cloned())
+                .unwrap_or_else(|| "Unknown".to_string()),
+        );
+        let year = html_escape(
+            &entry
+                .fields
+                .get("PY")
+                .and_then(|years| years.first().cloned())
+                .unwrap_or_else(|| "Unknown".to_string()),
+        );
+
+        let library_link = match &app_data.openurl_resolver {
+            Some(resolver) => format!(
+                r#"<a href="{}" target="_blank" class="bg-teal-600 hover:bg-teal-700 text-white px-3 py-1 rounded">Find at library</a>"#,
+                html_escape(&openurl::build_link(resolver, entry))
+            ),
+            None => String::new(),
+        };
+
+        let open_button = if open_with::link_target(entry).is_some() {
+            format!(
+                r#"<form action="/open/{id}" method="post" class="inline"><button class="bg-green-600 hover:bg-green-700 text-white px-3 py-1 rounded" type="submit">Open</button></form>"#
+            )
+        } else {
+            String::new()
+        };
 
-    // Populate the table rows. We'll pretend "Edit" uses some ID. You can generate IDs as needed.
-    for (i, entry) in ris_entries.iter().enumerate() {
-        let author = entry
-            .fields
-            .get("AU")
-            .map(|authors| authors.join(", "))
-            .unwrap_or_else(|| "Unknown".to_string());
-        let title = entry
-            .fields
-            .get("TI")
-            .and_then(|titles| titles.first().cloned())
-            .unwrap_or_else(|| "Unknown".to_string());
-        let year = entry
-            .fields
-            .get("PY")
-            .and_then(|years| years.first().cloned())
-            .unwrap_or_else(|| "Unknown".to_string());
-
-        // We'll use `i` as a placeholder ID. If you have an actual unique ID in your data, use that.
         html.push_str(&format!(
             r#"
                                 <tr class="border-b border-gray-700 hover:bg-gray-800 transition-colors">
+                                    <td class="px-4 py-3 align-top"><input type="checkbox" name="ids" value="{raw_id}" form="bulk" /></td>
                                     <td class="px-4 py-3 align-top">{author}</td>
-                                    <td class="px-4 py-3 align-top">{title}</td>
+                                    <td class="px-4 py-3 align-top"><a href="/reference/{id}" class="hover:underline">{title}</a></td>
                                     <td class="px-4 py-3 align-top">{year}</td>
+                                    <td class="px-4 py-3 align-top">{tag_links}</td>
                                     <td class="px-4 py-3 align-top">
-                                        <a href="/edit/{i}" class="bg-purple-600 hover:bg-purple-700 text-white px-3 py-1 rounded">
+                                        <a href="/edit/{edit_id}" class="bg-purple-600 hover:bg-purple-700 text-white px-3 py-1 rounded">
                                             Edit
                                         </a>
+                                        <button type="button" class="bg-blue-600 hover:bg-blue-700 text-white px-3 py-1 rounded" onclick="copyBibtex(this, '{id}')">Copy BibTeX</button>
+                                        {open_button}
+                                        {library_link}
                                     </td>
                                 </tr>
             "#
@@ -118,8 +355,47 @@ async fn index_handler(
                             </tbody>
                         </table>
                     </div>
+    "#,
+    );
+
+    if pages > 1 {
+        let link_class = "bg-gray-700 hover:bg-gray-600 text-white py-1 px-3 rounded";
+        let previous = if page > 1 {
+            format!(r#"<a href="{}" class="{link_class}">Previous</a>"#, html_escape(&filter.page_link(page - 1, per_page)))
+        } else {
+            String::new()
+        };
+        let next = if page < pages {
+            format!(r#"<a href="{}" class="{link_class}">Next</a>"#, html_escape(&filter.page_link(page + 1, per_page)))
+        } else {
+            String::new()
+        };
+        html.push_str(&format!(
+            r#"
+                    <nav class="flex items-center justify-center gap-4 mt-4 text-gray-400">
+                        {previous}
+                        <span>Page {page} of {pages}</span>
+                        {next}
+                    </nav>
+            "#
+        ));
+    }
+
+    html.push_str(
+        r#"
                 </section>
             </main>
+            <script>
+            // Refetch the table when `.ris` files change, e.g. after `refrs import`.
+            new EventSource('/events').addEventListener('change', () => {
+                fetch(location.href)
+                    .then(response => response.text())
+                    .then(html => {
+                        const page = new DOMParser().parseFromString(html, 'text/html');
+                        document.querySelector('main').replaceWith(page.querySelector('main'));
+                    });
+            });
+            </script>
 
             <footer class="bg-gray-800 p-4 text-center text-sm text-gray-500 mt-auto">
                 <p>© 2024 Reference Tracker. All rights reserved.</p>
@@ -133,96 +409,740 @@ async fn index_handler(
 }
 
 /// GET /upload
-/// A simple page with a placeholder form for uploading a new reference file.
+/// A drop zone for files. PDFs are sent to `/upload/pdf` one at a time;
+/// RIS and BibTeX files go through the preview of `/add`.
 async fn upload_handler() -> Html<String> {
-    let html = r#"
-        <html lang="en">
-        <head>
-            <meta charset="UTF-8">
-            <title>Upload File</title>
-              <script src="https://cdn.tailwindcss.com"></script>
-        </head>
-        <body class="bg-gray-900 text-gray-100 min-h-screen">
-            <header class="p-4 bg-gray-800 shadow-md mb-6">
-                <h1 class="text-2xl font-bold text-center tracking-wider">Reference Tracker - Upload</h1>
-            </header>
-            <main class="max-w-lg mx-auto px-4">
-                <form action="/upload" method="post" enctype="multipart/form-data" class="bg-gray-800 p-4 rounded shadow">
-                    <label class="block mb-2 font-medium" for="file">Select a file to upload:</label>
-                    <input class="mb-4 block w-full text-sm text-gray-200 file:mr-4 file:py-2 file:px-4
-                              file:rounded file:border-0
-                              file:text-sm file:font-semibold
-                              file:bg-purple-600 file:text-white
-                              hover:file:bg-purple-700
-                              " type="file" id="file" name="file" required />
-                    <button class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded"
-                            type="submit">Upload</button>
-                </form>
-            </main>
-        </body>
-        </html>
+    let body = r#"
+            <section class="bg-gray-800 p-4 rounded shadow mb-4">
+                <div id="drop" class="border-2 border-dashed border-gray-700 rounded-lg px-4 py-12 mb-4 text-center text-gray-400">
+                    <p class="mb-2">Drop PDFs, RIS or BibTeX files here</p>
+                    <input type="file" id="file" multiple accept=".pdf,.ris,.bib,.txt" class="text-sm text-gray-200" />
+                </div>
+                <label class="block mb-2 text-sm text-gray-400" for="attach-to">Attach PDFs to (optional reference id; otherwise matched by DOI or title)</label>
+                <input id="attach-to" class="block w-full text-gray-200 bg-gray-700 p-2 rounded mb-4" />
+                <ul id="results"></ul>
+            </section>
+            <form id="add-form" action="/add" method="post" class="hidden">
+                <textarea name="references"></textarea>
+            </form>
+            <script>
+            const results = document.getElementById('results');
+            function report(text, href) {
+                const item = document.createElement('li');
+                item.className = 'mb-2';
+                const link = document.createElement(href ? 'a' : 'span');
+                link.textContent = text;
+                if (href) {
+                    link.href = href;
+                    link.className = 'text-blue-400 hover:underline';
+                }
+                item.appendChild(link);
+                results.appendChild(item);
+            }
+            async function upload(files) {
+                const texts = [];
+                for (const file of files) {
+                    if (!file.name.toLowerCase().endsWith('.pdf')) {
+                        texts.push(await file.text());
+                        continue;
+                    }
+                    const id = document.getElementById('attach-to').value.trim();
+                    const url = '/upload/pdf' + (id ? '?id=' + encodeURIComponent(id) : '');
+                    const response = await fetch(url, { method: 'POST', body: file, headers: { 'Content-Type': 'application/pdf' } });
+                    if (response.ok) {
+                        const result = await response.json();
+                        report(file.name + ': ' + result.message, '/reference/' + encodeURIComponent(result.id));
+                    } else {
+                        report(file.name + ': ' + (response.status == 413 ? 'too large for --max-pdf' : await response.text()));
+                    }
+                }
+                if (texts.length) {
+                    const form = document.getElementById('add-form');
+                    form.references.value = texts.join('\n\n');
+                    form.submit();
+                }
+            }
+            const drop = document.getElementById('drop');
+            drop.addEventListener('dragover', event => event.preventDefault());
+            drop.addEventListener('drop', event => {
+                event.preventDefault();
+                upload(event.dataTransfer.files);
+            });
+            document.getElementById('file').addEventListener('change', event => upload(event.target.files));
+            </script>
     "#;
-    Html(html.to_string())
+    Html(render_layout("Upload", body))
 }
 
-/// POST /upload
-/// A placeholder for actually processing the uploaded file.
-async fn upload_post_handler() -> impl IntoResponse {
-    // TODO: Implement file handling logic here
-    // e.g., store the uploaded file in `ris_folder`, parse it, etc.
-    Html(r#"<p class="text-white">File uploaded successfully (placeholder)!</p>"#)
+#[derive(Deserialize)]
+struct UploadPdfQuery {
+    /// Reference to attach the PDF to, instead of matching it by its metadata
+    id: Option<String>,
 }
 
-/// GET /edit/:id
-/// A simple page for editing an existing reference, identified by :id.
-async fn edit_handler(Path(id): Path<usize>) -> Html<String> {
-    // In a real app, you'd load the reference from the database or memory using `id`.
-    // Then you’d generate a form pre-filled with that reference's data.
-    // For now, we’ll just have a placeholder form.
-    let html = format!(
+#[derive(serde::Serialize)]
+struct UploadPdfResult {
+    id: String,
+    message: String,
+}
+
+/// POST /upload/pdf
+/// Stores the PDF in the request body as an attachment. It is attached to
+/// `?id=`, else to the reference with the same DOI or title, else to a new
+/// reference built from its metadata like `refrs import --pdf`.
+async fn upload_pdf_handler(
+    State(app_data): State<AppData>,
+    Query(query): Query<UploadPdfQuery>,
+    content: Bytes,
+) -> Result<Json<UploadPdfResult>, (StatusCode, String)> {
+    blocking(move || attach_uploaded_pdf(&app_data, query.id.as_deref(), &content))
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, format!("{:#}", e)))
+}
+
+fn attach_uploaded_pdf(app_data: &AppData, id: Option<&str>, content: &[u8]) -> Result<UploadPdfResult> {
+    let project = &app_data.project_path;
+    let metadata = pdf::parse(content)?;
+
+    let mut created = false;
+    let id = match id.filter(|id| !id.trim().is_empty()) {
+        Some(id) => id.trim().to_string(),
+        None => {
+            let pending =
+                serialization::prepare_import(vec![pdf::to_entry(&metadata)?], project, app_data.key_format, &app_data.key_pattern)?;
+            match &pending[0].duplicate_of {
+                Some(existing) => existing.clone(),
+                None => {
+                    created = true;
+                    let file_names = serialization::commit_import(&pending, project, app_data.key_format)?;
+                    file_names[0].trim_end_matches(".ris").to_string()
+                }
+            }
+        }
+    };
+
+    let entries = library::load_entries(project)?;
+    let reference = library::find_entry(&entries, &id).ok_or_else(|| anyhow::anyhow!("No reference named {}", id))?;
+    let message = match attach::store_attachment(project, reference, content, "pdf")? {
+        Some((relative_path, _)) => {
+            repo::add_all(project)?;
+            repo::commit(project, &format!("Attached {} to {}", relative_path, reference.id))?;
+            if created {
+                format!("added {} with the PDF attached", reference.id)
+            } else {
+                format!("attached to {}", reference.id)
+            }
+        }
+        None => format!("already attached to {}", reference.id),
+    };
+
+    Ok(UploadPdfResult { id: reference.id.clone(), message })
+}
+
+/// GET /reference/:id
+/// Every field of one reference, with its abstract, keywords, links and attachments.
+async fn reference_handler(State(app_data): State<AppData>, Path(id): Path<String>) -> Html<String> {
+    let entries = match library::load_entries(&app_data.project_path) {
+        Ok(entries) => entries,
+        Err(err) => return render_server_error(&err),
+    };
+    let Some(reference) = library::find_entry(&entries, &id) else {
+        return render_not_found(&id);
+    };
+    let entry = &reference.entry;
+    let action = path_encode(&reference.id);
+    let edit_action = path_encode(&edit_id(&entries, reference));
+    let button = "text-white py-2 px-4 rounded";
+
+    let title = entry.get_field("TI").cloned().unwrap_or_else(|| reference.id.clone());
+    let authors = entry.fields.get("AU").map(|authors| authors.join("; ")).unwrap_or_default();
+    let year = entry.get_field("PY").cloned().unwrap_or_default();
+    let mut body = format!(
         r#"
-        <html lang="en">
-        <head>
-            <meta charset="UTF-8">
-            <title>Edit Reference</title>
-  <script src="https://cdn.tailwindcss.com"></script>
-        </head>
-        <body class="bg-gray-900 text-gray-100 min-h-screen">
-            <header class="p-4 bg-gray-800 shadow-md mb-6">
-                <h1 class="text-2xl font-bold text-center tracking-wider">Editing Reference #{id}</h1>
-            </header>
-            <main class="max-w-lg mx-auto px-4">
-                <form action="/edit/{id}" method="post" class="bg-gray-800 p-4 rounded shadow">
-                    <label class="block mb-2 font-medium" for="author">Author:</label>
-                    <input id="author" name="author" class="mb-4 block w-full text-gray-200 bg-gray-700 p-2 rounded" value="Doe, John" />
+            <section class="bg-gray-800 p-4 rounded shadow mb-4">
+                <h2 class="text-xl font-semibold mb-2">{title}</h2>
+                <p class="text-gray-400">{authors} {year}</p>
+                <p class="text-gray-500 text-sm font-mono mt-2">{key} &middot; {ty}</p>
+        "#,
+        title = html_escape(&title),
+        authors = html_escape(&authors),
+        year = html_escape(&year),
+        key = html_escape(reference.key()),
+        ty = html_escape(entry.ty.to_str()),
+    );
 
-                    <label class="block mb-2 font-medium" for="title">Title:</label>
-                    <input id="title" name="title" class="mb-4 block w-full text-gray-200 bg-gray-700 p-2 rounded" value="Placeholder Title" />
+    let keywords: String = tags::tags(entry)
+        .iter()
+        .map(|tag| {
+            format!(
+                r#"<a href="/?tag={}" class="inline-block bg-indigo-700 hover:bg-indigo-600 text-white text-xs px-2 py-0.5 rounded mr-1 mb-1">{}</a>"#,
+                query_encode(tag.trim()),
+                html_escape(tag.trim())
+            )
+        })
+        .collect();
+    if !keywords.is_empty() {
+        body.push_str(&format!(r#"<p class="mt-2">{keywords}</p>"#));
+    }
 
-                    <label class="block mb-2 font-medium" for="year">Year:</label>
-                    <input id="year" name="year" class="mb-4 block w-full text-gray-200 bg-gray-700 p-2 rounded" value="2024" />
+    let mut links = String::new();
+    if let Some(doi) = entry.get_field("DO") {
+        let target = open_with::Target::Doi(doi.clone());
+        links.push_str(&format!(
+            r#"<a href="{}" target="_blank" class="text-blue-400 hover:underline mr-1">doi:{}</a> "#,
+            html_escape(&target.location()),
+            html_escape(doi.trim())
+        ));
+    }
+    for url in entry.fields.get("UR").into_iter().flatten() {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            links.push_str(&format!(
+                r#"<a href="{url}" target="_blank" class="text-blue-400 hover:underline mr-1">{url}</a> "#,
+                url = html_escape(url.trim())
+            ));
+        }
+    }
+    if !links.is_empty() {
+        body.push_str(&format!(r#"<p class="mt-2">{links}</p>"#));
+    }
+    body.push_str("</section>");
 
-                    <button class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded" type="submit">
-                        Save
-                    </button>
+    if let Some(abstract_text) = entry.fields.get("AB") {
+        body.push_str(&format!(
+            r#"
+            <section class="bg-gray-800 p-4 rounded shadow mb-4">
+                <h3 class="font-semibold mb-2">Abstract</h3>
+                <p class="whitespace-pre-wrap text-gray-200">{}</p>
+            </section>
+            "#,
+            html_escape(&abstract_text.join("\n\n"))
+        ));
+    }
+
+    if let Some(files) = entry.fields.get("L1") {
+        let rows: String = files
+            .iter()
+            .enumerate()
+            .map(|(n, file)| {
+                format!(
+                    r#"
+                    <li class="mb-2">
+                        <form action="/reference/{action}/attachments/{n}" method="post" class="inline">
+                            <button class="bg-green-600 hover:bg-green-700 text-white px-3 py-1 rounded" type="submit">Open</button>
+                        </form>
+                        <span class="font-mono text-sm ml-2">{}</span>
+                    </li>
+                    "#,
+                    html_escape(file)
+                )
+            })
+            .collect();
+        body.push_str(&format!(
+            r#"
+            <section class="bg-gray-800 p-4 rounded shadow mb-4">
+                <h3 class="font-semibold mb-2">Attachments</h3>
+                <ul>{rows}</ul>
+            </section>
+            "#
+        ));
+    }
+
+    let mut tags: Vec<&String> = entry.fields.keys().collect();
+    tags.sort();
+    let rows: String = tags
+        .into_iter()
+        .map(|tag| {
+            format!(
+                r#"
+                    <tr class="border-b border-gray-700">
+                        <td class="px-2 py-2 font-mono align-top text-gray-400">{}</td>
+                        <td class="px-2 py-2 whitespace-pre-wrap">{}</td>
+                    </tr>
+                "#,
+                html_escape(tag),
+                html_escape(&entry.fields[tag].join("\n"))
+            )
+        })
+        .collect();
+    body.push_str(&format!(
+        r#"
+            <section class="bg-gray-800 p-4 rounded shadow mb-4">
+                <h3 class="font-semibold mb-2">Fields</h3>
+                <table class="min-w-full border-collapse">
+                    <tbody>{rows}</tbody>
+                </table>
+            </section>
+        "#
+    ));
+
+    let bibtex = ris::ris_entry_to_bibtex_string(entry, reference.key());
+    body.push_str(&format!(
+        r#"
+            <section class="bg-gray-800 p-4 rounded shadow mb-4">
+                <h3 class="font-semibold mb-2">BibTeX</h3>
+                <pre class="bg-gray-700 text-gray-200 p-2 mt-2 rounded whitespace-pre-wrap">{bibtex}</pre>
+            </section>
+            <div class="flex gap-2">
+                <a href="/edit/{edit_action}" class="bg-purple-600 hover:bg-purple-700 {button}">Edit</a>
+                <button type="button" class="bg-blue-600 hover:bg-blue-700 {button}" onclick="copyBibtex(this, '{action}')">Copy BibTeX</button>
+                <form action="/reference/{action}/delete" method="post" onsubmit="return confirm('Remove this reference and its attachments?')">
+                    <button type="submit" class="bg-red-800 {button}">Delete</button>
                 </form>
-            </main>
-        </body>
-        </html>
-    "#
+                <a href="/" class="bg-gray-600 hover:bg-gray-700 {button}">Back to index</a>
+            </div>
+        "#,
+        bibtex = html_escape(&bibtex),
+    ));
+
+    Html(render_layout(&html_escape(&reference.id), &body))
+}
+
+/// GET /reference/:id/bibtex
+/// The reference as a single BibTeX entry under its citation key, as plain text.
+async fn reference_bibtex_handler(
+    State(app_data): State<AppData>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let entries = library::load_entries(&app_data.project_path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let reference = library::find_entry(&entries, &id).ok_or((StatusCode::NOT_FOUND, "No such reference".to_string()))?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        ris::ris_entry_to_bibtex_string(&reference.entry, reference.key()),
+    ))
+}
+
+/// POST /reference/:id/delete
+/// Removes the reference like `refrs remove` and returns to the index.
+async fn delete_reference_handler(State(app_data): State<AppData>, Path(id): Path<String>) -> Response {
+    let project = app_data.project_path.clone();
+    let wanted = id.clone();
+    let result = blocking(move || {
+        let entries = library::load_entries(&project)?;
+        match library::find_entry(&entries, &wanted) {
+            Some(reference) => remove::remove_reference(&project, &entries, reference).map(Some),
+            None => Ok(None),
+        }
+    })
+    .await;
+    match result {
+        Ok(Some(())) => Redirect::to("/").into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, render_not_found(&id)).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, render_server_error(&err)).into_response(),
+    }
+}
+
+/// POST /reference/:id/attachments/:n
+/// Opens the reference's n-th `L1` file on the server machine, like `refrs open --attachment`.
+async fn open_attachment_handler(
+    State(app_data): State<AppData>,
+    Path((id, n)): Path<(String, usize)>,
+) -> Result<Redirect, (StatusCode, String)> {
+    let entries = library::load_entries(&app_data.project_path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let reference = library::find_entry(&entries, &id).ok_or((StatusCode::NOT_FOUND, "No such reference".to_string()))?;
+    let file = reference
+        .entry
+        .fields
+        .get("L1")
+        .and_then(|files| files.get(n))
+        .ok_or((StatusCode::NOT_FOUND, "No such attachment".to_string()))?;
+    let target = open_with::Target::File(std::path::Path::new(&app_data.project_path).join(file));
+    open_with::open(&target, &app_data.open_with)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    Ok(Redirect::to(&format!("/reference/{}", path_encode(&id))))
+}
+
+/// The references named by the `ids` fields of a bulk form, in library order.
+fn selected_references<'a>(entries: &'a [LibraryEntry], form: &[(String, String)]) -> Vec<&'a LibraryEntry> {
+    let ids: Vec<&str> = form
+        .iter()
+        .filter(|(name, _)| name == "ids")
+        .map(|(_, id)| id.as_str())
+        .collect();
+    entries.iter().filter(|e| ids.contains(&e.id.as_str())).collect()
+}
+
+/// POST /bulk/export
+/// The checked references as one download in the format of the pressed button.
+async fn bulk_export_handler(
+    State(app_data): State<AppData>,
+    Form(form): Form<Vec<(String, String)>>,
+) -> Response {
+    let entries = match library::load_entries(&app_data.project_path) {
+        Ok(entries) => entries,
+        Err(err) => return render_server_error(&err).into_response(),
+    };
+    let format = form
+        .iter()
+        .find(|(name, _)| name == "format")
+        .and_then(|(_, format)| ExportFormat::from_str(format, true).ok())
+        .unwrap_or(ExportFormat::Bibtex);
+    let selected: Vec<(String, RisEntry)> = selected_references(&entries, &form)
+        .into_iter()
+        .map(|reference| (reference.key().to_string(), reference.entry.clone()))
+        .collect();
+    if selected.is_empty() {
+        return render_message("Export", "No references selected.").into_response();
+    }
+
+    let disposition = format!("attachment; filename=\"references.{}\"", format.extension());
+    (
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        export::render(format, &selected),
+    )
+        .into_response()
+}
+
+/// POST /bulk/delete
+/// Removes the checked references in a single commit.
+async fn bulk_delete_handler(
+    State(app_data): State<AppData>,
+    Form(form): Form<Vec<(String, String)>>,
+) -> Html<String> {
+    let project = app_data.project_path.clone();
+    let removed = blocking(move || {
+        let entries = library::load_entries(&project)?;
+        let selected = selected_references(&entries, &form);
+        if !selected.is_empty() {
+            remove::remove_references(&project, &entries, &selected)?;
+        }
+        Ok(selected.len())
+    })
+    .await;
+    match removed {
+        Ok(0) => render_message("Delete", "No references selected."),
+        Ok(count) => render_message("Deleted", &format!("Removed {} reference(s).", count)),
+        Err(err) => render_server_error(&err),
+    }
+}
+
+#[derive(Deserialize)]
+struct GraphQuery {
+    /// `coauthors` (the default) or `keywords`
+    kind: Option<String>,
+}
+
+/// GET /graph/data
+/// Nodes and edges as JSON: authors linked by shared references for
+/// `?kind=coauthors`, references linked to their keywords for `?kind=keywords`.
+async fn graph_data_handler(
+    State(app_data): State<AppData>,
+    Query(query): Query<GraphQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let entries = library::load_entries(&app_data.project_path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let network = match query.kind.as_deref().unwrap_or("coauthors") {
+        "coauthors" => graph::Graph {
+            nodes: graph::author_nodes(&entries),
+            edges: graph::coauthorship_edges(&entries),
+        },
+        "keywords" => graph::Graph {
+            nodes: graph::reference_nodes(&entries).into_iter().chain(graph::keyword_nodes(&entries)).collect(),
+            edges: graph::keyword_edges(&entries),
+        },
+        other => return Err((StatusCode::BAD_REQUEST, format!("Unknown graph kind {other}"))),
+    };
+    Ok(([(header::CONTENT_TYPE, "application/json")], graph::to_json(&network)))
+}
+
+/// GET /graph
+/// Draws the network from `/graph/data` with a small force-directed layout.
+async fn graph_handler() -> Html<String> {
+    let body = r#"
+            <section class="bg-gray-800 p-4 rounded shadow mb-4">
+                <form class="flex gap-4 items-center mb-4">
+                    <label><input type="radio" name="kind" value="coauthors" checked /> Co-authors</label>
+                    <label><input type="radio" name="kind" value="keywords" /> References and keywords</label>
+                    <label><input type="checkbox" id="connected" checked /> Hide unconnected</label>
+                    <span id="summary" class="text-gray-400 text-sm"></span>
+                </form>
+                <svg id="graph" class="w-full bg-gray-900 rounded" viewBox="0 0 1000 700" height="700"></svg>
+            </section>
+            <script>
+            const svg = document.getElementById('graph');
+            const width = 1000, height = 700;
+
+            // Fruchterman-Reingold: nodes repel each other, edges pull their ends together.
+            function layout(nodes, edges) {
+                nodes.forEach(node => {
+                    node.x = width / 2 + (Math.random() - 0.5) * width / 2;
+                    node.y = height / 2 + (Math.random() - 0.5) * height / 2;
+                });
+                const byId = new Map(nodes.map(node => [node.id, node]));
+                const links = edges.map(edge => [byId.get(edge.source), byId.get(edge.target), edge.weight]);
+                const spacing = Math.sqrt(width * height / Math.max(nodes.length, 1));
+                for (let step = 0, heat = width / 10; step < 300; step++, heat *= 0.98) {
+                    nodes.forEach(node => { node.dx = 0; node.dy = 0; });
+                    for (let i = 0; i < nodes.length; i++) {
+                        for (let j = i + 1; j < nodes.length; j++) {
+                            const a = nodes[i], b = nodes[j];
+                            const dx = a.x - b.x, dy = a.y - b.y;
+                            const distance = Math.max(Math.hypot(dx, dy), 1);
+                            const force = spacing * spacing / distance / distance;
+                            a.dx += dx * force; a.dy += dy * force;
+                            b.dx -= dx * force; b.dy -= dy * force;
+                        }
+                    }
+                    links.forEach(([a, b, weight]) => {
+                        const dx = a.x - b.x, dy = a.y - b.y;
+                        const distance = Math.max(Math.hypot(dx, dy), 1);
+                        const force = distance * Math.sqrt(weight) / spacing;
+                        a.dx -= dx * force; a.dy -= dy * force;
+                        b.dx += dx * force; b.dy += dy * force;
+                    });
+                    nodes.forEach(node => {
+                        const length = Math.max(Math.hypot(node.dx, node.dy), 1);
+                        node.x = Math.min(width - 20, Math.max(20, node.x + node.dx / length * Math.min(length, heat)));
+                        node.y = Math.min(height - 20, Math.max(20, node.y + node.dy / length * Math.min(length, heat)));
+                    });
+                }
+                return links;
+            }
+
+            function element(name, attributes) {
+                const node = document.createElementNS('http://www.w3.org/2000/svg', name);
+                Object.entries(attributes).forEach(([key, value]) => node.setAttribute(key, value));
+                return node;
+            }
+
+            async function draw() {
+                const kind = document.querySelector('input[name=kind]:checked').value;
+                const data = await (await fetch('/graph/data?kind=' + kind)).json();
+                const linked = new Set(data.edges.flatMap(edge => [edge.source, edge.target]));
+                const nodes = document.getElementById('connected').checked
+                    ? data.nodes.filter(node => linked.has(node.id))
+                    : data.nodes;
+                document.getElementById('summary').textContent = nodes.length + ' nodes, ' + data.edges.length + ' edges';
+
+                svg.replaceChildren();
+                const links = layout(nodes, data.edges);
+                links.forEach(([a, b, weight]) => svg.appendChild(element('line', {
+                    x1: a.x, y1: a.y, x2: b.x, y2: b.y, stroke: '#4b5563', 'stroke-width': Math.min(weight, 6),
+                })));
+                nodes.forEach(node => {
+                    const keyword = node.id.startsWith('keyword:');
+                    const group = kind == 'keywords' && !keyword
+                        ? element('a', { href: '/reference/' + encodeURIComponent(node.id.replace(/#\d+$/, '')) })
+                        : element('g', {});
+                    group.appendChild(element('circle', { cx: node.x, cy: node.y, r: 5, fill: keyword ? '#f59e0b' : '#60a5fa' }));
+                    const label = element('text', { x: node.x + 8, y: node.y + 4, fill: '#e5e7eb', 'font-size': 11 });
+                    label.textContent = node.label.length > 40 ? node.label.slice(0, 39) + '…' : node.label;
+                    group.appendChild(label);
+                    svg.appendChild(group);
+                });
+            }
+
+            document.querySelectorAll('input').forEach(input => input.addEventListener('change', draw));
+            draw();
+            </script>
+    "#;
+    Html(render_layout("Graph", body))
+}
+
+/// Names `entry` in `/edit/` links: its citation key, with `#2`, `#3` and
+/// so on for further entries sharing the key, so that every entry of a file
+/// holding several can be edited.
+fn edit_id(entries: &[LibraryEntry], entry: &LibraryEntry) -> String {
+    let key = entry.key();
+    let earlier = entries
+        .iter()
+        .take_while(|other| !std::ptr::eq(*other, entry))
+        .filter(|other| other.key() == key)
+        .count();
+    match earlier {
+        0 => key.to_string(),
+        n => format!("{}#{}", key, n + 1),
+    }
+}
+
+/// The entry an [`edit_id`] names.
+fn find_for_edit<'a>(entries: &'a [LibraryEntry], id: &str) -> Option<&'a LibraryEntry> {
+    let (key, n) = match id.rsplit_once('#').map(|(key, n)| (key, n.parse::<usize>())) {
+        Some((key, Ok(n))) if n >= 2 => (key, n),
+        _ => (id, 1),
+    };
+    entries.iter().filter(|e| e.key() == key).nth(n - 1)
+}
+
+/// GET /edit/:id
+/// A form with every field of the reference named by its [`edit_id`], one
+/// value per line, plus empty rows for adding fields.
+async fn edit_handler(State(app_data): State<AppData>, Path(id): Path<String>) -> Html<String> {
+    let entries = match library::load_entries(&app_data.project_path) {
+        Ok(entries) => entries,
+        Err(err) => return render_server_error(&err),
+    };
+    match find_for_edit(&entries, &id) {
+        Some(reference) => render_edit_form(&id, &reference.entry, None),
+        None => render_not_found(&id),
+    }
+}
+
+fn render_edit_form(id: &str, entry: &RisEntry, error: Option<&str>) -> Html<String> {
+    let textarea_class = "block w-full text-gray-200 bg-gray-700 p-2 rounded font-mono text-sm";
+    let mut tags: Vec<&String> = entry.fields.keys().collect();
+    tags.sort();
+
+    let mut rows = format!(
+        r#"
+            <tr class="border-b border-gray-700">
+                <td class="px-2 py-2 font-mono align-top"><label for="ty">TY</label></td>
+                <td class="px-2 py-2"><input id="ty" name="ty" class="{textarea_class}" value="{ty}" /></td>
+            </tr>
+        "#,
+        ty = html_escape(entry.ty.to_str()),
+    );
+    for tag in tags {
+        let values = &entry.fields[tag];
+        rows.push_str(&format!(
+            r#"
+            <tr class="border-b border-gray-700">
+                <td class="px-2 py-2 font-mono align-top"><label for="field_{tag}">{tag}</label></td>
+                <td class="px-2 py-2"><textarea id="field_{tag}" name="field_{tag}" rows="{rows}" class="{textarea_class}">{values}</textarea></td>
+            </tr>
+            "#,
+            tag = html_escape(tag),
+            rows = values.len().clamp(1, 8),
+            values = html_escape(&values.join("\n")),
+        ));
+    }
+    for n in 0..3 {
+        rows.push_str(&format!(
+            r#"
+            <tr class="border-b border-gray-700">
+                <td class="px-2 py-2 align-top"><input name="new_tag_{n}" maxlength="2" placeholder="Tag" class="{textarea_class} w-16 uppercase" /></td>
+                <td class="px-2 py-2"><textarea name="new_values_{n}" rows="1" placeholder="New field, one value per line" class="{textarea_class}"></textarea></td>
+            </tr>
+            "#
+        ));
+    }
+
+    let error = error
+        .map(|error| format!(r#"<div class="bg-red-800 p-4 rounded mb-4 text-red-100">{}</div>"#, html_escape(error)))
+        .unwrap_or_default();
+    let body = format!(
+        r#"
+            {error}
+            <form action="/edit/{action}" method="post" class="bg-gray-800 p-4 rounded mb-4">
+                <p class="text-white mb-4">One value per line. Clear a field to remove it.</p>
+                <table class="min-w-full border-collapse">
+                    <tbody>{rows}</tbody>
+                </table>
+                <button class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded mt-4" type="submit">Save</button>
+                <a href="/" class="bg-gray-600 hover:bg-gray-700 text-white py-2 px-4 rounded mt-4 inline-block">Cancel</a>
+            </form>
+        "#,
+        action = path_encode(id),
     );
-    Html(html)
+
+    Html(render_layout(&format!("Edit {}", html_escape(id)), &body))
+}
+
+/// Builds the edited entry from the form fields of `render_edit_form`.
+fn entry_from_form(form: &HashMap<String, String>) -> std::result::Result<RisEntry, String> {
+    let ty_code = form.get("ty").map(|ty| ty.trim().to_uppercase()).unwrap_or_default();
+    let Ok(ty) = ty_code.parse::<ReferenceType>();
+    if ty == ReferenceType::Unknown {
+        return Err(format!("Unknown reference type: {}", ty_code));
+    }
+
+    let lines = |text: &str| -> Vec<String> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let check_tag = |tag: &str| -> std::result::Result<(), String> {
+        let valid = tag.len() == 2
+            && tag.starts_with(|c: char| c.is_ascii_uppercase())
+            && tag.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+        if !valid || tag == "TY" || tag == "ER" {
+            return Err(format!("{} is not a RIS tag; tags are a letter and a letter or digit, like AU or T2", tag));
+        }
+        Ok(())
+    };
+    let mut fields = HashMap::new();
+    for (name, value) in form {
+        if let Some(tag) = name.strip_prefix("field_") {
+            check_tag(tag)?;
+            let values = lines(value);
+            if !values.is_empty() {
+                fields.insert(tag.to_string(), values);
+            }
+        }
+    }
+    for n in 0..3 {
+        let tag = form.get(&format!("new_tag_{n}")).map(|t| t.trim().to_uppercase()).unwrap_or_default();
+        let values = form.get(&format!("new_values_{n}")).map(|v| lines(v)).unwrap_or_default();
+        if tag.is_empty() && values.is_empty() {
+            continue;
+        }
+        check_tag(&tag)?;
+        fields.entry(tag).or_insert_with(Vec::new).extend(values);
+    }
+
+    Ok(RisEntry { ty, fields })
 }
 
 /// POST /edit/:id
-/// A placeholder for saving changes to the reference.
-async fn edit_post_handler(Path(id): Path<usize>) -> impl IntoResponse {
-    // TODO: Implement actual "edit reference" logic
-    // e.g., parse form, update .ris file or database, etc.
-    Html(format!(
-        r#"<p class="text-white">Reference #{} updated successfully (placeholder)!</p>"#,
-        id
-    ))
+/// Replaces the reference in its `.ris` file with the submitted fields, keeping
+/// the file's other entries, and commits it.
+async fn edit_post_handler(
+    State(app_data): State<AppData>,
+    Path(id): Path<String>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Html<String> {
+    let entries = match library::load_entries(&app_data.project_path) {
+        Ok(entries) => entries,
+        Err(err) => return render_server_error(&err),
+    };
+    let Some(reference) = find_for_edit(&entries, &id) else {
+        return render_not_found(&id);
+    };
+
+    let edited = match entry_from_form(&form) {
+        Ok(edited) => edited,
+        Err(error) => return render_edit_form(&id, &reference.entry, Some(&error)),
+    };
+    let changes = diff::diff_entries(&reference.entry, &edited);
+    if changes.is_empty() {
+        return render_message("No Changes", &format!("No changes to {}.", html_escape(&id)));
+    }
+
+    let project = app_data.project_path.clone();
+    let reference = reference.clone();
+    let saved = blocking(move || {
+        library::replace_entry(&reference, Some(&edited))?;
+        repo::add_all(&project)?;
+        repo::commit(&project, &format!("Edited {}", reference.key()))
+    })
+    .await;
+    if let Err(err) = saved {
+        return render_server_error(&err);
+    }
+
+    let changed: Vec<String> = changes.iter().map(|change| change.tag.clone()).collect();
+    let body = format!(
+        r#"
+            <div class="bg-gray-800 p-4 rounded mb-4">
+                <p class="text-white">Saved {id}: changed {changed}.</p>
+            </div>
+            <p>
+                <a href="/edit/{action}" class="bg-purple-600 hover:bg-purple-700 text-white py-2 px-4 rounded">Edit again</a>
+                <a href="/" class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded">Back to index</a>
+            </p>
+        "#,
+        id = html_escape(&id),
+        changed = html_escape(&changed.join(", ")),
+        action = path_encode(&id),
+    );
+    Html(render_layout("Saved", &body))
 }
 
 /// POST /update
@@ -233,6 +1153,22 @@ async fn update_handler() -> impl IntoResponse {
     Html(r#"<p class="text-white">Updated/synced with the cloud (placeholder)!</p>"#)
 }
 
+/// POST /open/:id
+/// Opens the reference's DOI or URL on this machine with the configured handler.
+async fn open_handler(
+    State(app_data): State<AppData>,
+    Path(id): Path<String>,
+) -> Result<Redirect, (StatusCode, String)> {
+    let entries = library::load_entries(&app_data.project_path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let target = library::find_entry(&entries, &id)
+        .and_then(|reference| open_with::link_target(&reference.entry))
+        .ok_or((StatusCode::NOT_FOUND, "Nothing to open".to_string()))?;
+    open_with::open(&target, &app_data.open_with)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    Ok(Redirect::to("/"))
+}
+
 /// GET /add
 /// Shows a page with a textarea for pasting RIS or BibTeX content.
 async fn add_ris_bibtex_handler() -> Html<String> {
@@ -241,7 +1177,7 @@ async fn add_ris_bibtex_handler() -> Html<String> {
         <head>
             <meta charset="UTF-8" />
             <title>Add RIS/BibTeX</title>
-            <script src="https://cdn.tailwindcss.com"></script>
+            <link rel="stylesheet" href="/static/style.css" />
         </head>
         <body class="bg-gray-900 text-gray-100 min-h-screen">
             <header class="p-4 bg-gray-800 shadow-md mb-6">
@@ -278,7 +1214,8 @@ struct AddReferencesForm {
 }
 
 /// POST /add
-/// Processes the pasted RIS/BibTeX data.
+/// Parses the pasted RIS/BibTeX data and shows a preview where entries can be
+/// deselected and their citation keys edited before anything is written.
 async fn add_ris_bibtex_post_handler(
     State(app_data): State<AppData>,
     Form(form): Form<AddReferencesForm>,
@@ -286,117 +1223,698 @@ async fn add_ris_bibtex_post_handler(
     // The raw text the user pasted:
     let pasted_content = form.references;
 
-    // Determine the user-facing message based on the import result.
-    let message = match serialization::import(&pasted_content, &app_data.project_path) {
-        Ok(result) => match result {
-            serialization::ImportResult::BibtexImported => {
-                "Recognized <b>BibTex</b> and imported successfully.".to_string()
-            }
-            serialization::ImportResult::BibtexError { error } => {
-                format!("BibTeX error: {error}")
-            }
-            serialization::ImportResult::RisImported => {
-                "Recognized <b>RIS</b> and imported successfully.".to_string()
-            }
-            serialization::ImportResult::RisError { error } => {
-                format!("RIS error: {error}")
-            }
-            serialization::ImportResult::UnrecognizedFormat => {
-                "Unrecognized format. Could not import the data.".to_string()
-            }
-        },
-        Err(err) => {
-            // Unknown error that should not happen; show full layout with error
-            let body = format!(
-                r#"
-                    <div class="bg-red-800 p-4 rounded mb-4">
-                        <p class="text-white font-bold">Unknown server error:</p>
-                        <p class="text-red-100">{}</p>
-                    </div>
-                    <p>
-                        <a href="/" class="bg-gray-600 hover:bg-gray-700 text-white py-2 px-4 rounded">
-                            Back to index
-                        </a>
-                    </p>
-                "#,
-                err
+    let entries = match serialization::parse(&pasted_content) {
+        serialization::ParseResult::Parsed { entries, .. } => entries,
+        serialization::ParseResult::BibtexError { error } => {
+            return render_add_message(&format!("BibTeX error: {error}"), &pasted_content);
+        }
+        serialization::ParseResult::Error { format, error } => {
+            return render_add_message(&format!("{format} error: {error}"), &pasted_content);
+        }
+        serialization::ParseResult::UnrecognizedFormat => {
+            return render_add_message(
+                "Unrecognized format. Could not import the data.",
+                &pasted_content,
+            );
+        }
+    };
+
+    let pending = match serialization::prepare_import(entries, &app_data.project_path, app_data.key_format, &app_data.key_pattern) {
+        Ok(pending) => pending,
+        Err(err) => return render_server_error(&err),
+    };
+
+    let existing = library::load_entries(&app_data.project_path).unwrap_or_default();
+
+    let mut rows = String::new();
+    for (i, import) in pending.iter().enumerate() {
+        let entry = &import.entry;
+        let field = |tag: &str| html_escape(entry.get_field(tag).map(String::as_str).unwrap_or("-"));
+        let checked = if import.selected { "checked" } else { "" };
+        let duplicate = match &import.duplicate_of {
+            Some(id) if library::find_entry(&existing, id).is_some() => format!(
+                r#"<span class="text-red-400">{}</span>
+                   <button formaction="/merge/incoming" name="merge" value="{i}"
+                           class="bg-yellow-600 hover:bg-yellow-700 text-white px-2 py-1 rounded ml-2">Merge</button>"#,
+                html_escape(id)
+            ),
+            Some(id) => format!(r#"<span class="text-red-400">{}</span>"#, html_escape(id)),
+            None => String::new(),
+        };
+
+        rows.push_str(&format!(
+            r#"
+                <tr class="border-b border-gray-700">
+                    <td class="px-2 py-2"><input type="checkbox" name="select_{i}" {checked} /></td>
+                    <td class="px-2 py-2">{ty}</td>
+                    <td class="px-2 py-2">{author}</td>
+                    <td class="px-2 py-2">{year}</td>
+                    <td class="px-2 py-2">{title}</td>
+                    <td class="px-2 py-2">{duplicate}</td>
+                    <td class="px-2 py-2">
+                        <input name="key_{i}" value="{key}" class="text-gray-200 bg-gray-700 p-1 rounded" />
+                    </td>
+                </tr>
+            "#,
+            ty = entry.ty.to_str(),
+            author = field("AU"),
+            year = field("PY"),
+            title = field("TI"),
+            key = html_escape(&import.key),
+        ));
+    }
+
+    let body = format!(
+        r#"
+            <form action="/add/confirm" method="post" class="bg-gray-800 p-4 rounded mb-4">
+                <p class="text-white mb-4">Review the references below. Uncheck entries to skip them and adjust citation keys as needed.</p>
+                <div class="overflow-x-auto">
+                    <table class="min-w-full border-collapse text-sm">
+                        <thead class="border-b border-gray-700">
+                            <tr>
+                                <th class="px-2 py-2 text-left">Import</th>
+                                <th class="px-2 py-2 text-left">Type</th>
+                                <th class="px-2 py-2 text-left">Author</th>
+                                <th class="px-2 py-2 text-left">Year</th>
+                                <th class="px-2 py-2 text-left">Title</th>
+                                <th class="px-2 py-2 text-left">Duplicate of</th>
+                                <th class="px-2 py-2 text-left">Citation key</th>
+                            </tr>
+                        </thead>
+                        <tbody>{rows}</tbody>
+                    </table>
+                </div>
+                <textarea name="references" class="hidden">{references}</textarea>
+                <button class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded mt-4" type="submit">
+                    Import selected
+                </button>
+            </form>
+            <p>
+                <a href="/" class="bg-gray-600 hover:bg-gray-700 text-white py-2 px-4 rounded">Cancel</a>
+            </p>
+        "#,
+        references = html_escape(&pasted_content),
+    );
+
+    Html(render_layout("Import Preview", &body))
+}
+
+/// POST /add/confirm
+/// Writes the entries selected on the preview page, using the edited keys.
+/// The form carries the original text, which is parsed again here.
+async fn add_confirm_post_handler(
+    State(app_data): State<AppData>,
+    Form(form): Form<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let pasted_content = form.get("references").cloned().unwrap_or_default();
+
+    let Some(entries) = serialization::parse(&pasted_content).entries() else {
+        return render_add_message("Could not parse the references again.", &pasted_content);
+    };
+
+    let mut pending = match serialization::prepare_import(entries, &app_data.project_path, app_data.key_format, &app_data.key_pattern) {
+        Ok(pending) => pending,
+        Err(err) => return render_server_error(&err),
+    };
+
+    for (i, import) in pending.iter_mut().enumerate() {
+        import.selected = form.contains_key(&format!("select_{i}"));
+        if let Some(key) = form.get(&format!("key_{i}")) {
+            import.key = key.clone();
+        }
+    }
+
+    let project = app_data.project_path.clone();
+    let key_format = app_data.key_format;
+    match blocking(move || serialization::commit_import(&pending, &project, key_format)).await {
+        Ok(file_names) => {
+            let message = if file_names.is_empty() {
+                "Nothing was imported.".to_string()
+            } else {
+                format!(
+                    "Imported {} reference(s): {}",
+                    file_names.len(),
+                    html_escape(&file_names.join(", "))
+                )
+            };
+            render_add_message(&message, &pasted_content)
+        }
+        Err(err) => render_server_error(&err),
+    }
+}
+
+/// GET /dedupe
+/// Lists pairs of entries that look like the same work.
+async fn dedupe_handler(State(app_data): State<AppData>) -> Html<String> {
+    let entries = match library::load_entries(&app_data.project_path) {
+        Ok(entries) => entries,
+        Err(err) => return render_server_error(&err),
+    };
+
+    let pairs = library::duplicate_pairs(&entries);
+    if pairs.is_empty() {
+        return Html(render_layout(
+            "Duplicates",
+            r#"<p class="bg-gray-800 p-4 rounded">No duplicates found.</p>"#,
+        ));
+    }
+
+    let mut items = String::new();
+    for (i, j) in pairs {
+        let (left, right) = (&entries[i], &entries[j]);
+        let title = left.entry.get_field("TI").map(String::as_str).unwrap_or("Unknown");
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("left", &left.id)
+            .append_pair("right", &right.id)
+            .finish();
+        items.push_str(&format!(
+            r#"
+                <li class="flex justify-between items-center border-b border-gray-700 py-2">
+                    <span>{title} <span class="text-gray-400">({left} / {right})</span></span>
+                    <a href="/merge?{query}" class="bg-yellow-600 hover:bg-yellow-700 text-white px-3 py-1 rounded">Compare</a>
+                </li>
+            "#,
+            title = html_escape(title),
+            left = html_escape(&left.id),
+            right = html_escape(&right.id),
+            query = html_escape(&query),
+        ));
+    }
+
+    let body = format!(r#"<ul class="bg-gray-800 p-4 rounded">{items}</ul>"#);
+    Html(render_layout("Duplicates", &body))
+}
+
+#[derive(Deserialize)]
+struct MergeQuery {
+    left: String,
+    right: String,
+}
+
+/// GET /merge?left=<id>&right=<id>
+/// Side-by-side comparison of two stored entries.
+async fn merge_handler(
+    State(app_data): State<AppData>,
+    Query(query): Query<MergeQuery>,
+) -> Html<String> {
+    let entries = match library::load_entries(&app_data.project_path) {
+        Ok(entries) => entries,
+        Err(err) => return render_server_error(&err),
+    };
+
+    match (
+        library::find_entry(&entries, &query.left),
+        library::find_entry(&entries, &query.right),
+    ) {
+        (Some(left), Some(right)) => {
+            let hidden = format!(
+                r#"<input type="hidden" name="right" value="{}" />"#,
+                html_escape(&right.id)
+            );
+            render_merge_form(left, &right.id, &right.entry, &hidden)
+        }
+        _ => render_add_message("One of the entries no longer exists.", ""),
+    }
+}
+
+/// POST /merge/incoming
+/// Compares an entry from the import preview with the stored entry it collides with.
+async fn merge_incoming_handler(
+    State(app_data): State<AppData>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Html<String> {
+    let pasted_content = form.get("references").cloned().unwrap_or_default();
+    let index: usize = form.get("merge").and_then(|i| i.parse().ok()).unwrap_or(usize::MAX);
+
+    let Some(entries) = serialization::parse(&pasted_content).entries() else {
+        return render_add_message("Could not parse the references again.", &pasted_content);
+    };
+
+    let pending = match serialization::prepare_import(entries, &app_data.project_path, app_data.key_format, &app_data.key_pattern) {
+        Ok(pending) => pending,
+        Err(err) => return render_server_error(&err),
+    };
+    let existing = match library::load_entries(&app_data.project_path) {
+        Ok(existing) => existing,
+        Err(err) => return render_server_error(&err),
+    };
+
+    let incoming = pending.get(index);
+    let left = incoming
+        .and_then(|p| p.duplicate_of.as_ref())
+        .and_then(|id| library::find_entry(&existing, id));
+
+    match (incoming, left) {
+        (Some(incoming), Some(left)) => {
+            let hidden = format!(
+                r#"<textarea name="incoming" class="hidden">{}</textarea>"#,
+                html_escape(&incoming.entry.to_string())
             );
-            return Html(render_layout("Error", &body));
+            render_merge_form(left, &incoming.key, &incoming.entry, &hidden)
         }
+        _ => render_add_message("Nothing to merge.", &pasted_content),
+    }
+}
+
+/// Renders the field comparison with a radio choice for every conflicting field.
+fn render_merge_form(
+    left: &LibraryEntry,
+    right_label: &str,
+    right: &RisEntry,
+    hidden_fields: &str,
+) -> Html<String> {
+    let mut rows = String::new();
+    for tag in merge::all_tags(&left.entry, right) {
+        let l = merge::values(&left.entry, &tag);
+        let r = merge::values(right, &tag);
+        let conflicting = !l.is_empty() && !r.is_empty() && l != r;
+
+        let cell = |values: &[String], side: &str, checked: bool| {
+            let text = html_escape(&values.join("; "));
+            if conflicting {
+                let checked = if checked { "checked" } else { "" };
+                format!(
+                    r#"<label><input type="radio" name="choice_{tag}" value="{side}" {checked} /> {text}</label>"#
+                )
+            } else {
+                text
+            }
+        };
+
+        let row_class = if conflicting { "bg-yellow-900" } else { "" };
+        rows.push_str(&format!(
+            r#"
+                <tr class="border-b border-gray-700 {row_class}">
+                    <td class="px-2 py-2 font-mono">{tag}</td>
+                    <td class="px-2 py-2">{left}</td>
+                    <td class="px-2 py-2">{right}</td>
+                </tr>
+            "#,
+            left = cell(&l, "left", true),
+            right = cell(&r, "right", false),
+        ));
+    }
+
+    let body = format!(
+        r#"
+            <form action="/merge" method="post" class="bg-gray-800 p-4 rounded mb-4">
+                <p class="text-white mb-4">Pick the value to keep for every highlighted field. The merged entry replaces both originals.</p>
+                <table class="min-w-full border-collapse text-sm">
+                    <thead class="border-b border-gray-700">
+                        <tr>
+                            <th class="px-2 py-2 text-left">Field</th>
+                            <th class="px-2 py-2 text-left">{left_label}</th>
+                            <th class="px-2 py-2 text-left">{right_label}</th>
+                        </tr>
+                    </thead>
+                    <tbody>{rows}</tbody>
+                </table>
+                <input type="hidden" name="left" value="{left_id}" />
+                {hidden_fields}
+                <button class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded mt-4" type="submit">Merge</button>
+            </form>
+        "#,
+        left_label = html_escape(&left.id),
+        left_id = html_escape(&left.id),
+        right_label = html_escape(right_label),
+    );
+
+    Html(render_layout("Merge References", &body))
+}
+
+/// POST /merge
+/// Merges the right entry (stored or incoming) into the left one.
+async fn merge_post_handler(
+    State(app_data): State<AppData>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Html<String> {
+    let entries = match library::load_entries(&app_data.project_path) {
+        Ok(entries) => entries,
+        Err(err) => return render_server_error(&err),
+    };
+
+    let left = form.get("left").and_then(|id| library::find_entry(&entries, id));
+    let stored_right = form.get("right").and_then(|id| library::find_entry(&entries, id));
+    let incoming = form
+        .get("incoming")
+        .and_then(|text| ris::parse_ris(text).ok())
+        .and_then(|mut parsed| (!parsed.is_empty()).then(|| parsed.remove(0)));
+
+    let (left, right_entry) = match (left, stored_right, &incoming) {
+        (Some(left), Some(right), _) => (left, &right.entry),
+        (Some(left), None, Some(incoming)) => (left, incoming),
+        _ => return render_add_message("Nothing to merge.", ""),
     };
 
-    // If we got here, we have a successful or recognized-but-with-errors import.
-    // Show the message and the original pasted content, plus a back button.
+    let choices = form
+        .iter()
+        .filter_map(|(name, value)| {
+            let tag = name.strip_prefix("choice_")?;
+            let side = if value == "right" { Side::Right } else { Side::Left };
+            Some((tag.to_string(), side))
+        })
+        .collect();
+
+    let merged = merge::merge(&left.entry, right_entry, &choices);
+    let message = format!("Merged into {}.", html_escape(&left.id));
+    let project = app_data.project_path.clone();
+    let (left, stored_right) = (left.clone(), stored_right.cloned());
+    match blocking(move || merge::save_merged(&project, &left, stored_right.as_ref(), &merged)).await {
+        Ok(()) => render_add_message(&message, ""),
+        Err(err) => render_server_error(&err),
+    }
+}
+
+/// Runs `work` on the blocking thread pool, like `api::blocking`, so writing
+/// files and committing don't stall the server's other requests.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(work).await?
+}
+
+/// Shows an import message together with the original pasted content.
+fn render_add_message(message: &str, pasted_content: &str) -> Html<String> {
     let body = format!(
         r#"
             <div class="bg-gray-800 p-4 rounded mb-4">
                 <p class="text-white">{message}</p>
                 <p class="text-white mt-2">Received references:</p>
-                <pre class="bg-gray-700 text-gray-200 p-2 mt-2 rounded whitespace-pre-wrap">{pasted_content}</pre>
+                <pre class="bg-gray-700 text-gray-200 p-2 mt-2 rounded whitespace-pre-wrap">{content}</pre>
             </div>
             <p>
                 <a href="/" class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded">Back to index</a>
             </p>
-        "#
+        "#,
+        content = html_escape(pasted_content),
     );
 
     Html(render_layout("Add References Result", &body))
 }
 
-pub fn handle_serve(state: &AppState) -> Result<()> {
+/// A short message with a link back to the index. `message` must already be escaped.
+fn render_message(title: &str, message: &str) -> Html<String> {
+    let body = format!(
+        r#"
+            <div class="bg-gray-800 p-4 rounded mb-4">
+                <p class="text-white">{message}</p>
+            </div>
+            <p>
+                <a href="/" class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded">Back to index</a>
+            </p>
+        "#
+    );
+    Html(render_layout(title, &body))
+}
+
+fn render_not_found(id: &str) -> Html<String> {
+    render_message("Not Found", &format!("No reference named {}.", html_escape(id)))
+}
+
+/// Unknown error that should not happen; show full layout with error
+fn render_server_error(err: &anyhow::Error) -> Html<String> {
+    let body = format!(
+        r#"
+            <div class="bg-red-800 p-4 rounded mb-4">
+                <p class="text-white font-bold">Unknown server error:</p>
+                <p class="text-red-100">{}</p>
+            </div>
+            <p>
+                <a href="/" class="bg-gray-600 hover:bg-gray-700 text-white py-2 px-4 rounded">
+                    Back to index
+                </a>
+            </p>
+        "#,
+        html_escape(&err.to_string())
+    );
+    Html(render_layout("Error", &body))
+}
+
+/// Serves the web interface and the JSON API under `/api`. With `api_only`
+/// the HTML routes are left out and no browser is opened.
+/// Requests are limited per client address and `max_body` bytes per request,
+/// or `max_pdf` bytes for PDF uploads, so an exposed instance can't be flooded. Listening beyond the loopback
+/// interface, every request must carry `token`, or one generated at start.
+pub fn handle_serve(
+    state: &AppState,
+    api_only: bool,
+    bind: SocketAddr,
+    token: Option<String>,
+    max_body: usize,
+    max_pdf: usize,
+    requests_per_minute: u32,
+) -> Result<()> {
     if !state.initialized {
         print_not_initialized();
         return Ok(());
     }
 
     if state.projects.is_empty() {
-        println!("{}", "No project selected.".blue().bold());
+        print_no_project_selected();
         return Ok(());
     }
 
     // Use your existing logic for choosing the folder.
     let project_path = state.current_project.clone();
-    let app_data = AppData { project_path };
+    let (library_watcher, library_changes) = watch::channel(library::fingerprint(&project_path));
+    let config = config::load(&project_path)?;
+    let (key_format, key_pattern) = config.key_scheme(state);
+    let app_data = AppData {
+        key_format,
+        key_pattern: key_pattern.to_string(),
+        project_path,
+        openurl_resolver: state.openurl_resolver.clone(),
+        open_with: state.open_with.clone(),
+        git_auth: state.git_auth_for(&state.current_project).clone(),
+        library_changes,
+    };
+
+    let limiter = Arc::new(RateLimiter::new(
+        requests_per_minute,
+        Duration::from_secs(60),
+    ));
+    let token = match token {
+        _ if bind.ip().is_loopback() => None,
+        Some(token) => Some(token),
+        None => Some(new_token()?),
+    };
+    let access = Arc::new(Access { token: token.clone() });
 
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
-        // Build the router with our multiple routes
-        let app = Router::new()
-            // Index page (list references)
-            .route("/", get(index_handler))
-            // Upload page
-            .route("/upload", get(upload_handler).post(upload_post_handler))
-            // Add references (new)
-            .route(
-                "/add",
-                get(add_ris_bibtex_handler).post(add_ris_bibtex_post_handler),
-            )
-            // Edit page
-            .route("/edit/:id", get(edit_handler).post(edit_post_handler))
-            // Update route
-            .route("/update", post(update_handler))
-            // Provide our shared state (ris_folder, etc.)
-            .with_state(app_data);
-
-        let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
-        println!("Server running on http://{}", addr);
-
-        // Optionally open the default browser
-        if webbrowser::open(&format!("http://{}", addr)).is_err() {
-            eprintln!(
-                "Failed to open browser. Please visit http://{} manually.",
-                addr
-            );
+        if !api_only {
+            tokio::spawn(watch_library(app_data.project_path.clone(), library_watcher));
+        }
+        let api = Router::new().nest("/api", api::router());
+        let app = if api_only {
+            api
+        } else {
+            Router::new()
+                // Index page (list references)
+                .route("/", get(index_handler))
+                // Upload page
+                .route("/upload", get(upload_handler))
+                .route("/upload/pdf", post(upload_pdf_handler).layer(DefaultBodyLimit::max(max_pdf)))
+                // Add references (new)
+                .route(
+                    "/add",
+                    get(add_ris_bibtex_handler).post(add_ris_bibtex_post_handler),
+                )
+                .route("/add/confirm", post(add_confirm_post_handler))
+                // Duplicate detection and merging
+                .route("/dedupe", get(dedupe_handler))
+                .route("/merge", get(merge_handler).post(merge_post_handler))
+                .route("/merge/incoming", post(merge_incoming_handler))
+                // Co-author and keyword networks
+                .route("/graph", get(graph_handler))
+                .route("/graph/data", get(graph_data_handler))
+                // Bulk actions on the checked rows
+                .route("/bulk/export", post(bulk_export_handler))
+                .route("/bulk/delete", post(bulk_delete_handler))
+                // Edit page
+                .route("/edit/:id", get(edit_handler).post(edit_post_handler))
+                .route("/open/:id", post(open_handler))
+                // Reference details
+                .route("/reference/:id", get(reference_handler))
+                .route("/reference/:id/bibtex", get(reference_bibtex_handler))
+                .route("/reference/:id/delete", post(delete_reference_handler))
+                .route("/reference/:id/attachments/:n", post(open_attachment_handler))
+                // Update route
+                .route("/update", post(update_handler))
+                .route("/static/style.css", get(stylesheet_handler))
+                .route("/events", get(events_handler))
+                .merge(api)
+        }
+        // Provide our shared state (ris_folder, etc.)
+        .with_state(app_data)
+        .layer(DefaultBodyLimit::max(max_body))
+        .layer(middleware::from_fn_with_state(access, check_access))
+        .layer(middleware::from_fn_with_state(limiter, rate_limit));
+
+        let addr = bind;
+        if api_only {
+            println!("{}", t!("serve-api-running", url = format!("http://{}/api", addr)));
+            if let Some(token) = &token {
+                println!("{}", t!("serve-send-header", header = format!("Authorization: Bearer {}", token)));
+            }
+        } else {
+            let url = match &token {
+                Some(token) => format!("http://{}/?token={}", addr, token),
+                None => format!("http://{}", addr),
+            };
+            println!("{}", t!("serve-running", url = url));
+
+            // Optionally open the default browser
+            if webbrowser::open(&url).is_err() {
+                eprintln!("{}", t!("serve-no-browser", url = url));
+            }
         }
 
         // Run the server
         axum::Server::bind(&addr)
-            .serve(app.into_make_service())
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
             .await
             .map_err(|e| anyhow::anyhow!("Server error: {e}"))
     })
 }
 
+/// Middleware rejecting clients that exceed their request budget.
+async fn rate_limit<B>(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if limiter.check(client.ip(), Instant::now()) {
+        next.run(request).await
+    } else {
+        (StatusCode::TOO_MANY_REQUESTS, "Too many requests").into_response()
+    }
+}
+
+/// Cookie holding the access token once a page was opened with `?token=`.
+const TOKEN_COOKIE: &str = "refrs_token";
+
+/// Who may use the server: when it listens beyond the loopback interface,
+/// only clients with the access token.
+struct Access {
+    token: Option<String>,
+}
+
+fn new_token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|err| anyhow::anyhow!("Failed to generate an access token: {}", err))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Middleware refusing requests that change something when a browser sends
+/// them from another site, and requests without the access token if one is
+/// required. The token is accepted from `?token=`, which also stores it in a
+/// cookie for the pages' own requests, the cookie, or a bearer header.
+async fn check_access<B>(State(access): State<Arc<Access>>, request: Request<B>, next: Next<B>) -> Response {
+    let changes = !matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if changes && !is_same_origin(&request) {
+        return (StatusCode::FORBIDDEN, "Cross-site request refused").into_response();
+    }
+
+    let Some(token) = &access.token else {
+        return next.run(request).await;
+    };
+    let from_query = url::form_urlencoded::parse(request.uri().query().unwrap_or_default().as_bytes())
+        .any(|(name, value)| name == "token" && value == token.as_str());
+    let header = |name| request.headers().get(name).and_then(|value| value.to_str().ok());
+    let from_header = header(header::AUTHORIZATION).and_then(|value| value.strip_prefix("Bearer ")) == Some(token.as_str());
+    let from_cookie = header(header::COOKIE).is_some_and(|cookies| {
+        cookies
+            .split(';')
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .any(|(name, value)| name == TOKEN_COOKIE && value == token)
+    });
+    if !(from_query || from_header || from_cookie) {
+        return (StatusCode::UNAUTHORIZED, "Open the address refrs serve printed, with its ?token=").into_response();
+    }
+
+    let mut response = next.run(request).await;
+    if from_query {
+        let cookie = format!("{}={}; Path=/; HttpOnly; SameSite=Strict", TOKEN_COOKIE, token);
+        if let Ok(cookie) = header::HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, cookie);
+        }
+    }
+    response
+}
+
+/// Whether a browser sent the request from one of the server's own pages.
+/// Requests without an `Origin` header don't come from another site's page.
+fn is_same_origin<B>(request: &Request<B>) -> bool {
+    let Some(origin) = request.headers().get(header::ORIGIN) else {
+        return true;
+    };
+    let host = request.headers().get(header::HOST).and_then(|host| host.to_str().ok());
+    let origin_host = origin
+        .to_str()
+        .ok()
+        .and_then(|origin| origin.strip_prefix("http://").or_else(|| origin.strip_prefix("https://")));
+    host.is_some() && origin_host == host
+}
+
+/// How often the library is checked for changes made outside the server.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Publishes the library's fingerprint whenever its `.ris` files change.
+async fn watch_library(project_path: String, changes: watch::Sender<u64>) {
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+    loop {
+        interval.tick().await;
+        let path = project_path.clone();
+        let Ok(fingerprint) = tokio::task::spawn_blocking(move || library::fingerprint(&path)).await else {
+            continue;
+        };
+        changes.send_if_modified(|current| std::mem::replace(current, fingerprint) != fingerprint);
+    }
+}
+
+/// GET /events
+/// Server-sent `change` events whenever the library changes on disk.
+async fn events_handler(State(app_data): State<AppData>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut changes = app_data.library_changes.clone();
+    changes.borrow_and_update();
+    let events = futures_util::stream::unfold(changes, |mut changes| async move {
+        changes.changed().await.ok()?;
+        let fingerprint = *changes.borrow_and_update();
+        Some((Ok(Event::default().event("change").data(fingerprint.to_string())), changes))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// The pages' stylesheet, compiled into the binary so the UI works offline.
+const STYLESHEET: &str = include_str!("../../static/style.css");
+
+/// Copies the BibTeX of a reference, fetched from `/reference/<id>/bibtex`,
+/// to the clipboard. Browsers without clipboard access get the text in a new tab.
+const COPY_BIBTEX_SCRIPT: &str = r#"<script>
+function copyBibtex(button, id) {
+    const url = '/reference/' + id + '/bibtex';
+    if (!navigator.clipboard) {
+        window.open(url);
+        return;
+    }
+    fetch(url)
+        .then(response => response.ok ? response.text() : Promise.reject(response.status))
+        .then(text => navigator.clipboard.writeText(text))
+        .then(() => button.textContent = 'Copied', () => window.open(url));
+}
+</script>"#;
+
+/// GET /static/style.css
+async fn stylesheet_handler() -> impl IntoResponse {
+    (
+        [
+            (header::CONTENT_TYPE, "text/css; charset=utf-8"),
+            (header::CACHE_CONTROL, "max-age=3600"),
+        ],
+        STYLESHEET,
+    )
+}
+
 /// Helper to wrap content in a consistent HTML layout with header & footer.
 fn render_layout(page_title: &str, main_content: &str) -> String {
     format!(
@@ -405,7 +1923,8 @@ fn render_layout(page_title: &str, main_content: &str) -> String {
 <head>
     <meta charset="UTF-8" />
     <title>{page_title}</title>
-    <script src="https://cdn.tailwindcss.com"></script>
+    <link rel="stylesheet" href="/static/style.css" />
+    {COPY_BIBTEX_SCRIPT}
 </head>
 <body class="bg-gray-900 text-gray-100 min-h-screen flex flex-col">
     <header class="p-4 bg-gray-800 shadow-md mb-6">
@@ -414,6 +1933,8 @@ fn render_layout(page_title: &str, main_content: &str) -> String {
         <div class="flex justify-center gap-4">
             <a href="/add" class="bg-orange-600 hover:bg-orange-700 text-white py-2 px-4 rounded">Add RIS/BibTeX</a>
             <a href="/upload" class="bg-blue-600 hover:bg-blue-700 text-white py-2 px-4 rounded">Upload File</a>
+            <a href="/dedupe" class="bg-yellow-600 hover:bg-yellow-700 text-white py-2 px-4 rounded">Duplicates</a>
+            <a href="/graph" class="bg-teal-600 hover:bg-teal-700 text-white py-2 px-4 rounded">Graph</a>
             <form action="/update" method="post">
                 <button type="submit" class="bg-green-600 hover:bg-green-700 text-white py-2 px-4 rounded">
                     Update
@@ -434,3 +1955,27 @@ fn render_layout(page_title: &str, main_content: &str) -> String {
 "#
     )
 }
+
+/// What stays unencoded in a path segment: the unreserved characters of
+/// RFC 3986.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// Percent-encodes text for a URL path segment, where a space is `%20`
+/// rather than the `+` of query values.
+fn path_encode(text: &str) -> String {
+    utf8_percent_encode(text, PATH_SEGMENT).to_string()
+}
+
+/// Percent-encodes text for a URL query value.
+fn query_encode(text: &str) -> String {
+    url::form_urlencoded::byte_serialize(text.as_bytes()).collect()
+}
+
+/// Escapes text for safe inclusion in HTML content and attribute values.
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
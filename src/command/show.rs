@@ -1,25 +1,169 @@
+use anyhow::Result;
 use colored::Colorize;
+use console::Term;
+use serde::Serialize;
 
-use crate::{state::AppState, util::print_not_initialized};
+use crate::{
+    model::{date::Date, reference::Reference, ris::RisEntry},
+    services::{citations::CitationCount, library, tags},
+    state::AppState,
+    util::{print_json, print_no_project_selected, print_not_initialized, resolve_reference},
+};
 
-pub fn handle_show(state: &AppState) {
+/// Width of the labels in front of a reference's fields.
+const LABEL_WIDTH: usize = 14;
+
+#[derive(Serialize)]
+struct ProjectJson<'a> {
+    name: &'a str,
+    path: &'a str,
+    url: &'a str,
+    current: bool,
+}
+
+pub fn handle_show(state: &AppState, json: bool) -> Result<()> {
     if !state.initialized {
         print_not_initialized();
-        return;
+        return Ok(());
+    }
+
+    if json {
+        let projects: Vec<ProjectJson> = state
+            .projects
+            .iter()
+            .map(|project| ProjectJson {
+                name: project.name(),
+                path: &project.absolute_path,
+                url: &project.url,
+                current: project.absolute_path == state.current_project,
+            })
+            .collect();
+        return print_json(&projects);
     }
 
     if state.projects.is_empty() {
-        println!("{}", "No projects found.".blue().bold());
-        return;
+        println!("{}", t!("no-projects").blue().bold());
+        return Ok(());
     }
 
     // Header with styled text
-    println!("{}", "# Projects".green().bold());
-    println!("{:<30} | {:<50}", "Absolute Path".underline(), "URL".underline());
-    println!("{:-<80}", "-");
+    println!("{}", t!("projects-header").green().bold());
+    println!(
+        "{:<20} | {:<30} | {:<50}",
+        t!("name").underline(),
+        t!("absolute-path").underline(),
+        t!("url").underline()
+    );
+    println!("{:-<100}", "-");
 
     // Iterate through projects and display them
     for project in &state.projects {
-        println!("{:<30} | {:<50}", project.absolute_path, project.url);
+        println!("{:<20} | {:<30} | {:<50}", project.name(), project.absolute_path, project.url);
+    }
+
+    Ok(())
+}
+
+/// Prints one reference with its fields labelled and the abstract wrapped
+/// to the terminal width.
+pub fn handle_show_ref(state: &AppState, id: Option<&str>) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let Some(reference) = resolve_reference(&entries, id)? else {
+        return Ok(());
+    };
+    let entry = &reference.entry;
+    let values = |tags: &[&str]| -> Vec<String> {
+        tags.iter()
+            .flat_map(|tag| entry.fields.get(*tag).into_iter().flatten())
+            .filter(|value| !value.trim().is_empty())
+            .cloned()
+            .collect()
+    };
+    let field = |label: String, value: String| {
+        if !value.is_empty() {
+            println!("{}{}", format!("{:<LABEL_WIDTH$}", label).dimmed(), value);
+        }
+    };
+
+    let title = values(&["TI", "T1"]).join(" ");
+    println!("{}", if title.is_empty() { t!("untitled") } else { title }.bold());
+    println!("{} {}", reference.key().cyan(), format!("[{}]", entry.ty.to_str()).dimmed());
+    println!();
+
+    field(t!("ref-authors"), values(&["AU", "A1"]).join("; "));
+    field(t!("ref-editors"), values(&["ED", "A2"]).join("; "));
+    field(t!("ref-venue"), venue(entry));
+    field(t!("ref-year"), Date::of(entry).map(|date| date.to_string()).unwrap_or_default());
+    field(t!("ref-publisher"), values(&["PB"]).join("; "));
+    for (label, tag) in [("DOI".to_string(), "DO"), (t!("url"), "UR")] {
+        for link in values(&[tag]) {
+            field(label.clone(), link.underline().to_string());
+        }
+    }
+    field(t!("ref-tags"), tags::tags(entry).join(", "));
+    if let Some(cited) = CitationCount::of(entry) {
+        field(t!("ref-cited"), t!("ref-cited-count", count = cited.count, checked = cited.checked.to_string()));
+    }
+
+    let width = (Term::stdout().size().1 as usize).saturating_sub(LABEL_WIDTH).max(20);
+    for (label, text) in [(t!("ref-abstract"), values(&["AB", "N2"]).join(" ")), (t!("ref-note"), values(&["N1"]).join(" "))] {
+        for (i, line) in wrap(&text, width).iter().enumerate() {
+            field(if i == 0 { label.clone() } else { String::new() }, line.clone());
+        }
+    }
+
+    let attachments = Reference::new(&reference.id, &reference.path.to_string_lossy(), entry).attachments;
+    for (i, attachment) in attachments.iter().enumerate() {
+        field(if i == 0 { t!("ref-attachments") } else { String::new() }, attachment.clone());
+    }
+    field(t!("ref-file"), reference.path.display().to_string());
+
+    Ok(())
+}
+
+/// `Journal of Pets, 7(2), 10–20`
+fn venue(entry: &RisEntry) -> String {
+    let get = |tags: &[&str]| tags.iter().find_map(|tag| entry.get_field(tag)).map(|v| v.trim().to_string());
+    let mut parts: Vec<String> = get(&["T2", "JO", "JF", "BT"]).into_iter().collect();
+    match (get(&["VL"]), get(&["IS"])) {
+        (Some(volume), Some(issue)) => parts.push(format!("{}({})", volume, issue)),
+        (Some(volume), None) => parts.push(volume),
+        (None, Some(issue)) => parts.push(format!("({})", issue)),
+        (None, None) => {}
+    }
+    match (get(&["SP"]), get(&["EP"])) {
+        (Some(start), Some(end)) => parts.push(format!("{}–{}", start, end)),
+        (Some(start), None) => parts.push(start),
+        _ => {}
+    }
+    parts.join(", ")
+}
+
+/// Splits `text` into lines of at most `width` characters, between words.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
     }
+    lines
 }
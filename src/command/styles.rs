@@ -0,0 +1,45 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::services::styles;
+
+pub fn handle_install(name_or_url: &str, locale: bool) -> Result<()> {
+    if locale {
+        let code = styles::install_locale(name_or_url)?;
+        println!("{}", t!("styles-locale-installed", code = code.bold()).green());
+    } else {
+        let name = styles::install_style(name_or_url)?;
+        println!("{}", t!("styles-installed", name = name.bold()).green());
+    }
+    Ok(())
+}
+
+pub fn handle_list() -> Result<()> {
+    let installed = styles::installed_styles()?;
+    println!("{}", t!("styles-header").green().bold());
+    if installed.is_empty() {
+        println!("{}", t!("styles-none", command = "refrs styles install apa".bold()));
+    }
+    for style in installed {
+        println!("{:<30} {}", style.name, style.title.unwrap_or_default());
+    }
+
+    let locales = styles::installed_locales()?;
+    println!("{}", t!("styles-locales-header").green().bold());
+    if locales.is_empty() {
+        println!("{}", t!("styles-no-locales"));
+    } else {
+        println!("{}", locales.join(", "));
+    }
+
+    Ok(())
+}
+
+pub fn handle_remove(name: &str) -> Result<()> {
+    if styles::remove_style(name)? {
+        println!("{}", t!("styles-removed", name = name.bold()).green());
+    } else {
+        println!("{}{}", t!("error").red().bold(), t!("styles-unknown", name = name));
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::model::ris::RisEntry;
+use crate::repo;
+use crate::services::library::{self, LibraryEntry};
+use crate::services::tags;
+use crate::state::AppState;
+use crate::util::{print_entry_diff, print_no_project_selected, print_not_initialized, resolve_reference};
+
+/// Adds or, with `remove`, removes tags of a reference and commits the change.
+pub fn handle_tag(state: &AppState, id: &str, names: &[String], remove: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    let Some(reference) = resolve_reference(&entries, Some(id))? else {
+        return Ok(());
+    };
+
+    let mut updated = reference.entry.clone();
+    let mut changed = Vec::new();
+    for name in names {
+        let done = if remove {
+            tags::remove_tag(&mut updated, name)
+        } else {
+            tags::add_tag(&mut updated, name)
+        };
+        if done {
+            changed.push(name.trim());
+        }
+    }
+
+    if changed.is_empty() {
+        let id = reference.id.bold();
+        if remove {
+            println!("{}", t!("tag-none-removed", id = id));
+        } else {
+            println!("{}", t!("tag-none-added", id = id));
+        }
+        return Ok(());
+    }
+
+    print_entry_diff(&reference.entry, &updated);
+    save_tags(&state.current_project, reference, &updated, &changed, remove)
+}
+
+/// Writes the retagged entry and commits it as "Tagged id: a, b" or "Untagged id: a, b".
+pub fn save_tags(
+    project_path: &str,
+    reference: &LibraryEntry,
+    updated: &RisEntry,
+    changed: &[&str],
+    remove: bool,
+) -> Result<()> {
+    library::replace_entry(reference, Some(updated))?;
+
+    let verb = if remove { "Untagged" } else { "Tagged" };
+    repo::add_all(project_path)?;
+    repo::commit(project_path, &format!("{} {}: {}", verb, reference.id, changed.join(", ")))
+}
+
+/// Lists the tags used in the current project with how many references have them.
+pub fn handle_tag_list(state: &AppState) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let counts = tags::tag_counts(&library::load_entries(&state.current_project)?);
+    if counts.is_empty() {
+        println!("{}", t!("tag-none-yet", command = "refrs tag add <id> <tag>").blue().bold());
+        return Ok(());
+    }
+
+    for (tag, count) in counts {
+        println!("{:>4}  {}", count, tag);
+    }
+    Ok(())
+}
@@ -0,0 +1,360 @@
+use std::io::IsTerminal;
+use std::ptr;
+
+use anyhow::{Error, Result};
+use arboard::Clipboard;
+use colored::Colorize;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::command::cite::{self, CiteFormat};
+use crate::command::remove;
+use crate::command::tag;
+use crate::services::library::{self, LibraryEntry};
+use crate::services::open_with;
+use crate::services::query::Query;
+use crate::services::tags;
+use crate::state::AppState;
+use crate::util::{describe_entry, print_no_project_selected, print_not_initialized};
+
+/// Browses the references matching `query` in a full-screen list with a
+/// preview, fuzzy search and keys to open, cite, tag and delete them.
+pub fn handle_tui(state: &AppState, query: &Query) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    if !std::io::stdout().is_terminal() {
+        println!("{}{}", t!("error").red().bold(), t!("tui-needs-terminal"));
+        return Ok(());
+    }
+
+    let mut browser = Browser::new(query.run(library::load_entries(&state.current_project)?));
+    // The alternate screen gives the shell its scrollback back when we quit.
+    let mut terminal = ratatui::init();
+    let result = run(state, query, &mut terminal, &mut browser);
+    ratatui::restore();
+    result
+}
+
+fn run(state: &AppState, query: &Query, terminal: &mut DefaultTerminal, browser: &mut Browser) -> Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, browser))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Ok(());
+        }
+
+        match &mut browser.mode {
+            Mode::Search => match key.code {
+                KeyCode::Char(c) => browser.set_search(format!("{}{}", browser.search, c)),
+                KeyCode::Backspace => {
+                    let mut search = browser.search.clone();
+                    search.pop();
+                    browser.set_search(search);
+                }
+                KeyCode::Enter => browser.mode = Mode::Browse,
+                KeyCode::Esc => {
+                    browser.mode = Mode::Browse;
+                    browser.set_search(String::new());
+                }
+                KeyCode::Up => browser.move_by(-1),
+                KeyCode::Down => browser.move_by(1),
+                _ => {}
+            },
+            Mode::Tag(name) => match key.code {
+                KeyCode::Char(c) => name.push(c),
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Enter => {
+                    let name = std::mem::take(name);
+                    browser.mode = Mode::Browse;
+                    browser.status = toggle_tag(state, browser, name.trim()).unwrap_or_else(error_status);
+                    reload(state, query, browser);
+                    // Committing prints progress over the screen.
+                    terminal.clear()?;
+                }
+                KeyCode::Esc => browser.mode = Mode::Browse,
+                _ => {}
+            },
+            Mode::Delete => {
+                browser.mode = Mode::Browse;
+                if key.code == KeyCode::Char('y') {
+                    browser.status = delete(state, browser).unwrap_or_else(error_status);
+                    reload(state, query, browser);
+                    terminal.clear()?;
+                }
+            }
+            Mode::Browse => {
+                browser.status.clear();
+                let page = browser.rows as isize;
+                match key.code {
+                    KeyCode::Char('q') => return Ok(()),
+                    KeyCode::Esc if !browser.search.is_empty() => browser.set_search(String::new()),
+                    KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('j') | KeyCode::Down => browser.move_by(1),
+                    KeyCode::Char('k') | KeyCode::Up => browser.move_by(-1),
+                    KeyCode::PageDown => browser.move_by(page),
+                    KeyCode::PageUp => browser.move_by(-page),
+                    KeyCode::Char('g') | KeyCode::Home => browser.move_by(isize::MIN),
+                    KeyCode::Char('G') | KeyCode::End => browser.move_by(isize::MAX),
+                    KeyCode::Char('/') => browser.mode = Mode::Search,
+                    KeyCode::Char('o') | KeyCode::Enter => browser.status = open(state, browser),
+                    KeyCode::Char('c') => browser.status = copy_citation(browser, CiteFormat::Latex),
+                    KeyCode::Char('C') => browser.status = copy_citation(browser, CiteFormat::Pandoc),
+                    KeyCode::Char('t') if browser.current().is_some() => browser.mode = Mode::Tag(String::new()),
+                    KeyCode::Char('d') if browser.current().is_some() => browser.mode = Mode::Delete,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// What the keys do at the moment.
+enum Mode {
+    Browse,
+    /// Typing on the search row
+    Search,
+    /// Typing the tag to add to or remove from the selected reference
+    Tag(String),
+    /// Asking whether to delete the selected reference
+    Delete,
+}
+
+/// The query's references and the ones among them matching the search.
+struct Browser {
+    entries: Vec<LibraryEntry>,
+    /// Indices into `entries`, best match first
+    visible: Vec<usize>,
+    selected: usize,
+    /// Selection and scroll position of the drawn list
+    list: ListState,
+    /// Rows the list had when last drawn, for paging
+    rows: usize,
+    search: String,
+    mode: Mode,
+    status: String,
+}
+
+impl Browser {
+    fn new(entries: Vec<LibraryEntry>) -> Browser {
+        let mut browser = Browser {
+            entries,
+            visible: Vec::new(),
+            selected: 0,
+            list: ListState::default(),
+            rows: 1,
+            search: String::new(),
+            mode: Mode::Browse,
+            status: String::new(),
+        };
+        browser.filter();
+        browser
+    }
+
+    fn current(&self) -> Option<&LibraryEntry> {
+        self.visible.get(self.selected).map(|&i| &self.entries[i])
+    }
+
+    fn set_search(&mut self, search: String) {
+        self.search = search;
+        self.selected = 0;
+        self.filter();
+    }
+
+    fn filter(&mut self) {
+        self.visible = if self.search.trim().is_empty() {
+            (0..self.entries.len()).collect()
+        } else {
+            library::fuzzy_find(&self.entries, &self.search)
+                .into_iter()
+                .filter_map(|found| self.entries.iter().position(|e| ptr::eq(e, found)))
+                .collect()
+        };
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+    }
+
+    fn move_by(&mut self, rows: isize) {
+        let last = self.visible.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize).saturating_add(rows).clamp(0, last) as usize;
+    }
+}
+
+/// Loads the references again after a change, keeping the selection where it can.
+fn reload(state: &AppState, query: &Query, browser: &mut Browser) {
+    let entries = match library::load_entries(&state.current_project) {
+        Ok(entries) => entries,
+        Err(e) => {
+            browser.status = error_status(e);
+            return;
+        }
+    };
+    let selected = browser.current().map(|e| e.id.clone());
+    browser.entries = query.run(entries);
+    browser.filter();
+    if let Some(position) = selected.and_then(|id| {
+        browser.visible.iter().position(|&i| browser.entries[i].id == id)
+    }) {
+        browser.selected = position;
+    }
+}
+
+fn error_status(error: Error) -> String {
+    format!("{}{:#}", t!("error"), error)
+}
+
+fn open(state: &AppState, browser: &Browser) -> String {
+    let Some(reference) = browser.current() else {
+        return String::new();
+    };
+    let Some(target) = open_with::link_target(&reference.entry) else {
+        return t!("open-no-link", id = reference.id);
+    };
+    match open_with::open(&target, &state.open_with) {
+        Ok(()) => format!("{} {}", t!("open-opening"), target.location()),
+        Err(e) => error_status(e),
+    }
+}
+
+fn copy_citation(browser: &Browser, format: CiteFormat) -> String {
+    let Some(reference) = browser.current() else {
+        return String::new();
+    };
+    let citation = cite::citation(reference, format);
+    match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(citation.clone())) {
+        Ok(()) => format!("{} {}", t!("cite-copied"), citation),
+        Err(e) => error_status(e.into()),
+    }
+}
+
+/// Adds the tag, or removes it when the reference has it already.
+fn toggle_tag(state: &AppState, browser: &Browser, name: &str) -> Result<String> {
+    let Some(reference) = browser.current() else {
+        return Ok(String::new());
+    };
+    if name.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut updated = reference.entry.clone();
+    let remove = tags::has_tag(&updated, name);
+    if remove {
+        tags::remove_tag(&mut updated, name);
+    } else {
+        tags::add_tag(&mut updated, name);
+    }
+    tag::save_tags(&state.current_project, reference, &updated, &[name], remove)?;
+
+    Ok(if remove {
+        t!("tui-untagged", id = reference.id, tag = name)
+    } else {
+        t!("tui-tagged", id = reference.id, tag = name)
+    })
+}
+
+fn delete(state: &AppState, browser: &Browser) -> Result<String> {
+    let Some(reference) = browser.current() else {
+        return Ok(String::new());
+    };
+    remove::remove_reference(&state.current_project, &browser.entries, reference)?;
+    Ok(t!("remove-done", id = reference.id))
+}
+
+fn draw(frame: &mut Frame, browser: &mut Browser) {
+    let dimmed = Style::new().add_modifier(Modifier::DIM);
+    let reversed = Style::new().add_modifier(Modifier::REVERSED);
+    let [header, search, body, status] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [list_area, preview_area] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(body);
+    browser.rows = list_area.height.max(1) as usize;
+
+    let title = t!("tui-header", shown = browser.visible.len(), total = browser.entries.len());
+    frame.render_widget(Paragraph::new(format!(" {}", title)).style(reversed), header);
+
+    let search_line = match browser.mode {
+        Mode::Search => Line::from(format!("/{}█", browser.search)),
+        _ if browser.search.is_empty() => Line::styled(t!("tui-search-hint"), dimmed),
+        _ => Line::from(format!("/{}", browser.search)),
+    };
+    frame.render_widget(Paragraph::new(search_line), search);
+
+    let items: Vec<ListItem> = browser
+        .visible
+        .iter()
+        .map(|&i| {
+            let reference = &browser.entries[i];
+            ListItem::new(format!("{}  {}", reference.key(), describe_entry(&reference.entry)))
+        })
+        .collect();
+    browser.list.select((!browser.visible.is_empty()).then_some(browser.selected));
+    frame.render_stateful_widget(List::new(items).highlight_style(reversed), list_area, &mut browser.list);
+
+    let lines = browser.current().map(preview).unwrap_or_default();
+    let block = Block::new().borders(Borders::LEFT).border_style(dimmed);
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(block), preview_area);
+
+    let id = browser.current().map(|reference| reference.id.as_str()).unwrap_or_default();
+    let status_line = match &browser.mode {
+        Mode::Tag(name) => Line::from(format!("{} {}█", t!("tui-tag-prompt", id = id), name)),
+        Mode::Delete => Line::styled(
+            t!("tui-delete-confirm", id = id),
+            Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        _ if browser.status.is_empty() => Line::styled(t!("tui-help"), dimmed),
+        _ => Line::from(browser.status.as_str()),
+    };
+    frame.render_widget(Paragraph::new(status_line), status);
+}
+
+/// The preview of a reference: title, every field and the abstract.
+fn preview(reference: &LibraryEntry) -> Vec<Line<'static>> {
+    let entry = &reference.entry;
+    let dimmed = Style::new().add_modifier(Modifier::DIM);
+    let mut lines = Vec::new();
+    if let Some(title) = entry.get_field("TI") {
+        lines.push(Line::styled(title.clone(), Style::new().add_modifier(Modifier::BOLD)));
+    }
+    lines.push(Line::from(vec![
+        Span::styled(reference.key().to_string(), Style::new().fg(Color::Cyan)),
+        Span::raw("  "),
+        Span::styled(entry.ty.to_str().to_string(), dimmed),
+    ]));
+    lines.push(Line::default());
+
+    let mut fields: Vec<(&String, &Vec<String>)> = entry.fields.iter().filter(|(tag, _)| *tag != "AB").collect();
+    fields.sort_by_key(|(tag, _)| *tag);
+    for (tag, values) in fields {
+        for value in values {
+            lines.push(Line::from(vec![Span::styled(tag.clone(), dimmed), Span::raw(format!("  {}", value))]));
+        }
+    }
+
+    if let Some(abstract_) = entry.get_field("AB") {
+        lines.push(Line::default());
+        lines.push(Line::from(abstract_.clone()));
+    }
+    lines
+}
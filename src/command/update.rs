@@ -1,35 +1,100 @@
+use std::io::IsTerminal;
 use std::path::Path;
 
 use anyhow::Result;
 use colored::Colorize;
 use crate::state::AppState;
-use crate::repo;
-use crate::util::print_not_initialized;
+use crate::repo::{self, Conflict, Resolution};
+use crate::services::merge;
+use crate::util::{print_no_project_selected, print_not_initialized};
 
-pub fn handle_update(state: &AppState) -> Result<()> {
+/// Pulls and pushes the current project, or with `dry_run` lists the
+/// commits that would be pulled and pushed.
+pub fn handle_update(state: &AppState, dry_run: bool) -> Result<()> {
     if !state.initialized {
         print_not_initialized();
         return Ok(());
     }
 
     if state.current_project.is_empty() {
-        println!("{}", "No project selected.".blue().bold());
-        println!("To select a project use: {}", "refrs workspace set".bold());
+        print_no_project_selected();
+        println!("{}", t!("select-project-hint", command = "refrs workspace set".bold()));
         return Ok(());
     }
 
     if !Path::new(state.current_project.as_str()).exists() {
-        println!("{}{}{}{}",
-                 "Error: ".bold().red(),
-                 "Failed because path '",
-                 state.current_project.as_str().underline().bold(),
-                 "' is not existing."
+        println!(
+            "{}{}",
+            t!("error").bold().red(),
+            t!("path-missing", path = state.current_project.as_str().underline().bold())
         );
         return Ok(());
     }
 
-    repo::pull_rebase(&state.current_project)?;
-    repo::push(&state.current_project)?;
+    if let Ok(branch) = repo::current_branch(&state.current_project) {
+        println!("{}", t!("updating-branch", branch = branch.bold()));
+    }
+    let auth = state.git_auth_for(&state.current_project);
+    if dry_run {
+        let preview = repo::sync_preview(&state.current_project, auth)?;
+        let print = |message: String, commits: &[String]| {
+            println!("{}", message.bold());
+            for commit in commits {
+                println!("  {}", commit);
+            }
+        };
+        print(t!("would-pull", count = preview.incoming.len(), remote = preview.remote), &preview.incoming);
+        print(t!("would-push", count = preview.outgoing.len(), remote = preview.remote), &preview.outgoing);
+        println!("{}", t!("dry-run-done").yellow());
+        return Ok(());
+    }
+    repo::pull_rebase(&state.current_project, auth, resolve_conflict)?;
+    repo::push(&state.current_project, auth)?;
 
     Ok(())
 }
+
+/// Merges conflicting RIS files field by field, and asks which version to
+/// keep of anything else.
+fn resolve_conflict(conflict: &Conflict) -> Result<Resolution> {
+    if let Some((merged, overridden)) = merge::merge_ris_conflict(conflict) {
+        print!("{} {}", "Merged".green().bold(), conflict.path);
+        if !overridden.is_empty() {
+            let side = if conflict.local_is_newer { "local" } else { "remote" };
+            print!(", keeping the newer {} {}", side, overridden.join(", "));
+        }
+        println!();
+        return Ok(Resolution::Content(merged.into_bytes()));
+    }
+
+    if !std::io::stdin().is_terminal() {
+        return Ok(Resolution::Abort);
+    }
+
+    println!("{}", t!("update-conflict", path = conflict.path.bold()).yellow());
+    let items = [
+        match conflict.remote {
+            Some(_) => t!("update-keep-remote"),
+            None => t!("update-delete-remote"),
+        },
+        match conflict.local {
+            Some(_) => t!("update-keep-local"),
+            None => t!("update-delete-local"),
+        },
+        t!("update-abort"),
+    ];
+    let selected = dialoguer::Select::new()
+        .with_prompt(t!("update-resolve"))
+        .items(&items)
+        .default(2)
+        .interact()?;
+    let content = match selected {
+        0 => &conflict.remote,
+        1 => &conflict.local,
+        _ => return Ok(Resolution::Abort),
+    };
+    Ok(match content {
+        Some(content) => Resolution::Content(content.clone()),
+        None => Resolution::Delete,
+    })
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::model::ris::ReferenceType;
+use crate::repo;
+use crate::services::crossref::{self, Journal};
+use crate::services::library;
+use crate::services::venues::{VenueAliases, ALIASES_FILE};
+use crate::state::AppState;
+use crate::util::{print_entry_diff, print_no_project_selected, print_not_initialized};
+
+/// Fills in missing ISSNs (SN) from the journal name (T2), and missing journal
+/// names from the ISSN, using the Crossref journals API.
+pub fn handle_complete_venues(state: &AppState) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let entries = library::load_entries(&state.current_project)?;
+    // Many entries share a journal, so look each one up only once.
+    let mut by_title: HashMap<String, Option<Journal>> = HashMap::new();
+    let mut by_issn: HashMap<String, Option<Journal>> = HashMap::new();
+    let mut completed = Vec::new();
+
+    for reference in &entries {
+        if !matches!(
+            reference.entry.ty,
+            ReferenceType::Journal | ReferenceType::MagazineArticle | ReferenceType::ElectronicArticle
+        ) {
+            continue;
+        }
+
+        let title = reference.entry.get_field("T2").cloned();
+        let issn = reference.entry.get_field("SN").cloned();
+        let (tag, journal) = match (title, issn) {
+            (Some(title), None) => {
+                if !by_title.contains_key(&title) {
+                    let found = lookup(&title, crossref::journal_by_title(&title));
+                    by_title.insert(title.clone(), found);
+                }
+                ("SN", by_title[&title].as_ref().and_then(|j| j.issns.first()))
+            }
+            (None, Some(issn)) => {
+                if !by_issn.contains_key(&issn) {
+                    let found = lookup(&issn, crossref::journal_by_issn(&issn));
+                    by_issn.insert(issn.clone(), found);
+                }
+                ("T2", by_issn[&issn].as_ref().map(|j| &j.title))
+            }
+            _ => continue,
+        };
+
+        let Some(value) = journal else {
+            continue;
+        };
+        let mut updated = reference.entry.clone();
+        updated.fields.insert(tag.to_string(), vec![value.clone()]);
+
+        println!("{}", reference.id.bold());
+        print_entry_diff(&reference.entry, &updated);
+        library::replace_entry(reference, Some(&updated))?;
+        completed.push(reference.id.clone());
+    }
+
+    if completed.is_empty() {
+        println!("{}", t!("venues-nothing").blue().bold());
+        return Ok(());
+    }
+
+    repo::add_all(&state.current_project)?;
+    repo::commit(
+        &state.current_project,
+        &format!("Completed venues of {}", completed.join(", ")),
+    )?;
+    println!("{}", t!("venues-completed", count = completed.len()).green().bold());
+    Ok(())
+}
+
+/// Reports a failed lookup and carries on with the other entries.
+fn lookup(query: &str, result: Result<Option<Journal>>) -> Option<Journal> {
+    match result {
+        Ok(Some(journal)) => Some(journal),
+        Ok(None) => {
+            println!("{}", t!("venues-not-found", query = query).yellow());
+            None
+        }
+        Err(err) => {
+            println!("{}{:#}", t!("error").red().bold(), err);
+            None
+        }
+    }
+}
+
+/// Rewrites journal and book titles (T2) that are aliases in the project's
+/// `venues.yaml` to their canonical name. `add` is an `alias=canonical` pair
+/// to record first.
+pub fn handle_normalize_venues(state: &AppState, add: Option<&str>) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.current_project.is_empty() {
+        print_no_project_selected();
+        return Ok(());
+    }
+
+    let mut aliases = VenueAliases::load(&state.current_project)?;
+    if let Some(pair) = add {
+        let (alias, canonical) = pair
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected ALIAS=CANONICAL, got {:?}", pair))?;
+        aliases.add(alias, canonical);
+        aliases.save(&state.current_project)?;
+        println!("{}", t!("venues-alias-added", alias = alias.trim(), canonical = canonical.trim()).green());
+    }
+
+    if aliases.table.is_empty() {
+        println!("{}", t!("venues-no-aliases", file = ALIASES_FILE).blue().bold());
+        return Ok(());
+    }
+
+    let mut normalized = Vec::new();
+    for reference in library::load_entries(&state.current_project)? {
+        let Some(venue) = reference.entry.get_field("T2") else {
+            continue;
+        };
+        let Some(canonical) = aliases.canonical(venue) else {
+            continue;
+        };
+        if canonical == venue {
+            continue;
+        }
+
+        let mut updated = reference.entry.clone();
+        updated.fields.insert("T2".to_string(), vec![canonical.to_string()]);
+        println!("{}", reference.id.bold());
+        print_entry_diff(&reference.entry, &updated);
+        library::replace_entry(&reference, Some(&updated))?;
+        normalized.push(reference.id);
+    }
+
+    if normalized.is_empty() && add.is_none() {
+        println!("{}", t!("venues-normalized").green().bold());
+        return Ok(());
+    }
+
+    let message = if normalized.is_empty() {
+        "Added venue alias".to_string()
+    } else {
+        format!("Normalized venues of {}", normalized.join(", "))
+    };
+    repo::add_all(&state.current_project)?;
+    repo::commit(&state.current_project, &message)?;
+    println!("{}", t!("venues-normalized-count", count = normalized.len()).green().bold());
+    Ok(())
+}
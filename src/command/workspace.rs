@@ -1,32 +1,37 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
 use crate::{state::{save_state, AppState}, util::print_not_initialized};
 use anyhow::Result;
 
-pub fn handle_set(state: &mut AppState) ->  Result<()> {
+/// Makes the project at `path` current, or the one picked in a fuzzy finder
+/// over project names and URLs when no path is given.
+pub fn handle_set(state: &mut AppState, path: Option<&str>) ->  Result<()> {
     if !state.initialized {
         print_not_initialized();
         return Ok(());
     }
 
     if state.projects.is_empty() {
-        println!("No projects available to select.");
+        println!("{}", t!("no-projects-to-select"));
         return Ok(());
     }
 
-    let selected_index = dialoguer::Select::new()
-        .with_prompt("Select a workspace to set as current")
-        .items(
-            &state
-                .projects
-                .iter()
-                .map(|p| &p.absolute_path)
-                .collect::<Vec<_>>(),
-        )
-        .default(0)
-        .interact()?;
+    let selected_index = match path {
+        Some(path) => match find_project(state, path) {
+            Some(index) => index,
+            None => anyhow::bail!(t!("workspace-not-registered", path = path)),
+        },
+        None => match pick_project(state, t!("select-workspace"), "set")? {
+            Some(index) => index,
+            None => return Ok(()),
+        },
+    };
 
-    state.current_project = state.projects[selected_index].absolute_path.clone();
-    save_state(&state)?;
-    println!("Current workspace set to: {}", state.current_project);
+    let project = &state.projects[selected_index];
+    state.current_project = project.absolute_path.clone();
+    println!("{}", t!("workspace-set", name = project.name(), path = project.absolute_path));
+    save_state(state)?;
 
     Ok(())
 }
@@ -37,9 +42,133 @@ pub fn handle_get(state: &AppState) {
         return;
     }
 
-    if state.current_project.is_empty() {
-        println!("No current project is set.");
-    } else {
-        println!("Current project: {}", state.current_project);
+    match state.projects.iter().find(|p| p.absolute_path == state.current_project) {
+        Some(project) => println!("{}", t!("current-project", name = project.name(), path = project.absolute_path)),
+        None if state.current_project.is_empty() => println!("{}", t!("no-current-project")),
+        None => println!("{}", t!("current-project", name = "?", path = state.current_project)),
     }
 }
+
+/// Gives the project `old`, a name or path, the name `new`.
+pub fn handle_rename(state: &mut AppState, old: &str, new: &str) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    let new = new.trim();
+    if new.is_empty() {
+        anyhow::bail!(t!("workspace-name-empty"));
+    }
+    let Some(index) = find_project(state, old) else {
+        anyhow::bail!(t!("workspace-not-registered", path = old));
+    };
+    if state.projects.iter().enumerate().any(|(i, p)| i != index && p.name() == new) {
+        anyhow::bail!(t!("workspace-name-taken", name = new));
+    }
+
+    let project = &mut state.projects[index];
+    let previous = project.name().to_string();
+    project.name = Some(new.to_string());
+    save_state(state)?;
+    println!("{}", t!("workspace-renamed", old = previous, new = new));
+
+    Ok(())
+}
+
+/// Unregisters the project at `path`, or a picked one, and with
+/// `delete_files` also deletes its folder.
+pub fn handle_remove(state: &mut AppState, path: Option<&str>, delete_files: bool, yes: bool) -> Result<()> {
+    if !state.initialized {
+        print_not_initialized();
+        return Ok(());
+    }
+
+    if state.projects.is_empty() {
+        println!("{}", t!("no-projects"));
+        return Ok(());
+    }
+
+    let index = match path {
+        Some(path) => match find_project(state, path) {
+            Some(index) => index,
+            None => anyhow::bail!(t!("workspace-not-registered", path = path)),
+        },
+        None => match pick_project(state, t!("select-workspace-to-remove"), "remove")? {
+            Some(index) => index,
+            None => return Ok(()),
+        },
+    };
+    let folder = state.projects[index].absolute_path.clone();
+
+    if delete_files && !yes {
+        let delete = dialoguer::Confirm::new()
+            .with_prompt(t!("confirm-delete-workspace", path = folder))
+            .default(false)
+            .interact()?;
+        if !delete {
+            return Ok(());
+        }
+    }
+
+    state.projects.remove(index);
+    let was_current = state.current_project == folder;
+    if was_current {
+        state.current_project.clear();
+    }
+    save_state(state)?;
+    println!("{}", t!("workspace-removed", path = folder));
+
+    if delete_files && Path::new(&folder).exists() {
+        fs::remove_dir_all(&folder)?;
+        println!("{}", t!("workspace-files-deleted", path = folder));
+    }
+    if was_current {
+        println!("{}", t!("select-project-hint", command = "refrs workspace set"));
+    }
+
+    Ok(())
+}
+
+/// Index of the registered project named `path`, else of the one at
+/// `path`, which may be relative or start with `~/`.
+fn find_project(state: &AppState, path: &str) -> Option<usize> {
+    if let Some(index) = state.projects.iter().position(|p| p.name() == path) {
+        return Some(index);
+    }
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => dirs_next::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    };
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let wanted = canonical(&path);
+    state
+        .projects
+        .iter()
+        .position(|p| canonical(Path::new(&p.absolute_path)) == wanted)
+}
+
+/// Lets the user pick a registered project for `refrs workspace <command>`.
+fn pick_project(state: &AppState, prompt: String, command: &str) -> Result<Option<usize>> {
+    if !console::Term::stderr().is_term() {
+        anyhow::bail!(t!("workspace-path-needed", command = format!("refrs workspace {} <path>", command)));
+    }
+
+    let items: Vec<String> = state
+        .projects
+        .iter()
+        .map(|p| format!("{}  {}", p.name(), p.url))
+        .collect();
+    let current = state
+        .projects
+        .iter()
+        .position(|p| p.absolute_path == state.current_project)
+        .unwrap_or(0);
+
+    Ok(dialoguer::FuzzySelect::new()
+        .with_prompt(prompt)
+        .items(&items)
+        .default(current)
+        .max_length(15)
+        .interact_opt()?)
+}
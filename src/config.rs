@@ -0,0 +1,368 @@
+//! Settings: per project from a `.refrs.toml` in the project root, and per
+//! user from `config.yaml` in [`state::config_dir`].
+//!
+//! A project's `.refrs.toml`:
+//!
+//! ```toml
+//! ris_folder = "references"
+//! layout = "year"
+//! filename_template = "{year}_{key}"
+//! key_pattern = "[auth][year]"
+//! auto_commit = false
+//! ```
+//!
+//! Every setting is optional; a project without the file behaves as before.
+
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use toml_edit::{DocumentMut, Item};
+
+use crate::model::ris::RisEntry;
+use crate::services::citekey::KeyFormat;
+use crate::services::export::ExportFormat;
+use crate::state::{self, AppState};
+
+/// Name of the configuration file in a project root.
+pub const CONFIG_FILE: &str = ".refrs.toml";
+
+/// How new `.ris` files are arranged inside the project's `.ris` folder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// All in the folder itself
+    #[default]
+    Flat,
+    /// In a subfolder per publication year, such as `2021/`
+    Year,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectConfig {
+    /// Folder inside the project holding the `.ris` files
+    pub ris_folder: String,
+    pub layout: Layout,
+    /// File name of new entries without `.ris`, with `{key}`, `{year}` and
+    /// `{type}` replaced. Defaults to the citation key.
+    pub filename_template: Option<String>,
+    /// Citation key pattern for new entries, replacing the user's key format
+    pub key_pattern: Option<String>,
+    /// Whether changes are committed, or only staged for a manual commit
+    pub auto_commit: bool,
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        ProjectConfig {
+            ris_folder: "ris_files".to_string(),
+            layout: Layout::Flat,
+            filename_template: None,
+            key_pattern: None,
+            auto_commit: true,
+        }
+    }
+}
+
+impl ProjectConfig {
+    pub fn parse(text: &str) -> Result<ProjectConfig> {
+        let document: DocumentMut = text.parse().map_err(|e| anyhow!("{}", e))?;
+        let mut config = ProjectConfig::default();
+        for (key, item) in document.iter() {
+            match key {
+                "ris_folder" => config.ris_folder = string(key, item)?,
+                "layout" => {
+                    config.layout = parse_enum(&string(key, item)?)?.ok_or_else(|| anyhow!("'layout' can't be empty"))?
+                }
+                "filename_template" => config.filename_template = Some(string(key, item)?),
+                "key_pattern" => config.key_pattern = Some(string(key, item)?),
+                "auto_commit" => {
+                    config.auto_commit = item.as_bool().ok_or_else(|| anyhow!("'{}' must be true or false", key))?
+                }
+                _ => bail!("Unknown setting '{}'", key),
+            }
+        }
+
+        let folder = Path::new(&config.ris_folder);
+        if config.ris_folder.trim().is_empty() || folder.is_absolute() || config.ris_folder.contains("..") {
+            bail!("'ris_folder' must be a folder inside the project");
+        }
+        Ok(config)
+    }
+
+    /// Key format and pattern for new entries: the project's pattern when it
+    /// sets one, else the user's settings.
+    pub fn key_scheme<'a>(&'a self, state: &'a AppState) -> (KeyFormat, &'a str) {
+        match &self.key_pattern {
+            Some(pattern) => (KeyFormat::Pattern, pattern),
+            None => (state.key_format, state.key_pattern()),
+        }
+    }
+
+    /// Subfolder of the `.ris` folder a new entry goes into. Entries without
+    /// a year stay in the folder itself.
+    pub fn entry_folder(&self, entry: &RisEntry) -> PathBuf {
+        match self.layout {
+            Layout::Flat => PathBuf::new(),
+            Layout::Year => {
+                let year: String = entry.get_field("PY").map(|py| py.trim().chars().take(4).collect()).unwrap_or_default();
+                if year.len() == 4 && year.chars().all(|c| c.is_ascii_digit()) {
+                    PathBuf::from(year)
+                } else {
+                    PathBuf::new()
+                }
+            }
+        }
+    }
+
+    /// File name, without `.ris`, for a new entry with citation key `key`.
+    pub fn file_stem(&self, key: &str, entry: &RisEntry) -> String {
+        let Some(template) = &self.filename_template else {
+            return key.to_string();
+        };
+        let field = |tag: &str| entry.get_field(tag).map(|v| v.trim().to_string()).unwrap_or_default();
+        let year: String = field("PY").chars().take(4).collect();
+        template
+            .replace("{key}", key)
+            .replace("{year}", &year)
+            .replace("{type}", &entry.ty.to_str().to_lowercase())
+    }
+}
+
+/// The settings of the project at `project_path`, or the defaults when it
+/// has no `.refrs.toml`.
+pub fn load(project_path: &str) -> Result<ProjectConfig> {
+    let path = Path::new(project_path).join(CONFIG_FILE);
+    if !path.exists() {
+        return Ok(ProjectConfig::default());
+    }
+    let text = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    ProjectConfig::parse(&text).with_context(|| format!("Invalid {}", path.display()))
+}
+
+/// When to color output.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorChoice {
+    /// Only when writing to a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color: `Auto` colors a terminal unless `NO_COLOR` is set.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Settings of the user, as opposed to those of a project, set with `refrs config`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct UserConfig {
+    /// Format of `refrs export` without `--format`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export_format: Option<ExportFormat>,
+    /// Editor of `refrs edit`, before $VISUAL and $EDITOR
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// Contact address sent to Crossref and other APIs for their polite pools
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crossref_mailto: Option<String>,
+    /// Port of `refrs serve` without `--bind`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_port: Option<u16>,
+    /// Access token `refrs serve` asks for when it listens beyond localhost
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorChoice>,
+    /// Zotero translation-server that `refrs import --url` asks first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation_server: Option<String>,
+}
+
+/// The settings `refrs config` knows, each with the environment variable
+/// that overrides it.
+pub const USER_SETTINGS: &[(&str, &str)] = &[
+    ("export-format", "REFRS_EXPORT_FORMAT"),
+    ("editor", "REFRS_EDITOR"),
+    ("crossref-mailto", "REFRS_CROSSREF_MAILTO"),
+    ("server-port", "REFRS_SERVER_PORT"),
+    ("server-token", "REFRS_SERVER_TOKEN"),
+    ("color", "REFRS_COLOR"),
+    ("translation-server", "REFRS_TRANSLATION_SERVER"),
+];
+
+impl UserConfig {
+    /// The value of the setting `name`, or `None` when it is unset.
+    pub fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(match name {
+            "export-format" => self.export_format.and_then(enum_name),
+            "editor" => self.editor.clone(),
+            "crossref-mailto" => self.crossref_mailto.clone(),
+            "server-port" => self.server_port.map(|port| port.to_string()),
+            "server-token" => self.server_token.clone(),
+            "color" => self.color.and_then(enum_name),
+            "translation-server" => self.translation_server.clone(),
+            _ => bail!("Unknown setting '{}'", name),
+        })
+    }
+
+    /// Sets `name` to `value`, checking it first. An empty value unsets it.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        let text = || (!value.is_empty()).then(|| value.to_string());
+        match name {
+            "export-format" => self.export_format = parse_enum(value)?,
+            "editor" => self.editor = text(),
+            "crossref-mailto" => {
+                if !value.is_empty() && !value.contains('@') {
+                    bail!("'{}' is not an email address", value);
+                }
+                self.crossref_mailto = text()
+            }
+            "server-port" => {
+                self.server_port = match value {
+                    "" => None,
+                    port => Some(port.parse().map_err(|_| anyhow!("'{}' is not a port number", port))?),
+                }
+            }
+            "server-token" => self.server_token = text(),
+            "color" => self.color = parse_enum(value)?,
+            "translation-server" => {
+                if !value.is_empty() && !value.starts_with("http://") && !value.starts_with("https://") {
+                    bail!("'{}' is not an http:// or https:// address", value);
+                }
+                self.translation_server = text()
+            }
+            _ => bail!("Unknown setting '{}'", name),
+        }
+        Ok(())
+    }
+
+    /// The settings with the environment variables in [`USER_SETTINGS`] applied.
+    pub fn with_env(mut self) -> Result<UserConfig> {
+        for (name, var) in USER_SETTINGS {
+            if let Ok(value) = env::var(var) {
+                self.set(name, &value).with_context(|| format!("Invalid {}", var))?;
+            }
+        }
+        Ok(self)
+    }
+}
+
+fn enum_name<T: ValueEnum>(value: T) -> Option<String> {
+    value.to_possible_value().map(|v| v.get_name().to_string())
+}
+
+fn parse_enum<T: ValueEnum>(value: &str) -> Result<Option<T>> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    T::from_str(value, true).map(Some).map_err(|_| {
+        let names: Vec<String> = T::value_variants()
+            .iter()
+            .filter_map(|v| v.to_possible_value())
+            .map(|v| v.get_name().to_string())
+            .collect();
+        anyhow!("'{}' is not one of {}", value, names.join(", "))
+    })
+}
+
+/// The user's settings as stored, without environment overrides.
+pub fn load_user_config() -> Result<UserConfig> {
+    let path = state::config_file_path();
+    if !path.exists() {
+        return Ok(UserConfig::default());
+    }
+    let content = fs::read_to_string(&path).context("Failed to read config file")?;
+    serde_yaml::from_str(&content).context("Failed to parse config file")
+}
+
+/// Stores the setting `name` of `config`, leaving the rest of the file
+/// alone, even settings in it that don't parse.
+pub fn save_user_setting(config: &UserConfig, name: &str) -> Result<()> {
+    let Value::Mapping(mut values) = serde_yaml::to_value(config).context("Failed to serialize config")? else {
+        return Ok(());
+    };
+    let key = name.replace('-', "_");
+    let value = values.remove(&key);
+    state::update_config_file(|settings| {
+        settings.remove(&key);
+        if let Some(value) = value {
+            settings.insert(Value::String(key), value);
+        }
+    })
+}
+
+fn string(key: &str, item: &Item) -> Result<String> {
+    item.as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("'{}' must be a string", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ris::parse_ris;
+
+    #[test]
+    fn parses_settings_and_rejects_unknown_ones() {
+        let config = ProjectConfig::parse("ris_folder = \"references\"\nlayout = \"year\"\nauto_commit = false\n").unwrap();
+        assert_eq!(config.ris_folder, "references");
+        assert_eq!(config.layout, Layout::Year);
+        assert!(!config.auto_commit);
+        assert_eq!(config.key_pattern, None);
+
+        assert!(ProjectConfig::parse("ris_foldr = \"x\"").is_err());
+        assert!(ProjectConfig::parse("auto_commit = \"no\"").is_err());
+        assert!(ProjectConfig::parse("ris_folder = \"../elsewhere\"").is_err());
+    }
+
+    #[test]
+    fn renders_file_names_from_the_template() {
+        let entry = &parse_ris("TY  - JOUR\nPY  - 2020/05/01\nER  - \n").unwrap()[0];
+        let config = ProjectConfig {
+            filename_template: Some("{year}_{type}_{key}".to_string()),
+            ..ProjectConfig::default()
+        };
+        assert_eq!(config.file_stem("doe_cats", entry), "2020_jour_doe_cats");
+        assert_eq!(ProjectConfig::default().file_stem("doe_cats", entry), "doe_cats");
+    }
+
+    #[test]
+    fn puts_entries_into_year_folders() {
+        let entries = parse_ris("TY  - JOUR\nPY  - 2020/05/01\nER  - \nTY  - JOUR\nPY  - n.d.\nER  - \n").unwrap();
+        let config = ProjectConfig { layout: Layout::Year, ..ProjectConfig::default() };
+        assert_eq!(config.entry_folder(&entries[0]), PathBuf::from("2020"));
+        assert_eq!(config.entry_folder(&entries[1]), PathBuf::new());
+        assert_eq!(ProjectConfig::default().entry_folder(&entries[0]), PathBuf::new());
+    }
+
+    #[test]
+    fn sets_and_gets_user_settings() {
+        let mut config = UserConfig::default();
+        assert!(config.set("export-format", "word").is_err());
+        config.set("export-format", "CSL-JSON").unwrap();
+        config.set("server-port", "8391").unwrap();
+        assert_eq!(config.get("export-format").unwrap().as_deref(), Some("csl-json"));
+        assert_eq!(config.server_port, Some(8391));
+
+        config.set("server-port", "").unwrap();
+        assert_eq!(config.get("server-port").unwrap(), None);
+        assert!(config.set("server-port", "http").is_err());
+        assert!(config.set("colour", "never").is_err());
+        assert!(config.set("translation-server", "localhost:1969").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Languages with a message catalog in `locales/`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    /// Norwegian (bokmål)
+    Nb,
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Language::En => "English",
+            Language::Nb => "norsk bokmål",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Language {
+    fn catalog_source(self) -> &'static str {
+        match self {
+            Language::En => include_str!("../locales/en.ftl"),
+            Language::Nb => include_str!("../locales/nb.ftl"),
+        }
+    }
+
+    /// The language of a POSIX locale such as `nb_NO.UTF-8`.
+    fn from_locale(locale: &str) -> Option<Language> {
+        let code = locale.split(['_', '.', '-', '@']).next()?.to_lowercase();
+        match code.as_str() {
+            "en" => Some(Language::En),
+            // Nynorsk and generic Norwegian users read bokmål better than English.
+            "nb" | "nn" | "no" => Some(Language::Nb),
+            _ => None,
+        }
+    }
+
+    /// From `LC_ALL`, `LC_MESSAGES` or `LANG`, the first one that is set.
+    fn from_env() -> Option<Language> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+            .and_then(|locale| Language::from_locale(&locale))
+    }
+}
+
+struct Catalogs {
+    language: Language,
+    messages: HashMap<Language, HashMap<String, String>>,
+}
+
+static CATALOGS: OnceLock<Catalogs> = OnceLock::new();
+
+/// Selects the language for the rest of the run: the configured one, else
+/// the one from the environment, else English. Later calls have no effect.
+pub fn init(configured: Option<Language>) {
+    let language = configured.or_else(Language::from_env).unwrap_or_default();
+    CATALOGS.get_or_init(|| load(language));
+}
+
+fn load(language: Language) -> Catalogs {
+    let messages = [Language::En, language]
+        .into_iter()
+        .map(|l| (l, parse_catalog(l.catalog_source())))
+        .collect();
+    Catalogs { language, messages }
+}
+
+/// Parses the subset of Fluent the catalogs use: `key = value` messages,
+/// indented continuation lines, `#` comments, and `{ $var }` and `{ "text" }`
+/// placeables. Variables are kept as `{$var}` for [`translate`] to fill in.
+fn parse_catalog(source: &str) -> HashMap<String, String> {
+    let mut messages: HashMap<String, String> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for line in source.lines() {
+        if line.trim_start().starts_with('#') || line.trim().is_empty() {
+            current = None;
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some(message) = current.as_ref().and_then(|key| messages.get_mut(key)) {
+                message.push('\n');
+                message.push_str(&placeables(line.trim()));
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().to_string();
+            messages.insert(key.clone(), placeables(value.trim()));
+            current = Some(key);
+        }
+    }
+
+    messages
+}
+
+/// Normalizes `{ $var }` to `{$var}` and replaces string literals with their text.
+fn placeables(value: &str) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let inner = rest[start + 1..start + end].trim();
+        match inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            Some(text) => result.push_str(text),
+            None => result.push_str(&format!("{{{}}}", inner)),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// The message for `key` in the selected language, falling back to English
+/// and then to the key itself, with `{$name}` variables filled in from `args`.
+pub fn translate(key: &str, args: &[(&str, String)]) -> String {
+    let catalogs = CATALOGS.get_or_init(|| load(Language::from_env().unwrap_or_default()));
+    let message = [catalogs.language, Language::En]
+        .iter()
+        .find_map(|language| catalogs.messages.get(language)?.get(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_string());
+
+    args.iter().fold(message, |message, (name, value)| {
+        message.replace(&format!("{{${}}}", name), value)
+    })
+}
+
+/// Looks up a message: `t!("workspace-removed", path = folder)`.
+#[macro_export]
+macro_rules! t {
+    ($key:literal) => {
+        $crate::i18n::translate($key, &[])
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalogs_define_the_same_messages() {
+        let english = parse_catalog(Language::En.catalog_source());
+        let norwegian = parse_catalog(Language::Nb.catalog_source());
+        let mut missing: Vec<&String> = english.keys().filter(|k| !norwegian.contains_key(*k)).collect();
+        missing.sort();
+        assert!(missing.is_empty(), "Missing Norwegian messages: {:?}", missing);
+    }
+
+    #[test]
+    fn parses_placeables() {
+        let messages = parse_catalog("# comment\nwarning = Warning:{\" \"}\nset = Set to: { $path }\n");
+        assert_eq!(messages["warning"], "Warning: ");
+        assert_eq!(messages["set"], "Set to: {$path}");
+        assert_eq!(Language::from_locale("nb_NO.UTF-8"), Some(Language::Nb));
+        assert_eq!(Language::from_locale("C"), None);
+    }
+}
@@ -0,0 +1,29 @@
+//! A reference manager that keeps each bibliography entry as a RIS file in a
+//! git repository.
+//!
+//! The `refrs` binary is a thin command line wrapper around this crate:
+//!
+//! - [`model`]: RIS entries, their reference types and BibTeX conversion.
+//! - [`services`]: importing, exporting, citation keys, lookups and the library.
+//! - [`repo`]: the git operations behind every change to a project.
+//! - [`state`]: the registered projects and settings in the data directory.
+//! - [`config`]: per-project settings from `.refrs.toml`.
+//!
+//! Converting RIS to BibTeX:
+//!
+//! ```
+//! use refrs::model::ris::parse_ris;
+//! use refrs::services::export;
+//!
+//! let entries = parse_ris("TY  - JOUR\nAU  - Doe, Jane\nTI  - Cats\nPY  - 2020\nER  - \n").unwrap();
+//! let bibtex = export::to_bibtex(&[("doe2020".to_string(), entries[0].clone())]);
+//! assert!(bibtex.starts_with("@article{doe2020,"));
+//! ```
+
+#[macro_use]
+pub mod i18n;
+pub mod config;
+pub mod model;
+pub mod repo;
+pub mod services;
+pub mod state;
@@ -1,13 +1,24 @@
 use anyhow::Result;
+use colored::Colorize;
+use clap::Args;
 use clap::Parser;
 use clap::Subcommand;
+use clap::CommandFactory;
+use clap_complete::{ArgValueCandidates, CompleteEnv};
+use command::files::ImportSource;
+use services::citekey::KeyFormat;
+use services::export::{ExportFormat, ExportSort, SplitBy};
+use services::query::{Filter, ListSort, Query, YearRange};
+use services::render::RenderFormat;
+use services::serialization::ImportFormat;
+
+#[macro_use]
+extern crate refrs;
+
+use refrs::{config, i18n, model, repo, services, state};
 
 mod command;
-mod repo;
-mod state;
 mod util;
-mod services;
-mod model;
 
 #[derive(Parser)]
 #[command(name = "refrs")]
@@ -15,6 +26,15 @@ mod model;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Show what `update` or `import` would do without writing or committing
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Print the results of show, list, search, import and daemon status as JSON
+    #[arg(long, global = true)]
+    json: bool,
+    /// When to color output; overrides the color setting and NO_COLOR
+    #[arg(long, global = true, value_enum, value_name = "WHEN")]
+    color: Option<config::ColorChoice>,
 }
 
 #[derive(Subcommand)]
@@ -23,47 +43,758 @@ enum Commands {
         #[arg(short, long)]
         force: bool
     },
-    Clone { relative_path: String, url: String },
+    Clone {
+        relative_path: String,
+        url: String,
+        /// Fetch only the last N commits of history
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        depth: Option<u32>,
+        /// Check out and fetch only this branch
+        #[arg(long)]
+        branch: Option<String>,
+    },
     Show,
+    /// Print one reference with all its details
+    ShowRef {
+        #[arg(add = ArgValueCandidates::new(command::completions::reference_keys))]
+        id: Option<String>,
+    },
     #[command(subcommand)]
     Workspace(WorkspaceSubcommands),
+    /// Pull and push the current project on its current branch
     Update,
+    /// Work on a branch of the current project
+    #[command(subcommand)]
+    Branch(BranchSubcommands),
     Import {
-        #[arg(long)]
-        clipboard: bool
+        /// .bib, .ris or other reference files to import
+        #[arg(conflicts_with_all = ["clipboard", "url", "pdf", "arxiv", "pmid"])]
+        files: Vec<String>,
+        #[arg(long, conflicts_with_all = ["url", "pdf", "arxiv", "pmid"])]
+        clipboard: bool,
+        /// Read citation meta tags from a web page, e.g. a publisher's landing page
+        #[arg(long, value_name = "PAGE", conflicts_with_all = ["pdf", "arxiv", "pmid"])]
+        url: Option<String>,
+        /// Look up a paper by the DOI or title in the PDF, then attach the PDF
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["arxiv", "pmid"])]
+        pdf: Option<String>,
+        /// Fetch a preprint by arXiv id or URL, e.g. 2401.12345
+        #[arg(long, value_name = "ID", conflicts_with = "pmid")]
+        arxiv: Option<String>,
+        /// Fetch an article by PubMed id from NCBI
+        #[arg(long, value_name = "ID")]
+        pmid: Option<String>,
+        /// Import without the interactive preview, skipping detected duplicates
+        #[arg(short, long)]
+        yes: bool,
+        /// Citation key format for this import, overriding the configured one
+        #[arg(long, value_enum)]
+        keys: Option<KeyFormat>,
+        /// Parse as this format instead of detecting it
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
     },
     Export {
-        output: String
+        #[arg(required_unless_present = "out_dir")]
+        output: Option<String>,
+        /// Defaults to the export-format setting, else bibtex
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Add JabRef group metadata with one group per keyword
+        #[arg(long)]
+        jabref_groups: bool,
+        /// Only export entries added or changed since a git revision or date
+        #[arg(long, value_name = "REV|DATE")]
+        since: Option<String>,
+        /// Entry order, defaults to the one set with `refrs export-sort`
+        #[arg(long, value_enum)]
+        sort: Option<ExportSort>,
+        /// Write one file per tag into --out-dir, e.g. one per thesis chapter
+        #[arg(long, value_enum, requires = "out_dir")]
+        split_by: Option<SplitBy>,
+        #[arg(long, requires = "split_by", conflicts_with = "output")]
+        out_dir: Option<String>,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Show or set the default entry order of exports
+    ExportSort {
+        #[arg(value_enum)]
+        sort: Option<ExportSort>,
+    },
+
+    Serve {
+        /// Only expose the JSON API under /api, without HTML pages or a browser
+        #[arg(long)]
+        api_only: bool,
+        /// Address to listen on, by default 127.0.0.1 with the server-port setting or 8080
+        #[arg(long)]
+        bind: Option<std::net::SocketAddr>,
+        /// Largest accepted request body in bytes
+        #[arg(long, default_value_t = 2 * 1024 * 1024)]
+        max_body: usize,
+        /// Largest accepted PDF upload in bytes
+        #[arg(long, default_value_t = 64 * 1024 * 1024)]
+        max_pdf: usize,
+        /// Requests each client address may make per minute
+        #[arg(long, default_value_t = 300)]
+        rate_limit: u32,
+    },
+    /// Find and merge duplicate references
+    Dedupe {
+        /// Look for references shared between all registered projects instead
+        #[arg(long)]
+        across_projects: bool,
+    },
+    /// Open the DOI or URL of a reference in the browser
+    Open {
+        #[arg(add = ArgValueCandidates::new(command::completions::reference_keys))]
+        id: Option<String>,
+        /// Open the reference in your library's OpenURL resolver instead
+        #[arg(long)]
+        library: bool,
+        /// Open the attached file instead
+        #[arg(long, conflicts_with = "library")]
+        attachment: bool,
+    },
+    /// Show or set the command that opens a kind of link (doi, url, library) or attachment (pdf, html, ...)
+    OpenWith {
+        kind: Option<String>,
+        /// e.g. "zathura {}"; the link or file replaces {} or is appended
+        command: Option<String>,
+        #[arg(long, requires = "kind", conflicts_with = "command")]
+        clear: bool,
+    },
+    /// Show or set the OpenURL resolver of your library
+    Openurl {
+        base_url: Option<String>,
+        #[arg(long, conflicts_with = "base_url")]
+        clear: bool,
+    },
+    /// Show or set how refrs authenticates to git remotes
+    Auth {
+        /// Private SSH key to use instead of the SSH agent
+        #[arg(long, value_name = "PATH")]
+        ssh_key: Option<String>,
+        /// Environment variable holding an HTTPS access token
+        #[arg(long, value_name = "VAR")]
+        token_env: Option<String>,
+        /// User name for the token or SSH key, e.g. oauth2 for GitLab tokens
+        #[arg(long)]
+        username: Option<String>,
+        /// Apply to the current project only
+        #[arg(long)]
+        project: bool,
+        /// Remove the credentials
+        #[arg(long, conflicts_with_all = ["ssh_key", "token_env", "username"])]
+        clear: bool,
+    },
+    /// Copy a \cite{...} command for a reference to the clipboard
+    Cite {
+        /// Citation key or words to fuzzily match against authors, year and title
+        #[arg(add = ArgValueCandidates::new(command::completions::reference_keys))]
+        query: Vec<String>,
+        #[arg(long, value_enum, default_value = "latex")]
+        format: command::cite::CiteFormat,
+    },
+    /// Edit the RIS file of a reference in the editor setting, $VISUAL or $EDITOR
+    Edit {
+        #[arg(add = ArgValueCandidates::new(command::completions::reference_keys))]
+        id: Option<String>,
+    },
+    /// Copy a file such as a PDF into the project and attach it to a reference
+    Attach {
+        #[arg(add = ArgValueCandidates::new(command::completions::reference_keys))]
+        id: String,
+        file: String,
+    },
+    /// Open the PDF attached to a reference
+    OpenPdf {
+        #[arg(add = ArgValueCandidates::new(command::completions::reference_keys))]
+        id: Option<String>,
+    },
+    /// Delete a reference and its attachments, and commit the deletion
+    Remove {
+        /// Reference id or .ris file name
+        #[arg(add = ArgValueCandidates::new(command::completions::reference_keys))]
+        id: Option<String>,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Archive the URL of a reference to protect it against link rot
+    Archive {
+        #[arg(add = ArgValueCandidates::new(command::completions::reference_keys))]
+        id: Option<String>,
+        /// Archive every reference whose only locator is a URL
+        #[arg(long, conflicts_with = "id")]
+        all: bool,
+        /// Save a local HTML copy instead of using the Wayback Machine
+        #[arg(long)]
+        snapshot: bool,
+    },
+    /// Export a citation and co-author graph of the project's references
+    Graph {
+        #[arg(long, value_enum, default_value = "dot")]
+        format: command::graph::GraphFormat,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Add citation edges from Semantic Scholar for entries with a DOI
+        #[arg(long)]
+        semantic_scholar: bool,
+    },
+    /// Export the co-authorship network of the project's authors
+    Coauthors {
+        #[arg(long, value_enum, default_value = "dot")]
+        format: command::graph::CoauthorFormat,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Generate literature notes from the references
+    #[command(subcommand)]
+    Notes(NotesSubcommands),
+    /// Manage CSL styles and locales used to render bibliographies
+    #[command(subcommand)]
+    Styles(StylesSubcommands),
+    /// Format the references as a bibliography in a CSL style
+    Render {
+        /// Built-in style (apa, ieee), installed style or path to a .csl file
+        #[arg(long, default_value = "apa")]
+        style: String,
+        #[arg(long, value_enum, default_value = "text")]
+        format: RenderFormat,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Tag references, stored as RIS keywords
+    #[command(subcommand)]
+    Tag(TagSubcommands),
+    /// Pandoc JSON filter resolving @citekeys against the current project
+    CiteprocFilter {
+        /// Output format, passed by pandoc
+        #[arg(hide = true)]
+        target_format: Option<String>,
+    },
+    /// Sync all projects and import watched folders in the background
+    #[command(subcommand)]
+    Daemon(DaemonSubcommands),
+    /// Show or set the citation key format used for new imports
+    KeyFormat {
+        #[arg(value_enum)]
+        format: Option<KeyFormat>,
+        /// Key pattern, e.g. "[auth][year][shorttitle]"; implies the pattern format
+        #[arg(long)]
+        pattern: Option<String>,
+    },
+    /// Pin a citation key in the ID tag of entries that have none
+    AssignKeys {
+        /// Recompute the keys of all entries with the current key format
+        #[arg(long)]
+        regenerate: bool,
+    },
+    /// Fill in missing ISSNs and journal names from Crossref
+    CompleteVenues,
+    /// Rewrite journal names to the canonical names in the project's venues.yaml
+    NormalizeVenues {
+        /// Record an alias first, as "Proc. VLDB Endow.=Proceedings of the VLDB Endowment"
+        #[arg(long, value_name = "ALIAS=CANONICAL")]
+        add: Option<String>,
+    },
+    /// Check that BibTeX and RIS conversions keep every mapped field
+    Selftest,
+    /// Check every reference for missing fields, malformed DOIs and ISSNs,
+    /// duplicate citation keys and empty files
+    Lint {
+        /// Repair what can be repaired and commit the changes
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Show or change user settings, stored in the config directory
+    #[command(subcommand)]
+    Config(ConfigSubcommands),
+    /// Show or set the language of messages
+    Language {
+        #[arg(value_enum)]
+        language: Option<i18n::Language>,
+    },
+    /// Remind of entries marked "reading" for too long and unread high-priority entries
+    Remind {
+        /// Days an entry may stay marked reading
+        #[arg(long, default_value_t = 14)]
+        days: i64,
+        /// Plain output, and none when there is nothing to report
+        #[arg(long)]
+        cron: bool,
+    },
+    /// List the references of the current project
+    List(QueryArgs),
+    /// Browse the references of the current project in the terminal
+    Tui(QueryArgs),
+    /// Find references by title, author, abstract or keyword
+    Search {
+        /// Words that must all occur, ignoring case
+        #[arg(required = true, num_args = 1..)]
+        query: Vec<String>,
+        /// Search every registered project, not just the current one
+        #[arg(long)]
+        all: bool,
+        /// Also search the text of attached PDFs
+        #[arg(long)]
+        fulltext: bool,
+    },
+    /// Look up how often each reference with a DOI has been cited, on Semantic
+    /// Scholar or OpenAlex, for `list --sort citations`
+    Citations {
+        /// Also look up counts checked within the last month
+        #[arg(long)]
+        refresh: bool,
     },
+    /// Search Crossref for a title, pick one of the matches and import it
+    Lookup {
+        /// Title or other details of the work, e.g. "parametric query optimization"
+        #[arg(required = true, num_args = 1..)]
+        query: Vec<String>,
+    },
+    /// Print a shell completion script, e.g. `source <(refrs completions bash)`
+    Completions {
+        #[arg(value_enum)]
+        shell: command::completions::CompletionShell,
+    },
+}
+
+/// Filters shared by `list`, `tui` and `export`.
+#[derive(Args)]
+struct FilterArgs {
+    /// Only references with an author whose name contains this
+    #[arg(long)]
+    author: Option<String>,
+    /// Publication year or range, e.g. 2020, 2018-2020, 2018..2020 or 2018-
+    #[arg(long, value_parser = YearRange::parse)]
+    year: Option<YearRange>,
+    /// RIS type (JOUR) or BibTeX type (article)
+    #[arg(long = "type", value_name = "TYPE")]
+    ty: Option<String>,
+    /// Only references with this keyword (tag)
+    #[arg(long, visible_alias = "tag")]
+    keyword: Option<String>,
+    /// Only references matching a condition, e.g. "year>=2020 && type==JOUR"
+    #[arg(long, value_parser = Filter::parse)]
+    filter: Option<Filter>,
+}
+
+impl FilterArgs {
+    fn query(&self, sort: ListSort) -> Query {
+        Query {
+            author: self.author.clone(),
+            year: self.year,
+            ty: self.ty.clone(),
+            keyword: self.keyword.clone(),
+            filter: self.filter.clone(),
+            sort,
+        }
+    }
+}
+
+/// Filters and order shared by `list` and `tui`.
+#[derive(Args)]
+struct QueryArgs {
+    #[command(flatten)]
+    filters: FilterArgs,
+    #[arg(long, value_enum, default_value = "id")]
+    sort: ListSort,
+}
+
+impl QueryArgs {
+    fn query(&self) -> Query {
+        self.filters.query(self.sort)
+    }
+}
+
+#[derive(Subcommand)]
+enum NotesSubcommands {
+    /// Create or update one Markdown note per reference
+    Generate {
+        /// Obsidian vault folder to write the notes to
+        #[arg(long)]
+        obsidian: String,
+        /// Template file with {{placeholders}} to use instead of the built-in one
+        #[arg(long)]
+        template: Option<String>,
+    },
+}
 
-    Serve,
+#[derive(Subcommand)]
+enum StylesSubcommands {
+    /// Download a style by name (e.g. apa, ieee) or URL
+    Install {
+        name_or_url: String,
+        /// Install the CSL locale with this language code (e.g. nb-NO) instead
+        #[arg(long)]
+        locale: bool,
+    },
+    /// List installed styles and locales
+    List,
+    /// Remove an installed style
+    Remove { name: String },
+}
+
+#[derive(Subcommand)]
+enum TagSubcommands {
+    /// Add tags to a reference
+    Add {
+        #[arg(add = ArgValueCandidates::new(command::completions::reference_keys))]
+        id: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a reference
+    Remove {
+        #[arg(add = ArgValueCandidates::new(command::completions::reference_keys))]
+        id: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// List the tags in use and how many references have them
+    List,
+}
+
+#[derive(Subcommand)]
+enum DaemonSubcommands {
+    /// Start the daemon as a background process
+    Start {
+        /// Seconds between sync cycles
+        #[arg(long, default_value_t = 300)]
+        interval: u64,
+    },
+    /// Run the daemon in the foreground, e.g. under a service manager
+    Run {
+        #[arg(long, default_value_t = 300)]
+        interval: u64,
+    },
+    /// Stop the running daemon
+    Stop,
+    /// Ask the running daemon to sync all projects now
+    Sync,
+    /// Ask the running daemon to import a .bib or .ris file
+    Import { file: String },
+    /// Show whether the daemon runs and when projects were last synced
+    Status,
+    /// Import .bib/.ris files dropped into this folder
+    Watch { folder: String },
+    /// Stop watching a folder
+    Unwatch { folder: String },
+}
+
+#[derive(Subcommand)]
+enum BranchSubcommands {
+    /// Create a branch at the current commit and switch to it
+    Create { name: String },
+    /// Switch to a branch, also one that so far exists only on the remote
+    Switch { name: String },
+    /// List branches, marking the current one
+    List,
+}
+
+#[derive(Subcommand)]
+enum ConfigSubcommands {
+    /// Print a setting
+    Get { name: String },
+    /// Change a setting; an empty value unsets it
+    Set { name: String, value: String },
+    /// Print every setting and the environment variables overriding them
+    List,
 }
 
 #[derive(Subcommand)]
 enum WorkspaceSubcommands {
-    Set,
+    /// Make a project current, picking it from a list when no path is given
+    Set {
+        /// Folder of a registered project, e.g. ~/refs/lab
+        #[arg(add = ArgValueCandidates::new(command::completions::workspace_names))]
+        path: Option<String>,
+    },
     Get,
+    /// Name a project, which `set` and `remove` then accept instead of its path
+    Rename {
+        #[arg(add = ArgValueCandidates::new(command::completions::workspace_names))]
+        old: String,
+        new: String,
+    },
+    /// Forget a project, picking it from a list when no path is given
+    Remove {
+        #[arg(add = ArgValueCandidates::new(command::completions::workspace_names))]
+        path: Option<String>,
+        /// Also delete the project's folder
+        #[arg(long)]
+        delete_files: bool,
+        /// Don't ask before deleting files
+        #[arg(short, long)]
+        yes: bool,
+    },
 }
 
 fn main() -> Result<()> {
+    // Answers the completion scripts' calls for candidates, then exits.
+    CompleteEnv::with_factory(Cli::command)
+        .var(command::completions::COMPLETE_VAR)
+        .complete();
     env_logger::init();
 
     let cli = Cli::parse();
     let mut state = state::load_state()?;
+    i18n::init(state.language);
+    // A broken config file mustn't lock out `refrs config`, which repairs it.
+    let user_config = config::load_user_config()
+        .and_then(config::UserConfig::with_env)
+        .unwrap_or_else(|err| {
+            eprintln!("{}{:#}", t!("warning").yellow().bold(), err);
+            config::UserConfig::default()
+        });
+    let color = cli.color.or(user_config.color).unwrap_or(config::ColorChoice::Auto).enabled();
+    colored::control::set_override(color);
+    // Prompts, pickers and the TUI draw with console rather than colored.
+    console::set_colors_enabled(color);
+    console::set_colors_enabled_stderr(color);
+    if let Some(mailto) = &user_config.crossref_mailto {
+        services::http::set_mailto(mailto);
+    }
+    if let Some(endpoint) = &user_config.translation_server {
+        services::translation_server::set_endpoint(endpoint);
+    }
+
+    if cli.dry_run && !matches!(cli.command, Commands::Update | Commands::Import { .. }) {
+        anyhow::bail!(t!("dry-run-unsupported", commands = "update, import"));
+    }
+
+    if cli.json {
+        let supported = matches!(
+            cli.command,
+            Commands::Show
+                | Commands::List(_)
+                | Commands::Search { .. }
+                | Commands::Import { .. }
+                | Commands::Daemon(DaemonSubcommands::Status)
+        );
+        if !supported {
+            anyhow::bail!(t!("json-unsupported", commands = "show, list, search, import, daemon status"));
+        }
+        // Scripts read the exit status, so what would be a hint is an error here.
+        let needs_project = matches!(cli.command, Commands::List(_) | Commands::Search { .. } | Commands::Import { .. });
+        if !state.initialized && (needs_project || matches!(cli.command, Commands::Show)) {
+            anyhow::bail!(t!("not-initialized", command = "refrs init"));
+        }
+        if needs_project && state.current_project.is_empty() {
+            anyhow::bail!(t!("no-project-selected"));
+        }
+        repo::status_to_stderr();
+        util::divert_stdout()?;
+    }
 
     match &cli.command {
         Commands::Init { force } => command::init::handle_init(&mut state, *force)?,
-        Commands::Clone { relative_path, url } => command::clone::handle_clone(&mut state, relative_path, url)?,
-        Commands::Show => command::show::handle_show(&state),
+        Commands::Clone {
+            relative_path,
+            url,
+            depth,
+            branch,
+        } => command::clone::handle_clone(&mut state, relative_path, url, *depth, branch.as_deref())?,
+        Commands::Show => command::show::handle_show(&state, cli.json)?,
+        Commands::ShowRef { id } => command::show::handle_show_ref(&state, id.as_deref())?,
         Commands::Workspace(subcommand) => match subcommand {
-            WorkspaceSubcommands::Set => command::workspace::handle_set(&mut state)?,
+            WorkspaceSubcommands::Set { path } => command::workspace::handle_set(&mut state, path.as_deref())?,
             WorkspaceSubcommands::Get => command::workspace::handle_get(&state),
+            WorkspaceSubcommands::Rename { old, new } => command::workspace::handle_rename(&mut state, old, new)?,
+            WorkspaceSubcommands::Remove { path, delete_files, yes } => {
+                command::workspace::handle_remove(&mut state, path.as_deref(), *delete_files, *yes)?
+            }
+        },
+        Commands::Update => command::update::handle_update(&state, cli.dry_run)?,
+        Commands::Branch(subcommand) => match subcommand {
+            BranchSubcommands::Create { name } => command::branch::handle_create(&state, name)?,
+            BranchSubcommands::Switch { name } => command::branch::handle_switch(&state, name)?,
+            BranchSubcommands::List => command::branch::handle_list(&state)?,
+        },
+        Commands::Import {
+            files,
+            clipboard,
+            url,
+            pdf,
+            arxiv,
+            pmid,
+            yes,
+            keys,
+            format,
+        } => {
+            let source = match (arxiv, pmid, url, pdf) {
+                (Some(id), _, _, _) => Some(ImportSource::Arxiv(id)),
+                (_, Some(pmid), _, _) => Some(ImportSource::Pubmed(pmid)),
+                (_, _, Some(url), _) => Some(ImportSource::Url(url)),
+                (_, _, _, Some(path)) => Some(ImportSource::Pdf(path)),
+                _ if *clipboard => Some(ImportSource::Clipboard),
+                _ if !files.is_empty() => Some(ImportSource::Files(files)),
+                _ => None,
+            };
+            command::files::handle_import(&state, source, *yes, *keys, *format, cli.dry_run, cli.json)?
+        }
+        Commands::Export {
+            output,
+            format,
+            jabref_groups,
+            since,
+            sort,
+            split_by,
+            out_dir,
+            filters,
+        } => command::files::handle_export(
+            &state,
+            output.as_deref(),
+            out_dir.as_deref(),
+            command::files::ExportOptions {
+                format: format.or(user_config.export_format).unwrap_or(ExportFormat::Bibtex),
+                jabref_groups: *jabref_groups,
+                since: since.as_deref(),
+                sort: *sort,
+                split_by: *split_by,
+                query: filters.query(ListSort::default()),
+            },
+        )?,
+        Commands::ExportSort { sort } => command::files::handle_export_sort(&mut state, *sort)?,
+        Commands::Serve {
+            api_only,
+            bind,
+            max_body,
+            max_pdf,
+            rate_limit,
+        } => {
+            let bind = bind.unwrap_or_else(|| ([127, 0, 0, 1], user_config.server_port.unwrap_or(8080)).into());
+            let token = user_config.server_token.clone();
+            command::serve::handle_serve(&state, *api_only, bind, token, *max_body, *max_pdf, *rate_limit)?
+        }
+        Commands::Dedupe { across_projects } => {
+            if *across_projects {
+                command::dedupe::handle_dedupe_across_projects(&state)?
+            } else {
+                command::dedupe::handle_dedupe(&state)?
+            }
+        }
+        Commands::Open { id, library, attachment } => {
+            command::open::handle_open(&state, id.as_deref(), *library, *attachment)?
+        }
+        Commands::OpenWith { kind, command, clear } => {
+            command::open::handle_open_with(&mut state, kind.as_deref(), command.as_deref(), *clear)?
+        }
+        Commands::Openurl { base_url, clear } => {
+            command::open::handle_openurl(&mut state, base_url.as_deref(), *clear)?
+        }
+        Commands::Auth {
+            ssh_key,
+            token_env,
+            username,
+            project,
+            clear,
+        } => {
+            let changes = repo::GitAuth {
+                ssh_key: ssh_key.clone(),
+                token_env: token_env.clone(),
+                username: username.clone(),
+            };
+            command::auth::handle_auth(&mut state, changes, *project, *clear)?
+        }
+        Commands::Cite { query, format } => {
+            let query = (!query.is_empty()).then(|| query.join(" "));
+            command::cite::handle_cite(&state, query.as_deref(), *format)?
+        }
+        Commands::Edit { id } => command::edit::handle_edit(&state, id.as_deref(), user_config.editor.as_deref())?,
+        Commands::Attach { id, file } => command::attach::handle_attach(&state, id, file)?,
+        Commands::OpenPdf { id } => command::open::handle_open_pdf(&state, id.as_deref())?,
+        Commands::Remove { id, yes } => command::remove::handle_remove(&state, id.as_deref(), *yes)?,
+        Commands::Archive { id, all, snapshot } => {
+            command::archive::handle_archive(&state, id.as_deref(), *all, *snapshot)?
+        }
+        Commands::Graph { format, output, semantic_scholar } => {
+            command::graph::handle_graph(&state, *format, output.as_deref(), *semantic_scholar)?
+        }
+        Commands::Coauthors { format, output } => {
+            command::graph::handle_coauthors(&state, *format, output.as_deref())?
+        }
+        Commands::Notes(subcommand) => match subcommand {
+            NotesSubcommands::Generate { obsidian, template } => {
+                command::notes::handle_generate_obsidian(&state, obsidian, template.as_deref())?
+            }
+        },
+        Commands::Render { style, format, output } => {
+            command::render::handle_render(&state, style, *format, output.as_deref())?
+        }
+        Commands::Styles(subcommand) => match subcommand {
+            StylesSubcommands::Install { name_or_url, locale } => {
+                command::styles::handle_install(name_or_url, *locale)?
+            }
+            StylesSubcommands::List => command::styles::handle_list()?,
+            StylesSubcommands::Remove { name } => command::styles::handle_remove(name)?,
         },
-        Commands::Update => command::update::handle_update(&state)?,
-        Commands::Import { clipboard } => command::files::handle_import(&state, *clipboard)?,
-        Commands::Export { output } => command::files::handle_export(&state, output)?,
-        Commands::Serve => command::serve::handle_serve(&state)?,
+        Commands::Tag(subcommand) => match subcommand {
+            TagSubcommands::Add { id, tags } => command::tag::handle_tag(&state, id, tags, false)?,
+            TagSubcommands::Remove { id, tags } => command::tag::handle_tag(&state, id, tags, true)?,
+            TagSubcommands::List => command::tag::handle_tag_list(&state)?,
+        },
+        Commands::CompleteVenues => command::venues::handle_complete_venues(&state)?,
+        Commands::NormalizeVenues { add } => {
+            command::venues::handle_normalize_venues(&state, add.as_deref())?
+        }
+        Commands::Selftest => command::selftest::handle_selftest(&state)?,
+        Commands::Lint { fix } => command::lint::handle_lint(&state, *fix)?,
+        Commands::Config(subcommand) => match subcommand {
+            ConfigSubcommands::Get { name } => command::config::handle_get(name)?,
+            ConfigSubcommands::Set { name, value } => command::config::handle_set(name, value)?,
+            ConfigSubcommands::List => command::config::handle_list()?,
+        },
+        Commands::Language { language } => command::language::handle_language(&mut state, *language)?,
+        Commands::Remind { days, cron } => command::remind::handle_remind(&state, *days, *cron)?,
+        Commands::List(args) => command::list::handle_list(&state, &args.query(), cli.json)?,
+        Commands::Tui(args) => command::tui::handle_tui(&state, &args.query())?,
+        Commands::Search { query, all, fulltext } => {
+            command::search::handle_search(&state, &query.join(" "), *all, *fulltext, cli.json)?
+        }
+        Commands::Citations { refresh } => command::citations::handle_citations(&state, *refresh)?,
+        Commands::Lookup { query } => command::lookup::handle_lookup(&state, &query.join(" "))?,
+        Commands::Completions { shell } => command::completions::handle_completions(*shell)?,
+        Commands::CiteprocFilter { .. } => command::pandoc::handle_citeproc_filter(&state)?,
+        Commands::Daemon(subcommand) => match subcommand {
+            DaemonSubcommands::Start { interval } => {
+                command::daemon::handle_start(&state, *interval)?
+            }
+            DaemonSubcommands::Run { interval } => {
+                command::daemon::handle_run(&state, *interval)?
+            }
+            DaemonSubcommands::Stop => command::daemon::handle_stop()?,
+            DaemonSubcommands::Sync => command::daemon::handle_sync()?,
+            DaemonSubcommands::Import { file } => command::daemon::handle_import(file)?,
+            DaemonSubcommands::Status => command::daemon::handle_status(&state, cli.json)?,
+            DaemonSubcommands::Watch { folder } => {
+                command::daemon::handle_watch(&mut state, folder)?
+            }
+            DaemonSubcommands::Unwatch { folder } => {
+                command::daemon::handle_unwatch(&mut state, folder)?
+            }
+        },
+        Commands::KeyFormat { format, pattern } => {
+            command::keys::handle_key_format(&mut state, *format, pattern.as_deref())?
+        }
+        Commands::AssignKeys { regenerate } => command::keys::handle_assign_keys(&state, *regenerate)?,
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_is_consistent() {
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::ris::RisEntry;
+
+/// A publication date, as precise as the source gives it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
+}
+
+impl Date {
+    /// Reads an ISO date as in BibLaTeX's `date` (`2021-05-17`, `2021-05`,
+    /// also the start of a range `2021-05/2021-06`), a RIS date
+    /// (`2021/05/17/Spring`, `2021///`) or, failing those, the first
+    /// four-digit year in the text, e.g. `c. 1984`.
+    pub fn parse(text: &str) -> Option<Date> {
+        let text = text.trim();
+        let parts: Vec<&str> = if text.contains('-') {
+            text.split('/').next().unwrap_or_default().split('-').collect()
+        } else {
+            text.split('/').collect()
+        };
+        let number = |i: usize| parts.get(i).and_then(|part| part.trim().parse::<u32>().ok());
+
+        match parts.first().filter(|year| year.trim().len() == 4).and_then(|year| year.trim().parse().ok()) {
+            Some(year) => {
+                let month = number(1).filter(|month| (1..=12).contains(month));
+                let day = month.and(number(2)).filter(|day| (1..=31).contains(day));
+                Some(Date { year, month, day })
+            }
+            None => {
+                let year = text
+                    .split(|c: char| !c.is_ascii_digit())
+                    .find(|part| part.len() == 4)?
+                    .parse()
+                    .ok()?;
+                Some(Date { year, month: None, day: None })
+            }
+        }
+    }
+
+    /// The date of an entry: the full date from `DA` or the older `Y1`,
+    /// else the year from `PY`.
+    pub fn of(entry: &RisEntry) -> Option<Date> {
+        ["DA", "Y1", "PY"]
+            .iter()
+            .find_map(|tag| entry.get_field(tag).and_then(|value| Date::parse(value)))
+    }
+
+    /// Today's date in UTC.
+    pub fn today() -> Date {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        civil_from_days((seconds / 86400) as i64)
+    }
+
+    /// Days from `self` to the later `other`, counting a missing month or
+    /// day as the first.
+    pub fn days_until(&self, other: &Date) -> i64 {
+        let days = |date: &Date| {
+            days_from_civil(date.year.into(), date.month.unwrap_or(1).into(), date.day.unwrap_or(1).into())
+        };
+        days(other) - days(self)
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The date `days` days after 1970-01-01, the inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> Date {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    Date { year: year as i32, month: Some(month as u32), day: Some(day as u32) }
+}
+
+/// Writes the RIS form: `2021/05/17`, `2021/05` or `2021`.
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.year)?;
+        if let Some(month) = self.month {
+            write!(f, "/{:02}", month)?;
+            if let Some(day) = self.day {
+                write!(f, "/{:02}", day)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ris::parse_ris;
+
+    fn date(year: i32, month: Option<u32>, day: Option<u32>) -> Option<Date> {
+        Some(Date { year, month, day })
+    }
+
+    #[test]
+    fn parses_iso_ris_and_loose_dates() {
+        assert_eq!(Date::parse("2021-05-17"), date(2021, Some(5), Some(17)));
+        assert_eq!(Date::parse("2021-05/2021-06"), date(2021, Some(5), None));
+        assert_eq!(Date::parse("2021/05/17/Spring"), date(2021, Some(5), Some(17)));
+        assert_eq!(Date::parse("2021///"), date(2021, None, None));
+        assert_eq!(Date::parse("c. 1984"), date(1984, None, None));
+        assert_eq!(Date::parse("in press"), None);
+        assert_eq!(date(2021, Some(5), Some(7)).unwrap().to_string(), "2021/05/07");
+    }
+
+    #[test]
+    fn counts_days_between_dates() {
+        assert_eq!(civil_from_days(days_from_civil(2024, 2, 29)), date(2024, Some(2), Some(29)).unwrap());
+        assert_eq!(civil_from_days(0), date(1970, Some(1), Some(1)).unwrap());
+        assert_eq!(Date::parse("2023/12/31").unwrap().days_until(&Date::parse("2024/03/01").unwrap()), 61);
+    }
+
+    #[test]
+    fn prefers_the_full_date_of_an_entry() {
+        let entry = parse_ris("TY  - JOUR\nPY  - 2021\nDA  - 2021/05/17/\nER  -").unwrap().remove(0);
+        assert_eq!(Date::of(&entry), date(2021, Some(5), Some(17)));
+        let entry = parse_ris("TY  - JOUR\nPY  - 2021-05-17\nER  -").unwrap().remove(0);
+        assert_eq!(Date::of(&entry), date(2021, Some(5), Some(17)));
+    }
+}
@@ -0,0 +1,168 @@
+//! LaTeX markup in BibTeX values. The biblatex parser already turns accents
+//! such as `\"{o}` and escapes such as `\&` into text; what is left here are
+//! formatting commands, quotes, braces and math.
+
+/// Commands that stand for a character, also inside math.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("alpha", "α"),
+    ("beta", "β"),
+    ("gamma", "γ"),
+    ("delta", "δ"),
+    ("epsilon", "ε"),
+    ("varepsilon", "ε"),
+    ("zeta", "ζ"),
+    ("eta", "η"),
+    ("theta", "θ"),
+    ("kappa", "κ"),
+    ("lambda", "λ"),
+    ("mu", "μ"),
+    ("nu", "ν"),
+    ("xi", "ξ"),
+    ("pi", "π"),
+    ("rho", "ρ"),
+    ("sigma", "σ"),
+    ("tau", "τ"),
+    ("phi", "φ"),
+    ("varphi", "φ"),
+    ("chi", "χ"),
+    ("psi", "ψ"),
+    ("omega", "ω"),
+    ("Gamma", "Γ"),
+    ("Delta", "Δ"),
+    ("Theta", "Θ"),
+    ("Lambda", "Λ"),
+    ("Pi", "Π"),
+    ("Sigma", "Σ"),
+    ("Phi", "Φ"),
+    ("Psi", "Ψ"),
+    ("Omega", "Ω"),
+    ("pm", "±"),
+    ("times", "×"),
+    ("leq", "≤"),
+    ("geq", "≥"),
+    ("neq", "≠"),
+    ("approx", "≈"),
+    ("infty", "∞"),
+    ("to", "→"),
+    ("rightarrow", "→"),
+    ("ldots", "…"),
+    ("dots", "…"),
+    ("textdegree", "°"),
+    ("textregistered", "®"),
+    ("texttrademark", "™"),
+    ("copyright", "©"),
+    ("textbackslash", "\\"),
+    ("textasciitilde", "~"),
+    ("textasciicircum", "^"),
+];
+
+/// Characters that must be escaped in BibTeX values.
+const SPECIALS: &[char] = &['&', '%', '$', '#', '_', '{', '}'];
+
+/// Characters that can't be escaped with a backslash, and the commands
+/// that print them.
+const SPELLED_OUT: &[(char, &str)] = &[('\\', "textbackslash"), ('~', "textasciitilde"), ('^', "textasciicircum")];
+
+/// Turns what the biblatex parser leaves of LaTeX markup into plain text:
+/// `\emph{x}` and other commands with an argument keep just the argument,
+/// symbol commands become the character, quotes become typographic ones and
+/// grouping braces go away. Unknown commands without an argument are kept.
+pub fn decode(text: &str) -> String {
+    decode_with(text, false)
+}
+
+/// Like [`decode`], for the text the parser found outside of braces. The
+/// parser has already turned escaped braces such as `\{` into braces
+/// there, so braces that don't hold the argument of a command are text.
+pub fn decode_unbraced(text: &str) -> String {
+    decode_with(text, true)
+}
+
+fn decode_with(text: &str, literal_braces: bool) -> String {
+    let text = text.replace("``", "“").replace("''", "”");
+    let mut decoded = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    // How deep inside the arguments of commands the text is
+    let mut arguments = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if arguments > 0 => arguments += 1,
+            '}' if arguments > 0 => arguments -= 1,
+            '{' | '}' if !literal_braces => {}
+            '\\' => {
+                let mut name = String::new();
+                while let Some(&next) = chars.peek() {
+                    if !next.is_ascii_alphabetic() {
+                        break;
+                    }
+                    name.push(next);
+                    chars.next();
+                }
+                let has_argument = chars.peek() == Some(&'{');
+                if name.is_empty() {
+                    // An escaped character such as `\{`, else a backslash
+                    // the parser has already unescaped
+                    match chars.next_if(|next| SPECIALS.contains(next)) {
+                        Some(escaped) => decoded.push(escaped),
+                        None => decoded.push('\\'),
+                    }
+                    continue;
+                } else if let Some((_, symbol)) = SYMBOLS.iter().find(|(command, _)| *command == name) {
+                    decoded.push_str(symbol);
+                } else if !has_argument {
+                    decoded.push('\\');
+                    decoded.push_str(&name);
+                }
+                // With an argument the command is dropped and the braces of
+                // the argument go away.
+                if has_argument {
+                    chars.next();
+                    arguments += 1;
+                }
+            }
+            _ => decoded.push(c),
+        }
+    }
+    decoded
+}
+
+/// Escapes the characters LaTeX treats specially, for writing `text` as a
+/// BibTeX value. Braces protecting case in titles are added afterwards.
+pub fn encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for c in text.chars() {
+        if let Some((_, command)) = SPELLED_OUT.iter().find(|(special, _)| *special == c) {
+            // Braced, so that the parser doesn't take the backslash it
+            // leaves and the text after it for a command
+            encoded.push_str("{\\");
+            encoded.push_str(command);
+            encoded.push('}');
+            continue;
+        }
+        if SPECIALS.contains(&c) {
+            encoded.push('\\');
+        }
+        encoded.push(c);
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_markup_left_by_the_parser() {
+        assert_eq!(decode(r"\textit{Café} and \emph{dogs}"), "Café and dogs");
+        assert_eq!(decode(r"``Quoted'' {DNA} \url{https://example.org}"), "“Quoted” DNA https://example.org");
+        assert_eq!(decode(r"\alpha-helix, 3 \times 3 \ldots"), "α-helix, 3 × 3 …");
+        assert_eq!(decode(r"\unknown stays"), r"\unknown stays");
+        assert_eq!(decode_unbraced(r"{x} and \emph{y {z}}, \alpha{}"), "{x} and y z, α");
+    }
+
+    #[test]
+    fn encodes_special_characters() {
+        assert_eq!(encode("Cats & dogs: 50% of #1_a"), r"Cats \& dogs: 50\% of \#1\_a");
+        assert_eq!(encode(r"The {DNA} of $5 ~ a^2\b"), r"The \{DNA\} of \$5 {\textasciitilde} a{\textasciicircum}2{\textbackslash}b");
+    }
+}
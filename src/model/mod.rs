@@ -1,2 +1,4 @@
+pub mod date;
+pub mod latex;
 pub mod reference;
 pub mod ris;
@@ -1,8 +1,67 @@
 use serde::{Deserialize, Serialize};
 
+use crate::model::ris::RisEntry;
+
+/// Folder inside a project where attached files are stored.
+pub const ATTACHMENTS_FOLDER: &str = "attachments";
+
+/// RIS field holding the paths of attached files, relative to the project.
+pub const ATTACHMENT_TAG: &str = "L1";
+
+/// A reference in a project together with the files belonging to it.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Reference {
     pub id: String,
     pub ris_path: String,
+    /// Attached files, relative to the project
     pub attachments: Vec<String>,
 }
+
+impl Reference {
+    pub fn new(id: &str, ris_path: &str, entry: &RisEntry) -> Reference {
+        Reference {
+            id: id.to_string(),
+            ris_path: ris_path.to_string(),
+            attachments: entry.fields.get(ATTACHMENT_TAG).cloned().unwrap_or_default(),
+        }
+    }
+
+    /// The first attached PDF.
+    pub fn pdf(&self) -> Option<&String> {
+        self.attachments
+            .iter()
+            .find(|path| path.to_lowercase().ends_with(".pdf"))
+    }
+
+    /// A path under [`ATTACHMENTS_FOLDER`] for a new attachment with the
+    /// given extension, named after the reference and not in `taken`.
+    pub fn attachment_path(&self, extension: &str, taken: impl Fn(&str) -> bool) -> String {
+        (1..)
+            .map(|n| match n {
+                1 => format!("{}/{}.{}", ATTACHMENTS_FOLDER, self.id, extension),
+                n => format!("{}/{}-{}.{}", ATTACHMENTS_FOLDER, self.id, n, extension),
+            })
+            .find(|path| !taken(path))
+            .expect("unbounded range")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ris::parse_ris;
+
+    #[test]
+    fn finds_pdf_and_free_attachment_path() {
+        let entry = parse_ris("TY  - JOUR\nL1  - attachments/doe-snapshot.html\nL1  - attachments/doe.PDF\nER  -")
+            .unwrap()
+            .remove(0);
+        let reference = Reference::new("doe", "ris_files/doe.ris", &entry);
+
+        assert_eq!(reference.pdf().map(String::as_str), Some("attachments/doe.PDF"));
+        assert_eq!(
+            reference.attachment_path("pdf", |path| path == "attachments/doe.pdf"),
+            "attachments/doe-2.pdf"
+        );
+    }
+}
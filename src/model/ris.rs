@@ -2,7 +2,14 @@ use anyhow::{anyhow, Result};
 use biblatex::{Chunk, Chunks};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
 
+use super::date::Date;
+use super::latex;
+
+/// The reference types of the RIS `TY` tag.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub enum ReferenceType {
     Abstract,
@@ -52,7 +59,7 @@ pub enum ReferenceType {
 }
 
 impl ReferenceType {
-    fn to_str(&self) -> &str {
+    pub fn to_str(&self) -> &str {
         match self {
             ReferenceType::Abstract => "ABST",
             ReferenceType::AggregatedDatabase => "AGGR",
@@ -100,10 +107,14 @@ impl ReferenceType {
             ReferenceType::Unknown => "GEN", // or some fallback
         }
     }
+}
+
+/// Parses a `TY` value. Unknown types become `ReferenceType::Unknown`.
+impl FromStr for ReferenceType {
+    type Err = Infallible;
 
-    /// Parse a `TY` value into a `ReferenceType` enum.
-    pub fn from_str(s: &str) -> ReferenceType {
-        match s {
+    fn from_str(s: &str) -> Result<ReferenceType, Infallible> {
+        Ok(match s {
             "ABST" => ReferenceType::Abstract,
             "ADVS" | "AGGR" => ReferenceType::AggregatedDatabase,
             "ANCIENT" => ReferenceType::AncientText,
@@ -148,13 +159,30 @@ impl ReferenceType {
             "UNPB" => ReferenceType::UnpublishedWork,
             "VIDEO" => ReferenceType::VideoRecording,
             _ => ReferenceType::Unknown,
-        }
+        })
     }
 }
 
+/// User-definable RIS field listing the case-protected phrases of a BibTeX
+/// title, one per value. The custom fields `C1` to `C8` are taken: `C7` is
+/// the article number, for one.
+pub const PROTECTED_TAG: &str = "U1";
+
+/// Order in which `Display` writes the tags it knows, roughly as in the RIS
+/// specification: key, people, titles, dates, publication details, links,
+/// then the rest. Other tags follow in alphabetical order.
+const TAG_ORDER: &[&str] = &[
+    "ID", "AU", "A1", "A2", "ED", "A3", "A4", "TI", "T1", "ST", "TT", "T2", "JO", "JF", "J2", "JA", "T3",
+    "PY", "Y1", "DA", "Y2", "ET", "VL", "IS", "SE", "SP", "EP", "PB", "CY", "SN", "LA", "DO", "UR",
+    "L1", "L2", "L4", "AB", "N2", "KW", "N1",
+];
+
+/// One RIS record. `Display` writes it back as RIS text, with the tags in a
+/// fixed order so that rewriting a file only changes what changed.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RisEntry {
     pub ty: ReferenceType,
+    /// Values of each two-letter tag, such as `AU` or `TI`, each in file order.
     pub fields: HashMap<String, Vec<String>>,
 }
 
@@ -183,7 +211,7 @@ impl RisEntry {
         let mut add_field = |tag: &str, value: String| {
             fields
                 .entry(tag.to_string())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(value);
         };
 
@@ -192,7 +220,7 @@ impl RisEntry {
             bibtex_entry
                 .fields
                 .get(key)
-                .map(|chunks| chunks_to_string(chunks))
+                .map(chunks_to_string)
         };
 
         // Handle authors
@@ -207,17 +235,31 @@ impl RisEntry {
             }
         }
 
-        // Title -> TI
-        if let Some(title) = field_as_string("title") {
-            add_field("TI", title);
+        // Title -> TI, remembering case-protected `{Words}` so export can brace them again
+        if let Some(title) = bibtex_entry.fields.get("title") {
+            add_field("TI", chunks_to_string(title));
+            for phrase in protected_phrases(title) {
+                add_field(PROTECTED_TAG, phrase);
+            }
         }
 
-        // Year or Date -> PY
-        if let Some(year) = field_as_string("year") {
-            add_field("PY", year);
-        } else if let Some(date) = field_as_string("date") {
-            // You could parse the date to extract the year part if needed.
-            add_field("PY", date);
+        // Year or date -> PY, and the full date -> DA when there is a month.
+        // BibLaTeX's date takes precedence over year and month.
+        let month = field_as_string("month").and_then(|month| month_number(&month));
+        let date = field_as_string("date")
+            .and_then(|date| Date::parse(&date))
+            .or_else(|| Some(Date { month, ..Date::parse(&field_as_string("year")?)? }));
+        match (field_as_string("year"), date) {
+            (Some(year), _) => add_field("PY", year),
+            (None, Some(date)) => add_field("PY", date.year.to_string()),
+            (None, None) => {
+                if let Some(date) = field_as_string("date") {
+                    add_field("PY", date);
+                }
+            }
+        }
+        if let Some(date) = date.filter(|date| date.month.is_some()) {
+            add_field("DA", date.to_string());
         }
 
         // Journal or Booktitle -> T2
@@ -227,8 +269,11 @@ impl RisEntry {
             add_field("T2", booktitle);
         }
 
-        // Publisher -> PB
-        if let Some(publisher) = field_as_string("publisher") {
+        // Publisher -> PB, or for reports and theses the institution or school
+        if let Some(publisher) = field_as_string("publisher")
+            .or_else(|| field_as_string("institution"))
+            .or_else(|| field_as_string("school"))
+        {
             add_field("PB", publisher);
         }
 
@@ -325,20 +370,62 @@ impl RisEntry {
             add_field("SN", issn);
         }
 
-        // Add other fields as needed...
+        // Editors -> ED
+        if let Some(editor_str) = field_as_string("editor") {
+            for editor in editor_str.split(" and ").map(str::trim).filter(|s| !s.is_empty()) {
+                add_field("ED", editor.to_string());
+            }
+        }
+
+        // Edition -> ET
+        if let Some(edition) = field_as_string("edition") {
+            add_field("ET", edition);
+        }
+
+        // Address or location -> CY
+        if let Some(address) = field_as_string("address").or_else(|| field_as_string("location")) {
+            add_field("CY", address);
+        }
+
+        // Series -> T3
+        if let Some(series) = field_as_string("series") {
+            add_field("T3", series);
+        }
+
+        // Chapter -> SE
+        if let Some(chapter) = field_as_string("chapter") {
+            add_field("SE", chapter);
+        }
+
+        // Language -> LA
+        if let Some(language) = field_as_string("language") {
+            add_field("LA", language);
+        }
+
+        // Note -> N1
+        if let Some(note) = field_as_string("note") {
+            add_field("N1", note);
+        }
 
         RisEntry { ty, fields }
     }
+}
 
-    pub fn to_string(&self) -> String {
+impl fmt::Display for RisEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut lines = Vec::new();
 
         // Print the TY line
         lines.push(format!("TY  - {}", self.ty.to_str()));
 
         // For each field, print every value
-        for (tag, values) in &self.fields {
-            for value in values {
+        let mut tags: Vec<&String> = self.fields.keys().collect();
+        tags.sort_by_key(|tag| {
+            let known = TAG_ORDER.iter().position(|known| known == tag);
+            (known.unwrap_or(TAG_ORDER.len()), tag.as_str())
+        });
+        for tag in tags {
+            for value in &self.fields[tag] {
                 lines.push(format!("{}  - {}", tag, value));
             }
         }
@@ -346,40 +433,102 @@ impl RisEntry {
         // Print the ending ER line
         lines.push("ER  -".to_string());
 
-        lines.join("\n")
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Brace-protected parts of a BibTeX value, e.g. `DNA` in `{The {DNA} of cats}`.
+fn protected_phrases(chunks: &Chunks) -> Vec<String> {
+    let mut phrases: Vec<String> = Vec::new();
+    for spanned in chunks {
+        if let Chunk::Verbatim(s) = &spanned.v {
+            let phrase = latex::decode(s).trim().to_string();
+            if phrase.chars().any(char::is_alphabetic) && !phrases.contains(&phrase) {
+                phrases.push(phrase);
+            }
+        }
     }
+    phrases
 }
 
-/// Convert chunks to a string
-fn chunks_to_string(chunks: &Chunks) -> String {
+/// Wraps every occurrence of the protected phrases in braces, longest first.
+/// Phrases only match whole words, so `DNA` leaves `RDNA` alone.
+fn protect_phrases(text: &str, phrases: &[String]) -> String {
+    let mut phrases: Vec<&String> = phrases.iter().filter(|p| !p.is_empty()).collect();
+    phrases.sort_by_key(|p| std::cmp::Reverse(p.len()));
+
+    let mut protected = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut previous: Option<char> = None;
+    while let Some(c) = rest.chars().next() {
+        let in_word = previous.is_some_and(char::is_alphanumeric);
+        let matches = |phrase: &str| {
+            let after = rest.strip_prefix(phrase);
+            let starts_word = !in_word || !phrase.starts_with(char::is_alphanumeric);
+            let ends_word = !phrase.ends_with(char::is_alphanumeric)
+                || !after.is_some_and(|after| after.starts_with(char::is_alphanumeric));
+            after.is_some() && starts_word && ends_word
+        };
+        match phrases.iter().find(|phrase| matches(phrase)) {
+            Some(phrase) => {
+                protected.push('{');
+                protected.push_str(phrase);
+                protected.push('}');
+                rest = &rest[phrase.len()..];
+                previous = phrase.chars().last();
+            }
+            None => {
+                protected.push(c);
+                rest = &rest[c.len_utf8()..];
+                previous = Some(c);
+            }
+        }
+    }
+    protected
+}
+
+/// The text of a BibTeX value, with the LaTeX markup the parser leaves decoded.
+pub fn chunks_to_string(chunks: &Chunks) -> String {
     chunks
         .iter()
         .map(|spanned| match &spanned.v {
-            Chunk::Normal(s) => s.clone(),
-            Chunk::Verbatim(s) => s.clone(),
-            Chunk::Math(s) => s.clone(),
+            Chunk::Normal(s) => latex::decode_unbraced(s),
+            Chunk::Verbatim(s) | Chunk::Math(s) => latex::decode(s),
         })
         .collect::<Vec<_>>()
         .join("")
 }
 
+/// Splits a line such as `AU  - Doe, Jane` into tag and value, or gives
+/// `None` for a line without a tag.
+fn split_tag(line: &str) -> Option<(&str, &str)> {
+    let (tag, value) = line.split_once("  -")?;
+    let tag = tag.trim();
+    let is_tag = tag.len() == 2 && tag.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    (is_tag && (value.is_empty() || value.starts_with(' '))).then(|| (tag, value.trim()))
+}
+
+/// Parses RIS records. A line without a tag continues the value before it,
+/// as EndNote and Scopus wrap long abstracts. Byte order marks, also those of
+/// concatenated exports, and CRLF or CR line endings are accepted.
 pub fn parse_ris(content: &str) -> Result<Vec<RisEntry>> {
     let mut entries = Vec::new();
-    let mut current_fields = HashMap::new();
+    let mut current_fields: HashMap<String, Vec<String>> = HashMap::new();
     let mut current_ty = ReferenceType::Unknown;
     let mut has_ty = false; // Flag to ensure at least one `TY` exists
+    // Tag of the value that untagged lines continue
+    let mut last_tag: Option<String> = None;
 
-    for (line_number, line) in content.lines().enumerate() {
-        let line = line.trim_end();
+    // Lines end in LF, CRLF or, from old Mac programs, CR alone.
+    let lines = content.split('\n').flat_map(|line| line.strip_suffix('\r').unwrap_or(line).split('\r'));
+    for (line_number, line) in lines.enumerate() {
+        let line = line.trim_start_matches('\u{feff}').trim_end();
         if line.is_empty() {
             continue;
         }
 
-        // Split on "  -" and then trim the value
-        if let Some((tag, value)) = line.split_once("  -") {
-            let tag = tag.trim();
-            let value = value.trim();
-
+        if let Some((tag, value)) = split_tag(line) {
+            last_tag = None;
             match tag {
                 "TY" => {
                     // If we already had fields (meaning a previous entry was started),
@@ -391,7 +540,7 @@ pub fn parse_ris(content: &str) -> Result<Vec<RisEntry>> {
                         });
                         current_fields.clear();
                     }
-                    current_ty = ReferenceType::from_str(value);
+                    current_ty = value.parse().unwrap_or(ReferenceType::Unknown);
                     has_ty = true;
                 }
                 "ER" => {
@@ -414,10 +563,20 @@ pub fn parse_ris(content: &str) -> Result<Vec<RisEntry>> {
                     // Add to fields
                     current_fields
                         .entry(tag.to_string())
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(value.to_string());
+                    last_tag = Some(tag.to_string());
                 }
             }
+        } else if let Some(value) = last_tag
+            .as_ref()
+            .and_then(|tag| current_fields.get_mut(tag))
+            .and_then(|values| values.last_mut())
+        {
+            if !value.is_empty() {
+                value.push(' ');
+            }
+            value.push_str(line.trim());
         } else {
             return Err(anyhow!(
                 "Format error: Invalid line format at line {}: '{}'",
@@ -438,6 +597,17 @@ pub fn parse_ris(content: &str) -> Result<Vec<RisEntry>> {
     Ok(entries)
 }
 
+/// Number of a BibTeX month: `5`, `may` or `May`, which is what `month = may` parses to.
+pub fn month_number(month: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    let month = month.trim().to_lowercase();
+    if let Ok(number) = month.parse::<u32>() {
+        return (1..=12).contains(&number).then_some(number);
+    }
+    let abbreviation = month.get(..3)?;
+    MONTHS.iter().position(|m| *m == abbreviation).map(|i| i as u32 + 1)
+}
+
 /// Convert `ReferenceType` to a BibTeX entry type string.
 fn reference_type_to_bibtex(ty: &ReferenceType) -> &'static str {
     match ty {
@@ -451,8 +621,8 @@ fn reference_type_to_bibtex(ty: &ReferenceType) -> &'static str {
     }
 }
 
-/// Convert a `RisEntry` to a BibTeX-formatted string.
-pub fn ris_entry_to_bibtex_string(ris: &RisEntry, entry_key: &str) -> String {
+/// BibTeX entry type and `(field, value)` pairs for a `RisEntry`, in export order.
+pub fn bibtex_fields(ris: &RisEntry) -> (&'static str, Vec<(&'static str, String)>) {
     let entry_type = reference_type_to_bibtex(&ris.ty);
 
     // Helper to get first value of a field
@@ -467,11 +637,14 @@ pub fn ris_entry_to_bibtex_string(ris: &RisEntry, entry_key: &str) -> String {
     // Authors (AU): join with " and "
     let author = join_field("AU", " and ");
 
-    // Title (TI)
-    let title = get_first("TI");
+    // Title (TI), with case-protected phrases braced again
+    let protected = ris.fields.get(PROTECTED_TAG).map(Vec::as_slice).unwrap_or_default();
+    let title = get_first("TI").map(|t| protect_phrases(t, protected));
 
-    // Year (PY)
-    let year = get_first("PY");
+    // Year and month from the date (DA or PY), else PY as it is
+    let date = Date::of(ris);
+    let year = date.map(|date| date.year.to_string()).or_else(|| get_first("PY").cloned());
+    let month = date.and_then(|date| date.month);
 
     // T2 -> journal or booktitle depending on type
     let t2 = get_first("T2");
@@ -482,8 +655,16 @@ pub fn ris_entry_to_bibtex_string(ris: &RisEntry, entry_key: &str) -> String {
         _ => (None, None),
     };
 
-    // Publisher (PB)
-    let publisher = get_first("PB");
+    // Publisher (PB), which is the institution of a report and the school of a thesis
+    let pb = get_first("PB");
+    let (publisher, institution, school) = match ris.ty {
+        ReferenceType::Report => (None, pb, None),
+        ReferenceType::Thesis => (None, None, pb),
+        _ => (pb, None, None),
+    };
+
+    // Editors (ED): join with " and "
+    let editor = join_field("ED", " and ");
 
     // Volume (VL)
     let volume = get_first("VL");
@@ -515,27 +696,58 @@ pub fn ris_entry_to_bibtex_string(ris: &RisEntry, entry_key: &str) -> String {
     // Keywords (KW): join by ", "
     let keywords = join_field("KW", ", ");
 
+    let mut fields = Vec::new();
+    if let Some(a) = author { fields.push(("author", a)); }
+    if let Some(e) = editor { fields.push(("editor", e)); }
+    if let Some(t) = title { fields.push(("title", t)); }
+    if let Some(y) = year { fields.push(("year", y)); }
+    if let Some(m) = month { fields.push(("month", m.to_string())); }
+    if let Some(j) = journal { fields.push(("journal", j.clone())); }
+    if let Some(bt) = booktitle { fields.push(("booktitle", bt.clone())); }
+    if let Some(s) = get_first("T3") { fields.push(("series", s.clone())); }
+    if let Some(e) = get_first("ET") { fields.push(("edition", e.clone())); }
+    if let Some(c) = get_first("SE") { fields.push(("chapter", c.clone())); }
+    if let Some(p) = publisher { fields.push(("publisher", p.clone())); }
+    if let Some(i) = institution { fields.push(("institution", i.clone())); }
+    if let Some(s) = school { fields.push(("school", s.clone())); }
+    if let Some(a) = get_first("CY") { fields.push(("address", a.clone())); }
+    if let Some(v) = volume { fields.push(("volume", v.clone())); }
+    if let Some(n) = number { fields.push(("number", n.clone())); }
+    if let Some(pg) = pages { fields.push(("pages", pg)); }
+    if let Some(d) = doi { fields.push(("doi", d.clone())); }
+    if let Some(u) = url { fields.push(("url", u.clone())); }
+    if let Some(ab) = abstract_field { fields.push(("abstract", ab.clone())); }
+    if let Some(i) = issn { fields.push(("issn", i.clone())); }
+    if let Some(k) = keywords { fields.push(("keywords", k)); }
+    if let Some(l) = get_first("LA") { fields.push(("language", l.clone())); }
+    if let Some(n) = join_field("N1", "; ") { fields.push(("note", n)); }
+
+    (entry_type, fields)
+}
+
+/// Convert a `RisEntry` to a BibTeX-formatted string.
+pub fn ris_entry_to_bibtex_string(ris: &RisEntry, entry_key: &str) -> String {
+    let (entry_type, fields) = bibtex_fields(ris);
+
     // Start building the BibTeX string
     let mut lines = Vec::new();
 
     // Start entry: @article{key,
     lines.push(format!("@{}{{{},", entry_type, entry_key));
 
-    // Add fields if present
-    if let Some(a) = author { lines.push(format!("  author = {{{}}},", a)); }
-    if let Some(t) = title { lines.push(format!("  title = {{{}}},", t)); }
-    if let Some(y) = year { lines.push(format!("  year = {{{}}},", y)); }
-    if let Some(j) = journal { lines.push(format!("  journal = {{{}}},", j)); }
-    if let Some(bt) = booktitle { lines.push(format!("  booktitle = {{{}}},", bt)); }
-    if let Some(p) = publisher { lines.push(format!("  publisher = {{{}}},", p)); }
-    if let Some(v) = volume { lines.push(format!("  volume = {{{}}},", v)); }
-    if let Some(n) = number { lines.push(format!("  number = {{{}}},", n)); }
-    if let Some(pg) = pages { lines.push(format!("  pages = {{{}}},", pg)); }
-    if let Some(d) = doi { lines.push(format!("  doi = {{{}}},", d)); }
-    if let Some(u) = url { lines.push(format!("  url = {{{}}},", u)); }
-    if let Some(ab) = abstract_field { lines.push(format!("  abstract = {{{}}},", ab)); }
-    if let Some(i) = issn { lines.push(format!("  issn = {{{}}},", i)); }
-    if let Some(k) = keywords { lines.push(format!("  keywords = {{{}}},", k)); }
+    // Add fields if present, escaping LaTeX's special characters except in
+    // links, which BibTeX styles print verbatim. The title is escaped before
+    // its protected phrases are braced, so that braces in the text itself
+    // are escaped but the protecting ones are not.
+    let protected: Vec<String> = ris.fields.get(PROTECTED_TAG).into_iter().flatten().map(|p| latex::encode(p)).collect();
+    for (name, value) in fields {
+        let value = match (name, ris.get_field("TI")) {
+            ("url" | "doi", _) => value,
+            ("title", Some(title)) => protect_phrases(&latex::encode(title), &protected),
+            _ => latex::encode(&value),
+        };
+        lines.push(format!("  {} = {{{}}},", name, value));
+    }
 
     // Close the entry
     lines.push("}".to_string());
@@ -545,19 +757,16 @@ pub fn ris_entry_to_bibtex_string(ris: &RisEntry, entry_key: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_ris, ReferenceType, RisEntry};
+    use super::{bibtex_fields, parse_ris, protect_phrases, ReferenceType, RisEntry, PROTECTED_TAG};
     use std::collections::HashMap;
 
     #[test]
     fn test_reference_type_from_str() {
-        assert_eq!(ReferenceType::from_str("ABST"), ReferenceType::Abstract);
-        assert_eq!(ReferenceType::from_str("BOOK"), ReferenceType::Book);
-        assert_eq!(ReferenceType::from_str("JOUR"), ReferenceType::Journal);
-        assert_eq!(
-            ReferenceType::from_str("VIDEO"),
-            ReferenceType::VideoRecording
-        );
-        assert_eq!(ReferenceType::from_str("UNKN"), ReferenceType::Unknown);
+        assert_eq!("ABST".parse(), Ok(ReferenceType::Abstract));
+        assert_eq!("BOOK".parse(), Ok(ReferenceType::Book));
+        assert_eq!("JOUR".parse(), Ok(ReferenceType::Journal));
+        assert_eq!("VIDEO".parse(), Ok(ReferenceType::VideoRecording));
+        assert_eq!("UNKN".parse(), Ok(ReferenceType::Unknown));
     }
 
     #[test]
@@ -666,11 +875,11 @@ ER  -
 
     #[test]
     fn test_parse_ris_invalid_format_line() {
-        // A line that does not contain "  - "
+        // A line that does not contain "  - " and continues no value
         let content = r#"
+InvalidLine
 TY  - JOUR
 AU  - Author One
-InvalidLine
 ER  -
 "#;
 
@@ -680,6 +889,30 @@ ER  -
         assert!(error_msg.contains("Invalid line format"));
     }
 
+    #[test]
+    fn test_parse_ris_continuation_lines() {
+        let content = "TY  - JOUR\nAB  - Long abstracts are\n  wrapped by EndNote -- without\na tag.\nKW  - cats\nER  -";
+
+        let entries = parse_ris(content).unwrap();
+        assert_eq!(
+            entries[0].get_field("AB"),
+            Some(&"Long abstracts are wrapped by EndNote -- without a tag.".to_string())
+        );
+        assert_eq!(entries[0].get_field("KW"), Some(&"cats".to_string()));
+    }
+
+    #[test]
+    fn test_parse_ris_bom_and_line_endings() {
+        let content = "\u{feff}TY  - JOUR\r\nTI  - Windows\r\nER  -\r\n\u{feff}TY  - BOOK\rTI  - Classic Mac\rER  -\r";
+
+        let entries = parse_ris(content).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].ty, ReferenceType::Journal);
+        assert_eq!(entries[0].get_field("TI"), Some(&"Windows".to_string()));
+        assert_eq!(entries[1].ty, ReferenceType::Book);
+        assert_eq!(entries[1].get_field("TI"), Some(&"Classic Mac".to_string()));
+    }
+
     #[test]
     fn test_parse_ris_multiple_values_for_same_tag() {
         let content = r#"
@@ -909,4 +1142,76 @@ ER  -
             ]
         );
     }
+
+    #[test]
+    fn test_ris_entry_from_biblatex_extra_fields() {
+        let bib = biblatex::Bibliography::parse(
+            "@techreport{r, title = {T}, year = {2020}, month = may, location = {Oslo},
+             institution = {SINTEF}, editor = {Doe, Jane and Roe, Rick}}",
+        )
+        .unwrap();
+        let entry = RisEntry::from(bib.get("r").unwrap());
+
+        assert_eq!(entry.fields["ED"], vec!["Doe, Jane", "Roe, Rick"]);
+        assert_eq!(entry.get_field("DA"), Some(&"2020/05".to_string()));
+        assert_eq!(entry.get_field("CY"), Some(&"Oslo".to_string()));
+        assert_eq!(entry.get_field("PB"), Some(&"SINTEF".to_string()));
+
+        let (_, fields) = bibtex_fields(&entry);
+        assert!(fields.contains(&("institution", "SINTEF".to_string())));
+        assert!(fields.contains(&("month", "5".to_string())));
+        assert!(fields.contains(&("address", "Oslo".to_string())));
+    }
+
+    #[test]
+    fn test_ris_entry_from_biblatex_date() {
+        let bib = biblatex::Bibliography::parse("@article{a, title = {T}, date = {2021-05-17}}").unwrap();
+        let entry = RisEntry::from(bib.get("a").unwrap());
+
+        assert_eq!(entry.get_field("PY"), Some(&"2021".to_string()));
+        assert_eq!(entry.get_field("DA"), Some(&"2021/05/17".to_string()));
+        let (_, fields) = bibtex_fields(&entry);
+        assert!(fields.contains(&("year", "2021".to_string())));
+        assert!(fields.contains(&("month", "5".to_string())));
+    }
+
+    #[test]
+    fn test_display_writes_tags_in_fixed_order() {
+        let mut fields = HashMap::new();
+        for (tag, value) in [
+            ("UR", "https://example.org"),
+            ("ZZ", "z"),
+            ("C1", "c"),
+            ("TI", "Title"),
+            ("PY", "2020"),
+            ("AU", "Doe, Jane"),
+        ] {
+            fields.insert(tag.to_string(), vec![value.to_string()]);
+        }
+        fields.get_mut("AU").unwrap().push("Roe, Rick".to_string());
+        let entry = RisEntry { ty: ReferenceType::Journal, fields };
+
+        assert_eq!(
+            entry.to_string(),
+            "TY  - JOUR\nAU  - Doe, Jane\nAU  - Roe, Rick\nTI  - Title\nPY  - 2020\nUR  - https://example.org\nC1  - c\nZZ  - z\nER  -"
+        );
+    }
+
+    #[test]
+    fn test_title_brace_protection_round_trip() {
+        let bibliography =
+            biblatex::Bibliography::parse("@article{k, title = {The {DNA} of {GPU} clusters}}")
+                .unwrap();
+        let entry = RisEntry::from(bibliography.iter().next().unwrap());
+
+        assert_eq!(entry.get_field("TI").unwrap(), "The DNA of GPU clusters");
+        assert_eq!(entry.fields[PROTECTED_TAG], vec!["DNA", "GPU"]);
+
+        let (_, fields) = bibtex_fields(&entry);
+        let title = fields.iter().find(|(name, _)| *name == "title").unwrap();
+        assert_eq!(title.1, "The {DNA} of {GPU} clusters");
+
+        let phrases = vec!["DNA".to_string()];
+        assert_eq!(protect_phrases("RDNA, DNA-binding and DNAs", &phrases), "RDNA, {DNA}-binding and DNAs");
+    }
 }
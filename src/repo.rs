@@ -1,107 +1,867 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use crate::config;
+use crate::model::date::days_from_civil;
 use colored::*;
-use std::process::Command;
-use std::path::Path;
-
-/// Executes a Git command with the provided arguments.
-fn execute_git_command<P: AsRef<Path>>(repo_path: P, args: &[&str]) -> Result<()> {
-    let status = Command::new("git")
-        .current_dir(repo_path.as_ref())
-        .args(args)
-        .status()
-        .context("Failed to execute git command")?;
-
-    if !status.success() {
-        return Err(anyhow::anyhow!(
-            "{}: Git command failed with exit status {}",
-            "Error".red().bold(),
-            status
-        ));
+use serde::{Deserialize, Serialize};
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{
+    BranchType, Commit, Cred, CredentialType, Delta, Diff, DiffFindOptions, DiffOptions, ErrorCode, FetchOptions,
+    IndexAddOption, PushOptions, RemoteCallbacks, Repository, Signature, StatusOptions,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Set by [`status_to_stderr`] for `--json`.
+static STATUS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Sends the lines printed with [`status`] to stderr from now on, keeping
+/// stdout for `--json` output.
+pub fn status_to_stderr() {
+    STATUS_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Prints a progress or status line, on stderr after [`status_to_stderr`].
+pub fn status(line: impl Display) {
+    if STATUS_TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
     }
+}
 
-    Ok(())
+fn open(repo_path: &str) -> Result<Repository> {
+    Repository::open(repo_path).with_context(|| format!("{} is not a git repository", repo_path))
+}
+
+/// The identity from git config that commits are made as.
+fn signature(repo: &Repository) -> Result<Signature<'static>> {
+    repo.signature()
+        .context("No git identity; set one with `git config --global user.name` and `user.email`")
+}
+
+/// How to authenticate to git remotes. Unset parts fall back to the SSH
+/// agent and git's credential helpers.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitAuth {
+    /// Private key for SSH remotes, used instead of the SSH agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<String>,
+    /// Environment variable holding an access token for HTTPS remotes. The
+    /// token itself is never stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
+    /// User name for the token or SSH key, when the remote URL has none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+impl GitAuth {
+    pub fn is_empty(&self) -> bool {
+        *self == GitAuth::default()
+    }
+}
+
+/// The usual private keys in `~/.ssh` that exist, tried after the SSH agent
+/// like `ssh` itself does.
+fn default_ssh_keys() -> Vec<PathBuf> {
+    let Some(home) = dirs_next::home_dir() else {
+        return Vec::new();
+    };
+    ["id_ed25519", "id_rsa"]
+        .iter()
+        .map(|name| home.join(".ssh").join(name))
+        .filter(|path| path.is_file())
+        .collect()
 }
 
-/// Clones a Git repository to the specified path.
-pub fn clone_repo(relative_path: &str, url: &str) -> Result<String> {
+/// Callbacks answering credential requests with the configured SSH key or
+/// token, else the SSH agent followed by the keys in `~/.ssh`, or git's
+/// credential helpers.
+fn remote_callbacks(auth: &GitAuth) -> RemoteCallbacks<'_> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut attempts = 0;
+    let mut tried_agent = false;
+    let mut ssh_keys = default_ssh_keys().into_iter();
+    callbacks.credentials(move |url, url_username, allowed| {
+        // libgit2 asks again after every rejected credential.
+        attempts += 1;
+        if attempts > 5 {
+            return Err(git2::Error::from_str(
+                "authentication failed; configure credentials with `refrs auth`",
+            ));
+        }
+        let username = url_username.or(auth.username.as_deref()).unwrap_or("git");
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username);
+        }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            if let Some(key) = &auth.ssh_key {
+                return Cred::ssh_key(username, None, Path::new(key), None);
+            }
+            if !tried_agent {
+                tried_agent = true;
+                return Cred::ssh_key_from_agent(username);
+            }
+            if let Some(key) = ssh_keys.next() {
+                return Cred::ssh_key(username, None, &key, None);
+            }
+            return Err(git2::Error::from_str(
+                "authentication failed; configure credentials with `refrs auth`",
+            ));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(var) = &auth.token_env {
+                let token = std::env::var(var)
+                    .map_err(|_| git2::Error::from_str(&format!("{} is not set", var)))?;
+                return Cred::userpass_plaintext(username, &token);
+            }
+            let config = git2::Config::open_default()?;
+            return Cred::credential_helper(&config, url, url_username);
+        }
+        Cred::default()
+    });
+    callbacks
+}
+
+/// Diffs a commit against its first parent, or an empty tree for the root
+/// commit, with renames detected.
+fn commit_diff<'r>(repo: &'r Repository, commit: &Commit, pathspec: &str) -> Result<Diff<'r>> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let mut options = DiffOptions::new();
+    options.pathspec(pathspec);
+    let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut options))?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    Ok(diff)
+}
+
+/// Non-merge commits reachable from HEAD, newest first, like `git log`.
+fn history(repo: &Repository) -> Result<Vec<Commit<'_>>> {
+    let mut walk = repo.revwalk()?;
+    if let Err(err) = walk.push_head() {
+        // A repository without commits has no history.
+        if err.code() == ErrorCode::UnbornBranch {
+            return Ok(Vec::new());
+        }
+        return Err(err.into());
+    }
+    walk.set_sorting(git2::Sort::TIME)?;
+    let mut commits = Vec::new();
+    for id in walk {
+        let commit = repo.find_commit(id?)?;
+        if commit.parent_count() <= 1 {
+            commits.push(commit);
+        }
+    }
+    Ok(commits)
+}
+
+fn is_added_modified_or_renamed(status: Delta) -> bool {
+    matches!(status, Delta::Added | Delta::Modified | Delta::Renamed)
+}
+
+/// Unix time of a `--since` date relative to `now`. Understands the common
+/// forms of git's approxidate: `2024-05-01`, `today`, `yesterday`,
+/// `last week`, `3 weeks ago`, `a month ago` and `3.weeks.ago`.
+fn parse_since(since: &str, now: i64) -> Option<i64> {
+    const DAY: i64 = 24 * 60 * 60;
+    let since = since.trim().to_lowercase().replace('.', " ");
+    match since.as_str() {
+        "now" => return Some(now),
+        "today" => return Some(now - now.rem_euclid(DAY)),
+        "yesterday" => return Some(now - DAY),
+        _ => {}
+    }
+
+    let parts: Vec<&str> = since.split('-').collect();
+    if let [year, month, day] = parts[..] {
+        let (year, month, day) = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        return Some(days_from_civil(year, month, day) * DAY);
+    }
+
+    let words: Vec<&str> = since.split_whitespace().collect();
+    let (count, unit) = match words[..] {
+        ["last", unit] => (1, unit),
+        ["a" | "an", unit, "ago"] => (1, unit),
+        [count, unit, "ago"] => (count.parse().ok()?, unit),
+        _ => return None,
+    };
+    let seconds = match unit.trim_end_matches('s') {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 60 * 60,
+        "day" => DAY,
+        "week" => 7 * DAY,
+        "month" => 30 * DAY,
+        "year" => 365 * DAY,
+        _ => return None,
+    };
+    Some(now - count * seconds)
+}
+
+/// Lists files below `pathspec` that were added or modified since `since`,
+/// which is either a revision (compared against the working tree) or a date
+/// such as `2024-05-01` or `"1 month ago"`.
+pub fn changed_files_since(repo_path: &str, since: &str, pathspec: &str) -> Result<Vec<String>> {
+    let repo = open(repo_path)?;
+    let mut files = Vec::new();
+    let mut collect = |diff: &Diff| {
+        for delta in diff.deltas().filter(|d| is_added_modified_or_renamed(d.status())) {
+            if let Some(path) = delta.new_file().path() {
+                files.push(path.to_string_lossy().to_string());
+            }
+        }
+    };
+
+    if let Ok(commit) = repo.revparse_single(since).and_then(|object| object.peel_to_commit()) {
+        let mut options = DiffOptions::new();
+        options.pathspec(pathspec);
+        let mut diff = repo.diff_tree_to_workdir_with_index(Some(&commit.tree()?), Some(&mut options))?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+        collect(&diff);
+    } else {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        let Some(time) = parse_since(since, now) else {
+            bail!("{} is neither a revision nor a date", since);
+        };
+        for commit in history(&repo)?.iter().filter(|c| c.time().seconds() >= time) {
+            collect(&commit_diff(&repo, commit, pathspec)?);
+        }
+    }
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Unix time each file below `pathspec` was first committed, keyed by file
+/// stem.
+pub fn added_times(repo_path: &str, pathspec: &str) -> Result<HashMap<String, i64>> {
+    let repo = open(repo_path)?;
+    let mut times = HashMap::new();
+    // History is newest first, so a re-added file ends up with its oldest addition.
+    for commit in history(&repo)? {
+        let diff = commit_diff(&repo, &commit, pathspec)?;
+        for delta in diff.deltas().filter(|d| d.status() == Delta::Added) {
+            if let Some(stem) = delta.new_file().path().and_then(Path::file_stem) {
+                times.insert(stem.to_string_lossy().to_string(), commit.time().seconds());
+            }
+        }
+    }
+    Ok(times)
+}
+
+/// Unix time of the last commit that added or removed the line `line` in
+/// each file matching `pathspec`, keyed by file stem. Lines are compared
+/// whole and regardless of case, so `KW  - Reading` counts for
+/// `KW  - reading` but `KW  - reading-list` doesn't.
+pub fn last_changed_times(repo_path: &str, pathspec: &str, line: &str) -> Result<HashMap<String, i64>> {
+    let repo = open(repo_path)?;
+    let line = line.trim().to_lowercase();
+    let occurrences = |id: git2::Oid| -> usize {
+        if id.is_zero() {
+            return 0;
+        }
+        repo.find_blob(id)
+            .map(|blob| {
+                String::from_utf8_lossy(blob.content())
+                    .lines()
+                    .filter(|other| other.trim().to_lowercase() == line)
+                    .count()
+            })
+            .unwrap_or(0)
+    };
+
+    let mut times = HashMap::new();
+    // Newest first, so the first time seen for a file is the latest change.
+    for commit in history(&repo)? {
+        let diff = commit_diff(&repo, &commit, pathspec)?;
+        for delta in diff.deltas() {
+            // Like `git log -S`, a change is a change in the number of occurrences.
+            if occurrences(delta.old_file().id()) == occurrences(delta.new_file().id()) {
+                continue;
+            }
+            let file = delta.new_file().path().or(delta.old_file().path());
+            if let Some(stem) = file.and_then(Path::file_stem) {
+                times
+                    .entry(stem.to_string_lossy().to_string())
+                    .or_insert(commit.time().seconds());
+            }
+        }
+    }
+    Ok(times)
+}
+
+/// Clones a Git repository to the specified path. `depth` limits the
+/// history fetched; `branch` checks out and fetches only that branch.
+pub fn clone_repo(
+    relative_path: &str,
+    url: &str,
+    auth: &GitAuth,
+    depth: Option<u32>,
+    branch: Option<&str>,
+) -> Result<String> {
     let absolute_path = std::env::current_dir()
         .context("Failed to get current working directory")?
         .join(Path::new(relative_path));
 
+    println!("{} {}", t!("git-cloning").green().bold(), url.underline().bold());
     println!(
         "{} {}",
-        "Cloning:".green().bold(),
-        url.underline().bold()
-    );
-    println!(
-        "{} {}",
-        "Absolute path:".cyan(),
+        t!("git-absolute-path").cyan(),
         absolute_path.display().to_string().underline().bold()
     );
 
-    execute_git_command(
-        std::env::current_dir()?,
-        &["clone", url, absolute_path.to_str().unwrap()],
-    )?;
+    let mut callbacks = remote_callbacks(auth);
+    callbacks.transfer_progress(|progress| {
+        print!(
+            "\r{}",
+            t!("git-receiving", received = progress.received_objects(), total = progress.total_objects())
+        );
+        let _ = std::io::stdout().flush();
+        true
+    });
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+    if let Some(depth) = depth {
+        fetch_options.depth(depth.try_into().context("--depth is too large")?);
+    }
+    let mut builder = RepoBuilder::new();
+    builder.fetch_options(fetch_options);
+    if let Some(branch) = branch {
+        builder.branch(branch);
+        let refspec = format!("+refs/heads/{0}:refs/remotes/origin/{0}", branch);
+        builder.remote_create(move |repo, name, url| repo.remote_with_fetch(name, url, &refspec));
+    }
+    let cloned = builder.clone(url, &absolute_path);
+    println!();
+    cloned.with_context(|| format!("Failed to clone {}", url))?;
 
-    println!("{}", "Repository cloned successfully!".green().bold());
+    println!("{}", t!("git-cloned").green().bold());
 
     Ok(absolute_path.to_string_lossy().to_string())
 }
 
-/// Performs a `git pull --rebase` in the specified repository.
-pub fn pull_rebase(repo_path: &str) -> Result<()> {
+/// Where the current branch pulls from and pushes to.
+struct Upstream {
+    /// `refs/heads/<branch>`
+    local: String,
+    branch: String,
+    remote: String,
+    /// The remote-tracking reference, e.g. `refs/remotes/origin/main`
+    tracking: String,
+    /// Whether git config names the upstream, rather than it being assumed.
+    configured: bool,
+}
+
+/// `origin`, or the only remote there is.
+fn default_remote(repo: &Repository) -> Result<String> {
+    let remotes = repo.remotes()?;
+    let names: Vec<&str> = remotes.iter().flatten().collect();
+    match names[..] {
+        _ if names.contains(&"origin") => Ok("origin".to_string()),
+        [only] => Ok(only.to_string()),
+        [] => bail!("The repository has no remote"),
+        _ => bail!("The repository has several remotes and none is named origin"),
+    }
+}
+
+fn upstream(repo: &Repository) -> Result<Upstream> {
+    let head = repo.head().context("The repository has no commits")?;
+    let (Some(local), Some(branch)) = (head.name().filter(|_| head.is_branch()), head.shorthand()) else {
+        bail!("HEAD is not on a branch");
+    };
+    let configured = repo
+        .branch_upstream_remote(local)
+        .ok()
+        .and_then(|remote| remote.as_str().map(str::to_string))
+        .zip(
+            repo.branch_upstream_name(local)
+                .ok()
+                .and_then(|tracking| tracking.as_str().map(str::to_string)),
+        );
+    if let Some((remote, tracking)) = configured {
+        return Ok(Upstream {
+            local: local.to_string(),
+            branch: branch.to_string(),
+            remote,
+            tracking,
+            configured: true,
+        });
+    }
+
+    // A new branch follows the branch of the same name on the default remote.
+    let remote = default_remote(repo)?;
+    Ok(Upstream {
+        local: local.to_string(),
+        branch: branch.to_string(),
+        tracking: format!("refs/remotes/{}/{}", remote, branch),
+        remote,
+        configured: false,
+    })
+}
+
+/// Name of the checked-out branch.
+pub fn current_branch(repo_path: &str) -> Result<String> {
+    let repo = open(repo_path)?;
+    let head = repo.head().context("The repository has no commits")?;
+    match head.shorthand().filter(|_| head.is_branch()) {
+        Some(branch) => Ok(branch.to_string()),
+        None => bail!("HEAD is not on a branch"),
+    }
+}
+
+/// A branch of a project repository.
+pub struct BranchInfo {
+    pub name: String,
+    pub current: bool,
+    /// Only on the remote so far; switching to it creates a local branch.
+    pub remote_only: bool,
+}
+
+/// Local branches, then branches only on the default remote, sorted by name.
+pub fn branches(repo_path: &str) -> Result<Vec<BranchInfo>> {
+    let repo = open(repo_path)?;
+    let current = current_branch(repo_path).ok();
+    let mut local = Vec::new();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        if let Some(name) = branch?.0.name()? {
+            local.push(name.to_string());
+        }
+    }
+    local.sort();
+
+    let mut remote_only = Vec::new();
+    if let Ok(remote) = default_remote(&repo) {
+        let prefix = format!("{}/", remote);
+        for branch in repo.branches(Some(BranchType::Remote))? {
+            let branch = branch?.0;
+            let Some(name) = branch.name()?.and_then(|name| name.strip_prefix(&prefix)) else {
+                continue;
+            };
+            if name != "HEAD" && !local.iter().any(|l| l == name) {
+                remote_only.push(name.to_string());
+            }
+        }
+    }
+    remote_only.sort();
+
+    Ok(local
+        .into_iter()
+        .map(|name| BranchInfo {
+            current: current.as_deref() == Some(name.as_str()),
+            name,
+            remote_only: false,
+        })
+        .chain(remote_only.into_iter().map(|name| BranchInfo {
+            name,
+            current: false,
+            remote_only: true,
+        }))
+        .collect())
+}
+
+/// Creates a branch at the current commit and switches to it.
+pub fn create_branch(repo_path: &str, name: &str) -> Result<()> {
+    let repo = open(repo_path)?;
+    let head = repo.head().context("The repository has no commits")?.peel_to_commit()?;
+    repo.branch(name, &head, false)
+        .with_context(|| format!("Failed to create branch {}", name))?;
+    switch_branch(repo_path, name)
+}
+
+/// Checks out a local branch, or creates one following the remote branch of
+/// that name. Fails rather than overwrite uncommitted changes.
+pub fn switch_branch(repo_path: &str, name: &str) -> Result<()> {
+    let repo = open(repo_path)?;
+    let branch = match repo.find_branch(name, BranchType::Local) {
+        Ok(branch) => branch,
+        Err(err) if err.code() == ErrorCode::NotFound => {
+            let remote = default_remote(&repo)?;
+            let tracking = format!("{}/{}", remote, name);
+            let Ok(remote_branch) = repo.find_branch(&tracking, BranchType::Remote) else {
+                bail!("No branch named {}", name);
+            };
+            let mut branch = repo.branch(name, &remote_branch.get().peel_to_commit()?, false)?;
+            branch.set_upstream(Some(&tracking))?;
+            branch
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let reference = branch.get();
+    let Some(refname) = reference.name() else {
+        bail!("Branch {} has an invalid name", name);
+    };
+    repo.checkout_tree(&reference.peel(git2::ObjectType::Commit)?, Some(CheckoutBuilder::new().safe()))
+        .with_context(|| format!("Cannot switch to {} without losing uncommitted changes", name))?;
+    repo.set_head(refname)?;
+    Ok(())
+}
+
+/// A file changed on both sides while rebasing local commits onto the
+/// remote. Contents are `None` on a side that deleted the file.
+pub struct Conflict {
+    /// Path relative to the repository root
+    pub path: String,
+    pub base: Option<Vec<u8>>,
+    pub remote: Option<Vec<u8>>,
+    pub local: Option<Vec<u8>>,
+    /// Whether the local commit is newer than the remote one it meets.
+    pub local_is_newer: bool,
+}
+
+/// How to settle a [`Conflict`].
+pub enum Resolution {
+    /// Write these contents
+    Content(Vec<u8>),
+    /// Remove the file
+    Delete,
+    /// Abort the pull, leaving the repository as it was
+    Abort,
+}
+
+/// Settles every conflict the commit being replayed ran into, or fails if
+/// `resolve` gives up on one.
+fn resolve_conflicts(
+    repo: &Repository,
+    applied: &Commit,
+    resolve: &mut impl FnMut(&Conflict) -> Result<Resolution>,
+) -> Result<()> {
+    let mut index = repo.index()?;
+    if !index.has_conflicts() {
+        return Ok(());
+    }
+    let workdir = repo.workdir().context("The repository has no working directory")?;
+    let remote_time = repo.head()?.peel_to_commit()?.time().seconds();
+    let contents = |entry: &Option<git2::IndexEntry>| -> Result<Option<Vec<u8>>> {
+        match entry {
+            Some(entry) => Ok(Some(repo.find_blob(entry.id)?.content().to_vec())),
+            None => Ok(None),
+        }
+    };
+
+    let conflicts: Vec<git2::IndexConflict> = index.conflicts()?.collect::<Result<_, _>>()?;
+    for found in conflicts {
+        // While rebasing, "ours" is the remote branch and "theirs" the local commit.
+        let Some(entry) = found.our.as_ref().or(found.their.as_ref()).or(found.ancestor.as_ref()) else {
+            continue;
+        };
+        let path = String::from_utf8_lossy(&entry.path).to_string();
+        let conflict = Conflict {
+            path: path.clone(),
+            base: contents(&found.ancestor)?,
+            remote: contents(&found.our)?,
+            local: contents(&found.their)?,
+            local_is_newer: applied.time().seconds() > remote_time,
+        };
+        match resolve(&conflict)? {
+            Resolution::Content(content) => {
+                fs::write(workdir.join(&path), content)?;
+                index.add_path(Path::new(&path))?;
+            }
+            Resolution::Delete => {
+                let _ = fs::remove_file(workdir.join(&path));
+                index.remove_path(Path::new(&path))?;
+            }
+            Resolution::Abort => bail!("Pull stopped on a conflict in {}; nothing was changed", path),
+        }
+    }
+    index.write()?;
+    Ok(())
+}
+
+/// Fetches the upstream's remote and returns the remote-tracking reference,
+/// or `None` for a new branch not yet on the remote.
+fn fetch<'r>(repo: &'r Repository, upstream: &Upstream, auth: &GitAuth) -> Result<Option<git2::Reference<'r>>> {
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(remote_callbacks(auth));
+    repo.find_remote(&upstream.remote)?
+        .fetch(&[] as &[&str], Some(&mut fetch_options), None)
+        .with_context(|| format!("Failed to fetch from {}", upstream.remote))?;
+
+    match repo.find_reference(&upstream.tracking) {
+        Ok(tracking) => Ok(Some(tracking)),
+        Err(err) if err.code() == ErrorCode::NotFound && !upstream.configured => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// What `pull_rebase` and `push` would do, as `<short id> <summary>` lines.
+pub struct SyncPreview {
+    pub branch: String,
+    pub remote: String,
+    /// Commits on the remote branch missing locally
+    pub incoming: Vec<String>,
+    /// Local commits missing on the remote branch
+    pub outgoing: Vec<String>,
+}
+
+/// Fetches and compares the current branch with its upstream. Only
+/// remote-tracking references change, as with `git fetch`.
+pub fn sync_preview(repo_path: &str, auth: &GitAuth) -> Result<SyncPreview> {
+    let repo = open(repo_path)?;
+    let upstream = upstream(&repo)?;
+    let head = repo.head()?.peel_to_commit()?.id();
+    let tracking = match fetch(&repo, &upstream, auth)? {
+        Some(tracking) => Some(tracking.peel_to_commit()?.id()),
+        None => None,
+    };
+
+    let commits = |from: git2::Oid, hide: Option<git2::Oid>| -> Result<Vec<String>> {
+        let mut walk = repo.revwalk()?;
+        walk.push(from)?;
+        if let Some(hide) = hide {
+            walk.hide(hide)?;
+        }
+        walk.map(|id| {
+            let commit = repo.find_commit(id?)?;
+            let short = commit.as_object().short_id()?;
+            Ok(format!("{} {}", short.as_str().unwrap_or_default(), commit.summary().unwrap_or_default()))
+        })
+        .collect()
+    };
+
+    Ok(SyncPreview {
+        incoming: match tracking {
+            Some(tracking) => commits(tracking, Some(head))?,
+            None => Vec::new(),
+        },
+        outgoing: commits(head, tracking)?,
+        branch: upstream.branch,
+        remote: upstream.remote,
+    })
+}
+
+/// Performs a `git pull --rebase` in the specified repository, settling
+/// conflicting files with `resolve`.
+pub fn pull_rebase(
+    repo_path: &str,
+    auth: &GitAuth,
+    mut resolve: impl FnMut(&Conflict) -> Result<Resolution>,
+) -> Result<()> {
     println!(
         "{} {}",
-        "Pulling with rebase in:".yellow().bold(),
+        t!("git-pulling").yellow().bold(),
         repo_path.underline().bold()
     );
 
-    execute_git_command(repo_path, &["pull", "--rebase"])?;
+    let repo = open(repo_path)?;
+    let upstream = upstream(&repo)?;
+
+    let mut status_options = StatusOptions::new();
+    status_options.include_untracked(false);
+    if !repo.statuses(Some(&mut status_options))?.is_empty() {
+        bail!("{} has uncommitted changes; commit them before pulling", repo_path);
+    }
+
+    let Some(tracking) = fetch(&repo, &upstream, auth)? else {
+        println!("{}", t!("git-nothing-to-pull", branch = upstream.branch.bold(), remote = upstream.remote));
+        return Ok(());
+    };
+    let local = upstream.local;
+    let upstream = repo.reference_to_annotated_commit(&tracking)?;
+    let (analysis, _) = repo.merge_analysis(&[&upstream])?;
+    if analysis.is_up_to_date() {
+        // Nothing new upstream
+    } else if analysis.is_fast_forward() {
+        let target = repo.find_object(upstream.id(), None)?;
+        repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))?;
+        repo.find_reference(&local)?
+            .set_target(upstream.id(), "refrs: pull --rebase (fast-forward)")?;
+    } else {
+        let head = repo.reference_to_annotated_commit(&repo.head()?)?;
+        let signature = signature(&repo)?;
+        let mut rebase = repo.rebase(Some(&head), Some(&upstream), None, None)?;
+        while let Some(operation) = rebase.next().map(|operation| operation.map(|o| o.id())) {
+            let applied = match operation {
+                Ok(id) => repo.find_commit(id)?,
+                Err(err) => {
+                    rebase.abort()?;
+                    return Err(err).context("Rebase failed");
+                }
+            };
+            if let Err(err) = resolve_conflicts(&repo, &applied, &mut resolve) {
+                rebase.abort()?;
+                return Err(err);
+            }
+            match rebase.commit(None, &signature, None) {
+                // The upstream already has this change.
+                Err(err) if err.code() == ErrorCode::Applied => {}
+                Err(err) => {
+                    rebase.abort()?;
+                    return Err(err).context("Rebase failed");
+                }
+                Ok(_) => {}
+            }
+        }
+        rebase.finish(Some(&signature))?;
+    }
 
-    println!("{}", "Rebase completed successfully!".green().bold());
+    println!("{}", t!("git-rebased").green().bold());
     Ok(())
 }
 
 /// Pushes changes to the remote repository.
-pub fn push(repo_path: &str) -> Result<()> {
+pub fn push(repo_path: &str, auth: &GitAuth) -> Result<()> {
     println!(
         "{} {}",
-        "Pushing changes in:".yellow().bold(),
+        t!("git-pushing").yellow().bold(),
         repo_path.underline().bold()
     );
 
-    execute_git_command(repo_path, &["push"])?;
+    let repo = open(repo_path)?;
+    let upstream = upstream(&repo)?;
+    let remote_name = &upstream.remote;
+    let remote_branch = upstream
+        .tracking
+        .strip_prefix(&format!("refs/remotes/{}/", remote_name))
+        .unwrap_or(&upstream.branch);
+    let refspec = format!("{}:refs/heads/{}", upstream.local, remote_branch);
 
-    println!("{}", "Push completed successfully!".green().bold());
+    let mut rejected = Vec::new();
+    {
+        let mut callbacks = remote_callbacks(auth);
+        callbacks.push_update_reference(|reference, status| {
+            if let Some(status) = status {
+                rejected.push(format!("{} ({})", reference, status));
+            }
+            Ok(())
+        });
+        let mut push_options = PushOptions::new();
+        push_options.remote_callbacks(callbacks);
+        repo.find_remote(remote_name)?
+            .push(&[refspec.as_str()], Some(&mut push_options))
+            .with_context(|| format!("Failed to push to {}", remote_name))?;
+    }
+    if !rejected.is_empty() {
+        bail!("{} rejected {}", remote_name, rejected.join(", "));
+    }
+    if !upstream.configured {
+        // Like `git push -u`, so later pulls follow the pushed branch.
+        repo.find_branch(&upstream.branch, BranchType::Local)?
+            .set_upstream(Some(&format!("{}/{}", remote_name, remote_branch)))?;
+    }
+
+    println!("{}", t!("git-pushed").green().bold());
     Ok(())
 }
 
 /// Stages all changes (adds all files) in the specified repository.
 pub fn add_all(repo_path: &str) -> Result<()> {
-    println!(
+    status(format!(
         "{} {}",
-        "Staging all changes in:".yellow().bold(),
+        t!("git-staging").yellow().bold(),
         repo_path.underline().bold()
-    );
+    ));
 
-    execute_git_command(repo_path, &["add", "--all"])?;
+    let repo = open(repo_path)?;
+    let mut index = repo.index()?;
+    index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+    // Stage deletions too, like `git add --all`.
+    index.update_all(["*"], None)?;
+    index.write()?;
 
-    println!("{}", "All changes staged successfully!".green().bold());
+    status(t!("git-staged").green().bold());
     Ok(())
 }
 
-/// Commits staged changes with the provided commit message.
+/// Commits the staged changes, unless the project's `.refrs.toml` turns
+/// `auto_commit` off, in which case they stay staged.
 pub fn commit(repo_path: &str, message: &str) -> Result<()> {
-    println!(
+    if !config::load(repo_path)?.auto_commit {
+        status(format!(
+            "{} \"{}\" {}",
+            t!("git-staged-only").yellow().bold(),
+            message.cyan().italic(),
+            repo_path.underline().bold()
+        ));
+        return Ok(());
+    }
+
+    status(format!(
         "{} \"{}\" {}",
-        "Committing changes with message:".yellow().bold(),
+        t!("git-committing").yellow().bold(),
         message.cyan().italic(),
         repo_path.underline().bold()
-    );
+    ));
 
-    execute_git_command(repo_path, &["commit", "-m", message])?;
+    let repo = open(repo_path)?;
+    let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(err) if err.code() == ErrorCode::UnbornBranch => None,
+        Err(err) => return Err(err.into()),
+    };
+    if parent.as_ref().is_some_and(|parent| parent.tree_id() == tree.id()) {
+        bail!("Nothing to commit in {}", repo_path);
+    }
+    let signature = signature(&repo)?;
+    let parents: Vec<&Commit> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?;
 
-    println!("{}", "Commit completed successfully!".green().bold());
+    status(t!("git-committed").green().bold());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_since_dates() {
+        let now = days_from_civil(2024, 5, 1) * 86400;
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(parse_since("2024-05-01", 0), Some(now));
+        assert_eq!(parse_since("2 weeks ago", now), Some(now - 14 * 86400));
+        assert_eq!(parse_since("1 day ago", now), parse_since("yesterday", now));
+        assert_eq!(parse_since("last week", now), Some(now - 7 * 86400));
+        assert_eq!(parse_since("3.weeks.ago", now), Some(now - 21 * 86400));
+        assert_eq!(parse_since("an hour ago", now + 3600), Some(now));
+        assert_eq!(parse_since("today", now + 3600), Some(now));
+        assert_eq!(parse_since("v1.0", now), None);
+    }
+
+    #[test]
+    fn commits_and_reads_history() {
+        let dir = std::env::temp_dir().join(format!("refrs-repo-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let repo = Repository::init(&dir).unwrap();
+        repo.config().unwrap().set_str("user.name", "Test").unwrap();
+        repo.config().unwrap().set_str("user.email", "test@example.org").unwrap();
+        let path = dir.to_str().unwrap();
+
+        fs::create_dir_all(dir.join("ris")).unwrap();
+        fs::write(dir.join("ris/doe2020.ris"), "TY  - JOUR\nER  - \n").unwrap();
+        add_all(path).unwrap();
+        commit(path, "Added doe2020.ris").unwrap();
+        assert!(commit(path, "Nothing").is_err());
+
+        fs::write(dir.join("ris/doe2020.ris"), "TY  - JOUR\nKW  - reading\nER  - \n").unwrap();
+        fs::write(dir.join("ris/roe2021.ris"), "TY  - BOOK\nKW  - reading-list\nER  - \n").unwrap();
+        fs::write(dir.join("ris/poe2022.ris"), "TY  - BOOK\nKW  - Reading\nER  - \n").unwrap();
+        assert_eq!(changed_files_since(path, "HEAD", "ris").unwrap(), vec!["ris/doe2020.ris"]);
+        add_all(path).unwrap();
+        commit(path, "Marked and added").unwrap();
+
+        let added = added_times(path, "ris").unwrap();
+        assert_eq!(added.keys().count(), 3);
+        let marked = last_changed_times(path, "ris", "KW  - reading").unwrap();
+        let mut marked: Vec<&String> = marked.keys().collect();
+        marked.sort();
+        assert_eq!(marked, vec!["doe2020", "poe2022"]);
+        assert_eq!(changed_files_since(path, "1 day ago", "ris").unwrap().len(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
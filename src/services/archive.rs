@@ -0,0 +1,73 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use url::Url;
+
+use crate::services::http;
+
+/// RIS has no tag for archived copies, so refrs keeps the archive link in the
+/// custom field `C8`.
+pub const ARCHIVE_TAG: &str = "C8";
+
+#[derive(Deserialize)]
+struct Availability {
+    archived_snapshots: Snapshots,
+}
+
+#[derive(Deserialize)]
+struct Snapshots {
+    closest: Option<Snapshot>,
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    url: String,
+}
+
+/// Asks the Wayback Machine to capture `url` and returns the address of the
+/// resulting snapshot.
+pub fn wayback_save(url: &str) -> Result<String> {
+    let mut save = Url::parse("https://web.archive.org/save/")?;
+    save.path_segments_mut()
+        .map_err(|_| anyhow!("Cannot build the Wayback Machine address"))?
+        .pop_if_empty()
+        .push(url);
+    let response = http::agent()
+        .get(save.as_str())
+        .call()
+        .context("The Wayback Machine refused to save the page")?;
+
+    // The save endpoint usually redirects straight to the new capture.
+    let final_url = response.get_url().to_string();
+    if final_url.contains("/web/") {
+        return Ok(final_url);
+    }
+
+    let available = Url::parse_with_params("https://archive.org/wayback/available", [("url", url)])?;
+    let availability: Availability = http::get_json(available.as_str())?;
+    availability
+        .archived_snapshots
+        .closest
+        .map(|snapshot| snapshot.url)
+        .ok_or_else(|| anyhow!("No snapshot of {} is available", url))
+}
+
+/// Downloads the page and adds a `<base>` element so relative links in the
+/// saved copy still point at the original site.
+pub fn fetch_snapshot(url: &str) -> Result<String> {
+    let html = http::get_text(url)?;
+    let base = format!("<base href=\"{}\">", url.replace('"', "%22"));
+
+    let lower = html.to_ascii_lowercase();
+    let snapshot = match lower.find("<head") {
+        Some(start) => match lower[start..].find('>') {
+            Some(end) => {
+                let insert_at = start + end + 1;
+                format!("{}{}{}", &html[..insert_at], base, &html[insert_at..])
+            }
+            None => format!("{}{}", base, html),
+        },
+        None => format!("{}{}", base, html),
+    };
+
+    Ok(snapshot)
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::model::ris::{ReferenceType, RisEntry};
+use crate::services::http;
+
+const API_URL: &str = "https://export.arxiv.org/api/query";
+const ATOM: &str = "http://www.w3.org/2005/Atom";
+const ARXIV: &str = "http://arxiv.org/schemas/atom";
+
+/// `2401.12345`, `hep-th/9901001` or `2401.12345v2` out of a bare id, an
+/// `arXiv:` id or an arxiv.org abs/pdf URL.
+pub fn normalize_id(input: &str) -> String {
+    let id = input.trim();
+    let id = id
+        .rsplit_once("arxiv.org/abs/")
+        .or_else(|| id.rsplit_once("arxiv.org/pdf/"))
+        .map(|(_, id)| id)
+        .unwrap_or(id);
+    let id = id.strip_prefix("arXiv:").or_else(|| id.strip_prefix("arxiv:")).unwrap_or(id);
+    id.trim_end_matches(".pdf").trim_end_matches('/').to_string()
+}
+
+/// Fetches the record for an arXiv id from the Atom API.
+pub fn fetch(id: &str) -> Result<RisEntry> {
+    let id = normalize_id(id);
+    let url = url::Url::parse_with_params(API_URL, &[("id_list", id.as_str())])?;
+    let feed = http::get_text(url.as_str())?;
+    parse_feed(&feed).with_context(|| format!("arXiv record {}", id))
+}
+
+/// Maps the single entry of an arXiv Atom feed to a RIS preprint.
+pub fn parse_feed(feed: &str) -> Result<RisEntry> {
+    let document = roxmltree::Document::parse(feed)?;
+    let entry = document
+        .descendants()
+        .find(|n| n.has_tag_name((ATOM, "entry")))
+        .ok_or_else(|| anyhow!("No such arXiv record"))?;
+
+    let text = |namespace: &str, name: &str| {
+        entry
+            .children()
+            .find(|n| n.has_tag_name((namespace, name)))
+            .and_then(|n| n.text())
+            .map(collapse_whitespace)
+    };
+
+    let abs_url = text(ATOM, "id").unwrap_or_default();
+    // Unknown ids yield an entry pointing at the API's error page.
+    if abs_url.contains("/api/errors") {
+        bail!("{}", text(ATOM, "summary").unwrap_or_else(|| "Invalid arXiv id".to_string()));
+    }
+
+    let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+    let mut add_field = |tag: &str, value: String| {
+        if !value.is_empty() {
+            fields.entry(tag.to_string()).or_default().push(value);
+        }
+    };
+
+    for author in entry.children().filter(|n| n.has_tag_name((ATOM, "author"))) {
+        let name = author
+            .children()
+            .find(|n| n.has_tag_name((ATOM, "name")))
+            .and_then(|n| n.text())
+            .map(collapse_whitespace);
+        if let Some(name) = name {
+            add_field("AU", invert_name(&name));
+        }
+    }
+    add_field("TI", text(ATOM, "title").unwrap_or_default());
+    add_field("AB", text(ATOM, "summary").unwrap_or_default());
+
+    if let Some(published) = text(ATOM, "published") {
+        // 2024-01-22T18:59:59Z
+        let date = published.get(..10).unwrap_or(&published);
+        add_field("PY", date.get(..4).unwrap_or(date).to_string());
+        add_field("DA", date.replace('-', "/"));
+    }
+
+    let id = abs_url.rsplit_once("/abs/").map(|(_, id)| id).unwrap_or_default();
+    let unversioned = match id.rsplit_once('v') {
+        Some((base, version)) if version.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => id,
+    };
+    if !unversioned.is_empty() {
+        add_field("UR", format!("https://arxiv.org/abs/{}", unversioned));
+        add_field("AN", format!("arXiv:{}", unversioned));
+    }
+    add_field("T2", "arXiv".to_string());
+
+    let category = entry
+        .children()
+        .find(|n| n.has_tag_name((ARXIV, "primary_category")))
+        .and_then(|n| n.attribute("term"));
+    if let Some(category) = category {
+        add_field("KW", category.to_string());
+    }
+    if let Some(doi) = text(ARXIV, "doi") {
+        add_field("DO", doi);
+    }
+    if let Some(journal_ref) = text(ARXIV, "journal_ref") {
+        add_field("N1", format!("Published as: {}", journal_ref));
+    }
+
+    Ok(RisEntry {
+        ty: ReferenceType::UnpublishedWork,
+        fields,
+    })
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// arXiv gives `First Middle Last`; RIS wants `Last, First Middle`.
+fn invert_name(name: &str) -> String {
+    match name.rsplit_once(' ') {
+        Some((first, last)) => format!("{}, {}", last, first),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>arXiv Query</title>
+  <entry>
+    <id>http://arxiv.org/abs/2401.12345v2</id>
+    <published>2024-01-22T18:59:59Z</published>
+    <title>Cats and
+      Dogs</title>
+    <summary>  We study cats.
+    </summary>
+    <author><name>Jane Q. Doe</name></author>
+    <author><name>Rick Roe</name></author>
+    <arxiv:primary_category xmlns:arxiv="http://arxiv.org/schemas/atom" term="cs.LG" scheme="http://arxiv.org/schemas/atom"/>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn maps_atom_entry_to_preprint() {
+        let entry = parse_feed(FEED).unwrap();
+        assert_eq!(entry.ty, ReferenceType::UnpublishedWork);
+        assert_eq!(entry.fields["AU"], vec!["Doe, Jane Q.", "Roe, Rick"]);
+        assert_eq!(entry.fields["TI"], vec!["Cats and Dogs"]);
+        assert_eq!(entry.fields["AB"], vec!["We study cats."]);
+        assert_eq!(entry.fields["PY"], vec!["2024"]);
+        assert_eq!(entry.fields["DA"], vec!["2024/01/22"]);
+        assert_eq!(entry.fields["UR"], vec!["https://arxiv.org/abs/2401.12345"]);
+        assert_eq!(entry.fields["KW"], vec!["cs.LG"]);
+    }
+
+    #[test]
+    fn normalizes_ids() {
+        assert_eq!(normalize_id("arXiv:2401.12345"), "2401.12345");
+        assert_eq!(normalize_id("https://arxiv.org/pdf/2401.12345v1.pdf"), "2401.12345v1");
+        assert_eq!(normalize_id("hep-th/9901001"), "hep-th/9901001");
+    }
+}
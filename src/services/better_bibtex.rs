@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use crate::model::ris::RisEntry;
+use crate::services::{tags, zotero};
+
+/// Whether `value` is a Better BibTeX JSON export of a Zotero library rather
+/// than CSL-JSON: an object holding the items and the collections.
+pub fn is_export(value: &Value) -> bool {
+    value["items"].is_array() && (value["config"].is_object() || value["collections"].is_object())
+}
+
+/// Parses a Better BibTeX JSON export. The citation key of each item is
+/// kept, and the collections it is filed in become keywords, named by their
+/// path such as `Thesis/Chapter 2`.
+pub fn parse(text: &str) -> Result<Vec<RisEntry>> {
+    let value: Value = serde_json::from_str(text)?;
+    if !is_export(&value) {
+        bail!("Expected a Better BibTeX JSON export with items and collections");
+    }
+
+    let collections: HashMap<&str, &Value> = value["collections"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(key, collection)| (key.as_str(), collection))
+        .collect();
+    let path = |key: &str| -> Option<String> {
+        let mut names = Vec::new();
+        let mut next = Some(key);
+        // Stops at the top, or after as many steps as there are collections
+        // should parents form a loop.
+        while let Some(key) = next.filter(|_| names.len() <= collections.len()) {
+            let collection = collections.get(key)?;
+            names.push(collection["name"].as_str()?);
+            next = collection["parent"].as_str();
+        }
+        names.reverse();
+        Some(names.join("/"))
+    };
+
+    // Older exports list the items of each collection instead of the
+    // collections of each item.
+    let mut filed: HashMap<String, Vec<&str>> = HashMap::new();
+    for (key, collection) in &collections {
+        for id in collection["items"].as_array().into_iter().flatten() {
+            filed.entry(id.to_string()).or_default().push(key);
+        }
+    }
+
+    let mut entries = Vec::new();
+    for item in value["items"].as_array().into_iter().flatten().filter(|item| zotero::is_reference(item)) {
+        let mut entry = zotero::item_to_entry(item);
+        let mut keys: Vec<&str> = item["collections"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+        keys.extend(filed.get(&item["itemID"].to_string()).into_iter().flatten());
+        let mut names: Vec<String> = keys.into_iter().filter_map(path).collect();
+        names.sort();
+        for name in names {
+            tags::add_tag(&mut entry, &name);
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_collections_as_keywords() {
+        let text = r#"{
+            "config": { "label": "BetterBibTeX JSON" },
+            "collections": {
+                "A": { "key": "A", "name": "Thesis", "parent": false, "items": [] },
+                "B": { "key": "B", "name": "Chapter 2", "parent": "A", "items": [2] }
+            },
+            "items": [
+                { "itemID": 1, "itemType": "journalArticle", "title": "Skip lists", "citationKey": "pugh1990",
+                  "tags": [{ "tag": "thesis" }], "collections": ["A"] },
+                { "itemID": 2, "itemType": "book", "title": "TAOCP" },
+                { "itemID": 3, "itemType": "note" }
+            ]
+        }"#;
+
+        let entries = parse(text).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].fields["ID"], vec!["pugh1990"]);
+        assert_eq!(entries[0].fields["KW"], vec!["thesis"]);
+        assert_eq!(entries[1].fields["KW"], vec!["Thesis/Chapter 2"]);
+        assert!(parse("[]").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::model::date::Date;
+use crate::model::ris::RisEntry;
+use crate::services::{http, semantic_scholar};
+
+/// RIS has no tag for citation counts, so refrs keeps the count and the day
+/// it was looked up in the custom field `C5`, as `412 (2026/10/16)`.
+pub const CITATIONS_TAG: &str = "C5";
+
+/// Counts looked up longer ago than this are looked up again.
+pub const MAX_AGE_DAYS: i64 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CitationCount {
+    pub count: u64,
+    pub checked: Date,
+}
+
+impl CitationCount {
+    pub fn parse(value: &str) -> Option<CitationCount> {
+        let (count, checked) = value.trim().split_once(' ')?;
+        let checked = checked.trim().strip_prefix('(')?.strip_suffix(')')?;
+        Some(CitationCount { count: count.parse().ok()?, checked: Date::parse(checked)? })
+    }
+
+    /// The count stored in `entry`, if it has been looked up.
+    pub fn of(entry: &RisEntry) -> Option<CitationCount> {
+        entry.get_field(CITATIONS_TAG).and_then(|value| CitationCount::parse(value))
+    }
+
+    /// Whether the count was looked up more than [`MAX_AGE_DAYS`] before `today`.
+    pub fn is_stale(&self, today: &Date) -> bool {
+        self.checked.days_until(today) > MAX_AGE_DAYS
+    }
+}
+
+impl fmt::Display for CitationCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.count, self.checked)
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenAlexWork {
+    cited_by_count: Option<u64>,
+}
+
+/// How often the work with the given DOI has been cited, from Semantic
+/// Scholar or, when it doesn't know the work or refuses the request, from
+/// OpenAlex. `None` when neither knows the work.
+pub fn fetch(doi: &str) -> Result<Option<u64>> {
+    if let Ok(Some(count)) = semantic_scholar::citation_count(doi) {
+        return Ok(Some(count));
+    }
+
+    let url = http::url_with_path("https://api.openalex.org/works", &format!("doi:{}", doi))?;
+    match http::agent().get(url.as_str()).call() {
+        Ok(response) => {
+            let work: OpenAlexWork = response
+                .into_json()
+                .with_context(|| format!("Failed to parse JSON from {}", url))?;
+            Ok(work.cited_by_count)
+        }
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("Request to {} failed", url)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_and_writes_counts_with_the_day_checked() {
+        let count = CitationCount::parse("412 (2026/10/16)").unwrap();
+        assert_eq!(count.count, 412);
+        assert_eq!(count.to_string(), "412 (2026/10/16)");
+        assert!(!count.is_stale(&Date::parse("2026/11/15").unwrap()));
+        assert!(count.is_stale(&Date::parse("2026/11/16").unwrap()));
+        assert_eq!(CitationCount::parse("many"), None);
+    }
+}
@@ -0,0 +1,334 @@
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::model::ris::RisEntry;
+
+/// How citation keys (and thereby file names) are generated for new entries.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyFormat {
+    /// `author_titleword_year`, the original refrs scheme
+    #[default]
+    Refrs,
+    /// Zotero Better BibTeX's default `auth.lower + shorttitle(3, 3) + year`
+    #[value(alias = "bbt")]
+    BetterBibtex,
+    /// A pattern of fields such as `[auth][year][shorttitle]`
+    Pattern,
+}
+
+/// Pattern used by `KeyFormat::Pattern` until one is configured.
+pub const DEFAULT_KEY_PATTERN: &str = "[auth][year][shorttitle]";
+
+/// Fields a key pattern can refer to, as `[field]` or `[field:modifier]`.
+const PATTERN_FIELDS: &[&str] = &["auth", "authors", "year", "shortyear", "shorttitle", "title", "firstpage"];
+
+/// Words Better BibTeX skips when building a short title.
+const BBT_FUNCTION_WORDS: &[&str] = &[
+    "a", "ab", "aboard", "about", "above", "across", "after", "against", "al", "along", "amid",
+    "among", "an", "and", "anti", "around", "as", "at", "before", "behind", "below", "beneath",
+    "beside", "besides", "between", "beyond", "but", "by", "d", "da", "das", "de", "del", "dell",
+    "dello", "dei", "degli", "della", "dem", "den", "der", "des", "despite", "die", "do", "down",
+    "du", "during", "ein", "eine", "einem", "einen", "einer", "eines", "el", "en", "et", "except",
+    "for", "from", "gli", "i", "il", "in", "inside", "into", "is", "l", "la", "las", "le", "les",
+    "like", "lo", "los", "near", "nor", "of", "off", "on", "onto", "or", "over", "past", "per",
+    "plus", "round", "save", "since", "so", "some", "sur", "than", "the", "through", "to",
+    "toward", "towards", "un", "una", "unas", "uno", "unos", "under", "underneath", "une",
+    "unlike", "until", "up", "upon", "versus", "via", "von", "while", "with", "within", "without",
+    "yet", "zu", "zum",
+];
+
+const ACADEMIC_STOPWORDS: &[&str] = &[
+    "a", "an", "and", "the", "of", "in", "on", "for", "with", "to", "from", "by", "about",
+    "as", "at", "into", "through", "between", "within", "without", "or", "nor", "but", "yet",
+    "so", "because", "although", "since", "while", "when", "where", "that", "which", "what",
+    "who", "whose", "whom", "how", "why", "it", "its", "this", "these", "those", "there",
+    "here", "such", "more", "less", "many", "much", "any", "every", "each", "other", "some",
+    "few", "all", "both", "either", "neither", "one", "two", "three", "four", "five", "six",
+    "seven", "eight", "nine", "ten", "up", "down", "out", "over", "under", "above", "below",
+    "new", "current", "recent", "future", "analysis", "study", "research", "results", "review",
+    "overview",
+];
+
+/// `pattern` is only used by `KeyFormat::Pattern`.
+pub fn generate(entry: &RisEntry, format: KeyFormat, pattern: &str) -> String {
+    match format {
+        KeyFormat::Refrs => refrs_key(entry),
+        KeyFormat::BetterBibtex => pinned_key(entry)
+            .or_else(|| source_key(entry))
+            .unwrap_or_else(|| bbt_key(entry)),
+        KeyFormat::Pattern => pinned_key(entry)
+            .or_else(|| source_key(entry))
+            .unwrap_or_else(|| pattern_key(entry, pattern)),
+    }
+}
+
+/// Builds the `author_titleword_year` key used as the file stem of new entries.
+pub fn refrs_key(entry: &RisEntry) -> String {
+    let title = first_non_stopword(
+        match entry.get_field("TI") {
+            Some(title) => title.trim(),
+            None => "notitle",
+        },
+        ACADEMIC_STOPWORDS,
+    )
+    .unwrap_or("notitle".to_string())
+    .to_lowercase();
+
+    let author = match entry.get_field("AU") {
+        Some(author) => {
+            author.split(',').next().unwrap_or("noauthor").trim()
+        }
+        None => "noauthor",
+    }
+    .to_lowercase();
+
+    let year = match entry.get_field("PY") {
+        Some(date) => date.trim(),
+        None => "nodate",
+    };
+
+    // Sanitize title and author to avoid invalid file characters
+    let sanitized_title = title.replace(|c: char| !c.is_alphanumeric(), "_");
+    let sanitized_author = author.replace(|c: char| !c.is_alphanumeric(), "_");
+
+    format!("{}_{}_{}", sanitized_author, sanitized_title, year)
+}
+
+/// Better BibTeX's default formula, e.g. `ioannidisParametricQueryOptimization1997`.
+pub fn bbt_key(entry: &RisEntry) -> String {
+    let author = entry
+        .get_field("AU")
+        .map(|a| last_name(a))
+        .map(|a| ascii_alphanumeric(&a).to_lowercase())
+        .unwrap_or_default();
+
+    let short_title: String = entry
+        .get_field("TI")
+        .map(|title| {
+            title
+                .split(|c: char| c.is_whitespace() || c == '-' || c == '/')
+                .map(ascii_alphanumeric)
+                .filter(|w| !w.is_empty())
+                .filter(|w| !BBT_FUNCTION_WORDS.contains(&w.to_lowercase().as_str()))
+                .take(3)
+                .map(|w| capitalize(&w))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let year = entry.get_field("PY").and_then(|py| year_of(py)).unwrap_or_default();
+
+    let key = format!("{}{}{}", author, short_title, year);
+    if key.is_empty() {
+        "unnamed".to_string()
+    } else {
+        key
+    }
+}
+
+/// Fills in a key pattern. Fields are lowercase unless modified with
+/// `:upper` or `:capitalize`; text outside brackets is kept as is.
+pub fn pattern_key(entry: &RisEntry, pattern: &str) -> String {
+    let mut key = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('[') {
+        key.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find(']') else {
+            break;
+        };
+        let (field, modifier) = match rest[start + 1..start + end].split_once(':') {
+            Some((field, modifier)) => (field, Some(modifier)),
+            None => (&rest[start + 1..start + end], None),
+        };
+        let words = pattern_field(entry, field);
+        key.push_str(&match modifier {
+            Some("upper") => words.concat().to_uppercase(),
+            Some("capitalize") => words.iter().map(|w| capitalize(&w.to_lowercase())).collect(),
+            _ => words.concat().to_lowercase(),
+        });
+        rest = &rest[start + end + 1..];
+    }
+    key.push_str(rest);
+
+    if key.is_empty() {
+        "unnamed".to_string()
+    } else {
+        key
+    }
+}
+
+/// Checks that every bracketed field of a key pattern is known.
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    let mut rest = pattern;
+    while let Some(start) = rest.find('[') {
+        let Some(end) = rest[start..].find(']') else {
+            bail!("Unclosed [ in key pattern {}", pattern);
+        };
+        let field = &rest[start + 1..start + end];
+        let (name, modifier) = field.split_once(':').unwrap_or((field, "lower"));
+        if !PATTERN_FIELDS.contains(&name) {
+            bail!("Unknown key pattern field [{}], expected one of: {}", name, PATTERN_FIELDS.join(", "));
+        }
+        if !["lower", "upper", "capitalize"].contains(&modifier) {
+            bail!("Unknown modifier :{}, expected lower, upper or capitalize", modifier);
+        }
+        rest = &rest[start + end + 1..];
+    }
+    Ok(())
+}
+
+/// ASCII words making up a pattern field.
+fn pattern_field(entry: &RisEntry, field: &str) -> Vec<String> {
+    let authors: Vec<String> = entry
+        .fields
+        .get("AU")
+        .into_iter()
+        .flatten()
+        .map(|author| ascii_alphanumeric(&last_name(author)))
+        .filter(|name| !name.is_empty())
+        .collect();
+    let title_words = |count: usize| -> Vec<String> {
+        entry
+            .get_field("TI")
+            .map(|title| {
+                title
+                    .split(|c: char| c.is_whitespace() || c == '-' || c == '/')
+                    .map(ascii_alphanumeric)
+                    .filter(|w| !w.is_empty())
+                    .filter(|w| !BBT_FUNCTION_WORDS.contains(&w.to_lowercase().as_str()))
+                    .take(count)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let year = entry.get_field("PY").and_then(|py| year_of(py));
+
+    match field {
+        "auth" => authors.into_iter().take(1).collect(),
+        "authors" if authors.len() > 3 => vec![authors[0].clone(), "EtAl".to_string()],
+        "authors" => authors,
+        "year" => year.into_iter().collect(),
+        "shortyear" => year.map(|y| y[2..].to_string()).into_iter().collect(),
+        "shorttitle" => title_words(1),
+        "title" => title_words(3),
+        "firstpage" => entry.get_field("SP").map(|sp| ascii_alphanumeric(sp)).into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A key pinned with Better BibTeX's `Citation Key: ...` (or legacy `bibtex: ...`)
+/// line, which Zotero keeps in the Extra field and exports as a note.
+pub fn pinned_key(entry: &RisEntry) -> Option<String> {
+    entry
+        .fields
+        .get("N1")
+        .into_iter()
+        .flatten()
+        .flat_map(|note| note.lines())
+        .find_map(|line| {
+            let (label, key) = line.split_once(':')?;
+            let label = label.trim().to_lowercase();
+            let is_pin = label == "citation key" || label == "bibtex" || label == "bibtex*";
+            let key = key.trim();
+            (is_pin && !key.is_empty()).then(|| key.to_string())
+        })
+}
+
+/// The key the entry had in the BibTeX file it was imported from.
+fn source_key(entry: &RisEntry) -> Option<String> {
+    entry
+        .get_field("ID")
+        .map(|id| id.trim().to_string())
+        .filter(|id| id.chars().any(char::is_alphabetic))
+}
+
+pub fn last_name(author: &str) -> String {
+    match author.split_once(',') {
+        Some((last, _)) => last.trim().to_string(),
+        None => author.split_whitespace().last().unwrap_or("").to_string(),
+    }
+}
+
+fn ascii_alphanumeric(text: &str) -> String {
+    deunicode::deunicode(text)
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+pub fn year_of(date: &str) -> Option<String> {
+    date.split(|c: char| !c.is_ascii_digit())
+        .find(|part| part.len() == 4)
+        .map(|year| year.to_string())
+}
+
+fn first_non_stopword(input: &str, stopwords: &[&str]) -> Option<String> {
+    // Convert the stopwords array into a HashSet for faster lookup
+    let stopwords_set: std::collections::HashSet<_> = stopwords.iter().copied().collect();
+
+    // Split the input into words, filter out stopwords, and return the first non-stopword
+    input
+        .split_whitespace() // Split the string into words
+        .find(|word| !stopwords_set.contains(*word)) // Get the first non-stopword
+        .map(|word| word.to_string()) // Convert it to a String
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bbt_key, generate, pattern_key, validate_pattern, KeyFormat, DEFAULT_KEY_PATTERN};
+    use crate::model::ris::parse_ris;
+
+    #[test]
+    fn test_bbt_key_matches_better_bibtex_default_formula() {
+        let entry = parse_ris(
+            "TY  - JOUR\nAU  - Ioannidis, Yannis E.\nTI  - Parametric query optimization\nPY  - 1997/05\nER  -",
+        )
+        .unwrap()
+        .remove(0);
+        assert_eq!(bbt_key(&entry), "ioannidisParametricQueryOptimization1997");
+
+        let entry = parse_ris(
+            "TY  - JOUR\nAU  - Jürgen Müller\nTI  - On the Theory of Everything and More\nPY  - 2001\nER  -",
+        )
+        .unwrap()
+        .remove(0);
+        assert_eq!(bbt_key(&entry), "mullerTheoryEverythingMore2001");
+    }
+
+    #[test]
+    fn test_pinned_key_takes_precedence() {
+        let entry = parse_ris(
+            "TY  - JOUR\nAU  - Doe, John\nTI  - Title\nID  - doe2020source\nN1  - Citation Key: pinnedKey\nER  -",
+        )
+        .unwrap()
+        .remove(0);
+        assert_eq!(generate(&entry, KeyFormat::BetterBibtex, ""), "pinnedKey");
+        assert_eq!(generate(&entry, KeyFormat::Refrs, ""), "doe_title_nodate");
+    }
+
+    #[test]
+    fn test_pattern_key() {
+        let entry = parse_ris(
+            "TY  - JOUR\nAU  - Doe, Jane\nAU  - Roe, Rick\nTI  - A Comprehensive Survey of Cats\nPY  - 2021\nSP  - 117\nER  -",
+        )
+        .unwrap()
+        .remove(0);
+        assert_eq!(pattern_key(&entry, DEFAULT_KEY_PATTERN), "doe2021comprehensive");
+        assert_eq!(pattern_key(&entry, "[authors:capitalize]_[shortyear]_[title:capitalize]"), "DoeRoe_21_ComprehensiveSurveyCats");
+        assert_eq!(pattern_key(&entry, "[auth:upper]:[firstpage]"), "DOE:117");
+
+        assert!(validate_pattern(DEFAULT_KEY_PATTERN).is_ok());
+        assert!(validate_pattern("[auth][journal]").is_err());
+        assert!(validate_pattern("[auth:camel]").is_err());
+    }
+}
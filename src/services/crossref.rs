@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::services::http;
+
+const API_BASE: &str = "https://api.crossref.org";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Journal {
+    pub title: String,
+    #[serde(rename = "ISSN", default)]
+    pub issns: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct JournalResponse {
+    message: Journal,
+}
+
+#[derive(Deserialize)]
+struct JournalSearch {
+    message: JournalItems,
+}
+
+#[derive(Deserialize)]
+struct JournalItems {
+    #[serde(default)]
+    items: Vec<Journal>,
+}
+
+#[derive(Deserialize)]
+struct WorkSearch {
+    message: WorkItems,
+}
+
+#[derive(Deserialize)]
+struct WorkItems {
+    #[serde(default)]
+    items: Vec<Work>,
+}
+
+/// A work found by [`search_works`], with what is needed to recognize it.
+#[derive(Deserialize, Debug)]
+pub struct Work {
+    #[serde(rename = "DOI")]
+    pub doi: String,
+    #[serde(default)]
+    pub title: Vec<String>,
+    #[serde(default)]
+    author: Vec<Person>,
+    #[serde(rename = "container-title", default)]
+    pub container_title: Vec<String>,
+    #[serde(default)]
+    issued: Option<Issued>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Person {
+    family: Option<String>,
+    name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Issued {
+    #[serde(rename = "date-parts", default)]
+    date_parts: Vec<Vec<Option<i32>>>,
+}
+
+impl Work {
+    /// Family names of the authors, or the names of organizations.
+    pub fn authors(&self) -> Vec<&str> {
+        self.author
+            .iter()
+            .filter_map(|person| person.family.as_deref().or(person.name.as_deref()))
+            .collect()
+    }
+
+    pub fn year(&self) -> Option<i32> {
+        self.issued.as_ref()?.date_parts.first()?.first().copied().flatten()
+    }
+}
+
+/// The DOI of the work titled `title`, if Crossref's best matches include
+/// one with the same title up to case and punctuation.
+pub fn doi_by_title(title: &str) -> Result<Option<String>> {
+    let url = url::Url::parse_with_params(
+        &format!("{}/works", API_BASE),
+        &[("query.bibliographic", title), ("rows", "5"), ("select", "DOI,title")],
+    )?;
+    let search: WorkSearch = http::get_json(url.as_str())?;
+    let wanted = normalize(title);
+    Ok(search
+        .message
+        .items
+        .into_iter()
+        .find(|work| work.title.iter().any(|t| normalize(t) == wanted))
+        .map(|work| work.doi))
+}
+
+/// Crossref's best `rows` matches for a title or other bibliographic text,
+/// e.g. `parametric query optimization`.
+pub fn search_works(query: &str, rows: usize) -> Result<Vec<Work>> {
+    let url = url::Url::parse_with_params(
+        &format!("{}/works", API_BASE),
+        &[
+            ("query.bibliographic", query),
+            ("rows", &rows.to_string()),
+            ("select", "DOI,title,author,container-title,issued"),
+        ],
+    )?;
+    let search: WorkSearch = http::get_json(url.as_str())?;
+    Ok(search.message.items)
+}
+
+/// The journal with the given ISSN, or `None` when Crossref doesn't know it.
+pub fn journal_by_issn(issn: &str) -> Result<Option<Journal>> {
+    let url = format!("{}/journals/{}", API_BASE, issn.trim());
+    match http::agent().get(&url).call() {
+        Ok(response) => {
+            let journal: JournalResponse = response
+                .into_json()
+                .with_context(|| format!("Failed to parse JSON from {}", url))?;
+            Ok(Some(journal.message))
+        }
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("Request to {} failed", url)),
+    }
+}
+
+/// The journal whose name matches `title`, ignoring case and punctuation.
+pub fn journal_by_title(title: &str) -> Result<Option<Journal>> {
+    let url = url::Url::parse_with_params(
+        &format!("{}/journals", API_BASE),
+        &[("query", title), ("rows", "20")],
+    )?;
+    let search: JournalSearch = http::get_json(url.as_str())?;
+    Ok(best_match(title, &search.message.items).cloned())
+}
+
+fn normalize(title: &str) -> String {
+    title
+        .to_lowercase()
+        .replace('&', "and")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && *word != "the")
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Search results are fuzzy, so only accept a journal with the same name.
+fn best_match<'a>(title: &str, journals: &'a [Journal]) -> Option<&'a Journal> {
+    let wanted = normalize(title);
+    journals
+        .iter()
+        .find(|j| normalize(&j.title) == wanted && !j.issns.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_journal_names_exactly_up_to_punctuation() {
+        let journal = |title: &str| Journal {
+            title: title.to_string(),
+            issns: vec!["0000-0000".to_string()],
+        };
+        let results = vec![
+            journal("Journal of Pets Research"),
+            journal("The Journal of Pets"),
+        ];
+
+        assert_eq!(best_match("Journal of pets", &results).unwrap().title, "The Journal of Pets");
+        assert!(best_match("Pets", &results).is_none());
+    }
+
+    #[test]
+    fn reads_authors_and_year_of_works() {
+        let work: Work = serde_json::from_str(
+            r#"{"DOI": "10.1145/170035.170077", "title": ["Parametric query optimization"],
+                "author": [{"given": "Yannis", "family": "Ioannidis"}, {"name": "ACM"}],
+                "issued": {"date-parts": [[1992, 8]]}}"#,
+        )
+        .unwrap();
+        assert_eq!(work.authors(), vec!["Ioannidis", "ACM"]);
+        assert_eq!(work.year(), Some(1992));
+    }
+}
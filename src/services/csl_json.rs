@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use serde_json::{json, Map, Value};
+
+use crate::model::date::Date;
+use crate::model::ris::{ReferenceType, RisEntry};
+
+/// Parses CSL-JSON, the format Zotero and citation.js export. Accepts an
+/// array of items or a single item.
+pub fn parse(text: &str) -> Result<Vec<RisEntry>> {
+    let value: Value = serde_json::from_str(text)?;
+    let items = match value {
+        Value::Array(items) => items,
+        item @ Value::Object(_) => vec![item],
+        _ => bail!("Expected a CSL-JSON item or an array of items"),
+    };
+
+    items
+        .iter()
+        .map(|item| match item {
+            Value::Object(_) => Ok(item_to_entry(item)),
+            _ => bail!("Expected every CSL-JSON item to be an object"),
+        })
+        .collect()
+}
+
+fn item_to_entry(item: &Value) -> RisEntry {
+    let ty = match item["type"].as_str().unwrap_or_default() {
+        "article-journal" | "article" => ReferenceType::Journal,
+        "article-magazine" => ReferenceType::MagazineArticle,
+        "article-newspaper" => ReferenceType::Newspaper,
+        "book" => ReferenceType::Book,
+        "chapter" => ReferenceType::Chart,
+        "paper-conference" => ReferenceType::ConferencePaper,
+        "thesis" => ReferenceType::Thesis,
+        "report" => ReferenceType::Report,
+        "webpage" | "post-weblog" => ReferenceType::ElectronicArticle,
+        "dataset" => ReferenceType::Dataset,
+        "patent" => ReferenceType::Patent,
+        "manuscript" => ReferenceType::Manuscript,
+        "software" => ReferenceType::ComputerProgram,
+        _ => ReferenceType::Generic,
+    };
+
+    let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+    let mut add_field = |tag: &str, value: String| {
+        if !value.trim().is_empty() {
+            fields.entry(tag.to_string()).or_default().push(value);
+        }
+    };
+
+    for name in names(&item["author"]) {
+        add_field("AU", name);
+    }
+    for name in names(&item["editor"]) {
+        add_field("A2", name);
+    }
+
+    let text = |key: &str| match &item[key] {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+
+    // Keep the item id so it can be reused as citation key.
+    if let Some(id) = text("id") {
+        add_field("ID", id);
+    }
+    if let Some(title) = text("title") {
+        add_field("TI", title);
+    }
+    if let Some(container) = text("container-title") {
+        add_field("T2", container);
+    }
+    if let Some((year, date)) = issued(&item["issued"]) {
+        add_field("PY", year);
+        if let Some(date) = date {
+            add_field("DA", date);
+        }
+    }
+    if let Some(publisher) = text("publisher") {
+        add_field("PB", publisher);
+    }
+    if let Some(volume) = text("volume") {
+        add_field("VL", volume);
+    }
+    if let Some(issue) = text("issue") {
+        add_field("IS", issue);
+    }
+    if let Some(page) = text("page") {
+        match page.split_once(['-', '–']) {
+            Some((start, end)) => {
+                add_field("SP", start.trim().to_string());
+                add_field("EP", end.trim().to_string());
+            }
+            None => add_field("SP", page),
+        }
+    }
+    if let Some(doi) = text("DOI") {
+        add_field("DO", doi);
+    }
+    if let Some(url) = text("URL") {
+        add_field("UR", url);
+    }
+    if let Some(abstract_text) = text("abstract") {
+        add_field("AB", abstract_text);
+    }
+    for key in ["ISBN", "ISSN"] {
+        if let Some(number) = text(key) {
+            add_field("SN", number);
+        }
+    }
+    if let Some(keywords) = text("keyword") {
+        for keyword in keywords.split([',', ';']) {
+            add_field("KW", keyword.trim().to_string());
+        }
+    }
+    if let Some(note) = text("note") {
+        add_field("N1", note);
+    }
+
+    RisEntry { ty, fields }
+}
+
+/// CSL names as RIS `Family, Given`, or the literal name.
+fn names(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|name| {
+            if let Some(literal) = name["literal"].as_str() {
+                return Some(literal.to_string());
+            }
+            let family = name["family"].as_str()?;
+            Some(match name["given"].as_str() {
+                Some(given) => format!("{}, {}", family, given),
+                None => family.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The year and, when known, the full RIS `YYYY/MM/DD` date.
+fn issued(value: &Value) -> Option<(String, Option<String>)> {
+    let parts: Vec<i64> = value["date-parts"][0]
+        .as_array()
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|p| p.as_i64().or_else(|| p.as_str()?.parse().ok()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    match parts.as_slice() {
+        [year] => Some((year.to_string(), None)),
+        [year, month] => Some((year.to_string(), Some(format!("{}/{:02}", year, month)))),
+        [year, month, day, ..] => Some((
+            year.to_string(),
+            Some(format!("{}/{:02}/{:02}", year, month, day)),
+        )),
+        _ => {
+            let raw = value["raw"].as_str().or_else(|| value["literal"].as_str())?;
+            let year = raw.split(|c: char| !c.is_ascii_digit()).find(|p| p.len() == 4)?;
+            Some((year.to_string(), None))
+        }
+    }
+}
+
+/// Renders `(key, entry)` pairs as a CSL-JSON array, the key becoming the item id.
+pub fn to_csl_json(entries: &[(String, RisEntry)]) -> String {
+    let items: Vec<Value> = entries
+        .iter()
+        .map(|(key, entry)| entry_to_item(key, entry))
+        .collect();
+    let mut json = serde_json::to_string_pretty(&items).unwrap_or_else(|_| "[]".to_string());
+    json.push('\n');
+    json
+}
+
+/// CSL type of a RIS reference type.
+pub fn csl_type(ty: &ReferenceType) -> &'static str {
+    match ty {
+        ReferenceType::Journal => "article-journal",
+        ReferenceType::MagazineArticle => "article-magazine",
+        ReferenceType::Newspaper => "article-newspaper",
+        ReferenceType::Book | ReferenceType::ElectronicBook => "book",
+        ReferenceType::Chart => "chapter",
+        ReferenceType::ConferencePaper | ReferenceType::ConferenceProceedings => "paper-conference",
+        ReferenceType::Thesis => "thesis",
+        ReferenceType::Report => "report",
+        ReferenceType::ElectronicArticle => "webpage",
+        ReferenceType::Dataset => "dataset",
+        ReferenceType::Patent => "patent",
+        ReferenceType::Manuscript | ReferenceType::UnpublishedWork => "manuscript",
+        ReferenceType::ComputerProgram => "software",
+        _ => "document",
+    }
+}
+
+fn entry_to_item(key: &str, entry: &RisEntry) -> Value {
+    let mut item = Map::new();
+    item.insert("id".to_string(), json!(key));
+    item.insert("type".to_string(), json!(csl_type(&entry.ty)));
+
+    let values = |tag: &str| entry.fields.get(tag).map(Vec::as_slice).unwrap_or_default();
+    let mut set = |name: &str, value: Option<Value>| {
+        if let Some(value) = value {
+            item.insert(name.to_string(), value);
+        }
+    };
+
+    set("author", csl_names(values("AU")));
+    set("editor", csl_names(values("A2")));
+    for (tag, name) in [
+        ("TI", "title"),
+        ("T2", "container-title"),
+        ("PB", "publisher"),
+        ("CY", "publisher-place"),
+        ("VL", "volume"),
+        ("IS", "issue"),
+        ("DO", "DOI"),
+        ("UR", "URL"),
+        ("AB", "abstract"),
+    ] {
+        set(name, entry.get_field(tag).map(|value| json!(value)));
+    }
+    set("issued", csl_date(entry));
+    set(
+        "page",
+        match (entry.get_field("SP"), entry.get_field("EP")) {
+            (Some(start), Some(end)) => Some(json!(format!("{}-{}", start, end))),
+            (Some(start), None) => Some(json!(start)),
+            _ => None,
+        },
+    );
+    let number = if matches!(entry.ty, ReferenceType::Book | ReferenceType::ElectronicBook) {
+        "ISBN"
+    } else {
+        "ISSN"
+    };
+    set(number, entry.get_field("SN").map(|value| json!(value)));
+    if !values("KW").is_empty() {
+        set("keyword", Some(json!(values("KW").join(", "))));
+    }
+    if !values("N1").is_empty() {
+        set("note", Some(json!(values("N1").join("\n"))));
+    }
+
+    Value::Object(item)
+}
+
+/// RIS `Family, Given` names as CSL names. Names without a comma, such as
+/// organizations, are kept literally.
+fn csl_names(names: &[String]) -> Option<Value> {
+    if names.is_empty() {
+        return None;
+    }
+    let names = names
+        .iter()
+        .map(|name| match name.split_once(',') {
+            Some((family, given)) if !given.trim().is_empty() => {
+                json!({"family": family.trim(), "given": given.trim()})
+            }
+            Some((family, _)) => json!({"family": family.trim()}),
+            None => json!({"literal": name.trim()}),
+        })
+        .collect();
+    Some(Value::Array(names))
+}
+
+/// `issued` from the RIS date, or from the year when there is no full date.
+fn csl_date(entry: &RisEntry) -> Option<Value> {
+    let date = Date::of(entry)?;
+    let mut parts = vec![i64::from(date.year)];
+    parts.extend(date.month.map(i64::from));
+    parts.extend(date.day.map(i64::from));
+    Some(json!({"date-parts": [parts]}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_zotero_item() {
+        let text = r#"[{
+            "id": "doe2020",
+            "type": "article-journal",
+            "title": "Cats",
+            "container-title": "Nature",
+            "author": [{"family": "Doe", "given": "Jane"}, {"literal": "ACME Lab"}],
+            "issued": {"date-parts": [[2020, 3]]},
+            "page": "10-20",
+            "DOI": "10.1/x"
+        }]"#;
+
+        let entries = parse(text).unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.ty, ReferenceType::Journal);
+        assert_eq!(entry.fields["AU"], vec!["Doe, Jane", "ACME Lab"]);
+        assert_eq!(entry.fields["PY"], vec!["2020"]);
+        assert_eq!(entry.fields["DA"], vec!["2020/03"]);
+        assert_eq!(entry.fields["SP"], vec!["10"]);
+        assert_eq!(entry.fields["EP"], vec!["20"]);
+        assert_eq!(entry.fields["ID"], vec!["doe2020"]);
+    }
+
+    #[test]
+    fn exported_items_parse_back() {
+        let entry = crate::model::ris::parse_ris(
+            "TY  - JOUR\nAU  - Doe, Jane\nAU  - ACME Lab\nTI  - Cats\nT2  - Nature\nPY  - 2020\nDA  - 2020/03/15\nSP  - 10\nEP  - 20\nKW  - pets\nER  -",
+        )
+        .unwrap()
+        .remove(0);
+
+        let json = to_csl_json(&[("doe2020".to_string(), entry.clone())]);
+        let item: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(item[0]["author"][0], json!({"family": "Doe", "given": "Jane"}));
+        assert_eq!(item[0]["issued"], json!({"date-parts": [[2020, 3, 15]]}));
+
+        let parsed = parse(&json).unwrap().remove(0);
+        for tag in ["AU", "TI", "T2", "PY", "DA", "SP", "EP", "KW"] {
+            assert_eq!(parsed.fields.get(tag), entry.fields.get(tag), "{}", tag);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::repo;
+use crate::services::ipc::{self, Incoming, Request, Response};
+use crate::services::encoding;
+use crate::services::merge;
+use crate::services::serialization::{self, ParseResult};
+use crate::state::{self, AppState};
+
+/// Subfolder of a watched folder that imported files are moved into.
+pub const IMPORTED_FOLDER: &str = "imported";
+
+/// How often the daemon refreshes its heartbeat and checks for a stop request.
+const TICK: Duration = Duration::from_secs(1);
+const HEARTBEAT_EVERY: u64 = 5;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct ProjectSync {
+    pub path: String,
+    pub last_sync: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub started_at: u64,
+    pub heartbeat: u64,
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub projects: Vec<ProjectSync>,
+    /// Most recently imported files, newest last.
+    #[serde(default)]
+    pub imported: Vec<String>,
+    /// Files in watched folders that held nothing importable, with their
+    /// modification time. They are tried again once they change.
+    #[serde(skip)]
+    pub unimportable: HashMap<PathBuf, SystemTime>,
+}
+
+impl DaemonStatus {
+    /// A daemon that has not refreshed its heartbeat for a while is considered gone.
+    pub fn is_running(&self) -> bool {
+        now().saturating_sub(self.heartbeat) <= HEARTBEAT_EVERY * 3
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn status_path() -> PathBuf {
+    state::data_dir().join("daemon.yaml")
+}
+
+fn stop_path() -> PathBuf {
+    state::data_dir().join("daemon.stop")
+}
+
+pub fn load_status() -> Result<Option<DaemonStatus>> {
+    let path = status_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).context("Failed to read daemon status")?;
+    Ok(Some(
+        serde_yaml::from_str(&content).context("Failed to parse daemon status")?,
+    ))
+}
+
+fn save_status(status: &DaemonStatus) -> Result<()> {
+    fs::create_dir_all(state::data_dir())?;
+    fs::write(status_path(), serde_yaml::to_string(status)?)?;
+    Ok(())
+}
+
+/// Asks a running daemon to exit at its next tick.
+pub fn request_stop() -> Result<()> {
+    fs::create_dir_all(state::data_dir())?;
+    fs::write(stop_path(), "")?;
+    Ok(())
+}
+
+/// Runs the sync loop in the current process until a stop is requested.
+pub fn run(interval: Duration) -> Result<()> {
+    let _ = fs::remove_file(stop_path());
+
+    let mut status = DaemonStatus {
+        pid: std::process::id(),
+        started_at: now(),
+        heartbeat: now(),
+        interval_secs: interval.as_secs(),
+        ..Default::default()
+    };
+    save_status(&status)?;
+
+    // Without the control socket the daemon still works, it just can't be
+    // asked to sync early or import on demand.
+    let requests = ipc::listen().ok();
+
+    let mut next_cycle = 0;
+    loop {
+        if stop_path().exists() {
+            let _ = fs::remove_file(stop_path());
+            return shut_down(&mut status);
+        }
+
+        if now() >= next_cycle {
+            run_cycle(&mut status)?;
+            next_cycle = now() + interval.as_secs();
+        }
+
+        beat(&mut status)?;
+
+        match &requests {
+            Some(requests) => {
+                if let Ok(incoming) = requests.recv_timeout(TICK) {
+                    if matches!(incoming.request, Request::Stop) {
+                        incoming.respond(Response::ok("Daemon stopped."));
+                        return shut_down(&mut status);
+                    }
+                    handle_request(incoming, &mut status);
+                }
+            }
+            None => thread::sleep(TICK),
+        }
+    }
+}
+
+/// Refreshes the heartbeat when it is due, also in the middle of a cycle so
+/// that a long sync doesn't make the daemon look gone.
+fn beat(status: &mut DaemonStatus) -> Result<()> {
+    if now().saturating_sub(status.heartbeat) >= HEARTBEAT_EVERY {
+        status.heartbeat = now();
+        save_status(status)?;
+    }
+    Ok(())
+}
+
+fn shut_down(status: &mut DaemonStatus) -> Result<()> {
+    let _ = fs::remove_file(ipc::socket_path());
+    status.heartbeat = 0;
+    save_status(status)
+}
+
+fn run_cycle(status: &mut DaemonStatus) -> Result<()> {
+    // Reload every cycle so projects and folders added meanwhile are picked up.
+    let app_state = state::load_state()?;
+    sync_projects(&app_state, status);
+    import_watched_folders(&app_state, status);
+    status.heartbeat = now();
+    save_status(status)
+}
+
+fn handle_request(incoming: Incoming, status: &mut DaemonStatus) {
+    let response = match &incoming.request {
+        Request::Status => Response {
+            ok: true,
+            status: Some(status.clone()),
+            ..Default::default()
+        },
+        Request::Sync => match run_cycle(status) {
+            Ok(()) => Response {
+                ok: true,
+                message: "Sync completed.".to_string(),
+                status: Some(status.clone()),
+            },
+            Err(err) => Response::error(format!("{:#}", err)),
+        },
+        Request::Import { path } => {
+            let imported = state::load_state()
+                .and_then(|app_state| import_file(&app_state, Path::new(path)));
+            match imported {
+                Ok(files) if files.is_empty() => {
+                    Response::error(format!("No importable references in {}", path))
+                }
+                Ok(files) => Response::ok(format!("Imported {}", files.join(", "))),
+                Err(err) => Response::error(format!("{:#}", err)),
+            }
+        }
+        Request::Stop => Response::ok("Daemon stopped."),
+    };
+    incoming.respond(response);
+}
+
+fn sync_projects(app_state: &AppState, status: &mut DaemonStatus) {
+    for project in &app_state.projects {
+        let path = &project.absolute_path;
+        let auth = app_state.git_auth_for(path);
+        let result = repo::pull_rebase(path, auth, merge::resolve_unattended)
+            .and_then(|_| repo::push(path, auth));
+
+        let index = match status.projects.iter().position(|p| &p.path == path) {
+            Some(index) => index,
+            None => {
+                status.projects.push(ProjectSync {
+                    path: path.clone(),
+                    ..Default::default()
+                });
+                status.projects.len() - 1
+            }
+        };
+        let entry = &mut status.projects[index];
+        match result {
+            Ok(()) => {
+                entry.last_sync = Some(now());
+                entry.last_error = None;
+            }
+            Err(err) => entry.last_error = Some(format!("{:#}", err)),
+        }
+        let _ = beat(status);
+    }
+}
+
+/// Imports `.bib` and `.ris` files dropped into watched folders into the
+/// current project and moves them out of the way afterwards.
+fn import_watched_folders(app_state: &AppState, status: &mut DaemonStatus) {
+    if app_state.current_project.is_empty() {
+        return;
+    }
+
+    for folder in &app_state.watch_folders {
+        let Ok(paths) = fs::read_dir(folder) else {
+            continue;
+        };
+
+        for path in paths.filter_map(|p| p.ok()).map(|p| p.path()) {
+            let importable = path
+                .extension()
+                .map(|ext| ext == "bib" || ext == "ris")
+                .unwrap_or(false);
+            if !importable {
+                continue;
+            }
+
+            let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+            if modified.is_some() && status.unimportable.get(&path) == modified.as_ref() {
+                continue;
+            }
+
+            let imported = import_file(app_state, &path).unwrap_or_default();
+            if imported.is_empty() {
+                if let Some(modified) = modified {
+                    status.unimportable.insert(path, modified);
+                }
+            } else {
+                let _ = move_to_imported(&path);
+                status.unimportable.remove(&path);
+                status.imported.push(path.display().to_string());
+            }
+            let _ = beat(status);
+        }
+    }
+
+    status.unimportable.retain(|path, _| path.exists());
+
+    let excess = status.imported.len().saturating_sub(20);
+    status.imported.drain(..excess);
+}
+
+/// Imports all references in a file into the current project and returns the
+/// names of the files written. Nothing is imported from unrecognized files;
+/// parse errors name the encoding the file was read as.
+fn import_file(app_state: &AppState, path: &Path) -> Result<Vec<String>> {
+    if app_state.current_project.is_empty() {
+        bail!("No project selected");
+    }
+
+    let (text, encoding) = encoding::read_text(path)?;
+    let entries = match serialization::parse(&text) {
+        ParseResult::Parsed { entries, .. } => entries,
+        ParseResult::UnrecognizedFormat => return Ok(Vec::new()),
+        ParseResult::BibtexError { error } => {
+            bail!("Invalid BibTeX in {} (read as {}): {}", path.display(), encoding, error)
+        }
+        ParseResult::Error { format, error } => bail!(
+            "Invalid {} in {} (read as {}): {:#}",
+            format,
+            path.display(),
+            encoding,
+            error
+        ),
+    };
+
+    let project = &app_state.current_project;
+    let config = config::load(project)?;
+    let (key_format, key_pattern) = config.key_scheme(app_state);
+    let pending = serialization::prepare_import(entries, project, key_format, key_pattern)?;
+    serialization::commit_import(&pending, project, key_format)
+}
+
+fn move_to_imported(path: &Path) -> Result<()> {
+    let folder = path
+        .parent()
+        .map(|parent| parent.join(IMPORTED_FOLDER))
+        .context("File has no parent folder")?;
+    fs::create_dir_all(&folder)?;
+    if let Some(file_name) = path.file_name() {
+        fs::rename(path, folder.join(file_name))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use crate::model::ris::RisEntry;
+use crate::services::merge;
+
+/// A tag whose values differ between two versions of an entry.
+/// `old` is empty for added tags and `new` is empty for removed ones.
+pub struct FieldChange {
+    pub tag: String,
+    pub old: Vec<String>,
+    pub new: Vec<String>,
+}
+
+pub fn diff_entries(old: &RisEntry, new: &RisEntry) -> Vec<FieldChange> {
+    merge::all_tags(old, new)
+        .into_iter()
+        .filter_map(|tag| {
+            let old_values = merge::values(old, &tag);
+            let new_values = merge::values(new, &tag);
+            (old_values != new_values).then_some(FieldChange {
+                tag,
+                old: old_values,
+                new: new_values,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff_entries;
+    use crate::model::ris::parse_ris;
+
+    #[test]
+    fn test_diff_entries_reports_changed_added_and_removed_tags() {
+        let old = parse_ris("TY  - JOUR\nTI  - Old\nPY  - 2020\nVL  - 1\nER  -")
+            .unwrap()
+            .remove(0);
+        let new = parse_ris("TY  - BOOK\nTI  - New\nPY  - 2020\nDO  - 10.1/x\nER  -")
+            .unwrap()
+            .remove(0);
+
+        let tags: Vec<String> = diff_entries(&old, &new).into_iter().map(|c| c.tag).collect();
+        assert_eq!(tags, vec!["TY", "DO", "TI", "VL"]);
+    }
+}
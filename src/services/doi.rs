@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Context, Result};
+
+use crate::model::ris::RisEntry;
+use crate::services::{csl_json, http};
+
+/// The first DOI in a text, such as `10.1007/s00778-005-0123-4` in a page or
+/// a `https://doi.org/...` link, without trailing punctuation.
+pub fn find(text: &str) -> Option<String> {
+    let mut rest = text;
+    while let Some(start) = rest.find("10.") {
+        let candidate = &rest[start..];
+        rest = &rest[start + 3..];
+
+        let (registrant, suffix) = candidate[3..].split_once('/')?;
+        if !(4..=9).contains(&registrant.len()) || !registrant.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let suffix: String = suffix
+            .chars()
+            .take_while(|c| !c.is_whitespace() && !matches!(c, '"' | '\'' | '<' | '>'))
+            .collect();
+        let suffix = suffix.trim_end_matches(['.', ',', ';', ':', ')', ']']);
+        if !suffix.is_empty() {
+            return Some(format!("10.{}/{}", registrant, suffix));
+        }
+    }
+    None
+}
+
+/// Looks up a DOI's metadata as CSL-JSON through doi.org content negotiation,
+/// which works for Crossref, DataCite and mEDRA DOIs alike.
+pub fn fetch(doi: &str) -> Result<RisEntry> {
+    let url = http::url_with_path("https://doi.org/", doi.trim())?;
+    let json = http::agent()
+        .get(url.as_str())
+        .set("Accept", "application/vnd.citationstyles.csl+json")
+        .call()
+        .with_context(|| format!("Request to {} failed", url))?
+        .into_string()?;
+    let mut entries = csl_json::parse(&json).with_context(|| format!("Metadata of DOI {}", doi))?;
+    if entries.is_empty() {
+        return Err(anyhow!("No metadata for DOI {}", doi));
+    }
+    Ok(entries.remove(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find;
+
+    #[test]
+    fn finds_doi_in_text() {
+        assert_eq!(
+            find("See https://doi.org/10.1007/s00778-005-0123-4. Thanks").as_deref(),
+            Some("10.1007/s00778-005-0123-4")
+        );
+        assert_eq!(
+            find(r#"version 10.2 <a href="/doi/10.1145/3318464.3389700">"#).as_deref(),
+            Some("10.1145/3318464.3389700")
+        );
+        assert_eq!(find("no identifiers, v10.5"), None);
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Text encodings recognized when reading files to import.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// Anything that is not valid UTF-8 is read as Windows-1252, the superset
+    /// of Latin-1 old BibTeX files are usually written in.
+    Windows1252,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 with BOM",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Windows1252 => "Latin-1/Windows-1252",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Windows-1252 characters for the bytes 0x80..=0x9F, where it differs from Latin-1.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž',
+    '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}',
+    'ž', 'Ÿ',
+];
+
+/// Decodes `bytes`, detecting the encoding from a byte order mark or by
+/// falling back to Windows-1252 when the text is not valid UTF-8. Line
+/// endings are normalized to `\n`.
+pub fn decode(bytes: &[u8]) -> (String, Encoding) {
+    let (text, encoding) = if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        (String::from_utf8_lossy(rest).to_string(), Encoding::Utf8Bom)
+    } else if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        (decode_utf16(rest, u16::from_le_bytes), Encoding::Utf16Le)
+    } else if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        (decode_utf16(rest, u16::from_be_bytes), Encoding::Utf16Be)
+    } else {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => (text.to_string(), Encoding::Utf8),
+            Err(_) => (decode_windows_1252(bytes), Encoding::Windows1252),
+        }
+    };
+
+    (normalize_line_endings(&text), encoding)
+}
+
+/// Reads and decodes a file, see [`decode`].
+pub fn read_text(path: &Path) -> Result<(String, Encoding)> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(decode(&bytes))
+}
+
+/// Strips a leading BOM and turns CRLF and CR line endings into LF, for text
+/// that is already a string, e.g. from the clipboard.
+pub fn normalize(text: &str) -> String {
+    normalize_line_endings(text.strip_prefix('\u{feff}').unwrap_or(text))
+}
+
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+fn decode_utf16(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| to_unit([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+            _ => b as char,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_encodings() {
+        assert_eq!(decode(b"\xEF\xBB\xBF@a{k,}\r\n"), ("@a{k,}\n".to_string(), Encoding::Utf8Bom));
+        assert_eq!(decode("Gödel".as_bytes()), ("Gödel".to_string(), Encoding::Utf8));
+        assert_eq!(decode(b"G\xF6del \x93x\x94"), ("Gödel “x”".to_string(), Encoding::Windows1252));
+        assert_eq!(decode(b"\xFF\xFEa\x00\xE9\x00"), ("aé".to_string(), Encoding::Utf16Le));
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::model::ris::{ReferenceType, RisEntry};
+
+/// Parses EndNote's tagged export (the `refer` format): one `%X value` line
+/// per field, records separated by blank lines.
+pub fn parse(text: &str) -> Result<Vec<RisEntry>> {
+    let mut entries = Vec::new();
+    let mut record: Vec<(char, String)> = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            if !record.is_empty() {
+                entries.push(record_to_entry(&record));
+                record.clear();
+            }
+            continue;
+        }
+
+        let mut chars = line.chars();
+        match (chars.next(), chars.next()) {
+            (Some('%'), Some(tag)) => {
+                record.push((tag, chars.as_str().trim().to_string()));
+            }
+            _ => match record.last_mut() {
+                // Long values may wrap onto lines without a tag.
+                Some((_, value)) => {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                None => bail!("Line {}: expected a %-tag, found {:?}", number + 1, line),
+            },
+        }
+    }
+    if !record.is_empty() {
+        entries.push(record_to_entry(&record));
+    }
+
+    Ok(entries)
+}
+
+fn record_to_entry(record: &[(char, String)]) -> RisEntry {
+    let mut ty = ReferenceType::Generic;
+    let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+    let mut add_field = |tag: &str, value: &str| {
+        if !value.is_empty() {
+            fields.entry(tag.to_string()).or_default().push(value.to_string());
+        }
+    };
+
+    for (tag, value) in record {
+        match tag {
+            '0' => ty = reference_type(value),
+            'A' => add_field("AU", value),
+            'E' => add_field("A2", value),
+            'T' => add_field("TI", value),
+            'J' | 'B' => add_field("T2", value),
+            'D' => add_field("PY", value),
+            '8' => add_field("DA", value),
+            'I' => add_field("PB", value),
+            'C' => add_field("CY", value),
+            'V' => add_field("VL", value),
+            'N' => add_field("IS", value),
+            'P' => match value.split_once(['-', '–']) {
+                Some((start, end)) => {
+                    add_field("SP", start.trim());
+                    add_field("EP", end.trim());
+                }
+                None => add_field("SP", value),
+            },
+            'R' => add_field("DO", value),
+            'U' => add_field("UR", value),
+            'X' => add_field("AB", value),
+            'K' => value
+                .split(['\n', ';', ','])
+                .for_each(|keyword| add_field("KW", keyword.trim())),
+            '@' => add_field("SN", value),
+            'Z' => add_field("N1", value),
+            'F' => add_field("ID", value),
+            _ => {}
+        }
+    }
+
+    RisEntry { ty, fields }
+}
+
+fn reference_type(name: &str) -> ReferenceType {
+    match name {
+        "Journal Article" => ReferenceType::Journal,
+        "Magazine Article" => ReferenceType::MagazineArticle,
+        "Newspaper Article" => ReferenceType::Newspaper,
+        "Book" | "Edited Book" => ReferenceType::Book,
+        "Book Section" => ReferenceType::Chart,
+        "Conference Paper" | "Conference Proceedings" => ReferenceType::ConferencePaper,
+        "Thesis" => ReferenceType::Thesis,
+        "Report" => ReferenceType::Report,
+        "Web Page" | "Electronic Article" => ReferenceType::ElectronicArticle,
+        "Patent" => ReferenceType::Patent,
+        "Manuscript" => ReferenceType::Manuscript,
+        "Dataset" => ReferenceType::Dataset,
+        _ => ReferenceType::Generic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_records_separated_by_blank_lines() {
+        let text = "%0 Journal Article\n%A Doe, Jane\n%A Roe, Rick\n%T Cats and\n  dogs\n%D 2020\n%P 1-9\n\n%0 Book\n%T Other\n";
+
+        let entries = parse(text).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].ty, ReferenceType::Journal);
+        assert_eq!(entries[0].fields["AU"], vec!["Doe, Jane", "Roe, Rick"]);
+        assert_eq!(entries[0].fields["TI"], vec!["Cats and dogs"]);
+        assert_eq!(entries[0].fields["EP"], vec!["9"]);
+        assert_eq!(entries[1].ty, ReferenceType::Book);
+    }
+
+    #[test]
+    fn rejects_untagged_text() {
+        assert!(parse("just some text").is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::model::ris::{bibtex_fields, ris_entry_to_bibtex_string, RisEntry};
+use crate::services::citekey::{last_name, year_of};
+use crate::services::serialization::letter_suffix;
+use crate::services::{csl_json, hayagriva};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    Bibtex,
+    Ris,
+    /// org-bibtex headings usable with org-ref
+    Org,
+    /// CSL-JSON as read by pandoc and citeproc
+    CslJson,
+    /// Hayagriva YAML as read by Typst
+    Hayagriva,
+    /// A bulleted reference list for READMEs and wikis
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Bibtex => "bib",
+            ExportFormat::Ris => "ris",
+            ExportFormat::Org => "org",
+            ExportFormat::CslJson => "json",
+            ExportFormat::Hayagriva => "yml",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// How `refrs export --split-by` groups entries into files.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SplitBy {
+    /// One file per keyword (`KW`), which refrs uses as tags and collections
+    #[value(alias = "collection")]
+    Tag,
+}
+
+/// Group name for entries without any tag.
+pub const UNTAGGED: &str = "untagged";
+
+/// Splits entries into named groups, keeping their order. An entry with
+/// several tags is written to each of their files. Entries without tags come
+/// last, in a group of their own even if a tag is called [`UNTAGGED`].
+pub fn split(entries: &[(String, RisEntry)], by: SplitBy) -> Vec<(String, Vec<(String, RisEntry)>)> {
+    let mut groups: Vec<(String, Vec<(String, RisEntry)>)> = Vec::new();
+    let mut untagged = Vec::new();
+    for (key, entry) in entries {
+        let names: Vec<String> = match by {
+            SplitBy::Tag => entry
+                .fields
+                .get("KW")
+                .into_iter()
+                .flatten()
+                .map(|kw| kw.trim().to_string())
+                .filter(|kw| !kw.is_empty())
+                .collect(),
+        };
+        if names.is_empty() {
+            untagged.push((key.clone(), entry.clone()));
+        }
+
+        for name in names {
+            match groups.iter_mut().find(|(group, _)| *group == name) {
+                Some((_, members)) => members.push((key.clone(), entry.clone())),
+                None => groups.push((name, vec![(key.clone(), entry.clone())])),
+            }
+        }
+    }
+    groups.sort_by(|a, b| a.0.cmp(&b.0));
+    if !untagged.is_empty() {
+        groups.push((UNTAGGED.to_string(), untagged));
+    }
+    groups
+}
+
+/// File names for the groups, in the same order. Names that would come out
+/// the same, such as `C++` and `C#`, get a number: `c`, `c-2`.
+pub fn file_stems<'a>(groups: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut stems: Vec<String> = Vec::new();
+    for group in groups {
+        let stem = file_stem(group);
+        let mut candidate = stem.clone();
+        let mut n = 1;
+        while stems.contains(&candidate) {
+            n += 1;
+            candidate = format!("{}-{}", stem, n);
+        }
+        stems.push(candidate);
+    }
+    stems
+}
+
+/// File name for a group, e.g. `Machine Learning` becomes `machine-learning`.
+fn file_stem(group: &str) -> String {
+    let slug: String = deunicode::deunicode(group)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "group".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Order of entries in an export. Every order falls back to the key, so the
+/// output is stable between runs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportSort {
+    #[default]
+    Key,
+    /// First author's last name, then year
+    Author,
+    Year,
+    /// When the entry was first committed to the project
+    Added,
+}
+
+/// Makes the keys of `(key, entry)` pairs unique, as BibTeX needs them to
+/// be, by appending `a`, `b`, ... to the second and later uses of a key.
+/// Keys differing only in case count as the same. Returns the index and old
+/// key of each renamed entry.
+pub fn unique_keys(entries: &mut [(String, RisEntry)]) -> Vec<(usize, String)> {
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut renamed = Vec::new();
+    let mut pending = Vec::new();
+    for (i, (key, _)) in entries.iter().enumerate() {
+        if !taken.insert(key.to_lowercase()) {
+            pending.push(i);
+        }
+    }
+    // Renamed keys must not take a key used further on either.
+    for i in pending {
+        let key = entries[i].0.clone();
+        let unique = (1..)
+            .map(|n| format!("{}{}", key, letter_suffix(n)))
+            .find(|candidate| !taken.contains(&candidate.to_lowercase()))
+            .expect("unbounded range");
+        taken.insert(unique.to_lowercase());
+        entries[i].0 = unique;
+        renamed.push((i, key));
+    }
+    renamed
+}
+
+/// Sorts `(key, entry)` pairs. `added` maps keys to the unix time they were
+/// added; entries missing from it (not committed yet) count as newest.
+pub fn sort(entries: &mut [(String, RisEntry)], order: ExportSort, added: &HashMap<String, i64>) {
+    let author = |entry: &RisEntry| {
+        entry
+            .get_field("AU")
+            .map(|a| last_name(a).to_lowercase())
+            .unwrap_or_default()
+    };
+    let year = |entry: &RisEntry| {
+        entry
+            .get_field("PY")
+            .and_then(|py| year_of(py))
+            .unwrap_or_default()
+    };
+
+    match order {
+        ExportSort::Key => entries.sort_by(|a, b| a.0.cmp(&b.0)),
+        ExportSort::Author => entries.sort_by(|a, b| {
+            (author(&a.1), year(&a.1), &a.0).cmp(&(author(&b.1), year(&b.1), &b.0))
+        }),
+        ExportSort::Year => {
+            entries.sort_by(|a, b| (year(&a.1), &a.0).cmp(&(year(&b.1), &b.0)))
+        }
+        ExportSort::Added => {
+            let time = |key: &String| added.get(key).copied().unwrap_or(i64::MAX);
+            entries.sort_by(|a, b| (time(&a.0), &a.0).cmp(&(time(&b.0), &b.0)))
+        }
+    }
+}
+
+/// Renders `(key, entry)` pairs in the requested format.
+pub fn render(format: ExportFormat, entries: &[(String, RisEntry)]) -> String {
+    match format {
+        ExportFormat::Bibtex => to_bibtex(entries),
+        ExportFormat::Ris => to_ris(entries),
+        ExportFormat::Org => to_org(entries),
+        ExportFormat::CslJson => csl_json::to_csl_json(entries),
+        ExportFormat::Hayagriva => hayagriva::to_hayagriva(entries),
+        ExportFormat::Markdown => to_markdown(entries),
+    }
+}
+
+pub fn to_bibtex(entries: &[(String, RisEntry)]) -> String {
+    let mut bibtex_entries = String::new();
+    for (key, entry) in entries {
+        bibtex_entries.push_str(&ris_entry_to_bibtex_string(entry, key));
+        bibtex_entries.push('\n'); // Add a newline between entries
+    }
+    bibtex_entries
+}
+
+pub fn to_ris(entries: &[(String, RisEntry)]) -> String {
+    let mut ris = String::new();
+    for (_, entry) in entries {
+        ris.push_str(&entry.to_string());
+        ris.push_str("\n\n");
+    }
+    ris
+}
+
+/// One org-bibtex heading per entry. `CUSTOM_ID` carries the key, so org-ref
+/// `cite:` links resolve to the heading, and RIS notes become the body.
+pub fn to_org(entries: &[(String, RisEntry)]) -> String {
+    let mut org = String::from("#+TITLE: Bibliography\n\n");
+
+    for (key, entry) in entries {
+        let (entry_type, fields) = bibtex_fields(entry);
+        let title = entry.get_field("TI").map(String::as_str).unwrap_or(key);
+
+        org.push_str(&format!("* {}\n", single_line(title)));
+        org.push_str("  :PROPERTIES:\n");
+        org.push_str(&format!("  :BTYPE:     {}\n", entry_type));
+        org.push_str(&format!("  :CUSTOM_ID: {}\n", key));
+        // Notes become the body instead.
+        for (name, value) in fields.into_iter().filter(|(name, _)| *name != "note") {
+            org.push_str(&format!("  :{}: {}\n", name.to_uppercase(), single_line(&value)));
+        }
+        org.push_str("  :END:\n");
+        org.push_str(&format!("  cite:&{}\n", key));
+
+        let notes = entry.fields.get("N1").map(Vec::as_slice).unwrap_or_default();
+        if !notes.is_empty() {
+            org.push_str("** Notes\n");
+            // Indented, a note line starting with `*` is not read as a heading.
+            for line in notes.iter().flat_map(|note| note.lines()) {
+                if !line.trim().is_empty() {
+                    org.push_str("   ");
+                    org.push_str(line);
+                }
+                org.push('\n');
+            }
+        }
+        org.push('\n');
+    }
+
+    org
+}
+
+/// One bullet per entry: authors, year, title, venue and a DOI (or URL) link.
+pub fn to_markdown(entries: &[(String, RisEntry)]) -> String {
+    let mut markdown = String::new();
+
+    for (key, entry) in entries {
+        let authors: Vec<String> = entry
+            .fields
+            .get("AU")
+            .into_iter()
+            .flatten()
+            .map(|author| match author.split_once(',') {
+                Some((last, first)) => format!("{} {}", first.trim(), last.trim()),
+                None => author.trim().to_string(),
+            })
+            .collect();
+        let authors = match authors.as_slice() {
+            [] => String::new(),
+            [only] => only.clone(),
+            [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+        };
+        let year = entry.get_field("PY").and_then(|py| year_of(py));
+        let title = entry.get_field("TI").map(String::as_str).unwrap_or(key);
+        let venue = ["T2", "JO", "JF", "BT", "PB"]
+            .iter()
+            .find_map(|tag| entry.get_field(tag))
+            .map(|venue| venue.trim())
+            .filter(|venue| !venue.is_empty());
+
+        let mut parts = Vec::new();
+        match (authors.is_empty(), year) {
+            (false, Some(year)) => parts.push(format!("{} ({}).", escape_markdown(&authors), year)),
+            (false, None) => parts.push(format!("{}.", escape_markdown(&authors))),
+            (true, Some(year)) => parts.push(format!("({}).", year)),
+            (true, None) => {}
+        }
+        parts.push(format!("**{}**.", escape_markdown(&single_line(title)).trim_end_matches('.')));
+        if let Some(venue) = venue {
+            parts.push(format!("*{}*.", escape_markdown(&single_line(venue))));
+        }
+        if let Some(doi) = entry.get_field("DO").map(|doi| doi.trim()).filter(|doi| !doi.is_empty()) {
+            parts.push(format!("[doi:{}](https://doi.org/{})", escape_markdown(doi), doi));
+        } else if let Some(url) = entry.get_field("UR").map(|url| url.trim()).filter(|url| !url.is_empty()) {
+            parts.push(format!("<{}>", url));
+        }
+
+        markdown.push_str(&format!("- {}\n", parts.join(" ")));
+    }
+
+    markdown
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']' | '`' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Property values and headings must fit on one line.
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_stem, file_stems, split, to_markdown, to_org, unique_keys, SplitBy, UNTAGGED};
+    use crate::model::ris::{parse_ris, RisEntry};
+
+    #[test]
+    fn test_to_org_writes_properties_and_notes() {
+        let entry = parse_ris(
+            "TY  - JOUR\nAU  - Doe, John\nTI  - A Title\nPY  - 2020\nN1  - Read chapter 2\nN1  - * not a heading\nER  -",
+        )
+        .unwrap()
+        .remove(0);
+
+        let org = to_org(&[("doe2020".to_string(), entry)]);
+        assert!(org.contains("* A Title\n  :PROPERTIES:\n  :BTYPE:     article\n  :CUSTOM_ID: doe2020\n"));
+        assert!(org.contains("  :AUTHOR: Doe, John\n"));
+        assert!(org.contains("  :END:\n  cite:&doe2020\n** Notes\n   Read chapter 2\n   * not a heading\n"));
+    }
+
+    #[test]
+    fn test_to_markdown_bullets() {
+        let entries: Vec<(String, _)> = [
+            "TY  - JOUR\nAU  - Doe, Jane\nAU  - Roe, Rick\nAU  - Zed, Zoe\nTI  - Cats_and *dogs*\nT2  - Nature\nPY  - 2020\nDO  - 10.1/x\nER  -",
+            "TY  - BOOK\nTI  - Untitled.\nPB  - Springer\nUR  - https://example.org\nER  -",
+        ]
+        .iter()
+        .map(|ris| ("key".to_string(), parse_ris(ris).unwrap().remove(0)))
+        .collect();
+
+        assert_eq!(
+            to_markdown(&entries),
+            "- Jane Doe, Rick Roe and Zoe Zed (2020). **Cats\\_and \\*dogs\\***. *Nature*. [doi:10.1/x](https://doi.org/10.1/x)\n\
+             - **Untitled**. *Springer*. <https://example.org>\n"
+        );
+    }
+
+    #[test]
+    fn test_unique_keys_appends_letters() {
+        let entry = parse_ris("TY  - JOUR\nER  -").unwrap().remove(0);
+        let mut entries: Vec<(String, RisEntry)> = ["doe2020", "Doe2020", "doe2020a", "roe", "doe2020"]
+            .iter()
+            .map(|key| (key.to_string(), entry.clone()))
+            .collect();
+
+        let renamed = unique_keys(&mut entries);
+        let keys: Vec<&str> = entries.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["doe2020", "Doe2020b", "doe2020a", "roe", "doe2020c"]);
+        assert_eq!(renamed, vec![(1, "Doe2020".to_string()), (4, "doe2020".to_string())]);
+    }
+
+    #[test]
+    fn test_split_by_tag() {
+        let entry = |kw: &str| parse_ris(&format!("TY  - JOUR\n{}ER  -", kw)).unwrap().remove(0);
+        let entries = vec![
+            ("a".to_string(), entry("KW  - Chapter 2\nKW  - Methods\n")),
+            ("b".to_string(), entry("KW  - Chapter 2\n")),
+            ("c".to_string(), entry("")),
+        ];
+
+        let groups: Vec<(String, Vec<String>)> = split(&entries, SplitBy::Tag)
+            .into_iter()
+            .map(|(name, members)| (name, members.into_iter().map(|(key, _)| key).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("Chapter 2".to_string(), vec!["a".to_string(), "b".to_string()]),
+                ("Methods".to_string(), vec!["a".to_string()]),
+                (UNTAGGED.to_string(), vec!["c".to_string()]),
+            ]
+        );
+        assert_eq!(file_stem("Chapter 2: Méthodes"), "chapter-2-methodes");
+        assert_eq!(file_stems(["C#", "C++", "untagged", UNTAGGED]), vec!["c", "c-2", "untagged", "untagged-2"]);
+    }
+}
@@ -0,0 +1,206 @@
+//! An inverted index of the words in a project's PDF attachments, kept next
+//! to the entry [`index`] and brought up to date before each full-text search.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::services::{index, pdf};
+
+/// Bumped when the way text is split into words or the layout changes.
+const VERSION: u32 = 2;
+
+/// An indexed attachment.
+#[derive(Serialize, Deserialize)]
+struct Document {
+    size: u64,
+    modified: SystemTime,
+    /// Its distinct words, to take it out of `words` again without going
+    /// through every word.
+    words: BTreeSet<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FulltextIndex {
+    version: u32,
+    /// The indexed attachments, by their paths relative to the project.
+    documents: HashMap<String, Document>,
+    /// Each word and the attachments containing it.
+    words: BTreeMap<String, BTreeSet<String>>,
+    /// Folder next to the index file keeping the text of each attachment,
+    /// so showing where it matched doesn't read the PDF again.
+    #[serde(skip)]
+    texts: Option<PathBuf>,
+}
+
+pub fn index_path(project_path: &str) -> PathBuf {
+    index::index_path(project_path).with_extension("fulltext.json")
+}
+
+/// Lowercased runs of letters and digits, so `B-Trees,` gives `b` and `trees`.
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+impl FulltextIndex {
+    fn new() -> Self {
+        FulltextIndex { version: VERSION, documents: HashMap::new(), words: BTreeMap::new(), texts: None }
+    }
+
+    /// Reads the word index at `path`, with the extracted texts in the
+    /// `.texts` folder beside it. An index from an older version starts over,
+    /// so every attachment is extracted again.
+    pub fn load(path: &Path) -> Self {
+        let mut index = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<FulltextIndex>(&bytes).ok())
+            .filter(|index| index.version == VERSION)
+            .unwrap_or_else(FulltextIndex::new);
+        index.texts = Some(path.with_extension("texts"));
+        index
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_file = path.with_extension(format!("json.{}.tmp", process::id()));
+        fs::write(&temp_file, serde_json::to_vec(self)?)?;
+        fs::rename(&temp_file, path)?;
+        Ok(())
+    }
+
+    /// Indexes the `attachments` of the project that are new or changed,
+    /// reading them in parallel, and forgets the ones no longer attached.
+    /// A PDF whose text can't be read is indexed without words. Returns the
+    /// number of attachments read.
+    pub fn update(&mut self, project_path: &str, attachments: &[String]) -> usize {
+        let wanted: HashSet<&String> = attachments.iter().collect();
+        let removed: Vec<String> = self.documents.keys().filter(|path| !wanted.contains(path)).cloned().collect();
+        for path in &removed {
+            self.remove(path);
+        }
+
+        let changed: Vec<(&String, (u64, SystemTime))> = attachments
+            .iter()
+            .filter_map(|path| {
+                let metadata = fs::metadata(Path::new(project_path).join(path)).ok()?;
+                let stamp = (metadata.len(), metadata.modified().ok()?);
+                let indexed = self.documents.get(path).map(|document| (document.size, document.modified));
+                (indexed != Some(stamp)).then_some((path, stamp))
+            })
+            .collect();
+        let texts: Vec<String> = changed
+            .par_iter()
+            .map(|(path, _)| pdf::read_text(&Path::new(project_path).join(path)).unwrap_or_default())
+            .collect();
+
+        for ((path, (size, modified)), text) in changed.iter().zip(texts) {
+            self.remove(path);
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let document_words: BTreeSet<String> = words(&text).collect();
+            for word in &document_words {
+                self.words.entry(word.clone()).or_default().insert(path.to_string());
+            }
+            self.documents.insert(
+                path.to_string(),
+                Document { size: *size, modified: *modified, words: document_words },
+            );
+            if let Some(text_path) = self.text_path(path) {
+                // Without it, `text` is None and the PDF gets read instead.
+                let _ = fs::create_dir_all(text_path.parent().unwrap_or(Path::new(".")))
+                    .and_then(|_| fs::write(&text_path, &text));
+            }
+        }
+        changed.len()
+    }
+
+    fn remove(&mut self, path: &str) {
+        let Some(document) = self.documents.remove(path) else {
+            return;
+        };
+        for word in &document.words {
+            if let Some(paths) = self.words.get_mut(word) {
+                paths.remove(path);
+                if paths.is_empty() {
+                    self.words.remove(word);
+                }
+            }
+        }
+        if let Some(text_path) = self.text_path(path) {
+            let _ = fs::remove_file(text_path);
+        }
+    }
+
+    fn text_path(&self, path: &str) -> Option<PathBuf> {
+        let name = utf8_percent_encode(path, NON_ALPHANUMERIC);
+        Some(self.texts.as_ref()?.join(format!("{}.txt", name)))
+    }
+
+    /// The text of an indexed attachment as kept when it was indexed, with
+    /// runs of whitespace made single spaces.
+    pub fn text(&self, path: &str) -> Option<String> {
+        fs::read_to_string(self.text_path(path)?).ok()
+    }
+
+    /// The attachments with a word starting with each word of the `terms`,
+    /// so `optim` finds "optimization".
+    pub fn search(&self, terms: &[String]) -> BTreeSet<String> {
+        let mut found: Option<BTreeSet<String>> = None;
+        for word in terms.iter().flat_map(|term| words(term)) {
+            let containing: BTreeSet<String> = self
+                .words
+                .range(word.clone()..)
+                .take_while(|(indexed, _)| indexed.starts_with(&word))
+                .flat_map(|(_, paths)| paths.iter().cloned())
+                .collect();
+            found = Some(match found {
+                Some(found) => found.intersection(&containing).cloned().collect(),
+                None => containing,
+            });
+        }
+        found.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_attachments_with_every_word() {
+        let project = std::env::temp_dir().join(format!("refrs-fulltext-{}", process::id()));
+        fs::create_dir_all(&project).unwrap();
+        let pdf = |text: &str| format!("%PDF-1.4\n1 0 obj\n<< /Length 40 >>\nstream\nBT ({}) Tj ET\nendstream\n", text);
+        fs::write(project.join("a.pdf"), pdf("Skip lists: a probabilistic alternative")).unwrap();
+        fs::write(project.join("b.pdf"), pdf("Balanced trees and lists")).unwrap();
+        let project_path = project.to_str().unwrap();
+        let attachments = vec!["a.pdf".to_string(), "b.pdf".to_string()];
+
+        let mut index = FulltextIndex::load(&project.join("index.json"));
+        assert_eq!(index.update(project_path, &attachments), 2);
+        assert_eq!(index.update(project_path, &attachments), 0);
+        assert_eq!(index.text("a.pdf").as_deref(), Some("Skip lists: a probabilistic alternative"));
+        let search = |index: &FulltextIndex, query: &str| -> Vec<String> {
+            index.search(&[query.to_string()]).into_iter().collect()
+        };
+        assert_eq!(search(&index, "skip list"), vec!["a.pdf"]);
+        assert_eq!(search(&index, "LISTS"), vec!["a.pdf", "b.pdf"]);
+
+        index.update(project_path, &attachments[1..]);
+        assert_eq!(search(&index, "list"), vec!["b.pdf"]);
+        assert!(search(&index, "probabilistic").is_empty());
+        assert_eq!(index.words.len(), 4);
+        assert_eq!(index.text("a.pdf"), None);
+        fs::remove_dir_all(&project).unwrap();
+    }
+}
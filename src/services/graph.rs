@@ -0,0 +1,434 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::services::library::LibraryEntry;
+use crate::services::{semantic_scholar, tags};
+
+#[derive(Serialize)]
+pub struct Node {
+    pub id: String,
+    pub label: String,
+}
+
+#[derive(Serialize)]
+pub struct Edge {
+    pub source: String,
+    pub target: String,
+    pub weight: usize,
+    pub directed: bool,
+    pub kind: &'static str,
+}
+
+#[derive(Default, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// Normalized author name used to match the same person across entries.
+pub fn author_key(author: &str) -> String {
+    author
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Node ids of the entries: the file stem, with `#2`, `#3` and so on added
+/// for further entries of a file holding several.
+pub fn reference_ids(entries: &[LibraryEntry]) -> Vec<String> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    entries
+        .iter()
+        .map(|e| {
+            let count = seen.entry(e.id.as_str()).or_default();
+            *count += 1;
+            match *count {
+                1 => e.id.clone(),
+                n => format!("{}#{}", e.id, n),
+            }
+        })
+        .collect()
+}
+
+/// One node per reference, labelled with its title.
+pub fn reference_nodes(entries: &[LibraryEntry]) -> Vec<Node> {
+    entries
+        .iter()
+        .zip(reference_ids(entries))
+        .map(|(e, id)| Node {
+            label: e.entry.get_field("TI").cloned().unwrap_or_else(|| id.clone()),
+            id,
+        })
+        .collect()
+}
+
+/// Undirected edges between references that share authors, weighted by the
+/// number of shared authors.
+pub fn coauthor_edges(entries: &[LibraryEntry]) -> Vec<Edge> {
+    let ids = reference_ids(entries);
+    let mut by_author: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, e) in entries.iter().enumerate() {
+        let authors: HashSet<String> = e
+            .entry
+            .fields
+            .get("AU")
+            .into_iter()
+            .flatten()
+            .map(|a| author_key(a))
+            .collect();
+        for author in authors {
+            by_author.entry(author).or_default().push(i);
+        }
+    }
+
+    let mut weights: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for indices in by_author.values() {
+        for (n, &a) in indices.iter().enumerate() {
+            for &b in &indices[n + 1..] {
+                *weights.entry((a.min(b), a.max(b))).or_default() += 1;
+            }
+        }
+    }
+
+    weights
+        .into_iter()
+        .map(|((a, b), weight)| Edge {
+            source: ids[a].clone(),
+            target: ids[b].clone(),
+            weight,
+            directed: false,
+            kind: "coauthor",
+        })
+        .collect()
+}
+
+/// Node id of a keyword, which is compared ignoring case.
+fn keyword_id(keyword: &str) -> String {
+    format!("keyword:{}", keyword.to_lowercase())
+}
+
+/// Distinct keywords of an entry, trimmed.
+fn entry_keywords(entry: &LibraryEntry) -> Vec<String> {
+    let mut keywords: Vec<String> = Vec::new();
+    for keyword in tags::tags(&entry.entry) {
+        let keyword = keyword.trim();
+        if !keyword.is_empty() && !keywords.iter().any(|k| k.to_lowercase() == keyword.to_lowercase()) {
+            keywords.push(keyword.to_string());
+        }
+    }
+    keywords
+}
+
+/// One node per keyword, labelled with the spelling first seen in the library.
+pub fn keyword_nodes(entries: &[LibraryEntry]) -> Vec<Node> {
+    let mut labels: BTreeMap<String, String> = BTreeMap::new();
+    for e in entries {
+        for keyword in entry_keywords(e) {
+            labels.entry(keyword_id(&keyword)).or_insert(keyword);
+        }
+    }
+    labels
+        .into_iter()
+        .map(|(id, label)| Node { id, label })
+        .collect()
+}
+
+/// Undirected edges from each reference to its keywords, so references
+/// sharing a keyword meet at its node. Unlike an edge between every pair of
+/// references, this stays linear in the size of the library.
+pub fn keyword_edges(entries: &[LibraryEntry]) -> Vec<Edge> {
+    entries
+        .iter()
+        .zip(reference_ids(entries))
+        .flat_map(|(e, id)| {
+            entry_keywords(e).into_iter().map(move |keyword| Edge {
+                source: id.clone(),
+                target: keyword_id(&keyword),
+                weight: 1,
+                directed: false,
+                kind: "keyword",
+            })
+        })
+        .collect()
+}
+
+/// Distinct authors of an entry, keyed by [`author_key`], in order of appearance.
+fn entry_authors(entry: &LibraryEntry) -> Vec<(String, &str)> {
+    let mut authors: Vec<(String, &str)> = Vec::new();
+    for author in entry.entry.fields.get("AU").into_iter().flatten() {
+        let key = author_key(author);
+        if !key.is_empty() && !authors.iter().any(|(k, _)| *k == key) {
+            authors.push((key, author.trim()));
+        }
+    }
+    authors
+}
+
+/// One node per author, labelled with the spelling first seen in the library.
+pub fn author_nodes(entries: &[LibraryEntry]) -> Vec<Node> {
+    let mut labels: BTreeMap<String, String> = BTreeMap::new();
+    for e in entries {
+        for (key, name) in entry_authors(e) {
+            labels.entry(key).or_insert_with(|| name.to_string());
+        }
+    }
+    labels
+        .into_iter()
+        .map(|(id, label)| Node { id, label })
+        .collect()
+}
+
+/// Undirected edges between authors who wrote an entry together, weighted by
+/// the number of entries they share.
+pub fn coauthorship_edges(entries: &[LibraryEntry]) -> Vec<Edge> {
+    let mut weights: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for e in entries {
+        let mut authors: Vec<String> = entry_authors(e).into_iter().map(|(key, _)| key).collect();
+        authors.sort();
+        for (n, a) in authors.iter().enumerate() {
+            for b in &authors[n + 1..] {
+                *weights.entry((a.clone(), b.clone())).or_default() += 1;
+            }
+        }
+    }
+
+    weights
+        .into_iter()
+        .map(|((source, target), weight)| Edge {
+            source,
+            target,
+            weight,
+            directed: false,
+            kind: "coauthor",
+        })
+        .collect()
+}
+
+/// Directed edges from citing to cited reference, using Semantic Scholar's
+/// reference lists for every entry with a DOI. Entries whose references
+/// can't be looked up are skipped with a warning.
+pub fn citation_edges(entries: &[LibraryEntry]) -> Vec<Edge> {
+    let ids = reference_ids(entries);
+    let by_doi: HashMap<String, &str> = entries
+        .iter()
+        .zip(&ids)
+        .filter_map(|(e, id)| Some((e.entry.get_field("DO")?.trim().to_lowercase(), id.as_str())))
+        .collect();
+
+    let mut edges = Vec::new();
+    for (doi, id) in &by_doi {
+        let cited_dois = match semantic_scholar::reference_dois(doi) {
+            Ok(cited_dois) => cited_dois,
+            Err(error) => {
+                eprintln!("Could not look up the references of {}: {:#}", id, error);
+                continue;
+            }
+        };
+        for cited in cited_dois {
+            if let Some(target) = by_doi.get(&cited.to_lowercase()) {
+                edges.push(Edge {
+                    source: id.to_string(),
+                    target: target.to_string(),
+                    weight: 1,
+                    directed: true,
+                    kind: "cites",
+                });
+            }
+        }
+    }
+
+    edges.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+    edges
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the graph for Graphviz. Undirected edges are drawn without arrows.
+pub fn to_dot(graph: &Graph) -> String {
+    let mut out = String::from("digraph refrs {\n");
+    for node in &graph.nodes {
+        out.push_str(&format!(
+            "  \"{}\" [label=\"{}\"];\n",
+            dot_escape(&node.id),
+            dot_escape(&node.label)
+        ));
+    }
+    for edge in &graph.edges {
+        let dir = if edge.directed { "" } else { ", dir=none" };
+        out.push_str(&format!(
+            "  \"{}\" -> \"{}\" [label=\"{}\", weight={}{}];\n",
+            dot_escape(&edge.source),
+            dot_escape(&edge.target),
+            edge.kind,
+            edge.weight,
+            dir
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Renders the graph as `{"nodes": [...], "edges": [...]}` for the web UI.
+pub fn to_json(graph: &Graph) -> String {
+    serde_json::to_string(graph).unwrap_or_default()
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Renders the edges as a `source,target,weight` CSV using the node labels,
+/// the edge list format Gephi and most spreadsheet tools import.
+pub fn to_csv(graph: &Graph) -> String {
+    let labels: HashMap<&str, &str> = graph
+        .nodes
+        .iter()
+        .map(|n| (n.id.as_str(), n.label.as_str()))
+        .collect();
+    let label = |id: &str| csv_field(labels.get(id).copied().unwrap_or(id));
+
+    let mut out = String::from("source,target,weight\n");
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "{},{},{}\n",
+            label(&edge.source),
+            label(&edge.target),
+            edge.weight
+        ));
+    }
+    out
+}
+
+/// Renders the graph as GraphML, which Gephi and yEd read directly.
+pub fn to_graphml(graph: &Graph) -> String {
+    let mut out = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <key id="kind" for="edge" attr.name="kind" attr.type="string"/>
+  <key id="weight" for="edge" attr.name="weight" attr.type="int"/>
+  <graph id="refrs" edgedefault="undirected">
+"#,
+    );
+    for node in &graph.nodes {
+        out.push_str(&format!(
+            "    <node id=\"{}\"><data key=\"label\">{}</data></node>\n",
+            xml_escape(&node.id),
+            xml_escape(&node.label)
+        ));
+    }
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\" directed=\"{}\"><data key=\"kind\">{}</data><data key=\"weight\">{}</data></edge>\n",
+            xml_escape(&edge.source),
+            xml_escape(&edge.target),
+            edge.directed,
+            edge.kind,
+            edge.weight
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        author_nodes, coauthor_edges, coauthorship_edges, keyword_edges, keyword_nodes, reference_nodes, to_csv, to_dot,
+        to_json, Graph,
+    };
+    use crate::model::ris::parse_ris;
+    use crate::services::library::LibraryEntry;
+
+    fn library_entry(id: &str, content: &str) -> LibraryEntry {
+        LibraryEntry {
+            id: id.to_string(),
+            path: format!("{id}.ris").into(),
+            entry: parse_ris(content).unwrap().remove(0),
+        }
+    }
+
+    #[test]
+    fn test_coauthor_edges_are_weighted_by_shared_authors() {
+        let entries = vec![
+            library_entry("a", "TY  - JOUR\nAU  - Doe, John\nAU  - Smith, Jane\nTI  - A\nER  -"),
+            library_entry("b", "TY  - JOUR\nAU  - Smith, Jane\nAU  - doe,  john\nTI  - B\nER  -"),
+            library_entry("c", "TY  - JOUR\nAU  - Other, Person\nTI  - C\nER  -"),
+        ];
+
+        let edges = coauthor_edges(&entries);
+        assert_eq!(edges.len(), 1);
+        assert_eq!((edges[0].source.as_str(), edges[0].target.as_str()), ("a", "b"));
+        assert_eq!(edges[0].weight, 2);
+
+        let graph = Graph {
+            nodes: reference_nodes(&entries),
+            edges,
+        };
+        let dot = to_dot(&graph);
+        assert!(dot.contains("\"a\" [label=\"A\"];"));
+        assert!(dot.contains("\"a\" -> \"b\" [label=\"coauthor\", weight=2, dir=none];"));
+    }
+
+    #[test]
+    fn test_coauthorship_edges_are_weighted_by_shared_entries() {
+        let entries = vec![
+            library_entry("a", "TY  - JOUR\nAU  - Doe, John\nAU  - Smith, Jane\nTI  - A\nER  -"),
+            library_entry("b", "TY  - JOUR\nAU  - Smith, Jane\nAU  - doe,  john\nAU  - Roe, Rick\nTI  - B\nER  -"),
+            library_entry("c", "TY  - JOUR\nAU  - Doe, John\nTI  - C\nER  -"),
+        ];
+
+        let graph = Graph {
+            nodes: author_nodes(&entries),
+            edges: coauthorship_edges(&entries),
+        };
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(
+            to_csv(&graph),
+            "source,target,weight\n\"Doe, John\",\"Roe, Rick\",1\n\"Doe, John\",\"Smith, Jane\",2\n\"Roe, Rick\",\"Smith, Jane\",1\n"
+        );
+    }
+
+    #[test]
+    fn test_keyword_edges_link_references_to_keyword_nodes() {
+        let entries = vec![
+            library_entry("a", "TY  - JOUR\nKW  - Graphs\nKW  - databases\nER  -"),
+            library_entry("b", "TY  - JOUR\nKW  - graphs\nKW  - Databases\nKW  - graphs\nER  -"),
+            library_entry("b", "TY  - JOUR\nKW  - graphs\nER  -"),
+        ];
+
+        let graph = Graph {
+            nodes: keyword_nodes(&entries),
+            edges: keyword_edges(&entries),
+        };
+        let labels: Vec<&str> = graph.nodes.iter().map(|n| n.label.as_str()).collect();
+        assert_eq!(labels, vec!["databases", "Graphs"]);
+        let edges: Vec<(&str, &str)> = graph.edges.iter().map(|e| (e.source.as_str(), e.target.as_str())).collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("a", "keyword:graphs"),
+                ("a", "keyword:databases"),
+                ("b", "keyword:graphs"),
+                ("b", "keyword:databases"),
+                ("b#2", "keyword:graphs"),
+            ]
+        );
+        assert!(to_json(&graph).starts_with(r#"{"nodes":[{"id":"keyword:databases","label":"databases"}"#));
+    }
+}
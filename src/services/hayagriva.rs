@@ -0,0 +1,159 @@
+use serde_yaml::{Mapping, Value};
+
+use crate::model::ris::{ReferenceType, RisEntry};
+use crate::services::citekey::year_of;
+
+/// Renders `(key, entry)` pairs as a Hayagriva bibliography, the YAML format
+/// Typst reads, with one top-level entry per citation key.
+pub fn to_hayagriva(entries: &[(String, RisEntry)]) -> String {
+    let mut bibliography = Mapping::new();
+    for (key, entry) in entries {
+        bibliography.insert(key.as_str().into(), Value::Mapping(entry_to_mapping(entry)));
+    }
+    serde_yaml::to_string(&bibliography).unwrap_or_default()
+}
+
+/// Hayagriva type of an entry and, for parts of a larger work, the type of
+/// the parent that carries the container title.
+fn types(ty: &ReferenceType) -> (&'static str, Option<&'static str>) {
+    match ty {
+        ReferenceType::Journal | ReferenceType::MagazineArticle => ("article", Some("periodical")),
+        ReferenceType::Newspaper => ("article", Some("newspaper")),
+        ReferenceType::ConferencePaper => ("article", Some("proceedings")),
+        ReferenceType::Chart => ("chapter", Some("book")),
+        ReferenceType::Book | ReferenceType::ElectronicBook => ("book", None),
+        ReferenceType::ConferenceProceedings => ("proceedings", None),
+        ReferenceType::Thesis => ("thesis", None),
+        ReferenceType::Report => ("report", None),
+        ReferenceType::ElectronicArticle => ("web", None),
+        ReferenceType::Patent => ("patent", None),
+        ReferenceType::Manuscript | ReferenceType::UnpublishedWork => ("manuscript", None),
+        ReferenceType::ComputerProgram => ("repository", None),
+        _ => ("misc", None),
+    }
+}
+
+fn entry_to_mapping(entry: &RisEntry) -> Mapping {
+    let values = |tag: &str| entry.fields.get(tag).cloned().unwrap_or_default();
+    let text = |tag: &str| entry.get_field(tag).map(|value| Value::from(value.trim()));
+    let names = |tag: &str| {
+        let names = values(tag);
+        (!names.is_empty()).then(|| Value::Sequence(names.into_iter().map(Value::from).collect()))
+    };
+
+    let (ty, parent_type) = types(&entry.ty);
+    let mut map = Mapping::new();
+
+    set(&mut map, "type", Some(ty.into()));
+    set(&mut map, "title", text("TI"));
+    set(&mut map, "author", names("AU"));
+    set(&mut map, "editor", names("A2"));
+    set(&mut map, "date", date(entry));
+
+    // Volume and issue belong to the journal, not the article.
+    let mut parent = Mapping::new();
+    let container = if parent_type.is_some() { &mut parent } else { &mut map };
+    set(container, "volume", text("VL"));
+    set(container, "issue", text("IS"));
+
+    set(
+        &mut map,
+        "page-range",
+        match (entry.get_field("SP"), entry.get_field("EP")) {
+            (Some(start), Some(end)) => Some(format!("{}-{}", start.trim(), end.trim()).into()),
+            (Some(start), None) => Some(start.trim().into()),
+            _ => None,
+        },
+    );
+    set(&mut map, "publisher", text("PB"));
+    set(&mut map, "location", text("CY"));
+    set(&mut map, "url", text("UR"));
+    set(&mut map, "abstract", text("AB"));
+    set(&mut map, "language", text("LA"));
+    let notes = values("N1");
+    set(&mut map, "note", (!notes.is_empty()).then(|| notes.join("\n").into()));
+
+    let mut serial = Mapping::new();
+    set(&mut serial, "doi", text("DO"));
+    let number = if ty == "book" { "isbn" } else { "issn" };
+    set(&mut serial, number, text("SN"));
+    if !serial.is_empty() {
+        map.insert("serial-number".into(), Value::Mapping(serial));
+    }
+
+    match (parent_type, text("T2")) {
+        (Some(parent_type), title) => {
+            if title.is_some() || !parent.is_empty() {
+                let mut full = Mapping::new();
+                full.insert("type".into(), parent_type.into());
+                set(&mut full, "title", title);
+                full.extend(parent);
+                map.insert("parent".into(), Value::Mapping(full));
+            }
+        }
+        // Books in a series and the like.
+        (None, Some(title)) => {
+            let mut series = Mapping::new();
+            series.insert("title".into(), title);
+            map.insert("parent".into(), Value::Mapping(series));
+        }
+        (None, None) => {}
+    }
+
+    map
+}
+
+fn set(map: &mut Mapping, name: &str, value: Option<Value>) {
+    if let Some(value) = value {
+        map.insert(name.into(), value);
+    }
+}
+
+/// `2020-03-15` from a RIS date, else the year as a number.
+fn date(entry: &RisEntry) -> Option<Value> {
+    let parts: Vec<&str> = entry
+        .get_field("DA")
+        .map(|date| {
+            date.split('/')
+                .take_while(|part| !part.trim().is_empty() && part.trim().chars().all(|c| c.is_ascii_digit()))
+                .collect()
+        })
+        .unwrap_or_default();
+    match parts.as_slice() {
+        [year, rest @ ..] if !rest.is_empty() && year.len() == 4 => {
+            let rest: Vec<String> = rest.iter().map(|part| format!("{:0>2}", part.trim())).collect();
+            Some(format!("{}-{}", year, rest.join("-")).into())
+        }
+        _ => {
+            let year: u32 = year_of(entry.get_field("PY")?)?.parse().ok()?;
+            Some(year.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ris::parse_ris;
+
+    #[test]
+    fn nests_journal_under_parent() {
+        let entry = parse_ris(
+            "TY  - JOUR\nAU  - Doe, Jane\nTI  - Cats\nT2  - Nature\nVL  - 12\nSP  - 10\nEP  - 20\nDA  - 2020/3/15\nDO  - 10.1/x\nER  -",
+        )
+        .unwrap()
+        .remove(0);
+
+        let yaml = to_hayagriva(&[("doe2020".to_string(), entry)]);
+        let value: Value = serde_yaml::from_str(&yaml).unwrap();
+        let item = &value["doe2020"];
+        assert_eq!(item["type"], "article");
+        assert_eq!(item["author"][0], "Doe, Jane");
+        assert_eq!(item["date"], "2020-03-15");
+        assert_eq!(item["page-range"], "10-20");
+        assert_eq!(item["parent"]["type"], "periodical");
+        assert_eq!(item["parent"]["title"], "Nature");
+        assert_eq!(item["parent"]["volume"], "12");
+        assert_eq!(item["serial-number"]["doi"], "10.1/x");
+    }
+}
@@ -0,0 +1,265 @@
+/// Tags whose content is never shown and must not end up in the text.
+const HIDDEN_TAGS: [&str; 3] = ["script", "style", "head"];
+
+/// Tags that start a new line when rendered.
+const BLOCK_TAGS: [&str; 12] = [
+    "br", "p", "div", "pre", "li", "tr", "h1", "h2", "h3", "h4", "textarea", "blockquote",
+];
+
+/// Converts an HTML fragment to plain text: markup is dropped, block elements
+/// become line breaks and entities are decoded.
+pub fn to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+
+        let name = tag_name(tag);
+        if HIDDEN_TAGS.contains(&name.as_str()) && !tag.starts_with('/') {
+            let closing = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&closing) {
+                Some(index) => &rest[index..],
+                None => "",
+            };
+        } else if BLOCK_TAGS.contains(&name.as_str()) && !text.ends_with('\n') {
+            text.push('\n');
+        }
+    }
+    text.push_str(&decode_entities(rest));
+
+    text.replace('\u{a0}', " ")
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end]).map(|c| (c, end)));
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => None,
+    }
+}
+
+/// `(name, content)` of every `<meta>` tag, taking the name from `name` or
+/// `property`. Names are lowercased; contents are entity-decoded.
+pub fn meta_tags(html: &str) -> Vec<(String, String)> {
+    let mut tags = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+        if tag_name(tag) != "meta" {
+            continue;
+        }
+
+        let attributes = attributes(tag);
+        let value = |name: &str| attributes.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone());
+        if let (Some(name), Some(content)) = (value("name").or_else(|| value("property")), value("content")) {
+            tags.push((name.to_lowercase(), decode_entities(content.trim())));
+        }
+    }
+    tags
+}
+
+/// Attributes of a start tag, with lowercased names and unquoted values.
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut chars = tag.char_indices().peekable();
+    // Skip the tag name.
+    while chars.next_if(|(_, c)| !c.is_whitespace()).is_some() {}
+
+    loop {
+        while chars.next_if(|(_, c)| c.is_whitespace() || *c == '/').is_some() {}
+        let Some(&(start, _)) = chars.peek() else {
+            break;
+        };
+        let mut end = start;
+        while let Some((i, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && *c != '=' && *c != '/') {
+            end = i + c.len_utf8();
+        }
+        let name = tag[start..end].to_ascii_lowercase();
+
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if(|(_, c)| *c == '=').is_some() {
+            while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+            match chars.next_if(|(_, c)| *c == '"' || *c == '\'') {
+                Some((_, quote)) => {
+                    for (_, c) in chars.by_ref() {
+                        if c == quote {
+                            break;
+                        }
+                        value.push(c);
+                    }
+                }
+                None => {
+                    while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace()) {
+                        value.push(c);
+                    }
+                }
+            }
+        }
+        if name.is_empty() {
+            // A stray `=` or quote; skip it to make progress.
+            chars.next();
+            continue;
+        }
+        attributes.push((name, value));
+    }
+    attributes
+}
+
+/// Cuts RIS records or BibTeX entries out of surrounding text, e.g. the page
+/// text around a copied export block. Text without either is returned
+/// unchanged.
+pub fn extract_citation(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(|line| line.trim()).collect();
+    let start = lines.iter().position(|line| line.starts_with("TY  -"));
+    let end = lines.iter().rposition(|line| line.starts_with("ER  -"));
+
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => lines[start..=end].join("\n") + "\n",
+        _ => extract_bibtex(text).unwrap_or_else(|| text.to_string()),
+    }
+}
+
+/// The `@type{...}` entries in `text`, each up to its matching closing brace.
+fn extract_bibtex(text: &str) -> Option<String> {
+    let mut entries = Vec::new();
+    let mut rest = text;
+    while let Some(at) = rest.find('@') {
+        let after = &rest[at + 1..];
+        let type_len = after.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(after.len());
+        let open = at + 1 + type_len;
+        if type_len == 0 || !rest[open..].starts_with('{') {
+            rest = after;
+            continue;
+        }
+
+        let mut depth = 0;
+        let close = rest[open..].char_indices().find_map(|(i, c)| {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            (depth == 0).then_some(open + i)
+        });
+        // An entry cut off by the end of the text is left out.
+        let Some(close) = close else {
+            break;
+        };
+        entries.push(&rest[at..=close]);
+        rest = &rest[close + 1..];
+    }
+
+    (!entries.is_empty()).then(|| entries.join("\n\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_markup_and_decodes_entities() {
+        let html = "<html><head><style>p{}</style></head><body>\
+            <pre>@article{doe2020,<br>  title = {Cats &amp; Dogs},<br>}</pre></body></html>";
+        assert_eq!(
+            to_text(html).trim(),
+            "@article{doe2020,\n  title = {Cats & Dogs},\n}"
+        );
+    }
+
+    #[test]
+    fn reads_meta_tags() {
+        let html = r#"<head><META NAME="citation_title" content="Cats &amp; Dogs">
+            <meta property='og:title' content='Cats' />
+            <meta name=citation_author content="Doe, Jane"><meta charset="utf-8"></head>"#;
+        assert_eq!(
+            meta_tags(html),
+            vec![
+                ("citation_title".to_string(), "Cats & Dogs".to_string()),
+                ("og:title".to_string(), "Cats".to_string()),
+                ("citation_author".to_string(), "Doe, Jane".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_entities_alone() {
+        assert_eq!(decode_entities("a &foo; b &#233; &"), "a &foo; b é &");
+    }
+
+    #[test]
+    fn extracts_ris_record_from_page_text() {
+        let text = "Export citation\nTY  - JOUR\nTI  - A title\nER  - \nClose";
+        assert_eq!(extract_citation(text), "TY  - JOUR\nTI  - A title\nER  -\n");
+    }
+
+    #[test]
+    fn extracts_bibtex_entries_from_page_text() {
+        let text = "Cite as jane@example.org\n@article{doe2020,\n  title = {Cats {and} Dogs},\n}\nCopy\n@book{roe, title = {B}}";
+        assert_eq!(
+            extract_citation(text),
+            "@article{doe2020,\n  title = {Cats {and} Dogs},\n}\n\n@book{roe, title = {B}}\n"
+        );
+        assert_eq!(extract_citation("No citation @ all"), "No citation @ all");
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use serde::de::DeserializeOwned;
+use url::Url;
+
+static MAILTO: OnceLock<String> = OnceLock::new();
+
+/// Adds a contact address to the user agent, which gets requests to Crossref
+/// into its faster "polite" pool.
+pub fn set_mailto(mailto: &str) {
+    let _ = MAILTO.set(mailto.to_string());
+}
+
+/// Shared HTTP agent so every request sends the same user agent and timeout.
+pub fn agent() -> ureq::Agent {
+    let user_agent = match MAILTO.get() {
+        Some(mailto) => format!("refrs/{} (mailto:{})", env!("CARGO_PKG_VERSION"), mailto),
+        None => concat!("refrs/", env!("CARGO_PKG_VERSION")).to_string(),
+    };
+    ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(60))
+        .user_agent(&user_agent)
+        .build()
+}
+
+pub fn get_text(url: &str) -> Result<String> {
+    agent()
+        .get(url)
+        .call()
+        .with_context(|| format!("Request to {} failed", url))?
+        .into_string()
+        .with_context(|| format!("Failed to read response from {}", url))
+}
+
+pub fn get_json<T: DeserializeOwned>(url: &str) -> Result<T> {
+    agent()
+        .get(url)
+        .call()
+        .with_context(|| format!("Request to {} failed", url))?
+        .into_json()
+        .with_context(|| format!("Failed to parse JSON from {}", url))
+}
+
+/// `base` with `path` appended, each `/`-separated part of it percent-encoded,
+/// for identifiers such as DOIs that may hold `?`, `#` or `%`.
+pub fn url_with_path(base: &str, path: &str) -> Result<Url> {
+    let mut url = Url::parse(base)?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("{} cannot take a path", base))?
+        .pop_if_empty()
+        .extend(path.split('/'));
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::url_with_path;
+
+    #[test]
+    fn encodes_each_part_of_the_path() {
+        let url = url_with_path("https://api.example.org/graph/v1", "paper/DOI:10.1002/(SICI)1097#x?y z").unwrap();
+        assert_eq!(url.as_str(), "https://api.example.org/graph/v1/paper/DOI:10.1002/(SICI)1097%23x%3Fy%20z");
+    }
+}
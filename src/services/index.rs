@@ -0,0 +1,170 @@
+//! A cache of the parsed entries of a project, so that listing and searching
+//! a large library doesn't re-read every `.ris` file. Files are parsed again
+//! when their size or modification time changes.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::model::ris::{parse_ris, RisEntry};
+use crate::services::encoding;
+use crate::state;
+
+/// Bumped when the cached data changes shape, which discards old indexes.
+const VERSION: u32 = 1;
+
+/// Files changed more recently than this are not cached, as a second change
+/// within the file system's timestamp resolution would go unnoticed.
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize)]
+pub struct Index {
+    version: u32,
+    files: HashMap<PathBuf, CachedFile>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+/// The entries of one file, and what to cache of them if they were parsed.
+enum Read {
+    Cached(Vec<RisEntry>),
+    Parsed(Vec<RisEntry>, Option<CachedFile>),
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedFile {
+    len: u64,
+    modified: SystemTime,
+    entries: Vec<RisEntry>,
+}
+
+/// Where the index of a project is kept: in the data directory, so that it
+/// stays out of the project's repository.
+pub fn index_path(project_path: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    Path::new(project_path).hash(&mut hasher);
+    state::data_dir().join("index").join(format!("{:016x}.json", hasher.finish()))
+}
+
+impl Index {
+    fn new() -> Self {
+        Index { version: VERSION, files: HashMap::new(), changed: false }
+    }
+
+    /// Reads the cached parses at `path`. Without a usable cache every file
+    /// is parsed again on the next [`Index::entries`].
+    pub fn load(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Index>(&bytes).ok())
+            .filter(|index| index.version == VERSION)
+            .unwrap_or_else(Index::new)
+    }
+
+    /// The entries in each of `files`, in order. Unchanged files come from
+    /// the index; the others are read and parsed in parallel.
+    pub fn entries(&mut self, files: &[PathBuf]) -> Result<Vec<Vec<RisEntry>>> {
+        let read = files
+            .par_iter()
+            .map(|file| self.read(file))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut entries = Vec::with_capacity(files.len());
+        for (file, read) in files.iter().zip(read) {
+            match read {
+                Read::Cached(cached) => entries.push(cached),
+                Read::Parsed(parsed, Some(cached)) => {
+                    entries.push(parsed);
+                    self.files.insert(file.clone(), cached);
+                    self.changed = true;
+                }
+                Read::Parsed(parsed, None) => {
+                    entries.push(parsed);
+                    self.changed |= self.files.remove(file).is_some();
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    fn read(&self, file: &Path) -> Result<Read> {
+        let metadata = fs::metadata(file)?;
+        let modified = metadata.modified()?;
+        if let Some(cached) = self.files.get(file) {
+            if cached.len == metadata.len() && cached.modified == modified {
+                return Ok(Read::Cached(cached.entries.clone()));
+            }
+        }
+
+        let (content, _) = encoding::read_text(file)?;
+        let entries = parse_ris(&content)?;
+        let settled = modified.elapsed().is_ok_and(|age| age >= SETTLE_TIME);
+        let cached = settled.then(|| CachedFile { len: metadata.len(), modified, entries: entries.clone() });
+        Ok(Read::Parsed(entries, cached))
+    }
+
+    /// Drops the files not in `files`, which were removed from the project.
+    pub fn retain(&mut self, files: &[PathBuf]) {
+        let files: HashSet<&PathBuf> = files.iter().collect();
+        let before = self.files.len();
+        self.files.retain(|path, _| files.contains(path));
+        self.changed |= self.files.len() != before;
+    }
+
+    /// Writes the index to `path` if anything changed since it was loaded.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Other processes may read the index at the same time.
+        let temp_file = path.with_extension(format!("json.{}.tmp", process::id()));
+        fs::write(&temp_file, serde_json::to_vec(self)?)?;
+        fs::rename(&temp_file, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reparses_only_changed_files() {
+        let dir = std::env::temp_dir().join(format!("refrs-index-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (file, index_file) = (dir.join("doe.ris"), dir.join("index.json"));
+        fs::write(&file, "TY  - JOUR\nTI  - Cats\nER  -\n").unwrap();
+        let old = SystemTime::now() - Duration::from_secs(60);
+        fs::File::options().write(true).open(&file).unwrap().set_modified(old).unwrap();
+
+        let files = vec![file.clone()];
+        let title = |index: &mut Index| index.entries(&files).unwrap()[0][0].get_field("TI").cloned();
+        let mut index = Index::load(&index_file);
+        assert_eq!(title(&mut index).as_deref(), Some("Cats"));
+        index.save(&index_file).unwrap();
+
+        // A cached file is not read again, so a change that keeps the size
+        // and time goes unseen; any other change is picked up.
+        fs::write(&file, "TY  - JOUR\nTI  - Dogs\nER  -\n").unwrap();
+        fs::File::options().write(true).open(&file).unwrap().set_modified(old).unwrap();
+        let mut index = Index::load(&index_file);
+        assert_eq!(title(&mut index).as_deref(), Some("Cats"));
+        fs::write(&file, "TY  - JOUR\nTI  - Birds\nER  -\n").unwrap();
+        assert_eq!(title(&mut index).as_deref(), Some("Birds"));
+
+        index.retain(&[]);
+        assert!(index.files.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
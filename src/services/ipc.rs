@@ -0,0 +1,166 @@
+use std::path::PathBuf;
+use std::sync::mpsc;
+#[cfg(unix)]
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::services::daemon::DaemonStatus;
+use crate::state;
+
+/// How long either side waits to send or read a request.
+#[cfg(unix)]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a client waits for the answer, which may follow a full sync.
+#[cfg(unix)]
+const REPLY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A request sent to a running daemon, one JSON object per line.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    Status,
+    Sync,
+    Import { path: String },
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Response {
+    pub ok: bool,
+    pub message: String,
+    pub status: Option<DaemonStatus>,
+}
+
+impl Response {
+    pub fn ok(message: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            message: message.into(),
+            status: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            message: message.into(),
+            status: None,
+        }
+    }
+}
+
+/// A request received by the listener, waiting for the daemon to answer it.
+pub struct Incoming {
+    pub request: Request,
+    reply: mpsc::Sender<Response>,
+}
+
+impl Incoming {
+    pub fn respond(self, response: Response) {
+        let _ = self.reply.send(response);
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    state::data_dir().join("refrs.sock")
+}
+
+/// Binds the control socket and forwards requests to the returned channel.
+/// Each connection blocks until the request has been answered.
+#[cfg(unix)]
+pub fn listen() -> Result<mpsc::Receiver<Incoming>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixListener;
+
+    let path = socket_path();
+    std::fs::create_dir_all(state::data_dir())?;
+    // A socket left behind by a daemon that was killed would make bind fail.
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().filter_map(|s| s.ok()) {
+            // A client that connects without sending anything must not hold
+            // up the others.
+            let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+            let _ = stream.set_write_timeout(Some(REQUEST_TIMEOUT));
+            let mut line = String::new();
+            let mut reader = BufReader::new(&stream);
+            if reader.read_line(&mut line).is_err() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<Request>(&line) {
+                Ok(request) => {
+                    let (reply, answer) = mpsc::channel();
+                    if sender.send(Incoming { request, reply }).is_err() {
+                        return;
+                    }
+                    answer
+                        .recv()
+                        .unwrap_or_else(|_| Response::error("The daemon is shutting down"))
+                }
+                Err(err) => Response::error(format!("Invalid request: {}", err)),
+            };
+
+            if let Ok(json) = serde_json::to_string(&response) {
+                let mut stream = &stream;
+                let _ = writeln!(stream, "{}", json);
+            }
+        }
+    });
+
+    Ok(receiver)
+}
+
+#[cfg(not(unix))]
+pub fn listen() -> Result<mpsc::Receiver<Incoming>> {
+    anyhow::bail!("The control socket is only supported on Unix")
+}
+
+/// Sends a request to the running daemon. Returns `None` when no daemon is
+/// listening, so callers can fall back to doing the work themselves.
+#[cfg(unix)]
+pub fn send(request: &Request) -> Result<Option<Response>> {
+    use anyhow::Context;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let Ok(mut stream) = UnixStream::connect(socket_path()) else {
+        return Ok(None);
+    };
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+    writeln!(stream, "{}", serde_json::to_string(request)?).context("Failed to send the request to the daemon")?;
+
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .context("The daemon did not answer in time")?;
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+#[cfg(not(unix))]
+pub fn send(_request: &Request) -> Result<Option<Response>> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_tagged_by_command() {
+        let json = serde_json::to_string(&Request::Import {
+            path: "a.bib".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"command":"import","path":"a.bib"}"#);
+
+        let parsed: Request = serde_json::from_str(r#"{"command":"status"}"#).unwrap();
+        assert!(matches!(parsed, Request::Status));
+    }
+}
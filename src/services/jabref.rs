@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+
+use crate::model::ris::{chunks_to_string, RisEntry};
+use crate::services::tags;
+
+/// Escapes a group name the way JabRef quotes its metadata values.
+fn quote(name: &str) -> String {
+    name.replace('\\', "\\\\").replace(';', r"\;")
+}
+
+/// Builds the `@Comment{jabref-meta: ...}` blocks that make JabRef show one
+/// keyword group per distinct keyword in the exported entries.
+pub fn groups_comment(entries: &[&RisEntry]) -> String {
+    // Keyed case-insensitively, keeping the first spelling seen.
+    let mut keywords: BTreeMap<String, String> = BTreeMap::new();
+    for entry in entries {
+        for keyword in entry.fields.get("KW").into_iter().flatten() {
+            let keyword = keyword.trim();
+            if !keyword.is_empty() {
+                keywords
+                    .entry(keyword.to_lowercase())
+                    .or_insert_with(|| keyword.to_string());
+            }
+        }
+    }
+
+    let mut lines = vec![
+        "@Comment{jabref-meta: databaseType:bibtex;}".to_string(),
+        String::new(),
+        "@Comment{jabref-meta: grouping:".to_string(),
+        "0 AllEntriesGroup:;".to_string(),
+    ];
+    for keyword in keywords.values() {
+        let name = quote(keyword);
+        lines.push(format!(
+            r"1 KeywordGroup:{name}\;0\;keywords\;{name}\;0\;0\;1\;0x8a8a8aff\;\;\;;"
+        ));
+    }
+    lines.push("}".to_string());
+
+    lines.join("\n")
+}
+
+/// Adds the JabRef groups an entry is assigned to, kept in its `groups`
+/// field, as tags.
+pub fn add_groups(bibtex_entry: &biblatex::Entry, entry: &mut RisEntry) {
+    let groups = bibtex_entry.fields.get("groups").map(chunks_to_string).unwrap_or_default();
+    for group in groups.split(',') {
+        tags::add_tag(entry, group);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_groups, groups_comment};
+    use crate::model::ris::{parse_ris, RisEntry};
+
+    #[test]
+    fn test_groups_comment_has_one_group_per_keyword() {
+        let entries = parse_ris(
+            "TY  - JOUR\nKW  - Databases\nKW  - Query Optimization\nER  -\nTY  - JOUR\nKW  - databases\nER  -",
+        )
+        .unwrap();
+        let refs: Vec<_> = entries.iter().collect();
+
+        let comment = groups_comment(&refs);
+        assert!(comment.contains("0 AllEntriesGroup:;"));
+        assert!(comment.contains(
+            r"1 KeywordGroup:Databases\;0\;keywords\;Databases\;0\;0\;1\;0x8a8a8aff\;\;\;;"
+        ));
+        assert!(comment.contains(r"1 KeywordGroup:Query Optimization\;"));
+        assert_eq!(comment.matches("KeywordGroup").count(), 2);
+    }
+
+    #[test]
+    fn test_groups_become_tags() {
+        let bibliography =
+            biblatex::Bibliography::parse("@article{k, keywords = {thesis}, groups = {Thesis, Reading list}}").unwrap();
+        let bibtex_entry = bibliography.iter().next().unwrap();
+        let mut entry = RisEntry::from(bibtex_entry);
+        add_groups(bibtex_entry, &mut entry);
+        assert_eq!(entry.fields["KW"], vec!["thesis", "Reading list"]);
+    }
+}
@@ -0,0 +1,426 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{bail, Result};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+use crate::config::{self, ProjectConfig};
+use crate::model::ris::{parse_ris, RisEntry};
+use crate::services::encoding;
+use crate::services::index::{self, Index};
+use crate::services::serialization::KEY_TAG;
+
+/// Custom RIS field pointing to copies of the same work in other projects,
+/// as `<project folder>/<id>`.
+pub const LINK_TAG: &str = "C6";
+
+/// A parsed RIS entry together with the file it was read from.
+#[derive(Clone)]
+pub struct LibraryEntry {
+    /// File stem of the `.ris` file.
+    pub id: String,
+    pub path: PathBuf,
+    pub entry: RisEntry,
+}
+
+impl LibraryEntry {
+    /// Citation key used on export: the key pinned in the `ID` tag, or the
+    /// file stem for entries that have none.
+    pub fn key(&self) -> &str {
+        self.entry
+            .get_field(KEY_TAG)
+            .map(|key| key.trim())
+            .filter(|key| !key.is_empty())
+            .unwrap_or(&self.id)
+    }
+}
+
+/// Name of the folder inside a project that holds the `.ris` files,
+/// `ris_files` unless the project's `.refrs.toml` sets `ris_folder`.
+pub fn ris_folder(project_path: &str) -> Result<String> {
+    Ok(config::load(project_path)?.ris_folder)
+}
+
+pub fn ris_folder_path(project_path: &str) -> Result<PathBuf> {
+    Ok(Path::new(project_path).join(ris_folder(project_path)?))
+}
+
+/// Where a new entry is written as `<stem>.ris`: in the project's `.ris`
+/// folder, or a subfolder of it when the project's layout asks for one.
+pub fn entry_path(project_path: &str, config: &ProjectConfig, stem: &str, entry: &RisEntry) -> PathBuf {
+    Path::new(project_path)
+        .join(&config.ris_folder)
+        .join(config.entry_folder(entry))
+        .join(format!("{}.ris", stem))
+}
+
+/// The `.ris` files in the project's folder and its subfolders. A missing
+/// folder has none. Hidden folders such as `.git` are skipped, and so are
+/// symlinked folders, which could lead outside the project or in circles.
+pub fn ris_files(project_path: &str) -> Result<Vec<PathBuf>> {
+    fn collect(folder: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        for dir_entry in fs::read_dir(folder)? {
+            let dir_entry = dir_entry?;
+            let path = dir_entry.path();
+            if dir_entry.file_type()?.is_dir() {
+                if !dir_entry.file_name().to_string_lossy().starts_with('.') {
+                    collect(&path, files)?;
+                }
+            } else if path.extension().is_some_and(|ext| ext == "ris") && path.is_file() {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    let folder = ris_folder_path(project_path)?;
+    if folder.exists() {
+        collect(&folder, &mut files)?;
+    }
+    Ok(files)
+}
+
+/// File stems of the `.ris` files in the project, which are the ids of its entries.
+pub fn file_stems(project_path: &str) -> Result<HashSet<String>> {
+    Ok(ris_files(project_path)?
+        .iter()
+        .filter_map(|path| path.file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Reads every `.ris` file in the project, also ones in Latin-1 or UTF-16
+/// that were copied in by hand. Unchanged files come from the project's
+/// [`Index`] instead, and the others are parsed in parallel. A missing
+/// folder yields an empty library.
+pub fn load_entries(project_path: &str) -> Result<Vec<LibraryEntry>> {
+    let index_path = index::index_path(project_path);
+    let mut index = Index::load(&index_path);
+    let files = ris_files(project_path)?;
+
+    let mut entries = Vec::new();
+    for (path, parsed) in files.iter().zip(index.entries(&files)?) {
+        let id = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        for entry in parsed {
+            entries.push(LibraryEntry {
+                id: id.clone(),
+                path: path.clone(),
+                entry,
+            });
+        }
+    }
+
+    index.retain(&files);
+    // The index only saves time; a library that can't be cached still loads.
+    let _ = index.save(&index_path);
+
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(entries)
+}
+
+/// A value that changes whenever a `.ris` file in the project is added,
+/// removed or modified, from the names, sizes and modification times of the
+/// files. Cheap enough to poll.
+pub fn fingerprint(project_path: &str) -> u64 {
+    let mut files: Vec<(PathBuf, u64, Option<SystemTime>)> = ris_files(project_path)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+            Some((path, metadata.len(), metadata.modified().ok()))
+        })
+        .collect();
+    files.sort();
+
+    let mut hasher = DefaultHasher::new();
+    files.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Rewrites the file `entry` was read from with `replacement` in its place,
+/// keeping the other entries the file holds. Without a replacement the entry
+/// is dropped, and a file left without entries is removed.
+pub fn replace_entry(entry: &LibraryEntry, replacement: Option<&RisEntry>) -> Result<()> {
+    let (content, _) = encoding::read_text(&entry.path)?;
+    let mut entries = parse_ris(&content)?;
+    let original = entry.entry.to_string();
+    let Some(position) = entries.iter().position(|other| other.to_string() == original) else {
+        bail!("{} no longer holds the entry {}", entry.path.display(), entry.id);
+    };
+
+    match replacement {
+        Some(replacement) => entries[position] = replacement.clone(),
+        None => {
+            entries.remove(position);
+        }
+    }
+
+    if entries.is_empty() {
+        fs::remove_file(&entry.path)?;
+    } else {
+        let content: Vec<String> = entries.iter().map(|other| other.to_string()).collect();
+        fs::write(&entry.path, content.join("\n"))?;
+    }
+    Ok(())
+}
+
+pub fn find_entry<'a>(entries: &'a [LibraryEntry], id: &str) -> Option<&'a LibraryEntry> {
+    entries.iter().find(|e| e.id == id)
+}
+
+/// Entries fuzzily matching `query` on key, authors, year and title, best
+/// match first. An exact key or file stem is the only result.
+pub fn fuzzy_find<'a>(entries: &'a [LibraryEntry], query: &str) -> Vec<&'a LibraryEntry> {
+    let query = query.trim();
+    if let Some(exact) = entries.iter().find(|e| e.key() == query || e.id == query) {
+        return vec![exact];
+    }
+
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let mut scored: Vec<(i64, &LibraryEntry)> = entries
+        .iter()
+        .filter_map(|e| {
+            let field = |tag: &str| e.entry.fields.get(tag).map(|v| v.join(" ")).unwrap_or_default();
+            let haystack = format!("{} {} {} {}", e.key(), field("AU"), field("PY"), field("TI"));
+            let haystack = deunicode::deunicode(&haystack);
+            // Every word has to match somewhere, in any order.
+            query
+                .split_whitespace()
+                .map(|word| matcher.fuzzy_match(&haystack, &deunicode::deunicode(word)))
+                .sum::<Option<i64>>()
+                .map(|score| (score, e))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.id.cmp(&b.1.id)));
+    scored.into_iter().map(|(_, e)| e).collect()
+}
+
+/// Index pairs of entries that describe the same work.
+pub fn duplicate_pairs(entries: &[LibraryEntry]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for i in 0..entries.len() {
+        for j in (i + 1)..entries.len() {
+            if is_duplicate(&entries[i].entry, &entries[j].entry) {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+/// Groups of `(project index, entry index)` describing the same work in at
+/// least two different projects. Duplicates within one project are left to
+/// [`duplicate_pairs`].
+pub fn cross_project_groups(projects: &[Vec<LibraryEntry>]) -> Vec<Vec<(usize, usize)>> {
+    let all: Vec<(usize, usize)> = projects
+        .iter()
+        .enumerate()
+        .flat_map(|(p, entries)| (0..entries.len()).map(move |e| (p, e)))
+        .collect();
+    let entry = |(p, e): (usize, usize)| &projects[p][e].entry;
+
+    let mut groups: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut grouped = vec![false; all.len()];
+    for i in 0..all.len() {
+        if grouped[i] {
+            continue;
+        }
+        let mut group = vec![all[i]];
+        for j in (i + 1)..all.len() {
+            let other_project = group.iter().all(|member| member.0 != all[j].0);
+            if !grouped[j] && other_project && is_duplicate(entry(all[i]), entry(all[j])) {
+                grouped[j] = true;
+                group.push(all[j]);
+            }
+        }
+        if group.len() > 1 {
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+/// Lowercased title with everything but letters and digits removed.
+fn normalized_title(entry: &RisEntry) -> Option<String> {
+    let title: String = entry
+        .get_field("TI")?
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    (!title.is_empty()).then_some(title)
+}
+
+fn normalized_doi(entry: &RisEntry) -> Option<String> {
+    let doi = entry.get_field("DO")?.trim().to_lowercase();
+    (!doi.is_empty()).then_some(doi)
+}
+
+/// Two entries describe the same work if their DOIs match, or if they have no
+/// conflicting DOIs and their titles (and years, when both are known) match.
+pub fn is_duplicate(a: &RisEntry, b: &RisEntry) -> bool {
+    if let (Some(x), Some(y)) = (normalized_doi(a), normalized_doi(b)) {
+        return x == y;
+    }
+
+    let same_title = matches!(
+        (normalized_title(a), normalized_title(b)),
+        (Some(x), Some(y)) if x == y
+    );
+    let same_year = match (a.get_field("PY"), b.get_field("PY")) {
+        (Some(x), Some(y)) => x.trim() == y.trim(),
+        _ => true,
+    };
+
+    same_title && same_year
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cross_project_groups, fingerprint, fuzzy_find, is_duplicate, replace_entry, ris_files, ris_folder_path,
+        LibraryEntry,
+    };
+    use crate::model::ris::parse_ris;
+
+    fn entry(content: &str) -> crate::model::ris::RisEntry {
+        parse_ris(content).unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_is_duplicate_by_doi() {
+        let a = entry("TY  - JOUR\nTI  - One title\nDO  - 10.1/ABC\nER  -");
+        let b = entry("TY  - JOUR\nTI  - Another title\nDO  - 10.1/abc\nER  -");
+        assert!(is_duplicate(&a, &b));
+    }
+
+    #[test]
+    fn test_is_duplicate_by_title_and_year() {
+        let a = entry("TY  - JOUR\nTI  - Parametric Query Optimization\nPY  - 1997\nER  -");
+        let b = entry("TY  - CONF\nTI  - Parametric query optimization.\nPY  - 1997\nER  -");
+        let c = entry("TY  - CONF\nTI  - Parametric query optimization\nPY  - 1992\nER  -");
+        assert!(is_duplicate(&a, &b));
+        assert!(!is_duplicate(&a, &c));
+    }
+
+    #[test]
+    fn test_is_not_duplicate_with_different_dois() {
+        let a = entry("TY  - JOUR\nTI  - Same\nDO  - 10.1/a\nER  -");
+        let b = entry("TY  - JOUR\nTI  - Same\nDO  - 10.1/b\nER  -");
+        assert!(!is_duplicate(&a, &b));
+    }
+
+    #[test]
+    fn test_cross_project_groups_skip_same_project() {
+        let library = |ids: &[(&str, &str)]| -> Vec<LibraryEntry> {
+            ids.iter()
+                .map(|(id, doi)| LibraryEntry {
+                    id: id.to_string(),
+                    path: format!("{}.ris", id).into(),
+                    entry: entry(&format!("TY  - JOUR\nTI  - {}\nDO  - {}\nER  -", id, doi)),
+                })
+                .collect()
+        };
+        let projects = vec![
+            library(&[("a", "10.1/x"), ("b", "10.1/x")]),
+            library(&[("c", "10.1/X"), ("d", "10.1/y")]),
+        ];
+
+        assert_eq!(cross_project_groups(&projects), vec![vec![(0, 0), (1, 0)]]);
+    }
+
+    #[test]
+    fn test_fuzzy_find_matches_words_in_any_order() {
+        let library: Vec<LibraryEntry> = [
+            ("godel_latin_1931", "TY  - JOUR\nAU  - Gödel, Kurt\nTI  - Latin Test\nPY  - 1931\nER  -"),
+            ("doe_cats_2020", "TY  - JOUR\nAU  - Doe, Jane\nTI  - Cats\nPY  - 2020\nID  - doe2020\nER  -"),
+        ]
+        .iter()
+        .map(|(id, content)| LibraryEntry {
+            id: id.to_string(),
+            path: format!("{}.ris", id).into(),
+            entry: entry(content),
+        })
+        .collect();
+
+        let ids = |query: &str| -> Vec<String> { fuzzy_find(&library, query).iter().map(|e| e.id.clone()).collect() };
+        assert_eq!(ids("1931 godel"), vec!["godel_latin_1931"]);
+        assert_eq!(ids("doe2020"), vec!["doe_cats_2020"]);
+        assert!(ids("zebra").is_empty());
+    }
+
+    #[test]
+    fn test_fingerprint_changes_with_ris_files() {
+        let project = std::env::temp_dir().join(format!("refrs-fingerprint-{}", std::process::id()));
+        let project = project.to_str().unwrap();
+        let folder = ris_folder_path(project).unwrap();
+        std::fs::create_dir_all(&folder).unwrap();
+
+        let empty = fingerprint(project);
+        std::fs::write(folder.join("notes.txt"), "ignored").unwrap();
+        assert_eq!(fingerprint(project), empty);
+        std::fs::write(folder.join("doe.ris"), "TY  - JOUR\nER  -\n").unwrap();
+        let added = fingerprint(project);
+        assert_ne!(added, empty);
+        std::fs::write(folder.join("doe.ris"), "TY  - BOOK\nTI  - Cats\nER  -\n").unwrap();
+        assert_ne!(fingerprint(project), added);
+
+        std::fs::remove_dir_all(project).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ris_files_skip_hidden_and_symlinked_folders() {
+        let project = std::env::temp_dir().join(format!("refrs-ris-files-{}", std::process::id()));
+        let project = project.to_str().unwrap();
+        let folder = ris_folder_path(project).unwrap();
+        for sub in ["chapter", ".git"] {
+            std::fs::create_dir_all(folder.join(sub)).unwrap();
+            std::fs::write(folder.join(sub).join("doe.ris"), "TY  - JOUR\nER  -\n").unwrap();
+        }
+        std::os::unix::fs::symlink(&folder, folder.join("loop")).unwrap();
+
+        assert_eq!(ris_files(project).unwrap(), vec![folder.join("chapter").join("doe.ris")]);
+
+        std::fs::remove_dir_all(project).unwrap();
+    }
+
+    #[test]
+    fn test_replace_entry_keeps_the_rest_of_the_file() {
+        let folder = std::env::temp_dir().join(format!("refrs-replace-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let path = folder.join("both.ris");
+        std::fs::write(&path, "TY  - JOUR\nTI  - Cats\nER  -\nTY  - BOOK\nTI  - Dogs\nER  -\n").unwrap();
+        let stored = |entry| LibraryEntry { id: "both".to_string(), path: path.clone(), entry };
+
+        let cats = stored(entry("TY  - JOUR\nTI  - Cats\nER  -"));
+        replace_entry(&cats, Some(&entry("TY  - JOUR\nTI  - Cats and kittens\nER  -"))).unwrap();
+        let titles = |path| -> Vec<String> {
+            parse_ris(&std::fs::read_to_string(path).unwrap())
+                .unwrap()
+                .iter()
+                .map(|e| e.get_field("TI").unwrap().clone())
+                .collect()
+        };
+        assert_eq!(titles(&path), vec!["Cats and kittens", "Dogs"]);
+
+        replace_entry(&stored(entry("TY  - BOOK\nTI  - Dogs\nER  -")), None).unwrap();
+        assert_eq!(titles(&path), vec!["Cats and kittens"]);
+        assert!(replace_entry(&cats, None).is_err());
+        replace_entry(&stored(entry("TY  - JOUR\nTI  - Cats and kittens\nER  -")), None).unwrap();
+        assert!(!path.exists());
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+}
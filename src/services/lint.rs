@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::model::date::Date;
+use crate::model::ris::{parse_ris, ReferenceType, RisEntry};
+use crate::services::library::{self, LibraryEntry};
+use crate::services::{doi, encoding};
+
+/// Something wrong with an entry or a `.ris` file.
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    /// The file does not parse as RIS.
+    Unreadable(String),
+    /// The file holds no entries.
+    EmptyFile,
+    /// A field the entry's type needs, by its main tag.
+    Missing(&'static str),
+    MalformedDoi(String),
+    MalformedIssn(String),
+    /// The citation key is also the key of the listed other entries.
+    DuplicateKey { key: String, others: Vec<String> },
+}
+
+impl Problem {
+    /// Whether `fix` and `lint --fix` repair this problem.
+    pub fn fixable(&self) -> bool {
+        match self {
+            Problem::EmptyFile => true,
+            Problem::MalformedDoi(value) => doi::find(value).is_some(),
+            Problem::MalformedIssn(value) => normalize_issn(value).is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// A problem found in the file at `path`, whose stem is `id`.
+pub struct Issue {
+    pub id: String,
+    pub path: PathBuf,
+    pub problem: Problem,
+}
+
+/// Tags that satisfy a required field. `PY` is checked through the date.
+const ALTERNATIVES: &[(&str, &[&str])] = &[
+    ("TI", &["TI", "T1"]),
+    ("AU", &["AU", "A1", "ED"]),
+    ("T2", &["T2", "JO", "JF", "JA", "BT"]),
+    ("PB", &["PB"]),
+];
+
+fn required(ty: &ReferenceType) -> &'static [&'static str] {
+    match ty {
+        ReferenceType::Journal
+        | ReferenceType::MagazineArticle
+        | ReferenceType::Newspaper
+        | ReferenceType::ElectronicArticle
+        | ReferenceType::ConferencePaper => &["TI", "AU", "PY", "T2"],
+        ReferenceType::Book | ReferenceType::ElectronicBook | ReferenceType::Thesis => {
+            &["TI", "AU", "PY", "PB"]
+        }
+        ReferenceType::Report => &["TI", "AU", "PY"],
+        _ => &["TI"],
+    }
+}
+
+fn is_journal(ty: &ReferenceType) -> bool {
+    matches!(
+        ty,
+        ReferenceType::Journal | ReferenceType::MagazineArticle | ReferenceType::ElectronicArticle
+    )
+}
+
+/// `1234-567X` from an ISSN written without the hyphen, with spaces or a
+/// lowercase check character, if its check digit is right.
+fn normalize_issn(value: &str) -> Option<String> {
+    let chars: Vec<char> = value
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if chars.len() != 8 || !chars[..7].iter().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let sum: u32 = chars[..7]
+        .iter()
+        .zip((2..=8).rev())
+        .map(|(c, weight)| c.to_digit(10).unwrap() * weight)
+        .sum();
+    let check = match (11 - sum % 11) % 11 {
+        10 => 'X',
+        digit => char::from_digit(digit, 10).unwrap(),
+    };
+    if chars[7] != check {
+        return None;
+    }
+    let digits: String = chars.iter().collect();
+    Some(format!("{}-{}", &digits[..4], &digits[4..]))
+}
+
+/// The problems of a single entry: missing required fields and malformed
+/// identifiers.
+pub fn check_entry(entry: &RisEntry) -> Vec<Problem> {
+    let has = |tags: &[&str]| {
+        tags.iter()
+            .any(|tag| entry.get_field(tag).is_some_and(|value| !value.trim().is_empty()))
+    };
+
+    let mut problems = Vec::new();
+    for &tag in required(&entry.ty) {
+        let present = match tag {
+            "PY" => Date::of(entry).is_some(),
+            _ => ALTERNATIVES
+                .iter()
+                .find(|(main, _)| *main == tag)
+                .is_some_and(|(_, tags)| has(tags)),
+        };
+        if !present {
+            problems.push(Problem::Missing(tag));
+        }
+    }
+
+    for value in entry.fields.get("DO").into_iter().flatten() {
+        if doi::find(value).as_deref() != Some(value.as_str()) {
+            problems.push(Problem::MalformedDoi(value.clone()));
+        }
+    }
+    // Journals often have a print and an electronic ISSN.
+    for value in entry.fields.get("SN").into_iter().flatten().filter(|_| is_journal(&entry.ty)) {
+        if normalize_issn(value).as_deref() != Some(value.as_str()) {
+            problems.push(Problem::MalformedIssn(value.clone()));
+        }
+    }
+    problems
+}
+
+/// Repairs what can be repaired in `entry`: DOIs given as links or with a
+/// `doi:` prefix, and ISSNs missing the hyphen. Each value is repaired where
+/// it is, so the others are kept. Returns whether it changed.
+pub fn fix(entry: &mut RisEntry) -> bool {
+    let doi = repair_values(entry, "DO", doi::find);
+    let issn = is_journal(&entry.ty) && repair_values(entry, "SN", normalize_issn);
+    doi || issn
+}
+
+/// Replaces each value of `tag` that `repair` knows a better form of.
+fn repair_values(entry: &mut RisEntry, tag: &str, repair: impl Fn(&str) -> Option<String>) -> bool {
+    let mut changed = false;
+    for value in entry.fields.get_mut(tag).into_iter().flatten() {
+        if let Some(fixed) = repair(value).filter(|fixed| fixed != value) {
+            *value = fixed;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Checks every `.ris` file in the project. Unlike `library::load_entries`
+/// a file that does not parse is reported rather than an error.
+pub fn check(project_path: &str) -> Result<Vec<Issue>> {
+    let mut issues = Vec::new();
+    let mut keys: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut entries = Vec::new();
+
+    for path in library::ris_files(project_path)? {
+        let id = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (content, _) = encoding::read_text(&path)?;
+        let problem = match parse_ris(&content) {
+            Err(err) => Some(Problem::Unreadable(format!("{:#}", err))),
+            Ok(parsed) if parsed.is_empty() => Some(Problem::EmptyFile),
+            Ok(parsed) => {
+                for entry in parsed {
+                    entries.push(LibraryEntry { id: id.clone(), path: path.clone(), entry });
+                }
+                None
+            }
+        };
+        if let Some(problem) = problem {
+            issues.push(Issue { id, path, problem });
+        }
+    }
+
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    for (i, entry) in entries.iter().enumerate() {
+        keys.entry(entry.key().to_string()).or_default().push(i);
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        for problem in check_entry(&entry.entry) {
+            issues.push(Issue { id: entry.id.clone(), path: entry.path.clone(), problem });
+        }
+        let key = entry.key().to_string();
+        let others: Vec<String> = keys[&key].iter().filter(|j| **j != i).map(|j| entries[*j].id.clone()).collect();
+        if !others.is_empty() {
+            issues.push(Issue {
+                id: entry.id.clone(),
+                path: entry.path.clone(),
+                problem: Problem::DuplicateKey { key, others },
+            });
+        }
+    }
+
+    issues.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_missing_fields_and_malformed_identifiers() {
+        let entry = parse_ris("TY  - JOUR\nTI  - T\nPY  - 2020\nDO  - https://doi.org/10.1000/x\nSN  - 0378 5955\nER  -")
+            .unwrap()
+            .remove(0);
+        let problems = check_entry(&entry);
+        assert_eq!(
+            problems,
+            vec![
+                Problem::Missing("AU"),
+                Problem::Missing("T2"),
+                Problem::MalformedDoi("https://doi.org/10.1000/x".to_string()),
+                Problem::MalformedIssn("0378 5955".to_string()),
+            ]
+        );
+        assert!(!problems[0].fixable());
+        assert!(problems[2].fixable() && problems[3].fixable());
+    }
+
+    #[test]
+    fn fixes_dois_and_issns() {
+        let mut entry = parse_ris("TY  - JOUR\nDO  - doi:10.1000/x\nSN  - 1234-5679\nSN  - 0949877x\nER  -")
+            .unwrap()
+            .remove(0);
+        assert_eq!(check_entry(&entry).iter().filter(|problem| problem.fixable()).count(), 2);
+        assert!(fix(&mut entry));
+        assert_eq!(entry.fields["DO"], vec!["10.1000/x"]);
+        assert_eq!(entry.fields["SN"], vec!["1234-5679", "0949-877X"]);
+        assert!(!fix(&mut entry));
+        assert_eq!(normalize_issn("0949-8770"), None);
+    }
+}
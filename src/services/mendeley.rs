@@ -0,0 +1,44 @@
+use crate::model::ris::{chunks_to_string, RisEntry, PROTECTED_TAG};
+use crate::services::tags;
+
+/// Whether `text` is a BibTeX export of Mendeley Desktop, which starts with
+/// a note saying so and keeps Mendeley's own tags and folders in fields.
+pub fn is_export(text: &str) -> bool {
+    text.contains("generated by Mendeley Desktop") || text.contains("mendeley-tags") || text.contains("mendeley-groups")
+}
+
+/// Undoes what Mendeley does to its BibTeX: its tags (`mendeley-tags`) and
+/// folders (`mendeley-groups`) become keywords, and the braces it puts around
+/// every title are not taken as protecting the case of the whole title.
+pub fn clean(bibtex_entry: &biblatex::Entry, entry: &mut RisEntry) {
+    for field in ["mendeley-tags", "mendeley-groups"] {
+        let value = bibtex_entry.fields.get(field).map(chunks_to_string).unwrap_or_default();
+        for name in value.split(',') {
+            tags::add_tag(entry, name);
+        }
+    }
+
+    if entry.fields.get(PROTECTED_TAG).map(Vec::as_slice) == entry.fields.get("TI").map(Vec::as_slice) {
+        entry.fields.remove(PROTECTED_TAG);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use biblatex::Bibliography;
+
+    #[test]
+    fn keeps_tags_and_folders_and_unprotects_titles() {
+        let text = "Automatically generated by Mendeley Desktop 1.19.8\n\n@article{Pugh1990,\nkeywords = {searching},\nmendeley-groups = {Thesis/Chapter 2,Reading list},\nmendeley-tags = {toread,Searching},\ntitle = {{Skip lists}}\n}\n";
+        assert!(is_export(text));
+        let bibliography = Bibliography::parse(text).unwrap();
+        let bibtex_entry = bibliography.iter().next().unwrap();
+        let mut entry = RisEntry::from(bibtex_entry);
+        assert!(entry.fields.contains_key(PROTECTED_TAG));
+
+        clean(bibtex_entry, &mut entry);
+        assert_eq!(entry.fields["KW"], vec!["searching", "toread", "Thesis/Chapter 2", "Reading list"]);
+        assert!(!entry.fields.contains_key(PROTECTED_TAG));
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Result;
+
+use crate::model::ris::{parse_ris, ReferenceType, RisEntry};
+use crate::repo::{self, Conflict, Resolution};
+use crate::services::library::{self, LibraryEntry};
+
+/// Which of the two compared entries a merged value is taken from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// A tag present in both entries with different values. The type is reported
+/// under the pseudo tag `TY`.
+pub struct FieldConflict {
+    pub tag: String,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+}
+
+/// All tags of both entries in a stable order, `TY` first.
+pub fn all_tags(left: &RisEntry, right: &RisEntry) -> Vec<String> {
+    let tags: BTreeSet<&String> = left.fields.keys().chain(right.fields.keys()).collect();
+    std::iter::once("TY".to_string())
+        .chain(tags.into_iter().cloned())
+        .collect()
+}
+
+/// Values of `tag` in `entry`, treating `TY` as a regular single-valued field.
+pub fn values(entry: &RisEntry, tag: &str) -> Vec<String> {
+    if tag == "TY" {
+        return vec![entry.ty.to_str().to_string()];
+    }
+    entry.fields.get(tag).cloned().unwrap_or_default()
+}
+
+pub fn conflicts(left: &RisEntry, right: &RisEntry) -> Vec<FieldConflict> {
+    all_tags(left, right)
+        .into_iter()
+        .filter_map(|tag| {
+            let l = values(left, &tag);
+            let r = values(right, &tag);
+            (!l.is_empty() && !r.is_empty() && l != r).then_some(FieldConflict {
+                tag,
+                left: l,
+                right: r,
+            })
+        })
+        .collect()
+}
+
+/// Combines two entries. Tags present on only one side are kept as they are;
+/// conflicting tags use the side picked in `choices`, defaulting to the left.
+pub fn merge(left: &RisEntry, right: &RisEntry, choices: &HashMap<String, Side>) -> RisEntry {
+    let pick = |tag: &str| choices.get(tag).copied().unwrap_or(Side::Left);
+
+    let ty = match pick("TY") {
+        Side::Left => left.ty.clone(),
+        Side::Right => right.ty.clone(),
+    };
+    let ty = if ty == ReferenceType::Unknown {
+        [left.ty.clone(), right.ty.clone()]
+            .into_iter()
+            .find(|t| *t != ReferenceType::Unknown)
+            .unwrap_or(ReferenceType::Unknown)
+    } else {
+        ty
+    };
+
+    let mut fields = HashMap::new();
+    for tag in all_tags(left, right).into_iter().skip(1) {
+        let l = values(left, &tag);
+        let r = values(right, &tag);
+        let chosen = match (l.is_empty(), r.is_empty(), pick(&tag)) {
+            (false, true, _) | (false, false, Side::Left) => l,
+            _ => r,
+        };
+        fields.insert(tag, chosen);
+    }
+
+    RisEntry { ty, fields }
+}
+
+/// Tags holding a set of values, where a three-way merge keeps additions and
+/// removals from both sides.
+const SET_TAGS: &[&str] = &["KW", "L1", "N1", "UR"];
+
+/// Merges one entry changed on two sides since `base`. A tag changed on only
+/// one side takes that change; set tags combine both; other tags changed on
+/// both sides take the newer side. Returns the entry and those last tags.
+pub fn merge_three_way(
+    base: Option<&RisEntry>,
+    remote: &RisEntry,
+    local: &RisEntry,
+    local_is_newer: bool,
+) -> (RisEntry, Vec<String>) {
+    let mut tags = all_tags(remote, local);
+    if let Some(base) = base {
+        tags.extend(base.fields.keys().filter(|tag| !tags.contains(tag)).cloned().collect::<Vec<_>>());
+    }
+
+    let mut ty = remote.ty.clone();
+    let mut fields = HashMap::new();
+    let mut overridden = Vec::new();
+    for tag in tags {
+        let b = base.map(|base| values(base, &tag)).unwrap_or_default();
+        let r = values(remote, &tag);
+        let l = values(local, &tag);
+        let merged = if r == l || l == b {
+            r
+        } else if r == b {
+            l
+        } else if SET_TAGS.contains(&tag.as_str()) {
+            let removed = |value: &String| b.contains(value) && !(r.contains(value) && l.contains(value));
+            let mut union: Vec<String> = r.iter().filter(|v| !removed(v)).cloned().collect();
+            union.extend(l.iter().filter(|v| !removed(v) && !r.contains(v)).cloned());
+            union
+        } else {
+            overridden.push(tag.clone());
+            if local_is_newer { l } else { r }
+        };
+
+        if tag == "TY" {
+            if let Some(value) = merged.first() {
+                ty = value.parse().unwrap_or(ReferenceType::Unknown);
+            }
+        } else if !merged.is_empty() {
+            fields.insert(tag, merged);
+        }
+    }
+
+    (RisEntry { ty, fields }, overridden)
+}
+
+/// Settles a conflict in a RIS file by merging it entry by entry with
+/// [`merge_three_way`]. Returns `None` for other files, deletions and files
+/// whose entries cannot be paired up.
+pub fn merge_ris_conflict(conflict: &Conflict) -> Option<(String, Vec<String>)> {
+    if !conflict.path.ends_with(".ris") {
+        return None;
+    }
+    let parse = |content: &Option<Vec<u8>>| parse_ris(std::str::from_utf8(content.as_ref()?).ok()?).ok();
+    let remote = parse(&conflict.remote)?;
+    let local = parse(&conflict.local)?;
+    let base = match &conflict.base {
+        Some(_) => Some(parse(&conflict.base)?),
+        None => None,
+    };
+    if remote.len() != local.len() || base.as_ref().is_some_and(|base| base.len() != remote.len()) {
+        return None;
+    }
+
+    let mut overridden = Vec::new();
+    let merged: Vec<String> = remote
+        .iter()
+        .zip(&local)
+        .enumerate()
+        .map(|(i, (r, l))| {
+            let base = base.as_ref().map(|base| &base[i]);
+            let (entry, tags) = merge_three_way(base, r, l, conflict.local_is_newer);
+            overridden.extend(tags);
+            entry.to_string()
+        })
+        .collect();
+    Some((merged.join("\n"), overridden))
+}
+
+/// Conflict resolver for syncs without a user to ask: merges RIS files and
+/// aborts on anything else.
+pub fn resolve_unattended(conflict: &Conflict) -> Result<Resolution> {
+    Ok(match merge_ris_conflict(conflict) {
+        Some((merged, _)) => Resolution::Content(merged.into_bytes()),
+        None => Resolution::Abort,
+    })
+}
+
+/// Writes the merged entry in place of `left` and removes `right`, if it is a
+/// stored entry, committing the result. Other entries in the same files are
+/// kept.
+pub fn save_merged(
+    project_path: &str,
+    left: &LibraryEntry,
+    right: Option<&LibraryEntry>,
+    merged: &RisEntry,
+) -> Result<()> {
+    library::replace_entry(left, Some(merged))?;
+
+    let message = match right {
+        Some(right) => {
+            library::replace_entry(right, None)?;
+            format!("Merged {} into {}", right.id, left.id)
+        }
+        None => format!("Merged imported entry into {}", left.id),
+    };
+
+    repo::add_all(project_path)?;
+    repo::commit(project_path, &message)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{conflicts, merge, merge_three_way, Side};
+    use crate::model::ris::parse_ris;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_merge_keeps_unique_fields_and_applies_choices() {
+        let left = parse_ris("TY  - JOUR\nTI  - Left title\nPY  - 2020\nDO  - 10.1/x\nER  -")
+            .unwrap()
+            .remove(0);
+        let right = parse_ris("TY  - JOUR\nTI  - Right title\nPY  - 2020\nVL  - 3\nER  -")
+            .unwrap()
+            .remove(0);
+
+        let found = conflicts(&left, &right);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].tag, "TI");
+
+        let choices = HashMap::from([("TI".to_string(), Side::Right)]);
+        let merged = merge(&left, &right, &choices);
+        assert_eq!(merged.get_field("TI"), Some(&"Right title".to_string()));
+        assert_eq!(merged.get_field("DO"), Some(&"10.1/x".to_string()));
+        assert_eq!(merged.get_field("VL"), Some(&"3".to_string()));
+        assert_eq!(merged.get_field("PY"), Some(&"2020".to_string()));
+    }
+
+    #[test]
+    fn test_three_way_merge_takes_changes_from_both_sides() {
+        let parse = |ris: &str| parse_ris(ris).unwrap().remove(0);
+        let base = parse("TY  - JOUR\nTI  - Cats\nPY  - 2020\nKW  - pets\nKW  - old\nER  -");
+        let remote = parse("TY  - JOUR\nTI  - Cats and dogs\nPY  - 2020\nKW  - pets\nKW  - old\nKW  - dogs\nER  -");
+        let local = parse("TY  - JOUR\nTI  - Cats!\nPY  - 2021\nKW  - pets\nKW  - reading\nER  -");
+
+        let (merged, overridden) = merge_three_way(Some(&base), &remote, &local, true);
+        assert_eq!(merged.get_field("PY"), Some(&"2021".to_string()));
+        assert_eq!(merged.get_field("TI"), Some(&"Cats!".to_string()));
+        assert_eq!(merged.fields["KW"], vec!["pets", "dogs", "reading"]);
+        assert_eq!(overridden, vec!["TI"]);
+
+        let (merged, _) = merge_three_way(Some(&base), &remote, &local, false);
+        assert_eq!(merged.get_field("TI"), Some(&"Cats and dogs".to_string()));
+    }
+}
@@ -1 +1,47 @@
+pub mod archive;
+pub mod arxiv;
+pub mod better_bibtex;
+pub mod citations;
+pub mod citekey;
+pub mod crossref;
+pub mod csl_json;
+pub mod daemon;
+pub mod diff;
+pub mod doi;
+pub mod encoding;
+pub mod endnote;
+pub mod export;
+pub mod fulltext;
+pub mod graph;
+pub mod hayagriva;
+pub mod html;
+pub mod http;
+pub mod index;
+pub mod ipc;
+pub mod jabref;
+pub mod library;
+pub mod lint;
+pub mod mendeley;
+pub mod merge;
+pub mod notes;
+pub mod open_with;
+pub mod openurl;
+pub mod pandoc;
+pub mod pdf;
+pub mod pubmed;
+pub mod query;
+pub mod rate_limit;
+pub mod remind;
+pub mod refworks;
+pub mod render;
+pub mod roundtrip;
+pub mod scholar;
+pub mod search;
+pub mod semantic_scholar;
 pub mod serialization;
+pub mod styles;
+pub mod tags;
+pub mod translation_server;
+pub mod venues;
+pub mod webpage;
+pub mod zotero;
@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::model::ris::RisEntry;
+
+/// Separates the generated part of a note from the user's own writing.
+/// Everything below it is left untouched when notes are regenerated.
+pub const GENERATED_MARKER: &str = "%% refrs: end of generated section, write your notes below %%";
+
+pub const DEFAULT_OBSIDIAN_TEMPLATE: &str = r#"---
+citekey: {{citekey:yaml}}
+title: {{title:yaml}}
+authors: {{authors:yaml}}
+year: {{year:yaml}}
+doi: {{doi:yaml}}
+tags: {{tags:yaml}}
+abstract: {{abstract:yaml}}
+---
+# {{title}}
+
+{{authors}} ({{year}})
+
+## Abstract
+
+{{abstract}}
+"#;
+
+pub enum NoteStatus {
+    Created,
+    Updated,
+    Unchanged,
+    /// The note exists but its marker was removed, so it is not touched.
+    Skipped,
+}
+
+enum Value {
+    Text(String),
+    List(Vec<String>),
+}
+
+fn variables(key: &str, entry: &RisEntry) -> Vec<(&'static str, Value)> {
+    let text = |tag: &str| Value::Text(entry.get_field(tag).cloned().unwrap_or_default());
+    let list = |tag: &str| entry.fields.get(tag).cloned().unwrap_or_default();
+
+    let tags = list("KW")
+        .iter()
+        .map(|kw| {
+            kw.split_whitespace()
+                .collect::<Vec<_>>()
+                .join("-")
+                .to_lowercase()
+        })
+        .collect();
+
+    vec![
+        ("citekey", Value::Text(key.to_string())),
+        ("title", text("TI")),
+        ("authors", Value::List(list("AU"))),
+        ("year", text("PY")),
+        ("journal", text("T2")),
+        ("doi", text("DO")),
+        ("url", text("UR")),
+        ("abstract", text("AB")),
+        ("keywords", Value::List(list("KW"))),
+        ("tags", Value::List(tags)),
+        ("type", Value::Text(entry.ty.to_str().to_string())),
+    ]
+}
+
+/// Fills `{{name}}` placeholders with plain text (lists joined by `; `) and
+/// `{{name:yaml}}` placeholders with YAML-safe quoted values or lists.
+pub fn render_template(template: &str, key: &str, entry: &RisEntry) -> String {
+    let mut rendered = template.to_string();
+    for (name, value) in variables(key, entry) {
+        let (plain, yaml) = match value {
+            Value::Text(text) => (text.clone(), serde_json::to_string(&text)),
+            Value::List(items) => (items.join("; "), serde_json::to_string(&items)),
+        };
+        rendered = rendered
+            .replace(&format!("{{{{{}:yaml}}}}", name), &yaml.unwrap_or_default())
+            .replace(&format!("{{{{{}}}}}", name), &plain);
+    }
+    rendered
+}
+
+/// Writes the generated part of a note, keeping whatever the user wrote below
+/// the marker in an existing note.
+pub fn sync_note(path: &Path, generated: &str) -> Result<NoteStatus> {
+    let generated = format!("{}\n\n{}", generated.trim_end(), GENERATED_MARKER);
+
+    if !path.exists() {
+        fs::write(path, format!("{}\n\n## Notes\n\n", generated))?;
+        return Ok(NoteStatus::Created);
+    }
+
+    let existing = fs::read_to_string(path)?;
+    let Some(marker) = existing.find(GENERATED_MARKER) else {
+        return Ok(NoteStatus::Skipped);
+    };
+    let user_part = &existing[marker + GENERATED_MARKER.len()..];
+
+    let updated = format!("{}{}", generated, user_part);
+    if updated == existing {
+        return Ok(NoteStatus::Unchanged);
+    }
+
+    fs::write(path, updated)?;
+    Ok(NoteStatus::Updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_template, DEFAULT_OBSIDIAN_TEMPLATE};
+    use crate::model::ris::parse_ris;
+
+    #[test]
+    fn test_render_template_quotes_yaml_values() {
+        let entry = parse_ris(
+            "TY  - JOUR\nAU  - Doe, John\nAU  - Smith, Jane\nTI  - Why: \"quotes\" matter\nPY  - 2020\nKW  - Query Optimization\nER  -",
+        )
+        .unwrap()
+        .remove(0);
+
+        let note = render_template(DEFAULT_OBSIDIAN_TEMPLATE, "doe2020", &entry);
+        assert!(note.contains("citekey: \"doe2020\"\n"));
+        assert!(note.contains("title: \"Why: \\\"quotes\\\" matter\"\n"));
+        assert!(note.contains("authors: [\"Doe, John\",\"Smith, Jane\"]\n"));
+        assert!(note.contains("tags: [\"query-optimization\"]\n"));
+        assert!(note.contains("# Why: \"quotes\" matter\n\nDoe, John; Smith, Jane (2020)\n"));
+    }
+}
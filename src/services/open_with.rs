@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::model::ris::RisEntry;
+
+/// Something `refrs open` can open, and the kind used to look up its handler.
+pub enum Target {
+    Doi(String),
+    Url(String),
+    /// A link to the library's OpenURL resolver
+    Library(String),
+    /// An attached file, matched by its extension
+    File(PathBuf),
+}
+
+impl Target {
+    /// Handler key: `doi`, `url`, `library`, or the lowercase file extension.
+    pub fn kind(&self) -> String {
+        match self {
+            Target::Doi(_) => "doi".to_string(),
+            Target::Url(_) => "url".to_string(),
+            Target::Library(_) => "library".to_string(),
+            Target::File(path) => path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_else(|| "file".to_string()),
+        }
+    }
+
+    pub fn location(&self) -> String {
+        match self {
+            Target::Doi(doi) => format!("https://doi.org/{}", doi.trim()),
+            Target::Url(url) | Target::Library(url) => url.trim().to_string(),
+            Target::File(path) => path.display().to_string(),
+        }
+    }
+}
+
+/// The DOI, else the URL, of an entry. DOIs are preferred since URLs tend to rot.
+pub fn link_target(entry: &RisEntry) -> Option<Target> {
+    match (entry.get_field("DO"), entry.get_field("UR")) {
+        (Some(doi), _) => Some(Target::Doi(doi.clone())),
+        (None, Some(url)) => Some(Target::Url(url.clone())),
+        (None, None) => None,
+    }
+}
+
+/// Program and arguments for a handler such as `zathura --fork {}`, split
+/// like a shell would, so arguments with spaces can be quoted. The location
+/// replaces `{}`, or is appended when there is none.
+pub fn command_line(template: &str, location: &str) -> Result<Vec<String>> {
+    let mut args = shell_words::split(template).with_context(|| format!("Could not split the handler {:?}", template))?;
+    if args.iter().any(|arg| arg.contains("{}")) {
+        for arg in &mut args {
+            *arg = arg.replace("{}", location);
+        }
+    } else {
+        args.push(location.to_string());
+    }
+    Ok(args)
+}
+
+/// Opens the target with its configured handler, or the system default.
+pub fn open(target: &Target, handlers: &BTreeMap<String, String>) -> Result<()> {
+    let location = target.location();
+    let Some(template) = handlers.get(&target.kind()) else {
+        return webbrowser::open(&location)
+            .map_err(|_| anyhow!("Failed to open {}. Please open it manually.", location));
+    };
+
+    let args = command_line(template, &location)?;
+    let Some((program, args)) = args.split_first() else {
+        bail!("The handler for {} is empty", target.kind());
+    };
+    // Don't wait for viewers and browsers, and keep them off our terminal.
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_the_location() {
+        assert_eq!(
+            command_line("firefox -P research", "https://doi.org/10.1/x").unwrap(),
+            vec!["firefox", "-P", "research", "https://doi.org/10.1/x"]
+        );
+        assert_eq!(
+            command_line("zathura --page=1 {}", "a.pdf").unwrap(),
+            vec!["zathura", "--page=1", "a.pdf"]
+        );
+        assert_eq!(
+            command_line(r#""/Applications/PDF Expert.app/run" --title 'My papers' {}"#, "a b.pdf").unwrap(),
+            vec!["/Applications/PDF Expert.app/run", "--title", "My papers", "a b.pdf"]
+        );
+        assert!(command_line("viewer 'unclosed", "a.pdf").is_err());
+        assert_eq!(Target::File(PathBuf::from("attachments/a.PDF")).kind(), "pdf");
+    }
+}
@@ -0,0 +1,99 @@
+use url::form_urlencoded;
+
+use crate::model::ris::{ReferenceType, RisEntry};
+
+/// Builds an OpenURL 1.0 (Z39.88-2004) link to the library resolver at `base`
+/// describing `entry`, so the library can locate a copy it has access to.
+pub fn build_link(base: &str, entry: &RisEntry) -> String {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    query.append_pair("url_ver", "Z39.88-2004");
+    query.append_pair("ctx_ver", "Z39.88-2004");
+
+    let field = |tag: &str| entry.get_field(tag).map(|v| v.trim()).filter(|v| !v.is_empty());
+    let is_book = matches!(entry.ty, ReferenceType::Book | ReferenceType::ElectronicBook);
+
+    if is_book {
+        query.append_pair("rft_val_fmt", "info:ofi/fmt:kev:mtx:book");
+        query.append_pair("rft.genre", "book");
+        if let Some(title) = field("TI") {
+            query.append_pair("rft.btitle", title);
+        }
+        if let Some(isbn) = field("SN") {
+            query.append_pair("rft.isbn", isbn);
+        }
+        if let Some(publisher) = field("PB") {
+            query.append_pair("rft.pub", publisher);
+        }
+    } else {
+        let genre = match entry.ty {
+            ReferenceType::ConferencePaper | ReferenceType::ConferenceProceedings => "proceeding",
+            _ => "article",
+        };
+        query.append_pair("rft_val_fmt", "info:ofi/fmt:kev:mtx:journal");
+        query.append_pair("rft.genre", genre);
+        if let Some(title) = field("TI") {
+            query.append_pair("rft.atitle", title);
+        }
+        if let Some(journal) = field("T2") {
+            query.append_pair("rft.jtitle", journal);
+        }
+        if let Some(issn) = field("SN") {
+            query.append_pair("rft.issn", issn);
+        }
+    }
+
+    if let Some(author) = field("AU") {
+        match author.split_once(',') {
+            Some((last, first)) => {
+                query.append_pair("rft.aulast", last.trim());
+                query.append_pair("rft.aufirst", first.trim());
+            }
+            None => {
+                query.append_pair("rft.au", author);
+            }
+        }
+    }
+
+    let optional = [
+        ("rft.date", "PY"),
+        ("rft.volume", "VL"),
+        ("rft.issue", "IS"),
+        ("rft.spage", "SP"),
+        ("rft.epage", "EP"),
+    ];
+    for (key, tag) in optional {
+        if let Some(value) = field(tag) {
+            query.append_pair(key, value);
+        }
+    }
+
+    if let Some(doi) = field("DO") {
+        query.append_pair("rft_id", &format!("info:doi/{}", doi));
+    }
+
+    let separator = if base.contains('?') { '&' } else { '?' };
+    format!("{}{}{}", base, separator, query.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build_link;
+    use crate::model::ris::parse_ris;
+
+    #[test]
+    fn test_build_link_for_journal_article() {
+        let entry = parse_ris(
+            "TY  - JOUR\nAU  - Ioannidis, Yannis E.\nTI  - Parametric query optimization\nT2  - The VLDB Journal\nPY  - 1997\nVL  - 6\nDO  - 10.1007/s007780050037\nER  -",
+        )
+        .unwrap()
+        .remove(0);
+
+        let link = build_link("https://resolver.example.edu/openurl", &entry);
+        assert!(link.starts_with("https://resolver.example.edu/openurl?url_ver=Z39.88-2004&"));
+        assert!(link.contains("rft.genre=article"));
+        assert!(link.contains("rft.atitle=Parametric+query+optimization"));
+        assert!(link.contains("rft.aulast=Ioannidis&rft.aufirst=Yannis+E."));
+        assert!(link.contains("rft.volume=6"));
+        assert!(link.contains("rft_id=info%3Adoi%2F10.1007%2Fs007780050037"));
+    }
+}
@@ -0,0 +1,480 @@
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+
+use crate::model::ris::{ReferenceType, RisEntry};
+use crate::services::citekey::{last_name, year_of};
+use crate::services::library::LibraryEntry;
+
+/// Id of the Div the bibliography is placed in, the same one pandoc's citeproc uses.
+const REFS_ID: &str = "refs";
+
+/// Order of the bibliography.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum BibliographySort {
+    #[default]
+    AuthorYear,
+    /// Order in which the entries are first cited
+    Appearance,
+    /// By the value of a RIS field, e.g. `T2`
+    Field(String),
+}
+
+/// Headings the bibliography can be divided under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Year,
+    Type,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BibliographyOptions {
+    pub sort: BibliographySort,
+    pub group_by: Option<GroupBy>,
+}
+
+impl BibliographyOptions {
+    /// Reads `refrs-sort` (`author-year`, `appearance` or a RIS tag) and
+    /// `refrs-group-by` (`year` or `type`) from the document metadata.
+    pub fn from_meta(meta: &Value) -> Result<Self> {
+        let sort = match meta_string(&meta["refrs-sort"]).as_deref() {
+            None | Some("author-year") => BibliographySort::AuthorYear,
+            Some("appearance") => BibliographySort::Appearance,
+            Some(tag) if tag.len() == 2 => BibliographySort::Field(tag.to_uppercase()),
+            Some(other) => bail!(
+                "refrs-sort must be author-year, appearance or a RIS tag, not {:?}",
+                other
+            ),
+        };
+        let group_by = match meta_string(&meta["refrs-group-by"]).as_deref() {
+            None | Some("none") => None,
+            Some("year") => Some(GroupBy::Year),
+            Some("type") => Some(GroupBy::Type),
+            Some(other) => bail!("refrs-group-by must be year or type, not {:?}", other),
+        };
+        Ok(Self { sort, group_by })
+    }
+}
+
+/// Text of a `MetaString` or `MetaInlines` value.
+fn meta_string(value: &Value) -> Option<String> {
+    let text = match value["t"].as_str()? {
+        "MetaString" => value["c"].as_str()?.to_string(),
+        "MetaInlines" => stringify(&value["c"]),
+        _ => return None,
+    };
+    Some(text.trim().to_lowercase())
+}
+
+/// Replaces the content of every `Cite` element in a Pandoc JSON document with
+/// an author-date citation and places a bibliography of the cited entries in
+/// the `#refs` Div, or at the end of the document. Returns the unknown keys.
+pub fn resolve_citations(
+    document: &mut Value,
+    entries: &[LibraryEntry],
+    options: &BibliographyOptions,
+) -> Vec<String> {
+    let mut cited: Vec<&LibraryEntry> = Vec::new();
+    let mut missing: Vec<String> = Vec::new();
+
+    if let Some(blocks) = document.get_mut("blocks") {
+        visit(blocks, &mut |cite| {
+            let citations = cite["c"][0].as_array().cloned().unwrap_or_default();
+            let rendered = render_cite(&citations, entries, &mut cited, &mut missing);
+            cite["c"][1] = Value::Array(rendered);
+        });
+    }
+
+    // `cited` is in order of appearance, which the stable sorts keep for ties.
+    match &options.sort {
+        BibliographySort::AuthorYear => cited.sort_by_key(|e| sort_key(&e.entry)),
+        BibliographySort::Appearance => {}
+        BibliographySort::Field(tag) => cited.sort_by_key(|e| {
+            let value = e.entry.get_field(tag).map(|v| v.to_lowercase());
+            // Entries without the field go last.
+            (value.is_none(), value)
+        }),
+    }
+
+    let mut bibliography: Vec<Value> = Vec::new();
+    match options.group_by {
+        None => {
+            for e in &cited {
+                bibliography.push(json!({ "t": "Para", "c": bibliography_inlines(&e.entry) }));
+            }
+        }
+        Some(group_by) => {
+            let group_of = |entry: &RisEntry| match group_by {
+                GroupBy::Year => entry_year(entry),
+                GroupBy::Type => type_heading(&entry.ty).to_string(),
+            };
+            let mut groups: Vec<String> = cited.iter().map(|e| group_of(&e.entry)).collect();
+            groups.sort();
+            groups.dedup();
+
+            for group in groups {
+                bibliography.push(json!({
+                    "t": "Header",
+                    "c": [2, ["", ["unnumbered"], []], text_inlines(&group)]
+                }));
+                for e in cited.iter().filter(|e| group_of(&e.entry) == group) {
+                    bibliography.push(json!({ "t": "Para", "c": bibliography_inlines(&e.entry) }));
+                }
+            }
+        }
+    }
+
+    if !bibliography.is_empty() {
+        insert_bibliography(document, bibliography);
+    }
+    missing
+}
+
+/// Calls `on_cite` for every `Cite` inline anywhere below `value`.
+fn visit(value: &mut Value, on_cite: &mut dyn FnMut(&mut Value)) {
+    match value {
+        Value::Object(map) if map.get("t").and_then(Value::as_str) == Some("Cite") => {
+            on_cite(value);
+        }
+        Value::Object(map) => map.values_mut().for_each(|v| visit(v, on_cite)),
+        Value::Array(values) => values.iter_mut().for_each(|v| visit(v, on_cite)),
+        _ => {}
+    }
+}
+
+fn render_cite<'a>(
+    citations: &[Value],
+    entries: &'a [LibraryEntry],
+    cited: &mut Vec<&'a LibraryEntry>,
+    missing: &mut Vec<String>,
+) -> Vec<Value> {
+    let mut in_text = Vec::new();
+    let mut parenthetical = Vec::new();
+
+    for citation in citations {
+        let id = citation["citationId"].as_str().unwrap_or_default();
+        let Some(entry) = entries.iter().find(|e| e.key() == id || e.id == id) else {
+            if !missing.iter().any(|m| m == id) {
+                missing.push(id.to_string());
+            }
+            parenthetical.push(format!("??{}", id));
+            continue;
+        };
+        if !cited.iter().any(|c| c.id == entry.id) {
+            cited.push(entry);
+        }
+
+        let authors = short_authors(&entry.entry);
+        let year = entry_year(&entry.entry);
+        let prefix = stringify(&citation["citationPrefix"]);
+        let suffix = stringify(&citation["citationSuffix"]);
+        let mode = citation["citationMode"]["t"].as_str().unwrap_or("NormalCitation");
+
+        let mut year_part = year;
+        if !suffix.is_empty() {
+            year_part = format!("{}{}", year_part, with_separator(&suffix));
+        }
+
+        let text = match mode {
+            "AuthorInText" => {
+                in_text.push(format!("{} ({})", authors, year_part));
+                continue;
+            }
+            "SuppressAuthor" => year_part,
+            _ => format!("{} {}", authors, year_part),
+        };
+        if prefix.is_empty() {
+            parenthetical.push(text);
+        } else {
+            parenthetical.push(format!("{} {}", prefix, text));
+        }
+    }
+
+    let mut text = in_text.join("; ");
+    if !parenthetical.is_empty() {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(&format!("({})", parenthetical.join("; ")));
+    }
+    text_inlines(&text)
+}
+
+/// A suffix like `p. 3` is separated by a comma unless it brings its own.
+fn with_separator(suffix: &str) -> String {
+    if suffix.starts_with(',') {
+        suffix.to_string()
+    } else {
+        format!(", {}", suffix)
+    }
+}
+
+/// `Doe`, `Doe and Roe` or `Doe et al.`.
+fn short_authors(entry: &RisEntry) -> String {
+    let authors: Vec<String> = entry
+        .fields
+        .get("AU")
+        .into_iter()
+        .flatten()
+        .map(|a| last_name(a))
+        .collect();
+
+    match authors.as_slice() {
+        [] => entry
+            .get_field("TI")
+            .cloned()
+            .unwrap_or_else(|| "Anonymous".to_string()),
+        [one] => one.clone(),
+        [first, second] => format!("{} and {}", first, second),
+        [first, ..] => format!("{} et al.", first),
+    }
+}
+
+fn entry_year(entry: &RisEntry) -> String {
+    entry
+        .get_field("PY")
+        .and_then(|py| year_of(py))
+        .unwrap_or_else(|| "n.d.".to_string())
+}
+
+fn type_heading(ty: &ReferenceType) -> &'static str {
+    match ty {
+        ReferenceType::Journal | ReferenceType::MagazineArticle | ReferenceType::Newspaper => {
+            "Articles"
+        }
+        ReferenceType::Book | ReferenceType::ElectronicBook => "Books",
+        ReferenceType::Chart => "Book chapters",
+        ReferenceType::ConferencePaper | ReferenceType::ConferenceProceedings => {
+            "Conference papers"
+        }
+        ReferenceType::Thesis => "Theses",
+        ReferenceType::Report | ReferenceType::GovernmentDocument => "Reports",
+        ReferenceType::ElectronicArticle => "Web pages",
+        _ => "Other",
+    }
+}
+
+fn sort_key(entry: &RisEntry) -> (String, String, String) {
+    (
+        short_authors(entry).to_lowercase(),
+        entry_year(entry),
+        entry.get_field("TI").cloned().unwrap_or_default().to_lowercase(),
+    )
+}
+
+/// `Doe, J. & Roe, R. (2020). Title. *Journal*, 12(3), 1–10. https://doi.org/…`
+fn bibliography_inlines(entry: &RisEntry) -> Vec<Value> {
+    let authors: Vec<&String> = entry.fields.get("AU").into_iter().flatten().collect();
+    let authors = match authors.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!(
+            "{} & {}",
+            rest.iter().map(|a| a.as_str()).collect::<Vec<_>>().join("; "),
+            last
+        ),
+    };
+
+    let mut head = String::new();
+    if !authors.is_empty() {
+        head.push_str(&format!("{} ", authors));
+    }
+    head.push_str(&format!("({}).", entry_year(entry)));
+    if let Some(title) = entry.get_field("TI") {
+        head.push_str(&format!(" {}.", title.trim_end_matches('.')));
+    }
+    let mut inlines = text_inlines(&head);
+
+    let container = entry.get_field("T2").or_else(|| entry.get_field("JO"));
+    if let Some(container) = container {
+        inlines.push(json!({ "t": "Space" }));
+        inlines.push(json!({ "t": "Emph", "c": text_inlines(container) }));
+    }
+
+    let mut tail = String::new();
+    if let Some(volume) = entry.get_field("VL") {
+        tail.push_str(&format!(", {}", volume));
+        if let Some(issue) = entry.get_field("IS") {
+            tail.push_str(&format!("({})", issue));
+        }
+    }
+    match (entry.get_field("SP"), entry.get_field("EP")) {
+        (Some(start), Some(end)) => tail.push_str(&format!(", {}–{}", start, end)),
+        (Some(start), None) => tail.push_str(&format!(", {}", start)),
+        _ => {}
+    }
+    if container.is_some() {
+        tail.push('.');
+    }
+    if let Some(doi) = entry.get_field("DO") {
+        tail.push_str(&format!(" https://doi.org/{}", doi));
+    } else if let Some(url) = entry.get_field("UR") {
+        tail.push_str(&format!(" {}", url));
+    }
+
+    let leading_space = tail.starts_with(' ');
+    let mut tail_inlines = text_inlines(&tail);
+    if leading_space {
+        tail_inlines.insert(0, json!({ "t": "Space" }));
+    }
+    inlines.extend(tail_inlines);
+    inlines
+}
+
+fn insert_bibliography(document: &mut Value, bibliography: Vec<Value>) {
+    let Some(blocks) = document.get_mut("blocks").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    let refs = blocks.iter_mut().find(|block| {
+        block["t"] == "Div" && block["c"][0][0].as_str() == Some(REFS_ID)
+    });
+    match refs {
+        Some(div) => {
+            if let Some(content) = div["c"][1].as_array_mut() {
+                content.extend(bibliography);
+            }
+        }
+        None => {
+            blocks.push(json!({
+                "t": "Header",
+                "c": [1, ["bibliography", ["unnumbered"], []], text_inlines("References")]
+            }));
+            blocks.push(json!({
+                "t": "Div",
+                "c": [[REFS_ID, ["references"], []], bibliography]
+            }));
+        }
+    }
+}
+
+/// Splits text into Pandoc `Str` and `Space` inlines.
+fn text_inlines(text: &str) -> Vec<Value> {
+    let mut inlines = Vec::new();
+    for word in text.split_whitespace() {
+        if !inlines.is_empty() {
+            inlines.push(json!({ "t": "Space" }));
+        }
+        inlines.push(json!({ "t": "Str", "c": word }));
+    }
+    inlines
+}
+
+/// Flattens inlines back to text, used for citation prefixes and suffixes.
+fn stringify(inlines: &Value) -> String {
+    let mut text = String::new();
+    for inline in inlines.as_array().into_iter().flatten() {
+        match inline["t"].as_str() {
+            Some("Str") => text.push_str(inline["c"].as_str().unwrap_or_default()),
+            Some("Space") | Some("SoftBreak") | Some("LineBreak") => text.push(' '),
+            _ => text.push_str(&stringify(&inline["c"])),
+        }
+    }
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn entry(id: &str, authors: &[&str], year: &str) -> LibraryEntry {
+        let mut fields = HashMap::new();
+        fields.insert(
+            "AU".to_string(),
+            authors.iter().map(|a| a.to_string()).collect(),
+        );
+        fields.insert("PY".to_string(), vec![year.to_string()]);
+        fields.insert("TI".to_string(), vec!["A title".to_string()]);
+        LibraryEntry {
+            id: id.to_string(),
+            path: PathBuf::from(format!("{}.ris", id)),
+            entry: RisEntry {
+                ty: crate::model::ris::ReferenceType::Journal,
+                fields,
+            },
+        }
+    }
+
+    fn cite(id: &str, mode: &str) -> Value {
+        json!({
+            "t": "Cite",
+            "c": [[{
+                "citationId": id,
+                "citationPrefix": [],
+                "citationSuffix": [],
+                "citationMode": { "t": mode },
+                "citationNoteNum": 1,
+                "citationHash": 0
+            }], [{ "t": "Str", "c": format!("@{}", id) }]]
+        })
+    }
+
+    #[test]
+    fn resolves_citations_and_appends_bibliography() {
+        let entries = vec![
+            entry("doe2020", &["Doe, Jane", "Roe, Rick"], "2020/01/01"),
+            entry("poe2019", &["Poe, Ed", "Moe, M", "Zoe, Z"], "2019"),
+        ];
+        let mut document = json!({
+            "pandoc-api-version": [1, 23, 1],
+            "meta": {},
+            "blocks": [{ "t": "Para", "c": [
+                cite("doe2020", "NormalCitation"),
+                cite("poe2019", "AuthorInText"),
+                cite("nope", "NormalCitation")
+            ]}]
+        });
+
+        let missing = resolve_citations(&mut document, &entries, &BibliographyOptions::default());
+        assert_eq!(missing, vec!["nope".to_string()]);
+
+        let para = &document["blocks"][0]["c"];
+        assert_eq!(stringify(&para[0]["c"][1]), "(Doe and Roe 2020)");
+        assert_eq!(stringify(&para[1]["c"][1]), "Poe et al. (2019)");
+        assert_eq!(stringify(&para[2]["c"][1]), "(??nope)");
+
+        let refs = &document["blocks"][2]["c"][1];
+        assert_eq!(refs.as_array().unwrap().len(), 2);
+        assert_eq!(stringify(&refs[0]["c"]), "Doe, Jane & Roe, Rick (2020). A title.");
+    }
+
+    #[test]
+    fn groups_bibliography_by_year_in_order_of_appearance() {
+        let entries = vec![
+            entry("new", &["Zed, Z"], "2021"),
+            entry("old", &["Abe, A"], "2001"),
+            entry("mid", &["Moe, M"], "2021"),
+        ];
+        let mut document = json!({
+            "meta": {
+                "refrs-sort": { "t": "MetaInlines", "c": [{ "t": "Str", "c": "appearance" }] },
+                "refrs-group-by": { "t": "MetaString", "c": "year" }
+            },
+            "blocks": [{ "t": "Para", "c": [
+                cite("new", "NormalCitation"),
+                cite("old", "NormalCitation"),
+                cite("mid", "NormalCitation")
+            ]}]
+        });
+
+        let options = BibliographyOptions::from_meta(&document["meta"]).unwrap();
+        assert_eq!(options.sort, BibliographySort::Appearance);
+        resolve_citations(&mut document, &entries, &options);
+
+        let refs: Vec<String> = document["blocks"][2]["c"][1]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|block| match block["t"].as_str() {
+                Some("Header") => stringify(&block["c"][2]),
+                _ => stringify(&block["c"]),
+            })
+            .collect();
+        assert_eq!(refs[0], "2001");
+        assert!(refs[1].starts_with("Abe"));
+        assert_eq!(refs[2], "2021");
+        assert!(refs[3].starts_with("Zed"));
+        assert!(refs[4].starts_with("Moe"));
+    }
+}
@@ -0,0 +1,590 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use flate2::read::ZlibDecoder;
+
+use crate::model::ris::{ReferenceType, RisEntry};
+use crate::services::{crossref, doi};
+
+/// Enough text of the first page(s) to find a DOI or title in.
+const TEXT_LIMIT: usize = 4000;
+
+/// Content streams are inflated to at most this many bytes, so that a small
+/// crafted PDF can't take all the memory.
+const INFLATE_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// What a PDF says about itself, from its Info dictionary, its XMP packet
+/// and the text at the start of the document.
+#[derive(Debug, Default)]
+pub struct PdfMetadata {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    pub doi: Option<String>,
+    pub year: Option<String>,
+    pub journal: Option<String>,
+    pub keywords: Vec<String>,
+    pub text: String,
+}
+
+pub fn read(path: &Path) -> Result<PdfMetadata> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse(&bytes).with_context(|| format!("Failed to read PDF {}", path.display()))
+}
+
+/// Reads metadata without a full PDF parser: the Info dictionary and XMP
+/// packet are usually stored uncompressed, and Flate-compressed content
+/// streams are scanned for text. Text in fonts with custom encodings comes
+/// out garbled and is of little use for matching.
+pub fn parse(bytes: &[u8]) -> Result<PdfMetadata> {
+    if !bytes.starts_with(b"%PDF-") {
+        bail!("Not a PDF file");
+    }
+
+    let mut metadata = PdfMetadata::default();
+    if let Some(xmp) = xmp_packet(bytes) {
+        read_xmp(&xmp, &mut metadata);
+    }
+    if let Some(info) = info_dictionary(bytes) {
+        let field = |key: &[u8]| dictionary_string(info, key).filter(|v| !v.trim().is_empty());
+        metadata.title = metadata
+            .title
+            .or_else(|| field(b"/Title").filter(|t| !is_file_name(t)));
+        if metadata.authors.is_empty() {
+            metadata.authors = field(b"/Author")
+                .map(|authors| split_authors(&authors))
+                .unwrap_or_default();
+        }
+        metadata.doi = metadata
+            .doi
+            .or_else(|| field(b"/doi").and_then(|d| doi::find(&d)));
+        metadata.year = metadata.year.or_else(|| {
+            // D:20200315120000+01'00'
+            let date = field(b"/CreationDate")?;
+            let year = date.trim_start_matches("D:").get(..4)?.to_string();
+            year.chars().all(|c| c.is_ascii_digit()).then_some(year)
+        });
+        if metadata.keywords.is_empty() {
+            metadata.keywords = field(b"/Keywords")
+                .map(|k| {
+                    k.split([';', ','])
+                        .map(|k| k.trim().to_string())
+                        .filter(|k| !k.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default();
+        }
+    }
+    metadata.text = page_text(bytes, TEXT_LIMIT);
+    metadata.doi = metadata.doi.or_else(|| doi::find(&metadata.text));
+
+    Ok(metadata)
+}
+
+/// Looks the PDF up by DOI, or by title on Crossref, and falls back to an
+/// entry built from the embedded metadata when neither matches.
+pub fn to_entry(metadata: &PdfMetadata) -> Result<RisEntry> {
+    let found = match (&metadata.doi, &metadata.title) {
+        (Some(found), _) => Some(found.clone()),
+        (None, Some(title)) => crossref::doi_by_title(title).unwrap_or_default(),
+        (None, None) => None,
+    };
+    if let Some(found) = found {
+        match doi::fetch(&found) {
+            Ok(entry) => return Ok(entry),
+            Err(error) => eprintln!("Could not look up DOI {}: {:#}", found, error),
+        }
+    }
+
+    let Some(title) = &metadata.title else {
+        bail!("The PDF has no DOI or title to import it by");
+    };
+    let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+    fields.insert("TI".to_string(), vec![title.clone()]);
+    let mut add = |tag: &str, values: Vec<String>| {
+        if !values.is_empty() {
+            fields.insert(tag.to_string(), values);
+        }
+    };
+    add("AU", metadata.authors.clone());
+    add("PY", metadata.year.iter().cloned().collect());
+    add("DO", metadata.doi.iter().cloned().collect());
+    add("T2", metadata.journal.iter().cloned().collect());
+    add("KW", metadata.keywords.clone());
+    let ty = if metadata.journal.is_some() {
+        ReferenceType::Journal
+    } else {
+        ReferenceType::Generic
+    };
+    Ok(RisEntry { ty, fields })
+}
+
+/// Word processors put the document's file name into the title.
+fn is_file_name(title: &str) -> bool {
+    let lower = title.trim().to_lowercase();
+    lower.starts_with("microsoft word - ")
+        || [".doc", ".docx", ".pdf", ".tex", ".dvi", ".odt"]
+            .iter()
+            .any(|ext| lower.ends_with(ext))
+}
+
+/// `Jane Doe; Rick Roe`, `Doe, Jane and Roe, Rick` or `Jane Doe, Rick Roe
+/// and Zoe Zed` as RIS names. Names are split at `;`, else at `and` and `&`.
+/// A single comma then separates a last name from the first names, unless
+/// there are several words on both sides of it.
+fn split_authors(authors: &str) -> Vec<String> {
+    let is_last_first = |part: &str| match part.split_once(',') {
+        Some((last, first)) => {
+            let two_names = last.trim().contains(' ') && first.trim().contains(' ');
+            !first.contains(',') && !two_names
+        }
+        None => false,
+    };
+    let names: Vec<&str> = if authors.contains(';') {
+        authors.split(';').collect()
+    } else {
+        authors
+            .split(" and ")
+            .flat_map(|part| part.split('&'))
+            .flat_map(|part| if is_last_first(part) { vec![part] } else { part.split(',').collect() })
+            .collect()
+    };
+    names
+        .iter()
+        .map(|name| name.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|name| !name.is_empty())
+        .map(|name| match (name.split_once(','), name.rsplit_once(' ')) {
+            (Some((last, first)), _) => format!("{}, {}", last.trim(), first.trim()),
+            (None, Some((first, last))) => format!("{}, {}", last, first),
+            (None, None) => name,
+        })
+        .collect()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+}
+
+fn xmp_packet(bytes: &[u8]) -> Option<String> {
+    let start = rfind(bytes, b"<x:xmpmeta")?;
+    let end = find(&bytes[start..], b"</x:xmpmeta>")? + start + b"</x:xmpmeta>".len();
+    Some(String::from_utf8_lossy(&bytes[start..end]).to_string())
+}
+
+fn read_xmp(xmp: &str, metadata: &mut PdfMetadata) {
+    let Ok(document) = roxmltree::Document::parse(xmp) else {
+        return;
+    };
+    let elements = |name: &str| {
+        document
+            .descendants()
+            .filter(move |n| n.is_element() && n.tag_name().name() == name)
+            .collect::<Vec<_>>()
+    };
+    // dc:title and dc:creator hold rdf:Alt/rdf:Seq lists of rdf:li.
+    let items = |name: &str| -> Vec<String> {
+        elements(name)
+            .iter()
+            .flat_map(|n| n.descendants().filter(|d| d.tag_name().name() == "li"))
+            .filter_map(|li| li.text())
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+            .collect()
+    };
+    let text = |name: &str| {
+        elements(name)
+            .iter()
+            .filter_map(|n| n.text())
+            .map(str::trim)
+            .find(|text| !text.is_empty())
+            .map(str::to_string)
+    };
+
+    metadata.title = items("title")
+        .into_iter()
+        .next()
+        .filter(|t| !is_file_name(t));
+    metadata.authors = items("creator")
+        .iter()
+        .flat_map(|a| split_authors(a))
+        .collect();
+    metadata.doi = text("doi")
+        .or_else(|| {
+            items("identifier")
+                .iter()
+                .chain(text("identifier").iter())
+                .find_map(|i| doi::find(i))
+        })
+        .and_then(|d| doi::find(&d));
+    metadata.journal = text("publicationName");
+    metadata.year = text("coverDate")
+        .or_else(|| text("CreateDate"))
+        .and_then(|date| date.get(..4).map(str::to_string));
+    metadata.keywords = items("subject");
+}
+
+/// The object the trailer's `/Info n g R` points to.
+fn info_dictionary(bytes: &[u8]) -> Option<&[u8]> {
+    let at = rfind(bytes, b"/Info")? + b"/Info".len();
+    let reference = String::from_utf8_lossy(&bytes[at..bytes.len().min(at + 30)]).to_string();
+    let mut parts = reference.split_whitespace();
+    let (number, generation) = (parts.next()?, parts.next()?);
+    if !parts.next()?.starts_with('R') {
+        return None;
+    }
+
+    let header = format!("{} {} obj", number, generation);
+    let mut search = 0;
+    let start = loop {
+        let index = find(&bytes[search..], header.as_bytes())? + search;
+        // `12 0 obj` must not match the end of `112 0 obj`.
+        if index == 0 || !bytes[index - 1].is_ascii_digit() {
+            break index + header.len();
+        }
+        search = index + 1;
+    };
+    let open = find(&bytes[start..], b"<<")? + start;
+    let mut depth = 0;
+    let mut i = open;
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"<<" => {
+                depth += 1;
+                i += 2;
+            }
+            b">>" => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some(&bytes[open..i]);
+                }
+            }
+            [b'(', _] => i = skip_literal(bytes, i),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Index just past the literal string starting at `start`.
+fn skip_literal(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+fn dictionary_string(dictionary: &[u8], key: &[u8]) -> Option<String> {
+    let mut search = 0;
+    let at = loop {
+        let index = find(&dictionary[search..], key)? + search + key.len();
+        // `/Title` must not match `/TitleFoo`.
+        if !dictionary
+            .get(index)
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+        {
+            break index;
+        }
+        search = index;
+    };
+    let rest = &dictionary[at..];
+    let start = rest.iter().position(|c| !c.is_ascii_whitespace())?;
+    let (raw, _) = read_string(rest, start)?;
+    Some(decode_text(&raw))
+}
+
+/// Bytes of the literal `(...)` or hex `<...>` string at `start`, and the
+/// index just past it.
+fn read_string(bytes: &[u8], start: usize) -> Option<(Vec<u8>, usize)> {
+    match bytes.get(start)? {
+        b'(' => {
+            let end = skip_literal(bytes, start);
+            Some((
+                unescape(&bytes[start + 1..end.saturating_sub(1).max(start + 1)]),
+                end,
+            ))
+        }
+        b'<' if bytes.get(start + 1) != Some(&b'<') => {
+            let end = find(&bytes[start..], b">")? + start;
+            let hex: Vec<u8> = bytes[start + 1..end]
+                .iter()
+                .copied()
+                .filter(u8::is_ascii_hexdigit)
+                .collect();
+            let decoded = hex
+                .chunks(2)
+                .map(|pair| {
+                    let digits = std::str::from_utf8(pair).unwrap_or("0");
+                    u8::from_str_radix(&format!("{:0<2}", digits), 16).unwrap_or(0)
+                })
+                .collect();
+            Some((decoded, end + 1))
+        }
+        _ => None,
+    }
+}
+
+fn unescape(raw: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] != b'\\' {
+            bytes.push(raw[i]);
+            i += 1;
+            continue;
+        }
+        i += 1;
+        match raw.get(i) {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b'r') => bytes.push(b'\r'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(b'b') => bytes.push(8),
+            Some(b'f') => bytes.push(12),
+            // Line continuation
+            Some(b'\r') if raw.get(i + 1) == Some(&b'\n') => i += 1,
+            Some(b'\r') | Some(b'\n') => {}
+            Some(b'0'..=b'7') => {
+                let digits: Vec<u8> = raw[i..]
+                    .iter()
+                    .take(3)
+                    .take_while(|c| (b'0'..=b'7').contains(c))
+                    .copied()
+                    .collect();
+                let value = digits
+                    .iter()
+                    .fold(0u32, |value, d| value * 8 + u32::from(d - b'0'));
+                bytes.push(value as u8);
+                i += digits.len() - 1;
+            }
+            Some(&c) => bytes.push(c),
+            None => {}
+        }
+        i += 1;
+    }
+    bytes
+}
+
+/// UTF-16 with a byte order mark, else PDFDocEncoding, which is close
+/// enough to Latin-1 for metadata.
+fn decode_text(bytes: &[u8]) -> String {
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Some(text) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return String::from_utf8_lossy(text).to_string();
+    }
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// All the text of a PDF, as far as it can be read without a full parser;
+/// see [`parse`].
+pub fn read_text(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if !bytes.starts_with(b"%PDF-") {
+        bail!("{} is not a PDF file", path.display());
+    }
+    Ok(page_text(&bytes, usize::MAX))
+}
+
+/// Text shown by the content streams, from the first one on, until there
+/// are `limit` characters.
+fn page_text(bytes: &[u8], limit: usize) -> String {
+    let mut text = String::new();
+    let mut search = 0;
+    while text.len() < limit {
+        let Some(keyword) = find(&bytes[search..], b"stream").map(|i| i + search) else {
+            break;
+        };
+        search = keyword + b"stream".len();
+        // Skip `endstream` and streams without a dictionary in front.
+        if keyword >= 3 && &bytes[keyword - 3..keyword] == b"end" {
+            continue;
+        }
+        let dictionary_start = rfind(&bytes[..keyword], b"obj").unwrap_or(0);
+        let dictionary = &bytes[dictionary_start..keyword];
+        let mut data_start = search;
+        if bytes.get(data_start) == Some(&b'\r') {
+            data_start += 1;
+        }
+        if bytes.get(data_start) == Some(&b'\n') {
+            data_start += 1;
+        }
+        let Some(data_end) = find(&bytes[data_start..], b"endstream").map(|i| i + data_start)
+        else {
+            break;
+        };
+        search = data_end;
+
+        let is_content = [&b"/Subtype"[..], b"/Type", b"/Length1", b"/DecodeParms"]
+            .iter()
+            .all(|key| find(dictionary, key).is_none());
+        if !is_content {
+            continue;
+        }
+        let data = &bytes[data_start..data_end];
+        let content = if find(dictionary, b"/FlateDecode").is_some() {
+            let mut decoded = Vec::new();
+            // A truncated stream still yields what was decoded so far.
+            let _ = ZlibDecoder::new(data).take(INFLATE_LIMIT).read_to_end(&mut decoded);
+            decoded
+        } else if find(dictionary, b"/Filter").is_none() {
+            data.to_vec()
+        } else {
+            continue;
+        };
+        text.push_str(&content_text(&content));
+    }
+    text
+}
+
+/// Strings shown with `Tj`, `TJ`, `'` and `"` in a content stream, with line
+/// breaks at text positioning operators and word gaps in `TJ` arrays.
+fn content_text(content: &[u8]) -> String {
+    let mut text = String::new();
+    let mut pending = String::new();
+    let mut i = 0;
+    while i < content.len() {
+        match content[i] {
+            b'(' | b'<' if content.get(i + 1) != Some(&b'<') => {
+                let Some((raw, end)) = read_string(content, i) else {
+                    break;
+                };
+                pending.push_str(&decode_text(&raw));
+                i = end;
+            }
+            b'-' | b'0'..=b'9' | b'.' => {
+                let end = content[i..]
+                    .iter()
+                    .position(|c| !(c.is_ascii_digit() || *c == b'.' || *c == b'-'))
+                    .map_or(content.len(), |p| p + i);
+                // Large negative kerning inside a TJ array is a word gap.
+                if std::str::from_utf8(&content[i..end])
+                    .ok()
+                    .and_then(|n| n.parse::<f32>().ok())
+                    .is_some_and(|n| n < -200.0)
+                    && !pending.is_empty()
+                {
+                    pending.push(' ');
+                }
+                i = end;
+            }
+            c if c.is_ascii_alphabetic() || c == b'\'' || c == b'"' || c == b'*' => {
+                let end = content[i..]
+                    .iter()
+                    .position(|c| {
+                        !(c.is_ascii_alphabetic() || *c == b'*' || *c == b'\'' || *c == b'"')
+                    })
+                    .map_or(content.len(), |p| p + i);
+                match &content[i..end] {
+                    b"Tj" | b"TJ" => text.push_str(&pending),
+                    b"'" | b"\"" => {
+                        text.push('\n');
+                        text.push_str(&pending);
+                    }
+                    b"Td" | b"TD" | b"T*" | b"Tm" | b"ET"
+                        if !text.ends_with('\n') && !text.is_empty() =>
+                    {
+                        text.push('\n')
+                    }
+                    _ => {}
+                }
+                if !matches!(
+                    &content[i..end],
+                    b"Tc" | b"Tw" | b"Tz" | b"TL" | b"Tf" | b"Ts" | b"Tr"
+                ) {
+                    pending.clear();
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    #[test]
+    fn reads_info_xmp_and_first_page_text() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(b"BT /F1 12 Tf 72 700 Td (Parametric query) Tj [(opti) -20 (mization) -300 (today)] TJ 0 -14 Td (doi: 10.1007/s007780050037) Tj ET")
+            .unwrap();
+        let content = encoder.finish().unwrap();
+
+        let mut pdf = b"%PDF-1.4\n1 0 obj\n<< /Title (Microsoft Word - draft.docx) /Author (Yannis E. Ioannidis; Ng, Raymond) /CreationDate (D:19970501120000) >>\nendobj\n".to_vec();
+        pdf.extend_from_slice(b"2 0 obj\n<< /Type /Metadata /Subtype /XML >>\nstream\n<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\"><rdf:Description><dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">Parametric Query Optimization</rdf:li></rdf:Alt></dc:title></rdf:Description></rdf:RDF></x:xmpmeta>\nendstream\nendobj\n");
+        pdf.extend_from_slice(
+            format!(
+                "3 0 obj\n<< /Length {} /Filter /FlateDecode >>\nstream\n",
+                content.len()
+            )
+            .as_bytes(),
+        );
+        pdf.extend_from_slice(&content);
+        pdf.extend_from_slice(
+            b"\nendstream\nendobj\ntrailer\n<< /Root 4 0 R /Info 1 0 R >>\n%%EOF\n",
+        );
+
+        let metadata = parse(&pdf).unwrap();
+        assert_eq!(
+            metadata.title.as_deref(),
+            Some("Parametric Query Optimization")
+        );
+        assert_eq!(
+            metadata.authors,
+            vec!["Ioannidis, Yannis E.", "Ng, Raymond"]
+        );
+        assert_eq!(metadata.year.as_deref(), Some("1997"));
+        assert_eq!(metadata.doi.as_deref(), Some("10.1007/s007780050037"));
+        assert_eq!(
+            metadata.text,
+            "Parametric queryoptimization today\ndoi: 10.1007/s007780050037\n"
+        );
+
+        assert!(parse(b"<html>").is_err());
+    }
+
+    #[test]
+    fn splits_author_lists() {
+        assert_eq!(split_authors("Doe, Jane Quinn"), vec!["Doe, Jane Quinn"]);
+        assert_eq!(split_authors("Doe, Jane and Roe, Rick"), vec!["Doe, Jane", "Roe, Rick"]);
+        assert_eq!(split_authors("Jane Doe, Rick Roe and Zoe Zed"), vec!["Doe, Jane", "Roe, Rick", "Zed, Zoe"]);
+        assert_eq!(split_authors("Jane Doe & Rick Roe"), vec!["Doe, Jane", "Roe, Rick"]);
+        assert_eq!(split_authors("Plato"), vec!["Plato"]);
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+
+use crate::model::ris::{ReferenceType, RisEntry};
+use crate::services::http;
+
+const EFETCH_URL: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/efetch.fcgi";
+
+/// Fetches the MEDLINE record for a PubMed id from NCBI E-utilities.
+pub fn fetch(pmid: &str) -> Result<RisEntry> {
+    let pmid = pmid.trim().trim_start_matches("PMID:").trim();
+    if pmid.is_empty() || !pmid.chars().all(|c| c.is_ascii_digit()) {
+        bail!("{:?} is not a PubMed id", pmid);
+    }
+    let url = url::Url::parse_with_params(
+        EFETCH_URL,
+        &[("db", "pubmed"), ("id", pmid), ("rettype", "medline"), ("retmode", "text")],
+    )?;
+    let text = http::get_text(url.as_str())?;
+    let record = parse_medline(&text).with_context(|| format!("PubMed record {}", pmid))?;
+    Ok(record_to_entry(&record))
+}
+
+/// Splits a MEDLINE record into `(tag, value)` pairs. Tags are padded to four
+/// characters and followed by `- `; wrapped values continue on lines indented
+/// by six spaces.
+pub fn parse_medline(text: &str) -> Result<Vec<(String, String)>> {
+    let mut record: Vec<(String, String)> = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            // efetch separates records with blank lines; only the first is used.
+            if record.is_empty() {
+                continue;
+            }
+            break;
+        }
+        match line.get(4..6) {
+            Some("- ") if !line.starts_with(' ') => {
+                record.push((line[..4].trim().to_string(), line[6..].trim().to_string()));
+            }
+            _ => match record.last_mut() {
+                Some((_, value)) => {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                None => bail!("Expected a MEDLINE tag, found {:?}", line),
+            },
+        }
+    }
+
+    if !record.iter().any(|(tag, _)| tag == "PMID") {
+        bail!("No such PubMed record");
+    }
+    Ok(record)
+}
+
+/// Maps a MEDLINE record to a RIS journal article.
+pub fn record_to_entry(record: &[(String, String)]) -> RisEntry {
+    let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+    let mut add_field = |tag: &str, value: &str| {
+        if !value.is_empty() {
+            fields.entry(tag.to_string()).or_default().push(value.to_string());
+        }
+    };
+
+    // `FAU` holds `Doe, Jane Q`; `AU` only `Doe JQ`, so it's the fallback.
+    let has_full_names = record.iter().any(|(tag, _)| tag == "FAU");
+    let mut has_doi = false;
+    let mut has_issn = false;
+
+    for (tag, value) in record {
+        match tag.as_str() {
+            "FAU" => add_field("AU", value),
+            "AU" if !has_full_names => add_field("AU", value),
+            "TI" => add_field("TI", value.trim_end_matches('.')),
+            "AB" => add_field("AB", value),
+            "JT" => add_field("T2", value),
+            "TA" => add_field("J2", value),
+            "VI" => add_field("VL", value),
+            "IP" => add_field("IS", value),
+            "PG" => match value.split_once('-') {
+                Some((start, end)) => {
+                    add_field("SP", start.trim());
+                    add_field("EP", &expand_end_page(start.trim(), end.trim()));
+                }
+                None => add_field("SP", value),
+            },
+            "DP" => {
+                add_field("PY", value.get(..4).unwrap_or(value));
+                if let Some(date) = ris_date(value) {
+                    add_field("DA", &date);
+                }
+            }
+            "LID" | "AID" if !has_doi => {
+                if let Some(doi) = value.strip_suffix("[doi]") {
+                    add_field("DO", doi.trim());
+                    has_doi = true;
+                }
+            }
+            "IS" if !has_issn => {
+                // `1476-4687 (Electronic)`
+                add_field("SN", value.split(" (").next().unwrap_or(value));
+                has_issn = true;
+            }
+            // MeSH headings look like `*Cats/physiology`; keep the descriptor.
+            "MH" => add_field("KW", value.trim_start_matches('*').split('/').next().unwrap_or(value)),
+            "OT" => add_field("KW", value),
+            "LA" => add_field("LA", value),
+            "PMID" => {
+                add_field("AN", &format!("PMID:{}", value));
+                add_field("UR", &format!("https://pubmed.ncbi.nlm.nih.gov/{}/", value));
+            }
+            _ => {}
+        }
+    }
+
+    RisEntry {
+        ty: ReferenceType::Journal,
+        fields,
+    }
+}
+
+/// MEDLINE abbreviates end pages, e.g. `1123-9` for 1123–1129.
+fn expand_end_page(start: &str, end: &str) -> String {
+    let numeric = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    if numeric(start) && numeric(end) && end.len() < start.len() {
+        format!("{}{}", &start[..start.len() - end.len()], end)
+    } else {
+        end.to_string()
+    }
+}
+
+/// `2020 Mar 15` as the RIS date `2020/03/15`, or `2020/03` without a day.
+fn ris_date(published: &str) -> Option<String> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let mut parts = published.split_whitespace();
+    let year = parts.next()?;
+    let month = parts.next().and_then(|m| MONTHS.iter().position(|name| *name == m))?;
+    match parts.next().and_then(|d| d.parse::<u32>().ok()) {
+        Some(day) => Some(format!("{}/{:02}/{:02}", year, month + 1, day)),
+        None => Some(format!("{}/{:02}", year, month + 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORD: &str = "
+PMID- 12345678
+OWN - NLM
+IS  - 1476-4687 (Electronic)
+IS  - 0028-0836 (Linking)
+VI  - 580
+IP  - 7802
+DP  - 2020 Apr 2
+TI  - Cats and dogs: a
+      comparative study.
+PG  - 1123-9
+LID - 10.1038/s41586-020-0000-0 [doi]
+AB  - We study cats.
+FAU - Doe, Jane Q
+AU  - Doe JQ
+FAU - Roe, Rick
+AU  - Roe R
+LA  - eng
+TA  - Nature
+JT  - Nature
+MH  - Animals
+MH  - *Cats/physiology
+AID - 10.1038/s41586-020-0000-0 [doi]
+";
+
+    #[test]
+    fn maps_medline_record_to_journal_article() {
+        let entry = record_to_entry(&parse_medline(RECORD).unwrap());
+        assert_eq!(entry.ty, ReferenceType::Journal);
+        assert_eq!(entry.fields["AU"], vec!["Doe, Jane Q", "Roe, Rick"]);
+        assert_eq!(entry.fields["TI"], vec!["Cats and dogs: a comparative study"]);
+        assert_eq!(entry.fields["DA"], vec!["2020/04/02"]);
+        assert_eq!(entry.fields["SP"], vec!["1123"]);
+        assert_eq!(entry.fields["EP"], vec!["1129"]);
+        assert_eq!(entry.fields["DO"], vec!["10.1038/s41586-020-0000-0"]);
+        assert_eq!(entry.fields["SN"], vec!["1476-4687"]);
+        assert_eq!(entry.fields["KW"], vec!["Animals", "Cats"]);
+    }
+
+    #[test]
+    fn rejects_empty_response() {
+        assert!(parse_medline("\n\n").is_err());
+    }
+}
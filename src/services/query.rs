@@ -0,0 +1,486 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+
+use crate::model::date::Date;
+use crate::model::ris::{bibtex_fields, RisEntry};
+use crate::services::citations::CitationCount;
+use crate::services::citekey::last_name;
+use crate::services::library::LibraryEntry;
+use crate::services::tags;
+
+/// Order of `refrs list`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ListSort {
+    /// Reference id
+    #[default]
+    Id,
+    Year,
+    /// First author's last name, then year
+    Author,
+    Title,
+    /// Citation count, most cited first, as stored by `refrs citations`
+    Citations,
+}
+
+/// Inclusive range of publication years, either end open.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct YearRange {
+    pub from: Option<u32>,
+    pub to: Option<u32>,
+}
+
+impl YearRange {
+    /// `2020`, `2018-2020`, `2018..2020`, `2018-` or `..2020`.
+    pub fn parse(text: &str) -> Result<YearRange> {
+        let year = |part: &str| -> Result<Option<u32>> {
+            let part = part.trim();
+            if part.is_empty() {
+                return Ok(None);
+            }
+            match part.parse() {
+                Ok(year) => Ok(Some(year)),
+                Err(_) => bail!("Invalid year {:?}, expected e.g. 2020 or 2018-2020", part),
+            }
+        };
+        match text.split_once("..").or_else(|| text.split_once('-')) {
+            Some((from, to)) => Ok(YearRange { from: year(from)?, to: year(to)? }),
+            None => {
+                let year = year(text)?;
+                Ok(YearRange { from: year, to: year })
+            }
+        }
+    }
+
+    fn contains(&self, year: u32) -> bool {
+        self.from.map(|from| year >= from).unwrap_or(true) && self.to.map(|to| year <= to).unwrap_or(true)
+    }
+}
+
+/// A field a [`Filter`] compares.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Field {
+    /// The year of the publication date
+    Year,
+    /// RIS type (`JOUR`) or BibTeX type (`article`)
+    Type,
+    /// A keyword, which is how tags are stored
+    Keyword,
+    /// Any value of these RIS tags
+    Text(Vec<String>),
+}
+
+/// Names of the fields that stand for RIS tags. Other RIS tags can be
+/// given as they are, e.g. `VL==12`.
+const TEXT_FIELDS: &[(&str, &[&str])] = &[
+    ("author", &["AU", "A1"]),
+    ("editor", &["ED"]),
+    ("title", &["TI", "T1"]),
+    ("venue", &["T2", "JO", "JF", "BT"]),
+    ("journal", &["T2", "JO", "JF"]),
+    ("publisher", &["PB"]),
+    ("doi", &["DO"]),
+    ("abstract", &["AB", "N2"]),
+    ("note", &["N1"]),
+];
+
+impl Field {
+    fn parse(name: &str) -> Result<Field> {
+        let lower = name.to_lowercase();
+        match lower.as_str() {
+            "year" => return Ok(Field::Year),
+            "type" => return Ok(Field::Type),
+            "keyword" | "tag" => return Ok(Field::Keyword),
+            _ => {}
+        }
+        if let Some((_, tags)) = TEXT_FIELDS.iter().find(|(field, _)| *field == lower) {
+            return Ok(Field::Text(tags.iter().map(|tag| tag.to_string()).collect()));
+        }
+        let is_tag = name.len() == 2
+            && name.starts_with(|c: char| c.is_ascii_uppercase())
+            && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+        match is_tag {
+            true => Ok(Field::Text(vec![name.to_string()])),
+            false => bail!(
+                "Unknown field {:?}, expected year, type, keyword, {} or a RIS tag",
+                name,
+                TEXT_FIELDS.iter().map(|(field, _)| *field).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+/// How a [`Filter`] compares a field with a value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `~`: contains, ignoring case
+    Contains,
+}
+
+/// A condition on references such as `year>=2020 && type==JOUR`, for
+/// `--filter`. Conditions compare a field with a value and are combined
+/// with `&&`, `||`, `!` and parentheses; values with spaces are quoted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    Compare { field: Field, op: Op, value: String },
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        let mut two = |second: char| {
+            chars.next();
+            chars.next_if_eq(&second).is_some()
+        };
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' | ')' => {
+                chars.next();
+                if c == '(' { Token::Open } else { Token::Close }
+            }
+            '&' | '|' => {
+                if !two(c) {
+                    bail!("Expected {}{} in {:?}", c, c, text);
+                }
+                if c == '&' { Token::And } else { Token::Or }
+            }
+            '!' => if two('=') { Token::Op(Op::Ne) } else { Token::Not },
+            '=' => {
+                two('=');
+                Token::Op(Op::Eq)
+            }
+            '<' => Token::Op(if two('=') { Op::Le } else { Op::Lt }),
+            '>' => Token::Op(if two('=') { Op::Ge } else { Op::Gt }),
+            '~' => {
+                chars.next();
+                Token::Op(Op::Contains)
+            }
+            '"' => {
+                chars.next();
+                let word: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                Token::Word(word)
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"()&|!=<>~\"".contains(*c)) {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next_if(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.position) == Some(token);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Filter> {
+        let mut filter = self.and()?;
+        while self.next_if(&Token::Or) {
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter> {
+        let mut filter = self.unary()?;
+        while self.next_if(&Token::And) {
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> Result<Filter> {
+        if self.next_if(&Token::Not) {
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+        if self.next_if(&Token::Open) {
+            let filter = self.or()?;
+            if !self.next_if(&Token::Close) {
+                bail!("Missing )");
+            }
+            return Ok(filter);
+        }
+
+        let tokens = self.tokens.get(self.position..self.position + 3);
+        let Some([Token::Word(field), Token::Op(op), Token::Word(value)]) = tokens else {
+            bail!("Expected a condition such as year>=2020");
+        };
+        self.position += 3;
+        let (field, op) = (Field::parse(field)?, *op);
+        let ordered = matches!(op, Op::Lt | Op::Le | Op::Gt | Op::Ge);
+        match field {
+            Field::Year if op == Op::Contains => bail!("year can't be compared with ~"),
+            Field::Year if value.trim().parse::<i32>().is_err() => bail!("Invalid year {:?}", value),
+            Field::Type if op == Op::Contains || ordered => bail!("type can only be compared with == or !="),
+            Field::Keyword | Field::Text(_) if ordered => bail!("Only year can be compared with <, <=, > and >="),
+            _ => {}
+        }
+        Ok(Filter::Compare { field, op, value: value.clone() })
+    }
+}
+
+impl Filter {
+    pub fn parse(text: &str) -> Result<Filter> {
+        let mut parser = Parser { tokens: tokenize(text)?, position: 0 };
+        let filter = parser.or()?;
+        if parser.position < parser.tokens.len() {
+            bail!("Unexpected {:?} in filter {:?}", parser.tokens[parser.position], text);
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, entry: &RisEntry) -> bool {
+        match self {
+            Filter::Not(filter) => !filter.matches(entry),
+            Filter::And(a, b) => a.matches(entry) && b.matches(entry),
+            Filter::Or(a, b) => a.matches(entry) || b.matches(entry),
+            Filter::Compare { field, op, value } => {
+                let equal = |a: &str| a.trim().eq_ignore_ascii_case(value.trim());
+                let values: Vec<&String> = match field {
+                    Field::Year => {
+                        let (Some(date), Ok(year)) = (Date::of(entry), value.trim().parse::<i32>()) else {
+                            return false;
+                        };
+                        return match op {
+                            Op::Eq | Op::Contains => date.year == year,
+                            Op::Ne => date.year != year,
+                            Op::Lt => date.year < year,
+                            Op::Le => date.year <= year,
+                            Op::Gt => date.year > year,
+                            Op::Ge => date.year >= year,
+                        };
+                    }
+                    Field::Type => {
+                        let (bibtex_type, _) = bibtex_fields(entry);
+                        let same = equal(entry.ty.to_str()) || equal(bibtex_type);
+                        return if *op == Op::Ne { !same } else { same };
+                    }
+                    Field::Keyword => tags::tags(entry).iter().collect(),
+                    Field::Text(tags) => tags.iter().flat_map(|tag| entry.fields.get(tag).into_iter().flatten()).collect(),
+                };
+                match op {
+                    Op::Contains => {
+                        let value = value.trim().to_lowercase();
+                        values.iter().any(|v| v.to_lowercase().contains(&value))
+                    }
+                    Op::Ne => !values.iter().any(|v| equal(v)),
+                    _ => values.iter().any(|v| equal(v)),
+                }
+            }
+        }
+    }
+}
+
+/// Filters and order shared by commands that list references. Text filters
+/// ignore case; unset filters match everything.
+#[derive(Debug, Default)]
+pub struct Query {
+    /// Part of any author's name
+    pub author: Option<String>,
+    pub year: Option<YearRange>,
+    /// RIS type (`JOUR`) or BibTeX type (`article`)
+    pub ty: Option<String>,
+    /// A whole keyword, which is how tags are stored
+    pub keyword: Option<String>,
+    pub filter: Option<Filter>,
+    pub sort: ListSort,
+}
+
+impl Query {
+    pub fn matches(&self, entry: &RisEntry) -> bool {
+        let values = |tag: &str| entry.fields.get(tag).into_iter().flatten();
+
+        if let Some(author) = &self.author {
+            let author = author.to_lowercase();
+            if !values("AU").any(|a| a.to_lowercase().contains(&author)) {
+                return false;
+            }
+        }
+        if let Some(range) = &self.year {
+            let year = Date::of(entry).and_then(|date| u32::try_from(date.year).ok());
+            if !year.map(|year| range.contains(year)).unwrap_or(false) {
+                return false;
+            }
+        }
+        if let Some(ty) = &self.ty {
+            let (bibtex_type, _) = bibtex_fields(entry);
+            if !entry.ty.to_str().eq_ignore_ascii_case(ty) && !bibtex_type.eq_ignore_ascii_case(ty) {
+                return false;
+            }
+        }
+        if let Some(keyword) = &self.keyword {
+            if !tags::has_tag(entry, keyword) {
+                return false;
+            }
+        }
+        if let Some(filter) = &self.filter {
+            if !filter.matches(entry) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The matching entries, in the query's order.
+    pub fn run(&self, entries: Vec<LibraryEntry>) -> Vec<LibraryEntry> {
+        let mut entries: Vec<LibraryEntry> = entries.into_iter().filter(|e| self.matches(&e.entry)).collect();
+        sort_entries(&mut entries, self.sort, false);
+        entries
+    }
+}
+
+/// Sorts references by `order`, ties broken by title and then id so the
+/// result doesn't depend on the input order. `descending` only reverses
+/// the primary order; ties stay in ascending title order.
+pub fn sort_entries<E: Borrow<LibraryEntry>>(entries: &mut [E], order: ListSort, descending: bool) {
+    let field = |entry: &LibraryEntry, tag: &str| {
+        entry.entry.get_field(tag).map(|v| v.to_lowercase()).unwrap_or_default()
+    };
+    let year = |entry: &LibraryEntry| Date::of(&entry.entry);
+    let author = |entry: &LibraryEntry| {
+        entry.entry.get_field("AU").map(|a| last_name(a).to_lowercase()).unwrap_or_default()
+    };
+    let citations = |entry: &LibraryEntry| CitationCount::of(&entry.entry).map(|count| count.count);
+    let primary = |a: &LibraryEntry, b: &LibraryEntry| -> Ordering {
+        match order {
+            ListSort::Id => a.id.cmp(&b.id),
+            ListSort::Year => year(a).cmp(&year(b)),
+            ListSort::Author => (author(a), year(a)).cmp(&(author(b), year(b))),
+            ListSort::Title => Ordering::Equal,
+            ListSort::Citations => citations(b).cmp(&citations(a)),
+        }
+    };
+
+    entries.sort_by(|a, b| {
+        let (a, b) = (a.borrow(), b.borrow());
+        let ordering = primary(a, b);
+        let ordering = if descending { ordering.reverse() } else { ordering };
+        let title = field(a, "TI").cmp(&field(b, "TI"));
+        let title = if descending && order == ListSort::Title { title.reverse() } else { title };
+        ordering.then(title).then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ris::parse_ris;
+    use crate::services::citations::CITATIONS_TAG;
+
+    fn library() -> Vec<LibraryEntry> {
+        let ris = "TY  - JOUR\nAU  - Roe, Rick\nTI  - B\nPY  - 2019\nKW  - Graphs\nER  -\n\
+                   TY  - BOOK\nAU  - Doe, Jane\nTI  - A\nPY  - 2021\nER  -\n\
+                   TY  - JOUR\nAU  - Doe, John\nTI  - C\nPY  - 2018\nKW  - graphs\nER  -\n";
+        parse_ris(ris)
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| LibraryEntry {
+                id: format!("e{}", i),
+                path: format!("e{}.ris", i).into(),
+                entry,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn filters_and_sorts() {
+        let ids = |query: &Query| -> Vec<String> { query.run(library()).into_iter().map(|e| e.id).collect() };
+
+        let query = Query { ty: Some("article".to_string()), sort: ListSort::Year, ..Query::default() };
+        assert_eq!(ids(&query), vec!["e2", "e0"]);
+
+        let query = Query {
+            author: Some("doe".to_string()),
+            year: Some(YearRange::parse("2020-").unwrap()),
+            ..Query::default()
+        };
+        assert_eq!(ids(&query), vec!["e1"]);
+
+        let query = Query { keyword: Some("GRAPHS".to_string()), sort: ListSort::Author, ..Query::default() };
+        assert_eq!(ids(&query), vec!["e2", "e0"]);
+    }
+
+    #[test]
+    fn sorts_ties_by_title() {
+        let mut entries = library();
+        entries[1].entry.fields.insert("PY".to_string(), vec!["2019".to_string()]);
+        let ids = |entries: &[&LibraryEntry]| -> Vec<String> { entries.iter().map(|e| e.id.clone()).collect() };
+
+        let mut refs: Vec<&LibraryEntry> = entries.iter().collect();
+        sort_entries(&mut refs, ListSort::Year, true);
+        assert_eq!(ids(&refs), vec!["e1", "e0", "e2"]);
+
+        sort_entries(&mut refs, ListSort::Title, true);
+        assert_eq!(ids(&refs), vec!["e2", "e0", "e1"]);
+
+        entries[0].entry.fields.insert(CITATIONS_TAG.to_string(), vec!["12 (2026/10/16)".to_string()]);
+        entries[2].entry.fields.insert(CITATIONS_TAG.to_string(), vec!["130 (2026/10/16)".to_string()]);
+        let mut refs: Vec<&LibraryEntry> = entries.iter().collect();
+        sort_entries(&mut refs, ListSort::Citations, false);
+        assert_eq!(ids(&refs), vec!["e2", "e0", "e1"]);
+    }
+
+    #[test]
+    fn filters_by_expression() {
+        let ids = |filter: &str| -> Vec<String> {
+            let query = Query { filter: Some(Filter::parse(filter).unwrap()), ..Query::default() };
+            query.run(library()).into_iter().map(|e| e.id).collect()
+        };
+
+        assert_eq!(ids("year>=2019 && type==JOUR"), vec!["e0"]);
+        assert_eq!(ids("type==book || (tag==graphs && !year<2019)"), vec!["e0", "e1"]);
+        assert_eq!(ids(r#"author~"doe, j" && TI!=A"#), vec!["e2"]);
+        assert!(Filter::parse("title>A").is_err());
+        assert!(Filter::parse("year>=2020 &&").is_err());
+        assert!(Filter::parse("colour==red").is_err());
+    }
+
+    #[test]
+    fn parses_year_ranges() {
+        assert_eq!(YearRange::parse("2020").unwrap(), YearRange { from: Some(2020), to: Some(2020) });
+        assert_eq!(YearRange::parse("-2020").unwrap(), YearRange { from: None, to: Some(2020) });
+        assert_eq!(YearRange::parse("2020..2023").unwrap(), YearRange { from: Some(2020), to: Some(2023) });
+        assert!(YearRange::parse("last year").is_err());
+    }
+}
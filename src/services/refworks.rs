@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::model::date::Date;
+use crate::model::ris::{month_number, ReferenceType, RisEntry};
+
+/// Whether a line starts a field: two capitals or digits, then a space.
+fn split_tag(line: &str) -> Option<(&str, &str)> {
+    let tag = line.get(..2)?;
+    let rest = line.get(2..)?;
+    let is_tag = tag.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    (is_tag && (rest.is_empty() || rest.starts_with(' '))).then(|| (tag, rest.trim()))
+}
+
+/// Whether `text` starts like a RefWorks tagged export.
+pub fn is_refworks(text: &str) -> bool {
+    text.lines()
+        .find(|line| !line.trim().is_empty())
+        .and_then(split_tag)
+        .is_some_and(|(tag, _)| tag == "RT")
+}
+
+/// Parses RefWorks' tagged export: one `XX value` line per field, each
+/// record starting with its `RT` reference type.
+pub fn parse(text: &str) -> Result<Vec<RisEntry>> {
+    let mut records: Vec<Vec<(&str, String)>> = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            continue;
+        }
+        match split_tag(line) {
+            Some(("RT", value)) => records.push(vec![("RT", value.to_string())]),
+            Some((tag, value)) => match records.last_mut() {
+                Some(record) => record.push((tag, value.to_string())),
+                None => bail!("Line {}: expected a record to start with RT, found {:?}", number + 1, line),
+            },
+            // An untagged line continues the value of the field before it.
+            None => match records.last_mut().and_then(|record| record.last_mut()) {
+                Some((_, value)) => {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                None => bail!("Line {}: expected a tag, found {:?}", number + 1, line),
+            },
+        }
+    }
+
+    Ok(records.iter().map(|record| record_to_entry(record)).collect())
+}
+
+fn record_to_entry(record: &[(&str, String)]) -> RisEntry {
+    let mut ty = ReferenceType::Generic;
+    let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+    let mut add_field = |tag: &str, value: &str| {
+        if !value.is_empty() {
+            fields.entry(tag.to_string()).or_default().push(value.to_string());
+        }
+    };
+
+    let mut full_date = None;
+    for (tag, value) in record {
+        match *tag {
+            "RT" => ty = reference_type(value),
+            "A1" => add_field("AU", value),
+            "A2" | "A3" => add_field("A2", value),
+            "T1" => add_field("TI", value),
+            "JF" | "T2" => add_field("T2", value),
+            "JO" => add_field("J2", value),
+            "YR" => add_field("PY", value),
+            "FD" => full_date = Some(value.as_str()),
+            "VO" => add_field("VL", value),
+            "IS" => add_field("IS", value),
+            "SP" => add_field("SP", value),
+            "OP" => add_field("EP", value),
+            "PB" => add_field("PB", value),
+            "PP" => add_field("CY", value),
+            "ED" => add_field("ET", value),
+            "SN" => add_field("SN", value),
+            "DO" => add_field("DO", value),
+            "UL" | "LK" => add_field("UR", value),
+            "AB" => add_field("AB", value),
+            "K1" => value.split(';').for_each(|keyword| add_field("KW", keyword.trim())),
+            "NO" => add_field("N1", value),
+            "LA" => add_field("LA", value),
+            // RefWorks numbers its records; the number is no citation key.
+            "ID" => add_field("AN", &format!("RefWorks:{}", value)),
+            _ => {}
+        }
+    }
+
+    // `FD` is a full date, or often just the month and day of the `YR` year.
+    let year = fields.get("PY").and_then(|years| Date::parse(&years[0]));
+    let date = full_date.and_then(|text| match Date::parse(text) {
+        Some(date) if date.month.is_some() => Some(date),
+        _ => {
+            let mut words = text.split_whitespace();
+            let month = month_number(words.next()?)?;
+            let day = words.next().and_then(|day| day.trim_end_matches(',').parse().ok());
+            Some(Date { month: Some(month), day, ..year? })
+        }
+    });
+    if let Some(date) = date {
+        fields.insert("DA".to_string(), vec![date.to_string()]);
+    }
+
+    RisEntry { ty, fields }
+}
+
+fn reference_type(name: &str) -> ReferenceType {
+    match name {
+        "Journal Article" | "Journal, Electronic" => ReferenceType::Journal,
+        "Magazine Article" => ReferenceType::MagazineArticle,
+        "Newspaper Article" => ReferenceType::Newspaper,
+        "Book, Whole" | "Book, Edited" => ReferenceType::Book,
+        "Book, Section" => ReferenceType::Chart,
+        "Conference Proceedings" => ReferenceType::ConferencePaper,
+        "Dissertation/Thesis" | "Dissertation/Thesis, Unpublished" => ReferenceType::Thesis,
+        "Report" => ReferenceType::Report,
+        "Web Page" => ReferenceType::ElectronicArticle,
+        "Patent" => ReferenceType::Patent,
+        "Computer Program" => ReferenceType::ComputerProgram,
+        "Map" => ReferenceType::Map,
+        "Unpublished Material" => ReferenceType::UnpublishedWork,
+        _ => ReferenceType::Generic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_records_starting_with_rt() {
+        let text = "RT Journal Article\nSR Electronic(1)\nID 17\nA1 Pugh, William\nT1 Skip lists: a probabilistic\n  alternative\nJF Communications of the ACM\nYR 1990\nFD Jun 15\nSP 668\nOP 676\nK1 data structures; searching\nRT Book, Whole\nT1 Other\n";
+
+        assert!(is_refworks(text));
+        let entries = parse(text).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].ty, ReferenceType::Journal);
+        assert_eq!(entries[0].fields["TI"], vec!["Skip lists: a probabilistic alternative"]);
+        assert_eq!(entries[0].fields["DA"], vec!["1990/06/15"]);
+        assert_eq!(entries[0].fields["KW"], vec!["data structures", "searching"]);
+        assert_eq!(entries[0].fields["EP"], vec!["676"]);
+        assert_eq!(entries[0].fields["AN"], vec!["RefWorks:17"]);
+        assert!(!entries[0].fields.contains_key("ID"));
+        assert_eq!(entries[1].ty, ReferenceType::Book);
+        assert!(parse("A1 Pugh, William").is_err());
+    }
+}
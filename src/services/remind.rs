@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use crate::services::library::LibraryEntry;
+
+/// Keyword marking an entry as currently being read.
+pub const READING: &str = "reading";
+/// Keyword marking an entry as read.
+pub const READ: &str = "read";
+/// Keyword marking an entry as high priority.
+pub const HIGH_PRIORITY: &str = "priority:high";
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Reminder {
+    /// Marked reading this many days ago.
+    StaleReading { id: String, title: String, days: i64 },
+    /// High priority and not yet read.
+    UnreadPriority { id: String, title: String },
+}
+
+fn has_keyword(entry: &LibraryEntry, keyword: &str) -> bool {
+    entry
+        .entry
+        .fields
+        .get("KW")
+        .is_some_and(|keywords| keywords.iter().any(|k| k.trim().eq_ignore_ascii_case(keyword)))
+}
+
+fn title(entry: &LibraryEntry) -> String {
+    entry
+        .entry
+        .get_field("TI")
+        .cloned()
+        .unwrap_or_else(|| entry.id.clone())
+}
+
+/// Entries marked reading for more than `max_days`, oldest first, followed by
+/// unread high-priority entries. `marked` holds when each entry was marked
+/// reading; entries missing from it were marked after the last commit.
+pub fn reminders(
+    entries: &[LibraryEntry],
+    marked: &HashMap<String, i64>,
+    now: i64,
+    max_days: i64,
+) -> Vec<Reminder> {
+    let mut stale: Vec<Reminder> = entries
+        .iter()
+        .filter(|e| has_keyword(e, READING))
+        .filter_map(|e| {
+            let days = (now - marked.get(&e.id)?) / SECONDS_PER_DAY;
+            (days > max_days).then(|| Reminder::StaleReading {
+                id: e.id.clone(),
+                title: title(e),
+                days,
+            })
+        })
+        .collect();
+    stale.sort_by_key(|r| match r {
+        Reminder::StaleReading { days, .. } => -days,
+        Reminder::UnreadPriority { .. } => 0,
+    });
+
+    let unread = entries
+        .iter()
+        .filter(|e| has_keyword(e, HIGH_PRIORITY))
+        .filter(|e| !has_keyword(e, READ) && !has_keyword(e, READING))
+        .map(|e| Reminder::UnreadPriority {
+            id: e.id.clone(),
+            title: title(e),
+        });
+
+    stale.extend(unread);
+    stale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ris::{ReferenceType, RisEntry};
+    use std::path::PathBuf;
+
+    fn entry(id: &str, keywords: &[&str]) -> LibraryEntry {
+        let mut fields = HashMap::new();
+        fields.insert("TI".to_string(), vec![format!("Title {}", id)]);
+        fields.insert("KW".to_string(), keywords.iter().map(|k| k.to_string()).collect());
+        LibraryEntry {
+            id: id.to_string(),
+            path: PathBuf::from(format!("{}.ris", id)),
+            entry: RisEntry {
+                ty: ReferenceType::Journal,
+                fields,
+            },
+        }
+    }
+
+    #[test]
+    fn reports_stale_reading_and_unread_priority() {
+        let entries = vec![
+            entry("fresh", &["reading"]),
+            entry("stale", &["Reading"]),
+            entry("uncommitted", &["reading"]),
+            entry("urgent", &["priority:high"]),
+            entry("done", &["priority:high", "read"]),
+        ];
+        let now = 100 * SECONDS_PER_DAY;
+        let marked = HashMap::from([
+            ("fresh".to_string(), now - 3 * SECONDS_PER_DAY),
+            ("stale".to_string(), now - 30 * SECONDS_PER_DAY),
+        ]);
+
+        assert_eq!(
+            reminders(&entries, &marked, now, 14),
+            vec![
+                Reminder::StaleReading {
+                    id: "stale".to_string(),
+                    title: "Title stale".to_string(),
+                    days: 30,
+                },
+                Reminder::UnreadPriority {
+                    id: "urgent".to_string(),
+                    title: "Title urgent".to_string(),
+                },
+            ]
+        );
+    }
+}
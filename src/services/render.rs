@@ -0,0 +1,945 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use roxmltree::{Document, Node};
+
+use crate::model::ris::RisEntry;
+use crate::services::citekey::year_of;
+use crate::services::csl_json::csl_type;
+use crate::services::styles;
+
+/// Styles that work without installing anything.
+pub const BUILTIN_STYLES: [(&str, &str); 2] = [
+    ("apa", include_str!("../../styles/apa.csl")),
+    ("ieee", include_str!("../../styles/ieee.csl")),
+];
+
+/// Markup of a rendered bibliography.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum RenderFormat {
+    #[default]
+    Text,
+    Markdown,
+    Html,
+}
+
+impl RenderFormat {
+    fn escape(self, text: &str) -> String {
+        match self {
+            RenderFormat::Text => text.to_string(),
+            RenderFormat::Markdown => text
+                .chars()
+                .flat_map(|c| match c {
+                    '\\' | '*' | '_' | '[' | ']' | '`' => vec!['\\', c],
+                    c => vec![c],
+                })
+                .collect(),
+            RenderFormat::Html => text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;"),
+        }
+    }
+
+    fn italic(self, text: String) -> String {
+        match self {
+            RenderFormat::Text => text,
+            RenderFormat::Markdown => format!("*{}*", text),
+            RenderFormat::Html => format!("<i>{}</i>", text),
+        }
+    }
+
+    fn bold(self, text: String) -> String {
+        match self {
+            RenderFormat::Text => text,
+            RenderFormat::Markdown => format!("**{}**", text),
+            RenderFormat::Html => format!("<b>{}</b>", text),
+        }
+    }
+
+    fn bibliography(self, entries: &[String]) -> String {
+        match self {
+            RenderFormat::Text => entries.iter().map(|e| format!("{}\n", e)).collect(),
+            RenderFormat::Markdown => entries.join("\n\n") + "\n",
+            RenderFormat::Html => {
+                let mut html = String::from("<div class=\"csl-bib-body\">\n");
+                for entry in entries {
+                    html.push_str(&format!("  <div class=\"csl-entry\">{}</div>\n", entry));
+                }
+                html.push_str("</div>\n");
+                html
+            }
+        }
+    }
+}
+
+/// The CSL source of a style: a `.csl` file, an installed style, or a
+/// built-in one, looked up in that order.
+pub fn load_style(name_or_path: &str) -> Result<String> {
+    let path = Path::new(name_or_path);
+    if path.extension().is_some_and(|ext| ext == "csl") && path.exists() {
+        return fs::read_to_string(path).with_context(|| format!("Failed to read {}", name_or_path));
+    }
+    if let Some(path) = styles::style_path(name_or_path) {
+        return Ok(fs::read_to_string(path)?);
+    }
+    let name = name_or_path.trim_end_matches(".csl");
+    BUILTIN_STYLES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, source)| source.to_string())
+        .ok_or_else(|| {
+            anyhow!(
+                "No style named {}. Install it with `refrs styles install {}` or use one of: {}",
+                name,
+                name,
+                BUILTIN_STYLES.map(|(name, _)| name).join(", ")
+            )
+        })
+}
+
+/// Formats `(key, entry)` pairs with the bibliography layout of a CSL style.
+/// Supports the commonly used subset of CSL 1.0: macros, `text`, `number`,
+/// `label`, `date`, `names` with substitution, `group`, `choose` and sorting.
+pub fn render(style: &str, entries: &[(String, RisEntry)], format: RenderFormat) -> Result<String> {
+    let document = Document::parse(style).context("Invalid CSL style")?;
+    let root = document.root_element();
+    let bibliography = child(root, "bibliography").ok_or_else(|| anyhow!("The style has no bibliography"))?;
+    let layout = child(bibliography, "layout").ok_or_else(|| anyhow!("The bibliography has no layout"))?;
+    let terms = Terms::load(root);
+    let renderer = Renderer {
+        macros: root
+            .children()
+            .filter(|n| n.has_tag_name_local("macro"))
+            .filter_map(|n| Some((n.attribute("name")?, n)))
+            .collect(),
+        terms: &terms,
+        format,
+    };
+
+    let mut items: Vec<Item> = entries
+        .iter()
+        .map(|(key, entry)| Item { key, entry, number: 0 })
+        .collect();
+    if let Some(sort) = child(bibliography, "sort") {
+        let keys: Vec<Node> = sort.children().filter(|n| n.has_tag_name_local("key")).collect();
+        let values: Vec<Vec<String>> = items
+            .iter()
+            .map(|item| keys.iter().map(|key| renderer.sort_value(*key, item)).collect())
+            .collect();
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        order.sort_by(|&a, &b| {
+            keys.iter()
+                .enumerate()
+                .map(|(k, key)| {
+                    let ordering = compare_sort_values(&values[a][k], &values[b][k]);
+                    if key.attribute("sort") == Some("descending") {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                })
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        items = order.into_iter().map(|i| items[i].clone()).collect();
+    }
+    for (i, item) in items.iter_mut().enumerate() {
+        item.number = i + 1;
+    }
+
+    let rendered: Vec<String> = items
+        .iter()
+        .map(|item| {
+            let mut context = Scope::new(item);
+            let body = renderer.render_children(layout, &mut context, layout.attribute("delimiter"));
+            renderer.decorate(layout, body)
+        })
+        .collect();
+    Ok(format.bibliography(&rendered))
+}
+
+/// Empty sort values go last, whatever the direction.
+fn compare_sort_values(a: &str, b: &str) -> Ordering {
+    match (a.is_empty(), b.is_empty()) {
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        _ => a.cmp(b),
+    }
+}
+
+trait LocalName {
+    fn has_tag_name_local(&self, name: &str) -> bool;
+}
+
+impl LocalName for Node<'_, '_> {
+    fn has_tag_name_local(&self, name: &str) -> bool {
+        self.is_element() && self.tag_name().name() == name
+    }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name_local(name))
+}
+
+#[derive(Clone)]
+struct Item<'e> {
+    key: &'e str,
+    entry: &'e RisEntry,
+    /// Position in the sorted bibliography, for numeric styles
+    number: usize,
+}
+
+struct Name {
+    family: String,
+    given: Option<String>,
+}
+
+impl<'e> Item<'e> {
+    fn field(&self, tags: &[&str]) -> Option<String> {
+        tags.iter()
+            .find_map(|tag| self.entry.get_field(tag))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn variable(&self, name: &str, short: bool) -> Option<String> {
+        match name {
+            "title" if short => self.field(&["ST"]).or_else(|| {
+                let title = self.field(&["TI", "T1"])?;
+                Some(title.split(':').next().unwrap_or(&title).trim().to_string())
+            }),
+            "title" => self.field(&["TI", "T1"]),
+            "title-short" => self.variable("title", true),
+            "container-title" if short => self.field(&["J2", "T2", "JO", "JF", "BT"]),
+            "container-title" => self.field(&["T2", "JO", "JF", "BT"]),
+            "container-title-short" => self.variable("container-title", true),
+            "collection-title" => self.field(&["T3"]),
+            "publisher" => self.field(&["PB"]),
+            "publisher-place" => self.field(&["CY"]),
+            "volume" => self.field(&["VL"]),
+            "issue" => self.field(&["IS"]),
+            "edition" => self.field(&["ET"]),
+            "page" => match (self.field(&["SP"]), self.field(&["EP"])) {
+                (Some(start), Some(end)) => Some(format!("{}–{}", start, end)),
+                (start, _) => start,
+            },
+            "page-first" => self.field(&["SP"]),
+            "DOI" => self.field(&["DO"]),
+            "URL" => self.field(&["UR"]),
+            "ISBN" | "ISSN" => self.field(&["SN"]),
+            "abstract" => self.field(&["AB"]),
+            "note" => self.field(&["N1"]),
+            "language" => self.field(&["LA"]),
+            "genre" => self.field(&["M3"]),
+            "citation-number" => Some(self.number.to_string()),
+            "citation-key" => Some(self.key.to_string()),
+            _ => None,
+        }
+    }
+
+    fn names(&self, variable: &str) -> Vec<Name> {
+        let tags: &[&str] = match variable {
+            "author" => &["AU", "A1"],
+            "editor" => &["A2", "ED"],
+            "collection-editor" => &["A3"],
+            "translator" => &["A4"],
+            _ => &[],
+        };
+        tags.iter()
+            .flat_map(|tag| self.entry.fields.get(*tag).into_iter().flatten())
+            .filter(|name| !name.trim().is_empty())
+            .map(|name| match name.split_once(',') {
+                Some((family, given)) => Name {
+                    family: family.trim().to_string(),
+                    given: Some(given.trim().to_string()).filter(|g| !g.is_empty()),
+                },
+                None => Name {
+                    family: name.trim().to_string(),
+                    given: None,
+                },
+            })
+            .collect()
+    }
+
+    /// Year, month and day of a date variable.
+    fn date(&self, variable: &str) -> Option<(String, Option<u32>, Option<u32>)> {
+        if variable != "issued" {
+            return None;
+        }
+        let mut parts = self
+            .entry
+            .get_field("DA")
+            .map(|date| date.split('/').map(|p| p.trim().parse::<u32>().ok()).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter();
+        match parts.next().flatten() {
+            Some(year) if year > 999 => {
+                let month = parts.next().flatten().filter(|m| (1..=12).contains(m));
+                let day = parts.next().flatten().filter(|d| month.is_some() && (1..=31).contains(d));
+                Some((year.to_string(), month, day))
+            }
+            _ => Some((year_of(self.entry.get_field("PY")?)?, None, None)),
+        }
+    }
+
+    fn has_variable(&self, name: &str) -> bool {
+        self.variable(name, false).is_some() || !self.names(name).is_empty() || self.date(name).is_some()
+    }
+}
+
+/// Per-entry rendering state.
+struct Scope<'i, 'e> {
+    item: &'i Item<'e>,
+    /// Variables already used as a substitute, which render empty afterwards.
+    suppressed: HashSet<String>,
+    /// Variables looked up and variables found, for suppressing empty groups.
+    called: usize,
+    found: usize,
+}
+
+impl<'i, 'e> Scope<'i, 'e> {
+    fn new(item: &'i Item<'e>) -> Self {
+        Scope {
+            item,
+            suppressed: HashSet::new(),
+            called: 0,
+            found: 0,
+        }
+    }
+
+    fn lookup<T>(&mut self, variable: &str, value: Option<T>) -> Option<T> {
+        self.called += 1;
+        let value = value.filter(|_| !self.suppressed.contains(variable));
+        if value.is_some() {
+            self.found += 1;
+        }
+        value
+    }
+}
+
+/// CSL terms such as `and` or `pp.`, by name and form, singular and plural.
+/// They come from the style's own `<locale>` elements, else from the
+/// installed locale file of the style's language, else refrs' English ones.
+struct Terms {
+    terms: HashMap<(String, String), (String, String)>,
+}
+
+impl Terms {
+    fn load(style: Node) -> Terms {
+        let mut terms = Terms::of_style(style);
+        let language = style.attribute("default-locale").unwrap_or("en-US");
+        if let Some(source) = installed_locale(language) {
+            if let Ok(document) = Document::parse(&source) {
+                terms.add(document.root_element());
+            }
+        }
+        terms
+    }
+
+    /// Just the terms the style defines for its own language.
+    fn of_style(style: Node) -> Terms {
+        let language = style.attribute("default-locale").unwrap_or("en-US");
+        let mut terms = Terms { terms: HashMap::new() };
+        // The first definition of a term wins, so the style's go first.
+        for locale in style.children().filter(|n| n.has_tag_name_local("locale")) {
+            let lang = locale.attribute(("http://www.w3.org/XML/1998/namespace", "lang"));
+            if lang.is_none_or(|lang| language.starts_with(lang)) {
+                terms.add(locale);
+            }
+        }
+        terms
+    }
+
+    fn add(&mut self, locale: Node) {
+        let Some(list) = child(locale, "terms") else {
+            return;
+        };
+        for term in list.children().filter(|n| n.has_tag_name_local("term")) {
+            let Some(name) = term.attribute("name") else {
+                continue;
+            };
+            let form = term.attribute("form").unwrap_or("long");
+            let text = |tag: &str| child(term, tag).map(|n| n.text().unwrap_or_default());
+            let (single, multiple) = match (text("single"), text("multiple")) {
+                (Some(single), multiple) => (single, multiple.unwrap_or(single)),
+                (None, _) => (term.text().unwrap_or_default(), term.text().unwrap_or_default()),
+            };
+            self.terms
+                .entry((name.to_string(), form.to_string()))
+                .or_insert_with(|| (single.to_string(), multiple.to_string()));
+        }
+    }
+
+    /// The term in `form`, falling back to shorter forms and then to the
+    /// long one as CSL does.
+    fn get(&self, term: &str, form: &str, plural: bool) -> Option<String> {
+        let forms: &[&str] = match form {
+            "symbol" => &["symbol", "short", "long"],
+            "verb-short" => &["verb-short", "verb", "long"],
+            "short" => &["short", "long"],
+            "verb" => &["verb", "long"],
+            _ => &["long"],
+        };
+        forms
+            .iter()
+            .find_map(|form| self.terms.get(&(term.to_string(), form.to_string())))
+            .map(|(single, multiple)| if plural { multiple.clone() } else { single.clone() })
+            .or_else(|| english_term(term, form, plural).map(str::to_string))
+    }
+
+    fn month(&self, month: u32, form: &str) -> String {
+        self.get(&format!("month-{:02}", month), form, false)
+            .unwrap_or_else(|| month_name(month, form).to_string())
+    }
+}
+
+/// The installed CSL locale file for `language`, such as `locales-en-US.xml`,
+/// or for a language without a region any file of that language.
+fn installed_locale(language: &str) -> Option<String> {
+    let exact = styles::locales_dir().join(format!("locales-{}.xml", language));
+    if exact.exists() {
+        return fs::read_to_string(exact).ok();
+    }
+    let prefix = format!("locales-{}-", language.split('-').next()?);
+    let path = fs::read_dir(styles::locales_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with(&prefix)))?;
+    fs::read_to_string(path).ok()
+}
+
+struct Renderer<'a, 'input> {
+    macros: HashMap<&'a str, Node<'a, 'input>>,
+    terms: &'a Terms,
+    format: RenderFormat,
+}
+
+impl<'a, 'input> Renderer<'a, 'input> {
+    fn render_children(&self, node: Node, context: &mut Scope, delimiter: Option<&str>) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        for child in node.children().filter(Node::is_element) {
+            let mut text = self.render(child, context);
+            if text.is_empty() {
+                continue;
+            }
+            if child.attribute("display") == Some("left-margin") {
+                text.push(' ');
+            }
+            parts.push(text);
+        }
+        parts.join(&self.format.escape(delimiter.unwrap_or("")))
+    }
+
+    fn render(&self, node: Node, context: &mut Scope) -> String {
+        let text = match node.tag_name().name() {
+            "text" => self.render_text(node, context),
+            "number" => {
+                let variable = node.attribute("variable").unwrap_or_default();
+                let value = context.lookup(variable, context.item.variable(variable, false));
+                value.map(|v| self.leaf(node, &v)).unwrap_or_default()
+            }
+            "label" => self.render_label(node, context, None),
+            "date" => self.render_date(node, context),
+            "names" => self.render_names(node, context, None),
+            "group" => {
+                let (called, found) = (context.called, context.found);
+                context.called = 0;
+                context.found = 0;
+                let text = self.render_children(node, context, node.attribute("delimiter"));
+                // A group is left out when all the variables it uses are empty.
+                let empty = context.called > 0 && context.found == 0;
+                context.called += called;
+                context.found += found;
+                if empty {
+                    String::new()
+                } else {
+                    text
+                }
+            }
+            "choose" => {
+                let branch = node
+                    .children()
+                    .filter(Node::is_element)
+                    .find(|branch| branch.has_tag_name_local("else") || self.condition(*branch, context.item));
+                return match branch {
+                    Some(branch) => self.render_children(branch, context, node.parent().and_then(|p| p.attribute("delimiter"))),
+                    None => String::new(),
+                };
+            }
+            _ => String::new(),
+        };
+        self.decorate(node, text)
+    }
+
+    fn render_text(&self, node: Node, context: &mut Scope) -> String {
+        let short = node.attribute("form") == Some("short");
+        if let Some(variable) = node.attribute("variable") {
+            let value = context.lookup(variable, context.item.variable(variable, short));
+            return value.map(|v| self.leaf(node, &v)).unwrap_or_default();
+        }
+        if let Some(name) = node.attribute("macro") {
+            return match self.macros.get(name) {
+                Some(definition) => self.render_children(*definition, context, None),
+                None => String::new(),
+            };
+        }
+        if let Some(term) = node.attribute("term") {
+            let plural = node.attribute("plural") == Some("true");
+            let form = node.attribute("form").unwrap_or("long");
+            return self.terms.get(term, form, plural).map(|t| self.leaf(node, &t)).unwrap_or_default();
+        }
+        node.attribute("value").map(|v| self.leaf(node, v)).unwrap_or_default()
+    }
+
+    /// Plural-aware term for a variable, e.g. `pp.` for a page range.
+    fn render_label(&self, node: Node, context: &mut Scope, names_variable: Option<(&str, usize)>) -> String {
+        let form = node.attribute("form").unwrap_or("long");
+        let (variable, plural) = match names_variable {
+            Some((variable, count)) => (variable, count > 1),
+            None => {
+                let variable = node.attribute("variable").unwrap_or_default();
+                let Some(value) = context.item.variable(variable, false) else {
+                    return String::new();
+                };
+                (variable, value.contains(['–', '-', ',', '&']))
+            }
+        };
+        let plural = match node.attribute("plural") {
+            Some("always") => true,
+            Some("never") => false,
+            _ => plural,
+        };
+        self.terms.get(variable, form, plural).map(|t| self.leaf(node, &t)).unwrap_or_default()
+    }
+
+    fn render_date(&self, node: Node, context: &mut Scope) -> String {
+        let variable = node.attribute("variable").unwrap_or_default();
+        let Some((year, month, day)) = context.lookup(variable, context.item.date(variable)) else {
+            return String::new();
+        };
+
+        let parts: Vec<Node> = node.children().filter(|n| n.has_tag_name_local("date-part")).collect();
+        if parts.is_empty() {
+            // Localized date, as in `<date variable="issued" form="text"/>`.
+            let wanted = node.attribute("date-parts").unwrap_or("year-month-day");
+            let month = month.filter(|_| wanted != "year");
+            let day = day.filter(|_| wanted == "year-month-day");
+            let text = match (node.attribute("form"), month, day) {
+                (Some("numeric"), Some(m), Some(d)) => format!("{}/{}/{}", m, d, year),
+                (Some("numeric"), Some(m), None) => format!("{}/{}", m, year),
+                (_, Some(m), Some(d)) => format!("{} {}, {}", self.terms.month(m, "long"), d, year),
+                (_, Some(m), None) => format!("{} {}", self.terms.month(m, "long"), year),
+                _ => year,
+            };
+            return self.format.escape(&text);
+        }
+
+        let rendered: Vec<String> = parts
+            .iter()
+            .filter_map(|part| {
+                let form = part.attribute("form").unwrap_or("long");
+                let text = match part.attribute("name")? {
+                    "year" if form == "short" => year[year.len().saturating_sub(2)..].to_string(),
+                    "year" => year.clone(),
+                    "month" => match form {
+                        "numeric" => month?.to_string(),
+                        "numeric-leading-zeros" => format!("{:02}", month?),
+                        form => self.terms.month(month?, form),
+                    },
+                    "day" if form == "numeric-leading-zeros" => format!("{:02}", day?),
+                    "day" => day?.to_string(),
+                    _ => return None,
+                };
+                Some(self.decorate(*part, self.leaf(*part, &text)))
+            })
+            .collect();
+        rendered.join(&self.format.escape(node.attribute("delimiter").unwrap_or("")))
+    }
+
+    fn render_names(&self, node: Node, context: &mut Scope, inherited: Option<Node>) -> String {
+        let name_node = child(node, "name").or(inherited);
+        let label_node = child(node, "label");
+        let label_first = match (label_node, name_node) {
+            (Some(label), Some(name)) => label.range().start < name.range().start,
+            _ => false,
+        };
+
+        let mut parts = Vec::new();
+        for variable in node.attribute("variable").unwrap_or_default().split_whitespace() {
+            let names = context.item.names(variable);
+            let Some(names) = context.lookup(variable, Some(names).filter(|n| !n.is_empty())) else {
+                continue;
+            };
+            let mut text = self.format_names(name_node, &names);
+            if let Some(label) = label_node {
+                let label = self.decorate(label, self.render_label(label, context, Some((variable, names.len()))));
+                text = if label_first { label + &text } else { text + &label };
+            }
+            parts.push(text);
+        }
+        if !parts.is_empty() {
+            return parts.join(&self.format.escape(node.attribute("delimiter").unwrap_or(", ")));
+        }
+
+        // No names: the first substitute that renders takes their place, and
+        // its variables are not repeated further on.
+        let Some(substitute) = child(node, "substitute") else {
+            return String::new();
+        };
+        for candidate in substitute.children().filter(Node::is_element) {
+            let found_before = context.found;
+            let text = if candidate.has_tag_name_local("names") {
+                self.decorate(candidate, self.render_names(candidate, context, name_node))
+            } else {
+                self.render(candidate, context)
+            };
+            if !text.is_empty() && context.found > found_before {
+                for variable in substitute_variables(candidate, &self.macros) {
+                    context.suppressed.insert(variable);
+                }
+                return text;
+            }
+        }
+        String::new()
+    }
+
+    fn format_names(&self, node: Option<Node>, names: &[Name]) -> String {
+        let attribute = |name: &str| node.and_then(|n| n.attribute(name));
+        let delimiter = attribute("delimiter").unwrap_or(", ");
+        let and = match attribute("and") {
+            Some("symbol") => Some("&".to_string()),
+            Some("text") => self.terms.get("and", "long", false),
+            _ => None,
+        };
+        let sort_order = attribute("name-as-sort-order");
+        let sort_separator = attribute("sort-separator").unwrap_or(", ");
+        let short = attribute("form") == Some("short");
+        let initialize_with = attribute("initialize-with").filter(|_| attribute("initialize") != Some("false"));
+
+        let et_al_min: Option<usize> = attribute("et-al-min").and_then(|n| n.parse().ok());
+        let use_first: usize = attribute("et-al-use-first").and_then(|n| n.parse().ok()).unwrap_or(1);
+        let truncated = et_al_min.is_some_and(|min| names.len() >= min) && use_first < names.len();
+        let shown = if truncated { &names[..use_first.max(1)] } else { names };
+
+        let formatted: Vec<String> = shown
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let Some(given) = &name.given else {
+                    return self.format.escape(&name.family);
+                };
+                if short {
+                    return self.format.escape(&name.family);
+                }
+                let given = match initialize_with {
+                    Some(with) => initials(given, with),
+                    None => given.clone(),
+                };
+                let inverted = match sort_order {
+                    Some("all") => true,
+                    Some("first") => i == 0,
+                    _ => false,
+                };
+                let text = if inverted {
+                    format!("{}{}{}", name.family, sort_separator, given)
+                } else {
+                    format!("{} {}", given, name.family)
+                };
+                self.format.escape(&text)
+            })
+            .collect();
+
+        let delimiter = self.format.escape(delimiter);
+        match (formatted.as_slice(), and) {
+            ([one], _) if !truncated => one.clone(),
+            (all, _) if truncated => {
+                let separator = if all.len() > 1 { delimiter.clone() } else { " ".to_string() };
+                let et_al = self.terms.get("et-al", "long", false).unwrap_or_else(|| "et al.".to_string());
+                format!("{}{}{}", all.join(&delimiter), separator, et_al)
+            }
+            ([rest @ .., last], Some(and)) => {
+                let before_last = match attribute("delimiter-precedes-last").unwrap_or("contextual") {
+                    "always" => true,
+                    "never" => false,
+                    _ => formatted.len() > 2,
+                };
+                let and = self.format.escape(&and);
+                if before_last {
+                    format!("{}{}{} {}", rest.join(&delimiter), delimiter, and, last)
+                } else {
+                    format!("{} {} {}", rest.join(&delimiter), and, last)
+                }
+            }
+            (all, _) => all.join(&delimiter),
+        }
+    }
+
+    fn condition(&self, branch: Node, item: &Item) -> bool {
+        let mut results = Vec::new();
+        for attribute in branch.attributes() {
+            let values = attribute.value().split_whitespace();
+            match attribute.name() {
+                "type" => {
+                    let ty = csl_type(&item.entry.ty);
+                    results.extend(values.map(|v| v == ty));
+                }
+                "variable" => results.extend(values.map(|v| item.has_variable(v))),
+                "is-numeric" => results.extend(values.map(|v| {
+                    item.variable(v, false)
+                        .is_some_and(|value| value.chars().next().is_some_and(|c| c.is_ascii_digit()))
+                })),
+                "match" => {}
+                // Citation positions and the like never apply to a bibliography.
+                _ => results.push(false),
+            }
+        }
+        match branch.attribute("match").unwrap_or("all") {
+            "any" => results.iter().any(|r| *r),
+            "none" => !results.iter().any(|r| *r),
+            _ => !results.is_empty() && results.iter().all(|r| *r),
+        }
+    }
+
+    /// Escaped text with the text case and quotes of `node` applied.
+    fn leaf(&self, node: Node, text: &str) -> String {
+        let mut text = text.to_string();
+        if node.attribute("strip-periods") == Some("true") {
+            text = text.replace('.', "");
+        }
+        text = match node.attribute("text-case") {
+            Some("lowercase") => text.to_lowercase(),
+            Some("uppercase") => text.to_uppercase(),
+            Some("capitalize-first") | Some("sentence") => capitalize_first(&text),
+            Some("capitalize-all") | Some("title") => text.split(' ').map(capitalize_first).collect::<Vec<_>>().join(" "),
+            _ => text,
+        };
+        let text = self.format.escape(&text);
+        if node.attribute("quotes") == Some("true") {
+            format!("“{}”", text)
+        } else {
+            text
+        }
+    }
+
+    /// Applies the formatting and affixes of `node` to rendered, non-empty text.
+    fn decorate(&self, node: Node, text: String) -> String {
+        if text.is_empty() {
+            return text;
+        }
+        let mut text = text;
+        if node.attribute("font-style") == Some("italic") {
+            text = self.format.italic(text);
+        }
+        if node.attribute("font-weight") == Some("bold") {
+            text = self.format.bold(text);
+        }
+        let prefix = self.format.escape(node.attribute("prefix").unwrap_or_default());
+        let mut suffix = self.format.escape(node.attribute("suffix").unwrap_or_default());
+        // No `..` or `?.` where a title or initial already ends the sentence.
+        if suffix.starts_with('.') && matches!(last_visible_char(&text), Some('.' | '?' | '!')) {
+            suffix.remove(0);
+        }
+        format!("{}{}{}", prefix, text, suffix)
+    }
+
+    fn sort_value(&self, key: Node, item: &Item) -> String {
+        let mut context = Scope::new(item);
+        if let Some(name) = key.attribute("macro") {
+            let plain = Renderer {
+                macros: self.macros.clone(),
+                terms: self.terms,
+                format: RenderFormat::Text,
+            };
+            return match self.macros.get(name) {
+                Some(definition) => plain.render_children(*definition, &mut context, None).to_lowercase(),
+                None => String::new(),
+            };
+        }
+        let variable = key.attribute("variable").unwrap_or_default();
+        let names = item.names(variable);
+        if !names.is_empty() {
+            return names
+                .iter()
+                .map(|n| format!("{} {}", n.family, n.given.as_deref().unwrap_or_default()))
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase();
+        }
+        if let Some((year, month, day)) = item.date(variable) {
+            return format!("{:0>4}{:02}{:02}", year, month.unwrap_or(0), day.unwrap_or(0));
+        }
+        item.variable(variable, false).unwrap_or_default().to_lowercase()
+    }
+}
+
+/// Variables a substitute renders, directly or through macros.
+fn substitute_variables(node: Node, macros: &HashMap<&str, Node>) -> Vec<String> {
+    let mut variables = Vec::new();
+    for descendant in node.descendants().filter(Node::is_element) {
+        if let Some(variable) = descendant.attribute("variable") {
+            variables.extend(variable.split_whitespace().map(str::to_string));
+        }
+        if let Some(definition) = descendant.attribute("macro").and_then(|name| macros.get(name)) {
+            variables.extend(substitute_variables(*definition, macros));
+        }
+    }
+    variables
+}
+
+/// `Jane Quinn` as `J. Q.` with `initialize-with=". "`, keeping hyphens.
+fn initials(given: &str, with: &str) -> String {
+    given
+        .split_whitespace()
+        .map(|word| {
+            word.split('-')
+                .filter_map(|part| part.chars().next())
+                .map(|c| format!("{}{}", c.to_uppercase(), with.trim_end()))
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect::<Vec<_>>()
+        .join(if with.ends_with(' ') { " " } else { "" })
+}
+
+fn capitalize_first(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The last character that is not HTML or Markdown markup.
+fn last_visible_char(text: &str) -> Option<char> {
+    let mut in_tag = false;
+    text.chars().rev().find(|&c| {
+        match c {
+            '>' => in_tag = true,
+            '<' if in_tag => in_tag = false,
+            '*' | '”' if !in_tag => {}
+            _ => return !in_tag,
+        }
+        false
+    })
+}
+
+fn month_name(month: u32, form: &str) -> &'static str {
+    const LONG: [&str; 12] = [
+        "January", "February", "March", "April", "May", "June", "July", "August", "September", "October",
+        "November", "December",
+    ];
+    const SHORT: [&str; 12] = [
+        "Jan.", "Feb.", "Mar.", "Apr.", "May", "Jun.", "Jul.", "Aug.", "Sep.", "Oct.", "Nov.", "Dec.",
+    ];
+    let index = (month as usize).saturating_sub(1).min(11);
+    if form == "short" {
+        SHORT[index]
+    } else {
+        LONG[index]
+    }
+}
+
+/// English CSL terms, for when no locale defines them.
+fn english_term(term: &str, form: &str, plural: bool) -> Option<&'static str> {
+    let short = matches!(form, "short" | "symbol");
+    let text = match (term, short, plural) {
+        ("and", _, _) => "and",
+        ("et-al", _, _) => "et al.",
+        ("in", _, _) => "in",
+        ("from", _, _) => "from",
+        ("retrieved", _, _) => "retrieved",
+        ("accessed", _, _) => "accessed",
+        ("available at", _, _) => "available at",
+        ("presented at", _, _) => "presented at",
+        ("no date", true, _) => "n.d.",
+        ("no date", false, _) => "no date",
+        ("page", true, false) => "p.",
+        ("page", true, true) => "pp.",
+        ("page", false, false) => "page",
+        ("page", false, true) => "pages",
+        ("editor", true, false) => "ed.",
+        ("editor", true, true) => "eds.",
+        ("editor", false, false) => "editor",
+        ("editor", false, true) => "editors",
+        ("translator", true, _) => "trans.",
+        ("translator", false, false) => "translator",
+        ("translator", false, true) => "translators",
+        ("volume", true, false) => "vol.",
+        ("volume", true, true) => "vols.",
+        ("issue", true, _) => "no.",
+        ("edition", true, _) => "ed.",
+        ("edition", false, _) => "edition",
+        _ => return None,
+    };
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ris::parse_ris;
+
+    fn library() -> Vec<(String, RisEntry)> {
+        let ris = "TY  - JOUR\nAU  - Roe, Rick\nAU  - Doe, Jane Quinn\nAU  - Zed, Zoe\nTI  - Why cats?\nT2  - Nature\nVL  - 12\nIS  - 3\nSP  - 10\nEP  - 20\nPY  - 2020\nDO  - 10.1/x\nER  -\n\
+                   TY  - BOOK\nA2  - Doe, Jane\nTI  - Dogs\nPB  - Springer\nDA  - 2019/05/02\nER  -\n";
+        parse_ris(ris)
+            .unwrap()
+            .into_iter()
+            .zip(["roe2020", "doe2019"])
+            .map(|(entry, key)| (key.to_string(), entry))
+            .collect()
+    }
+
+    #[test]
+    fn renders_builtin_apa() {
+        let style = load_style("apa").unwrap();
+        let text = render(&style, &library(), RenderFormat::Text).unwrap();
+        assert_eq!(
+            text,
+            "Doe, J. (Ed.). (2019). Dogs. Springer.\n\
+             Roe, R., Doe, J. Q., & Zed, Z. (2020). Why cats? Nature, 12(3), 10–20. https://doi.org/10.1/x\n"
+        );
+
+        let markdown = render(&style, &library(), RenderFormat::Markdown).unwrap();
+        assert!(markdown.contains("*Nature*, *12*(3)"));
+    }
+
+    #[test]
+    fn renders_builtin_ieee_numbered() {
+        let style = load_style("ieee").unwrap();
+        let html = render(&style, &library(), RenderFormat::Html).unwrap();
+        assert!(html.contains(
+            "<div class=\"csl-entry\">[1] R. Roe, J. Q. Doe, and Z. Zed, “Why cats?”, <i>Nature</i>, vol. 12, no. 3, pp. 10–20, 2020, doi: 10.1/x.</div>"
+        ));
+        assert!(html.contains("[2] J. Doe, ed., <i>Dogs</i>, Springer, May 2019."));
+    }
+
+    #[test]
+    fn reads_terms_from_locales() {
+        let style = r#"<style xmlns="http://purl.org/net/xbiblio/csl" default-locale="nb-NO">
+            <locale xml:lang="nb"><terms><term name="and">og</term></terms></locale>
+            <locale xml:lang="de"><terms><term name="and">und</term></terms></locale>
+        </style>"#;
+        let locale = r#"<locale xmlns="http://purl.org/net/xbiblio/csl" xml:lang="nb-NO"><terms>
+            <term name="and">and</term>
+            <term name="page" form="short"><single>s.</single><multiple>s.</multiple></term>
+            <term name="month-05">mai</term>
+        </terms></locale>"#;
+        let style = Document::parse(style).unwrap();
+        let mut terms = Terms::of_style(style.root_element());
+        terms.add(Document::parse(locale).unwrap().root_element());
+
+        assert_eq!(terms.get("and", "long", false).as_deref(), Some("og"));
+        assert_eq!(terms.get("page", "symbol", true).as_deref(), Some("s."));
+        assert_eq!(terms.get("editor", "short", true).as_deref(), Some("eds."));
+        assert_eq!(terms.month(5, "long"), "mai");
+        assert_eq!(terms.month(6, "short"), "Jun.");
+    }
+}
@@ -0,0 +1,301 @@
+use anyhow::{anyhow, Result};
+use biblatex::{Bibliography, Entry};
+
+use crate::model::ris::{chunks_to_string, parse_ris, ris_entry_to_bibtex_string, RisEntry, PROTECTED_TAG};
+
+/// RIS tags that have a BibTeX field and should survive RIS→BibTeX→RIS.
+pub const MAPPED_TAGS: [&str; 22] = [
+    "AU", "TI", "PY", "T2", "PB", "VL", "IS", "SP", "EP", "DO", "UR", "AB", "SN", "KW", PROTECTED_TAG, "ED",
+    "ET", "CY", "T3", "SE", "LA", "N1",
+];
+
+/// BibTeX fields that have a RIS tag and should survive BibTeX→RIS→BibTeX.
+pub const MAPPED_FIELDS: [&str; 24] = [
+    "author", "title", "year", "journal", "booktitle", "publisher", "volume", "number", "pages",
+    "doi", "url", "abstract", "issn", "keywords", "editor", "edition", "address", "series",
+    "chapter", "institution", "school", "note", "month", "language",
+];
+
+/// A field whose value changed on the way through the other format.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Loss {
+    pub path: &'static str,
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Converts `entry` to BibTeX and back, returning every mapped field that changed.
+pub fn ris_round_trip(entry: &RisEntry) -> Result<Vec<Loss>> {
+    const PATH: &str = "RIS→BibTeX→RIS";
+
+    let bibtex = ris_entry_to_bibtex_string(entry, "key");
+    let bibliography =
+        Bibliography::parse(&bibtex).map_err(|e| anyhow!("{}: invalid BibTeX written: {}", PATH, e))?;
+    let back = bibliography
+        .iter()
+        .next()
+        .map(RisEntry::from)
+        .ok_or_else(|| anyhow!("{}: no entry in the written BibTeX", PATH))?;
+
+    let mut losses = Vec::new();
+    if back.ty != entry.ty {
+        losses.push(Loss {
+            path: PATH,
+            field: "TY".to_string(),
+            before: entry.ty.to_str().to_string(),
+            after: back.ty.to_str().to_string(),
+        });
+    }
+    for tag in MAPPED_TAGS {
+        let before = entry.fields.get(tag).map(|v| v.join("; ")).unwrap_or_default();
+        let after = back.fields.get(tag).map(|v| v.join("; ")).unwrap_or_default();
+        if before != after {
+            losses.push(Loss {
+                path: PATH,
+                field: tag.to_string(),
+                before,
+                after,
+            });
+        }
+    }
+    Ok(losses)
+}
+
+/// Converts `entry` to RIS and back, returning every mapped field that changed.
+pub fn bibtex_round_trip(entry: &Entry) -> Result<Vec<Loss>> {
+    const PATH: &str = "BibTeX→RIS→BibTeX";
+
+    let ris = RisEntry::from(entry).to_string();
+    let back = parse_ris(&ris)
+        .map_err(|e| anyhow!("{}: invalid RIS written: {}", PATH, e))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("{}: no entry in the written RIS", PATH))?;
+    let bibtex = ris_entry_to_bibtex_string(&back, &entry.key);
+    let back = Bibliography::parse(&bibtex)
+        .map_err(|e| anyhow!("{}: invalid BibTeX written: {}", PATH, e))?
+        .iter()
+        .next()
+        .cloned()
+        .ok_or_else(|| anyhow!("{}: no entry in the written BibTeX", PATH))?;
+
+    let mut losses = Vec::new();
+    let (before_type, after_type) = (entry.entry_type.to_string(), back.entry_type.to_string());
+    if before_type != after_type {
+        losses.push(Loss {
+            path: PATH,
+            field: "type".to_string(),
+            before: before_type,
+            after: after_type,
+        });
+    }
+    for field in MAPPED_FIELDS {
+        let before = entry.fields.get(field).map(chunks_to_string).unwrap_or_default();
+        let after = back.fields.get(field).map(chunks_to_string).unwrap_or_default();
+        if before != after {
+            losses.push(Loss {
+                path: PATH,
+                field: field.to_string(),
+                before,
+                after,
+            });
+        }
+    }
+    Ok(losses)
+}
+
+/// Entries covering every mapped field, which must convert without loss.
+pub const CORPUS_RIS: &str = "TY  - JOUR
+AU  - Doe, Jane
+AU  - Roe, Rick
+TI  - Cats and dogs in RNA research
+U1  - RNA
+PY  - 2020
+T2  - Journal of Pets
+VL  - 12
+IS  - 3
+SP  - 45
+EP  - 67
+DO  - 10.1000/pets.2020.3
+UR  - https://example.org/cats
+AB  - A study of cats.
+SN  - 1234-5678
+KW  - cats
+KW  - dogs
+ER  -
+
+TY  - BOOK
+AU  - Knuth, Donald E.
+TI  - The TeXbook
+PY  - 1984
+T2  - Computers and Typesetting
+T3  - Computers and Typesetting
+ET  - 2
+PB  - Addison-Wesley
+CY  - Reading, MA
+VL  - A
+LA  - english
+N1  - With corrections
+ER  -
+
+TY  - RPRT
+AU  - Roe, Rick
+ED  - Doe, Jane
+TI  - Parsing reports
+PY  - 2021
+PB  - Institute of Parsing
+ER  -
+
+TY  - CONF
+AU  - Smith, John
+TI  - Fast parsing
+PY  - 2019
+T2  - Proceedings of the Parsing Conference
+SP  - 1
+EP  - 9
+ER  -
+";
+
+pub const CORPUS_BIBTEX: &str = "@article{doe2020,
+  author = {Doe, Jane and Roe, Rick},
+  title = {Cats and dogs in {RNA} research},
+  year = {2020},
+  journal = {Journal of Pets},
+  volume = {12},
+  number = {3},
+  pages = {45--67},
+  doi = {10.1000/pets.2020.3},
+  url = {https://example.org/cats},
+  abstract = {A study of cats.},
+  issn = {1234-5678},
+  keywords = {cats, dogs},
+}
+
+@inproceedings{smith2019,
+  author = {Smith, John},
+  title = {Fast parsing},
+  year = {2019},
+  booktitle = {Proceedings of the Parsing Conference},
+  publisher = {ACM},
+  pages = {1--9},
+}
+
+@book{knuth1984,
+  author = {Knuth, Donald E.},
+  editor = {Doe, Jane and Roe, Rick},
+  title = {The TeXbook},
+  year = {1984},
+  month = {5},
+  series = {Computers and Typesetting},
+  edition = {2},
+  chapter = {3},
+  publisher = {Addison-Wesley},
+  address = {Reading, MA},
+  language = {english},
+  note = {With corrections},
+}
+
+@thesis{roe2022,
+  author = {Roe, Rick},
+  title = {On parsing \\& G\\\"{o}del's \\emph{numbers}},
+  year = {2022},
+  school = {University of Parsing},
+}
+
+@techreport{doe2023,
+  author = {Doe, Jane},
+  title = {Parsing report},
+  year = {2023},
+  institution = {Institute of Parsing},
+}
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ris::ReferenceType;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn corpus_round_trips_without_loss() {
+        for entry in parse_ris(CORPUS_RIS).unwrap() {
+            assert_eq!(ris_round_trip(&entry).unwrap(), vec![]);
+        }
+        for entry in Bibliography::parse(CORPUS_BIBTEX).unwrap().iter() {
+            assert_eq!(bibtex_round_trip(entry).unwrap(), vec![]);
+        }
+    }
+
+    fn word() -> impl Strategy<Value = String> {
+        "[A-Za-zÉØÜ][a-z0-9éøüß]{0,8}"
+    }
+
+    /// Words with braces, LaTeX special characters and non-ASCII letters,
+    /// which the conversions have to escape and unescape.
+    fn text_word() -> impl Strategy<Value = String> {
+        prop_oneof![
+            3 => word(),
+            1 => "[a-z]{1,4}[&%$#_~^\\\\][a-z]{0,4}",
+            1 => word().prop_map(|word| format!("{{{}}}", word)),
+            1 => "[ÆØÅæøåçñ中文]{1,4}",
+        ]
+    }
+
+    fn words() -> impl Strategy<Value = String> {
+        prop::collection::vec(text_word(), 1..6).prop_map(|w| w.join(" "))
+    }
+
+    fn ris_entry() -> impl Strategy<Value = RisEntry> {
+        (
+            prop::sample::select(vec![
+                ReferenceType::Journal,
+                ReferenceType::Book,
+                ReferenceType::ConferencePaper,
+            ]),
+            prop::collection::vec((word(), word()), 1..4),
+            words(),
+            1900u32..2100,
+            words(),
+            prop::option::of(1u32..500),
+            prop::option::of((1u32..500, 1u32..500)),
+            prop::collection::vec(words(), 0..4),
+            prop::collection::vec((word(), word()), 0..3),
+        )
+            .prop_map(|(ty, authors, title, year, container, volume, pages, keywords, editors)| {
+                let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+                let authors = authors.iter().map(|(last, first)| format!("{}, {}", last, first));
+                fields.insert("AU".to_string(), authors.collect());
+                fields.insert("TI".to_string(), vec![title]);
+                fields.insert("PY".to_string(), vec![year.to_string()]);
+                fields.insert("T2".to_string(), vec![container]);
+                if let Some(volume) = volume {
+                    fields.insert("VL".to_string(), vec![volume.to_string()]);
+                }
+                if let Some((start, length)) = pages {
+                    fields.insert("SP".to_string(), vec![start.to_string()]);
+                    fields.insert("EP".to_string(), vec![(start + length).to_string()]);
+                }
+                if !keywords.is_empty() {
+                    fields.insert("KW".to_string(), keywords);
+                }
+                if !editors.is_empty() {
+                    let editors = editors.iter().map(|(last, first)| format!("{}, {}", last, first));
+                    fields.insert("ED".to_string(), editors.collect());
+                }
+                RisEntry { ty, fields }
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn generated_entries_round_trip_without_loss(entry in ris_entry()) {
+            prop_assert_eq!(ris_round_trip(&entry).unwrap(), vec![]);
+
+            let bibtex = ris_entry_to_bibtex_string(&entry, "key");
+            let bibliography = Bibliography::parse(&bibtex).unwrap();
+            prop_assert_eq!(bibtex_round_trip(bibliography.iter().next().unwrap()).unwrap(), vec![]);
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::model::ris::{ReferenceType, RisEntry};
+use crate::services::citekey::year_of;
+
+/// Styles in Google Scholar's "Cite" dialog, most structured first. The
+/// dialog shows the same work in every style, so the first one that parses wins.
+const STYLES: [&str; 5] = ["APA", "MLA", "Chicago", "Harvard", "Vancouver"];
+
+/// Export links at the bottom of the dialog, copied along with the citations.
+const EXPORT_LINKS: [&str; 4] = ["BibTeX", "EndNote", "RefMan", "RefWorks"];
+
+/// Parses plain-text citations as Google Scholar formats them: either the
+/// whole "Cite" dialog, with a style label before each citation, or one
+/// APA, MLA or Chicago citation per line.
+pub fn parse(text: &str) -> Result<Vec<RisEntry>> {
+    let labelled = labelled_citations(text);
+    if !labelled.is_empty() {
+        for style in STYLES {
+            let parsed = labelled
+                .iter()
+                .filter(|(label, _)| *label == style)
+                .find_map(|(_, citation)| parse_citation(citation));
+            if let Some(entry) = parsed {
+                return Ok(vec![entry]);
+            }
+        }
+        bail!("None of the {} labelled citations could be parsed", labelled.len());
+    }
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_citation(line).ok_or_else(|| anyhow!("Not a citation: {:?}", line)))
+        .collect()
+}
+
+/// `(style, citation)` pairs for text copied from the "Cite" dialog, where a
+/// label is either on its own line or separated from the citation by a tab.
+fn labelled_citations(text: &str) -> Vec<(&'static str, String)> {
+    let mut citations: Vec<(&'static str, String)> = Vec::new();
+    let mut current: Option<usize> = None;
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.split_whitespace().all(|w| EXPORT_LINKS.contains(&w)) {
+            current = None;
+            continue;
+        }
+
+        let label = STYLES.iter().find(|style| {
+            line == **style
+                || line
+                    .strip_prefix(**style)
+                    .is_some_and(|rest| rest.starts_with('\t'))
+        });
+        if let Some(style) = label {
+            let citation = line[style.len()..].trim().to_string();
+            citations.push((style, citation));
+            current = Some(citations.len() - 1);
+            continue;
+        }
+
+        if let Some(index) = current {
+            let citation = &mut citations[index].1;
+            if !citation.is_empty() {
+                citation.push(' ');
+            }
+            citation.push_str(line);
+        }
+    }
+
+    citations
+}
+
+fn parse_citation(citation: &str) -> Option<RisEntry> {
+    parse_apa(citation)
+        .or_else(|| parse_quoted(citation))
+        .or_else(|| parse_book(citation))
+}
+
+#[derive(Default)]
+struct Citation {
+    authors: Vec<String>,
+    year: String,
+    title: String,
+    container: Option<String>,
+    volume: Option<String>,
+    issue: Option<String>,
+    pages: Option<String>,
+    publisher: Option<String>,
+    place: Option<String>,
+}
+
+impl Citation {
+    fn into_entry(self) -> RisEntry {
+        let ty = if self.container.is_some() {
+            ReferenceType::Journal
+        } else {
+            ReferenceType::Book
+        };
+
+        let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+        let mut add_field = |tag: &str, value: &str| {
+            let value = value.trim();
+            if !value.is_empty() {
+                fields.entry(tag.to_string()).or_default().push(value.to_string());
+            }
+        };
+
+        for author in &self.authors {
+            add_field("AU", author);
+        }
+        add_field("TI", &self.title);
+        add_field("PY", &self.year);
+        for (tag, value) in [
+            ("T2", &self.container),
+            ("VL", &self.volume),
+            ("IS", &self.issue),
+            ("PB", &self.publisher),
+            ("CY", &self.place),
+        ] {
+            if let Some(value) = value {
+                add_field(tag, value);
+            }
+        }
+        if let Some(pages) = &self.pages {
+            let pages = pages.trim_start_matches("pp.").trim_start_matches("p.");
+            match pages.split_once(['-', '–']) {
+                Some((start, end)) => {
+                    add_field("SP", start);
+                    add_field("EP", end);
+                }
+                None => add_field("SP", pages),
+            }
+        }
+
+        RisEntry { ty, fields }
+    }
+}
+
+/// APA: `Doe, J., & Smith, J. (2020). Title. Journal, 12(3), 45-67.`
+fn parse_apa(citation: &str) -> Option<RisEntry> {
+    let (authors, year, rest) = citation.match_indices(" (").find_map(|(i, _)| {
+        let after = &citation[i + 2..];
+        let close = after.find(')')?;
+        let year = year_of(&after[..close])?;
+        let rest = after[close..].strip_prefix(").")?;
+        Some((&citation[..i], year, rest.trim()))
+    })?;
+
+    let (title, source) = split_sentence(rest);
+    let source = source.trim_end_matches('.');
+
+    let mut result = Citation {
+        authors: apa_authors(authors),
+        year,
+        title: title.to_string(),
+        ..Default::default()
+    };
+
+    let parts: Vec<&str> = source.split(", ").collect();
+    let numbered = (1..parts.len()).find(|&k| parts[k].starts_with(|c: char| c.is_ascii_digit()));
+    match numbered {
+        Some(k) => {
+            result.container = Some(parts[..k].join(", "));
+            let (volume, issue) = match parts[k].split_once('(') {
+                Some((volume, issue)) => (volume, Some(issue.trim_end_matches(')'))),
+                None => (parts[k], None),
+            };
+            result.volume = Some(volume.to_string());
+            result.issue = issue.map(str::to_string);
+            result.pages = parts.get(k + 1).map(|p| p.to_string());
+        }
+        None if !source.is_empty() => result.publisher = Some(source.to_string()),
+        None => {}
+    }
+
+    Some(result.into_entry())
+}
+
+/// `Doe, J., Smith, J. R., & Roe, R.` as `["Doe, J.", "Smith, J. R.", "Roe, R."]`.
+fn apa_authors(authors: &str) -> Vec<String> {
+    let parts: Vec<&str> = authors
+        .split(", ")
+        .map(|part| part.trim().trim_start_matches('&').trim())
+        .filter(|part| !part.is_empty() && *part != "..." && *part != "…")
+        .collect();
+    parts.chunks(2).map(|pair| pair.join(", ")).collect()
+}
+
+/// MLA and Chicago articles, with the title in quotes:
+/// `Doe, John, and Jane Smith. "Title." Journal 12.3 (2020): 45-67.`
+/// `Doe, John, and Jane Smith. "Title." Journal 12, no. 3 (2020): 45-67.`
+fn parse_quoted(citation: &str) -> Option<RisEntry> {
+    let open = citation.find(['"', '“'])?;
+    let after = citation[open..].strip_prefix(['"', '“'])?;
+    let close = after.find(['"', '”'])?;
+    let title = after[..close].trim().trim_end_matches(['.', ',']);
+    let rest = after[close..].strip_prefix(['"', '”'])?;
+    let rest = rest.trim().trim_end_matches('.');
+
+    let (source, year, pages) = match rest.rfind(" (") {
+        Some(i) if year_of(&rest[i..]).is_some() => {
+            let after = &rest[i + 2..];
+            let close = after.find(')')?;
+            let pages = after[close + 1..].trim_start_matches(':').trim();
+            (&rest[..i], year_of(&after[..close])?, Some(pages).filter(|p| !p.is_empty()))
+        }
+        _ => {
+            let year = year_of(rest)?;
+            let i = rest.find(&year)?;
+            (&rest[..i], year, None)
+        }
+    };
+    let source = source.trim().trim_end_matches([',', '.']);
+
+    let (source, mut issue) = match source.split_once(", no. ") {
+        Some((source, issue)) => (source, Some(issue.to_string())),
+        None => (source, None),
+    };
+    let mut container = source.to_string();
+    let mut volume = None;
+    if let Some((name, number)) = source.rsplit_once(' ') {
+        if number.starts_with(|c: char| c.is_ascii_digit()) {
+            container = name.trim_end_matches(',').to_string();
+            match number.split_once('.') {
+                Some((vol, iss)) => {
+                    volume = Some(vol.to_string());
+                    issue = issue.or(Some(iss.to_string()));
+                }
+                None => volume = Some(number.to_string()),
+            }
+        }
+    }
+
+    let result = Citation {
+        authors: mla_authors(citation[..open].trim()),
+        year,
+        title: title.to_string(),
+        container: Some(container).filter(|c| !c.is_empty()),
+        volume,
+        issue,
+        pages: pages.map(str::to_string),
+        ..Default::default()
+    };
+    Some(result.into_entry())
+}
+
+/// MLA and Chicago books: `Doe, John. Title. Publisher, 2020.` or
+/// `Doe, John. Title. City: Publisher, 2020.`
+fn parse_book(citation: &str) -> Option<RisEntry> {
+    // The authors may end in an initial, so try every period as their end.
+    let (authors, title, rest) = citation.match_indices(". ").find_map(|(i, _)| {
+        let (title, rest) = split_sentence(&citation[i + 2..]);
+        let rest = rest.trim().trim_end_matches('.');
+        (!title.is_empty() && year_of(rest).is_some()).then(|| (&citation[..=i], title, rest))
+    })?;
+
+    let year = year_of(rest)?;
+    let publisher = rest[..rest.find(&year)?].trim().trim_end_matches([',', '.']);
+    let (place, publisher) = match publisher.split_once(": ") {
+        Some((place, publisher)) => (Some(place.to_string()), publisher),
+        None => (None, publisher),
+    };
+
+    let result = Citation {
+        authors: mla_authors(authors),
+        year,
+        title: title.to_string(),
+        publisher: Some(publisher.to_string()).filter(|p| !p.is_empty()),
+        place,
+        ..Default::default()
+    };
+    Some(result.into_entry())
+}
+
+/// `Doe, John, Jane Smith, and Rick Roe` as RIS names. Only the first author
+/// is inverted; `et al.` is dropped.
+fn mla_authors(authors: &str) -> Vec<String> {
+    let authors = authors.trim_end_matches("et al.").trim_end_matches([',', ' ']);
+    // Keep the period of a final initial such as `Donald E.`.
+    let last_word = authors.rsplit(' ').next().unwrap_or_default();
+    let authors = if last_word.len() == 2 && last_word.starts_with(char::is_uppercase) {
+        authors
+    } else {
+        authors.trim_end_matches('.')
+    };
+    let mut parts = authors.split(", ").map(str::trim);
+
+    let mut result = Vec::new();
+    match (parts.next(), parts.next()) {
+        (Some(last), Some(first)) if !first.starts_with("and ") => {
+            result.push(format!("{}, {}", last, first))
+        }
+        (Some(name), other) => {
+            result.push(name.to_string());
+            result.extend(other.map(invert_name));
+        }
+        _ => {}
+    }
+    result.extend(parts.map(invert_name));
+    result.retain(|name| !name.is_empty());
+    result
+}
+
+/// `and Jane Smith` as `Smith, Jane`.
+fn invert_name(name: &str) -> String {
+    let name = name.trim().trim_start_matches("and ").trim();
+    match name.rsplit_once(' ') {
+        Some((first, last)) => format!("{}, {}", last, first),
+        None => name.to_string(),
+    }
+}
+
+/// Splits at the end of the first sentence, skipping the period after an
+/// initial such as `J.`. A question or exclamation mark stays in the sentence.
+fn split_sentence(text: &str) -> (&str, &str) {
+    for (i, c) in text.char_indices() {
+        if !matches!(c, '.' | '?' | '!') || !text[i + 1..].starts_with(' ') {
+            continue;
+        }
+        let word = text[..i].rsplit(' ').next().unwrap_or_default();
+        let is_initial = c == '.'
+            && word.chars().count() == 1
+            && word.chars().all(|c| c.is_uppercase());
+        if is_initial {
+            continue;
+        }
+        let end = if c == '.' { i } else { i + 1 };
+        return (text[..end].trim(), text[i + 1..].trim());
+    }
+    (text.trim().trim_end_matches('.'), "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_apa_from_the_cite_dialog() {
+        let text = "MLA\n\
+            Doe, John, and Jane Smith. \"Cats and dogs: a review.\" Nature 12.3 (2020): 45-67.\n\
+            APA\n\
+            Doe, J., & Smith, J. (2020). Cats and dogs: a review. Nature, 12(3), 45-67.\n\
+            Chicago\n\
+            Doe, John, and Jane Smith. \"Cats and dogs: a review.\" Nature 12, no. 3 (2020): 45-67.\n\
+            BibTeX EndNote RefMan RefWorks";
+
+        let entries = parse(text).unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.ty, ReferenceType::Journal);
+        assert_eq!(entry.fields["AU"], vec!["Doe, J.", "Smith, J."]);
+        assert_eq!(entry.fields["TI"], vec!["Cats and dogs: a review"]);
+        assert_eq!(entry.fields["T2"], vec!["Nature"]);
+        assert_eq!(entry.fields["VL"], vec!["12"]);
+        assert_eq!(entry.fields["IS"], vec!["3"]);
+        assert_eq!(entry.fields["SP"], vec!["45"]);
+        assert_eq!(entry.fields["EP"], vec!["67"]);
+    }
+
+    #[test]
+    fn parses_mla_and_chicago_lines() {
+        let mla = "Doe, John, Rick Roe, and Jane Smith. \"Cats?\" Nature 12.3 (2020): 45-67.";
+        let chicago = "Doe, John, et al. “Cats.” Journal of Pets 7, no. 2 (2019): 1-9.";
+
+        let entries = parse(&format!("{}\n{}", mla, chicago)).unwrap();
+        assert_eq!(entries[0].fields["AU"], vec!["Doe, John", "Roe, Rick", "Smith, Jane"]);
+        assert_eq!(entries[0].fields["TI"], vec!["Cats?"]);
+        assert_eq!(entries[0].fields["IS"], vec!["3"]);
+        assert_eq!(entries[1].fields["AU"], vec!["Doe, John"]);
+        assert_eq!(entries[1].fields["T2"], vec!["Journal of Pets"]);
+        assert_eq!(entries[1].fields["VL"], vec!["7"]);
+        assert_eq!(entries[1].fields["IS"], vec!["2"]);
+        assert_eq!(entries[1].fields["PY"], vec!["2019"]);
+    }
+
+    #[test]
+    fn parses_books() {
+        let entries = parse("Knuth, Donald E. The TeXbook. Reading: Addison-Wesley, 1984.").unwrap();
+        let entry = &entries[0];
+        assert_eq!(entry.ty, ReferenceType::Book);
+        assert_eq!(entry.fields["AU"], vec!["Knuth, Donald E."]);
+        assert_eq!(entry.fields["TI"], vec!["The TeXbook"]);
+        assert_eq!(entry.fields["PB"], vec!["Addison-Wesley"]);
+        assert_eq!(entry.fields["CY"], vec!["Reading"]);
+        assert_eq!(entry.fields["PY"], vec!["1984"]);
+    }
+
+    #[test]
+    fn rejects_prose() {
+        assert!(parse("Just some text without a year").is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use std::ops::Range;
+
+use crate::model::ris::RisEntry;
+
+/// RIS fields `refrs search` looks in, with the label shown for matches.
+pub const SEARCH_FIELDS: [(&str, &str); 4] = [
+    ("TI", "title"),
+    ("AU", "author"),
+    ("AB", "abstract"),
+    ("KW", "keyword"),
+];
+
+/// A field value containing at least one search term.
+#[derive(Debug)]
+pub struct Hit {
+    pub label: &'static str,
+    pub value: String,
+    /// Byte ranges of the matched terms in `value`, sorted and merged.
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl Hit {
+    /// At most `width` characters of the value around the first match, with
+    /// the match ranges moved to fit. Cuts are marked with an ellipsis.
+    pub fn excerpt(&self, width: usize) -> (String, Vec<Range<usize>>) {
+        if self.value.chars().count() <= width {
+            return (self.value.clone(), self.ranges.clone());
+        }
+        let first = self.ranges.first().map(|r| r.start).unwrap_or(0);
+        let context = width / 4;
+        let start = self.value[..first]
+            .char_indices()
+            .rev()
+            .nth(context.saturating_sub(1))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        let end = self.value[start..]
+            .char_indices()
+            .nth(width)
+            .map(|(i, _)| start + i)
+            .unwrap_or(self.value.len());
+
+        let prefix = if start > 0 { "…" } else { "" };
+        let suffix = if end < self.value.len() { "…" } else { "" };
+        let offset = prefix.len();
+        let ranges = self
+            .ranges
+            .iter()
+            .filter(|r| r.start >= start && r.end <= end)
+            .map(|r| r.start - start + offset..r.end - start + offset)
+            .collect();
+        (format!("{}{}{}", prefix, &self.value[start..end], suffix), ranges)
+    }
+}
+
+/// Lowercased, whitespace-separated terms of a query.
+pub fn terms(query: &str) -> Vec<String> {
+    query.split_whitespace().map(str::to_lowercase).collect()
+}
+
+/// Byte ranges where `term` occurs in `text`, ignoring case.
+pub fn find_ranges(text: &str, term: &str) -> Vec<Range<usize>> {
+    let term: Vec<char> = term.chars().flat_map(char::to_lowercase).collect();
+    if term.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (start, _) in text.char_indices() {
+        if ranges.last().is_some_and(|r| r.end > start) {
+            continue;
+        }
+        let mut pending = term.iter().peekable();
+        let mut end = start;
+        for (offset, c) in text[start..].char_indices() {
+            if pending.peek().is_none() {
+                break;
+            }
+            // Characters lowercasing to several chars must match all of them.
+            if !c.to_lowercase().all(|l| pending.next() == Some(&l)) {
+                end = start;
+                break;
+            }
+            end = start + offset + c.len_utf8();
+        }
+        if end > start && pending.peek().is_none() {
+            ranges.push(start..end);
+        }
+    }
+    ranges
+}
+
+/// Sorts `ranges` and joins the ones that overlap.
+pub fn merge(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|r| r.start);
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// The searchable fields of an entry that contain a term, or `None` unless
+/// every term occurs in at least one of them.
+pub fn search(entry: &RisEntry, terms: &[String]) -> Option<Vec<Hit>> {
+    let mut hits = Vec::new();
+    let mut found = vec![false; terms.len()];
+
+    for (tag, label) in SEARCH_FIELDS {
+        for value in entry.fields.get(tag).into_iter().flatten() {
+            let mut ranges = Vec::new();
+            for (i, term) in terms.iter().enumerate() {
+                let matches = find_ranges(value, term);
+                found[i] |= !matches.is_empty();
+                ranges.extend(matches);
+            }
+            if !ranges.is_empty() {
+                hits.push(Hit {
+                    label,
+                    value: value.clone(),
+                    ranges: merge(ranges),
+                });
+            }
+        }
+    }
+
+    (!terms.is_empty() && found.iter().all(|f| *f)).then_some(hits)
+}
+
+/// Whether every term occurs, ignoring case, in some value of the given tags.
+pub fn matches_all(entry: &RisEntry, terms: &[String], tags: &[&str]) -> bool {
+    terms.iter().all(|term| {
+        tags.iter()
+            .flat_map(|tag| entry.fields.get(*tag).into_iter().flatten())
+            .any(|value| !find_ranges(value, term).is_empty())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ris::parse_ris;
+
+    #[test]
+    fn requires_every_term() {
+        let entry = parse_ris(
+            "TY  - JOUR\nAU  - Doe, Jane\nTI  - Query Optimization\nKW  - databases\nER  -",
+        )
+        .unwrap()
+        .remove(0);
+
+        let hits = search(&entry, &terms("doe QUERY")).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].label, "title");
+        assert_eq!(hits[0].ranges, vec![0..5]);
+        assert_eq!(hits[1].ranges, vec![0..3]);
+        assert!(search(&entry, &terms("doe graphs")).is_none());
+    }
+
+    #[test]
+    fn matches_terms_in_the_given_tags_only() {
+        let entry = parse_ris("TY  - JOUR\nAU  - Doe, Jane\nPY  - 2020\nAB  - Cats\nER  -")
+            .unwrap()
+            .remove(0);
+
+        assert!(matches_all(&entry, &terms("doe 2020"), &["AU", "PY"]));
+        assert!(!matches_all(&entry, &terms("doe cats"), &["AU", "PY"]));
+        assert!(matches_all(&entry, &[], &["AU"]));
+    }
+
+    #[test]
+    fn finds_ranges_ignoring_case() {
+        assert_eq!(find_ranges("Über über", "ÜBER"), vec![0..5, 6..11]);
+        assert_eq!(find_ranges("aaa", "aa"), vec![0..2]);
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use url::Url;
+
+use crate::services::http;
+
+const API_BASE: &str = "https://api.semanticscholar.org/graph/v1";
+
+#[derive(Deserialize)]
+struct PaperReferences {
+    #[serde(default)]
+    references: Vec<ReferencedPaper>,
+}
+
+#[derive(Deserialize)]
+struct ReferencedPaper {
+    #[serde(rename = "externalIds", default)]
+    external_ids: Option<ExternalIds>,
+}
+
+#[derive(Deserialize)]
+struct ExternalIds {
+    #[serde(rename = "DOI")]
+    doi: Option<String>,
+}
+
+/// The API address of the paper with the given DOI, asking for `fields`.
+fn paper_url(doi: &str, fields: &str) -> Result<Url> {
+    let mut url = http::url_with_path(API_BASE, &format!("paper/DOI:{}", doi))?;
+    url.query_pairs_mut().append_pair("fields", fields);
+    Ok(url)
+}
+
+/// DOIs of the works cited by the paper with the given DOI.
+pub fn reference_dois(doi: &str) -> Result<Vec<String>> {
+    let url = paper_url(doi, "references.externalIds")?;
+    let paper: PaperReferences = http::get_json(url.as_str())?;
+
+    Ok(paper
+        .references
+        .into_iter()
+        .filter_map(|r| r.external_ids?.doi)
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct PaperCitations {
+    #[serde(rename = "citationCount")]
+    citation_count: Option<u64>,
+}
+
+/// How often the paper with the given DOI has been cited, or `None` when
+/// Semantic Scholar doesn't know the paper.
+pub fn citation_count(doi: &str) -> Result<Option<u64>> {
+    let url = paper_url(doi, "citationCount")?;
+    match http::agent().get(url.as_str()).call() {
+        Ok(response) => {
+            let paper: PaperCitations = response
+                .into_json()
+                .with_context(|| format!("Failed to parse JSON from {}", url))?;
+            Ok(paper.citation_count)
+        }
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("Request to {} failed", url)),
+    }
+}
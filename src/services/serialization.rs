@@ -1,141 +1,553 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
 use std::path::Path;
 
+use crate::config::{self, ProjectConfig};
 use crate::model::ris::{self, RisEntry};
 use crate::repo;
-use anyhow::Result;
+use crate::services::citekey::{self, KeyFormat};
+use crate::services::{better_bibtex, csl_json, endnote, jabref, mendeley, refworks, scholar};
+use crate::services::library::{self, LibraryEntry};
+use anyhow::{Context, Result};
 use biblatex::{Bibliography, ParseError};
+use clap::ValueEnum;
 
-pub enum ImportResult {
-    BibtexImported,
-    BibtexError{error: ParseError},
-    RisImported,
-    RisError{error: anyhow::Error},
+/// Formats `refrs import --format` can be forced to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    Bibtex,
+    Ris,
+    CslJson,
+    /// Better BibTeX JSON export of a Zotero library, with its collections
+    BetterBibtex,
+    /// EndNote tagged export (`%A`, `%T`, ...)
+    Endnote,
+    /// RefWorks tagged export (`RT`, `A1`, ...)
+    Refworks,
+    /// Plain-text citations as Google Scholar's "Cite" dialog formats them
+    Text,
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ImportFormat::Bibtex => "BibTeX",
+            ImportFormat::Ris => "RIS",
+            ImportFormat::CslJson => "CSL-JSON",
+            ImportFormat::BetterBibtex => "Better BibTeX JSON",
+            ImportFormat::Endnote => "EndNote",
+            ImportFormat::Refworks => "RefWorks",
+            ImportFormat::Text => "plain-text citation",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub enum ParseResult {
+    Parsed {
+        format: ImportFormat,
+        entries: Vec<RisEntry>,
+    },
+    BibtexError {
+        error: ParseError,
+    },
+    Error {
+        format: ImportFormat,
+        error: anyhow::Error,
+    },
     UnrecognizedFormat,
 }
 
+impl ParseResult {
+    /// The parsed entries, or `None` when parsing failed.
+    pub fn entries(self) -> Option<Vec<RisEntry>> {
+        match self {
+            ParseResult::Parsed { entries, .. } => Some(entries),
+            _ => None,
+        }
+    }
+}
 
-pub fn import(text: &String, project_path: &String) -> Result<ImportResult> {
-    fs::create_dir_all(project_path)?;
+/// How sure auto-detection is about the format it picked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Confidence {
+    /// The text starts the way the format does.
+    High,
+    /// The text parsed, but had to be picked out of surrounding text.
+    Low,
+}
 
-    println!("{project_path}");
+/// An entry that has been parsed but not yet written to the project.
+pub struct PendingImport {
+    pub entry: RisEntry,
+    /// Citation key, which is also the file stem of the `.ris` file.
+    pub key: String,
+    /// Id of an existing (or earlier pending) entry describing the same work.
+    pub duplicate_of: Option<String>,
+    pub selected: bool,
+}
 
-    match Bibliography::parse(&text) {
-        Ok(bibliography) => {
-            if !bibliography.is_empty() {
-                for entry in bibliography.iter() {
-                    add_entry(&ris::RisEntry::from(entry), project_path)?;
-                }
-                return Ok(ImportResult::BibtexImported);
-            }
-        }
-        Err(error) => {
-            return Ok(ImportResult::BibtexError { error });
-        }
+/// Whether `text` is a JSON array or object, which only CSL-JSON and Better
+/// BibTeX JSON can be.
+fn is_json(text: &str) -> bool {
+    let start = text.trim_start();
+    (start.starts_with('[') || start.starts_with('{'))
+        && serde_json::from_str::<serde_json::Value>(text).is_ok()
+}
+
+/// Detects the format of `text` and parses it.
+pub fn parse(text: &str) -> ParseResult {
+    // JSON first: a CSL-JSON note or abstract may well contain BibTeX.
+    if is_json(text) {
+        let better_bibtex = serde_json::from_str(text).is_ok_and(|value| better_bibtex::is_export(&value));
+        return parse_as(text, if better_bibtex { ImportFormat::BetterBibtex } else { ImportFormat::CslJson });
+    }
+    if refworks::is_refworks(text) {
+        return parse_as(text, ImportFormat::Refworks);
     }
 
-    // Did not recognize bibtex, try RIS
-    match ris::parse_ris(&text) {
-        Ok(entries) => {
-            if !entries.is_empty() {
-                for entry in entries.iter() {
-                    add_entry(entry, project_path)?;
-                }
-                return Ok(ImportResult::RisImported);
-            }
-        }
-        Err(error) => {
-            return Ok(ImportResult::RisError{error})
-        }
+    let looks_like_ris = text.lines().any(|line| line.trim_start().starts_with("TY  -"));
+
+    match parse_as(text, ImportFormat::Bibtex) {
+        ParseResult::Parsed { entries, .. } if entries.is_empty() => {}
+        // Text with an `@` somewhere, e.g. an email in a RIS abstract, can
+        // trip the BibTeX parser. Don't let that hide the RIS result.
+        ParseResult::BibtexError { .. } if looks_like_ris => {}
+        result => return result,
     }
 
-    Ok(ImportResult::UnrecognizedFormat)
+    // Did not recognize bibtex, try RIS
+    let ris_result = match parse_as(text, ImportFormat::Ris) {
+        ParseResult::Parsed { entries, .. } if entries.is_empty() => ParseResult::UnrecognizedFormat,
+        result @ ParseResult::Parsed { .. } => return result,
+        result if looks_like_ris => return result,
+        result => result,
+    };
+
+    // Fall back to free text, which is what most people paste.
+    match parse_as(text, ImportFormat::Text) {
+        ParseResult::Parsed { entries, .. } if !entries.is_empty() => ParseResult::Parsed {
+            format: ImportFormat::Text,
+            entries,
+        },
+        _ => ris_result,
+    }
 }
 
-pub fn add_entry(entry: &RisEntry, project_path: &String) -> Result<()> {
-    let ris_folder = "ris_files";
-    let ris_folder_path = Path::new(&project_path).join(ris_folder);
+/// Like [`parse`], and also reports how confident the detection is.
+pub fn detect(text: &str) -> (ParseResult, Confidence) {
+    let result = parse(text);
+    let start = text.trim_start();
+    let confidence = match &result {
+        ParseResult::Parsed {
+            format: ImportFormat::Bibtex,
+            ..
+        } if start.starts_with('@') || mendeley::is_export(text) => Confidence::High,
+        ParseResult::Parsed {
+            format: ImportFormat::Ris,
+            ..
+        } if start.starts_with("TY  -") => Confidence::High,
+        ParseResult::Parsed {
+            format: ImportFormat::CslJson | ImportFormat::BetterBibtex | ImportFormat::Refworks,
+            ..
+        } => Confidence::High,
+        _ => Confidence::Low,
+    };
+    (result, confidence)
+}
 
-    if let Err(e) = fs::create_dir_all(&ris_folder_path) {
-        eprintln!(
-            "Error creating directory {}: {}",
-            ris_folder_path.display(),
-            e
-        );
-        return Ok(());
-    }
-
-    let academic_stopwords = [
-        "a", "an", "and", "the", "of", "in", "on", "for", "with", "to", "from", "by", "about",
-        "as", "at", "into", "through", "between", "within", "without", "or", "nor", "but", "yet",
-        "so", "because", "although", "since", "while", "when", "where", "that", "which", "what",
-        "who", "whose", "whom", "how", "why", "it", "its", "this", "these", "those", "there",
-        "here", "such", "more", "less", "many", "much", "any", "every", "each", "other", "some",
-        "few", "all", "both", "either", "neither", "one", "two", "three", "four", "five", "six",
-        "seven", "eight", "nine", "ten", "up", "down", "out", "over", "under", "above", "below",
-        "new", "current", "recent", "future", "analysis", "study", "research", "results", "review",
-        "overview",
-    ];
-
-    let title = first_non_stopword(
-        match entry.get_field("TI") {
-            Some(title) => title.trim(),
-            None => "notitle",
+/// Parses `text` as the given format without any detection.
+pub fn parse_as(text: &str, format: ImportFormat) -> ParseResult {
+    let from_mendeley = format == ImportFormat::Bibtex && mendeley::is_export(text);
+    let parsed = match format {
+        ImportFormat::Bibtex => match Bibliography::parse(text) {
+            Ok(bibliography) => Ok(bibliography
+                .iter()
+                .map(|entry| {
+                    // Keep the original key so it can be reused as citation key.
+                    let mut ris_entry = RisEntry::from(entry);
+                    ris_entry.fields.insert("ID".to_string(), vec![entry.key.clone()]);
+                    if from_mendeley {
+                        mendeley::clean(entry, &mut ris_entry);
+                    }
+                    jabref::add_groups(entry, &mut ris_entry);
+                    ris_entry
+                })
+                .collect()),
+            Err(error) => return ParseResult::BibtexError { error },
         },
-        &academic_stopwords,
-    )
-    .unwrap_or("notitle".to_string())
-    .to_lowercase();
-
-    let author = match entry.get_field("AU") {
-        Some(author) => {
-            author.split(',').next().unwrap_or("noauthor").trim()
-        }
-        None => "noauthor",
+        ImportFormat::Ris => ris::parse_ris(text),
+        ImportFormat::CslJson => csl_json::parse(text),
+        ImportFormat::BetterBibtex => better_bibtex::parse(text),
+        ImportFormat::Endnote => endnote::parse(text),
+        ImportFormat::Refworks => refworks::parse(text),
+        ImportFormat::Text => scholar::parse(text),
+    };
+
+    match parsed {
+        Ok(entries) => ParseResult::Parsed { format, entries },
+        Err(error) => ParseResult::Error { format, error },
     }
-    .to_lowercase();
+}
 
-    let year = match entry.get_field("PY") {
-        Some(date) => date.trim(),
-        None => "nodate",
-    };
+/// RIS field the citation key of an entry is pinned in.
+pub const KEY_TAG: &str = "ID";
 
-    // Sanitize title and author to avoid invalid file characters
-    let sanitized_title = title.replace(|c: char| !c.is_alphanumeric(), "_");
-    let sanitized_author = author.replace(|c: char| !c.is_alphanumeric(), "_");
+/// Assigns keys to freshly parsed entries and flags the ones already present in
+/// the project. Duplicates start out deselected.
+pub fn prepare_import(
+    entries: Vec<RisEntry>,
+    project_path: &str,
+    key_format: KeyFormat,
+    key_pattern: &str,
+) -> Result<Vec<PendingImport>> {
+    let existing = library::load_entries(project_path)?;
+    let on_disk: HashSet<String> = existing.iter().map(|e| e.id.clone()).collect();
 
-    let mut file_name = format!("{}_{}_{}.ris", sanitized_author, sanitized_title, year);
-    let mut file_path = ris_folder_path.join(&file_name);
+    // Keys pinned in existing entries stay reserved even if their file was renamed.
+    let mut taken: HashSet<String> = existing
+        .iter()
+        .filter_map(|e| e.entry.get_field(KEY_TAG).cloned())
+        .collect();
 
-    // Check if file exists and append (1), (2), ... if necessary
-    let mut counter = 1;
-    while file_path.exists() {
-        file_name = format!(
-            "{}_{}_{}_{}.ris",
-            sanitized_author, sanitized_title, year, counter
-        );
-        file_path = ris_folder_path.join(&file_name);
-        counter += 1;
+    // Entries competing for the same key get their suffixes in a fixed order,
+    // so importing the same references in another order gives the same keys.
+    let base_keys: Vec<String> = entries
+        .iter()
+        .map(|entry| citekey::generate(entry, key_format, key_pattern))
+        .collect();
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    order.sort_by_key(|&i| (base_keys[i].clone(), disambiguation_order(&entries[i]), i));
+
+    let mut keys = vec![String::new(); entries.len()];
+    for i in order {
+        keys[i] = unique_key(&base_keys[i], &on_disk, &taken, key_format);
+        taken.insert(keys[i].clone());
+    }
+
+    let mut pending: Vec<PendingImport> = Vec::new();
+    for (entry, key) in entries.into_iter().zip(keys) {
+        let duplicate_of = find_duplicate(&entry, &existing, &pending);
+        pending.push(PendingImport {
+            selected: duplicate_of.is_none(),
+            entry,
+            key,
+            duplicate_of,
+        });
+    }
+
+    Ok(pending)
+}
+
+fn disambiguation_order(entry: &RisEntry) -> (String, String, String) {
+    let field = |tag: &str| entry.get_field(tag).map(|v| v.trim().to_lowercase()).unwrap_or_default();
+    (field("TI"), field("PY"), field("DO"))
+}
+
+fn find_duplicate(
+    entry: &RisEntry,
+    existing: &[LibraryEntry],
+    pending: &[PendingImport],
+) -> Option<String> {
+    existing
+        .iter()
+        .map(|e| (&e.id, &e.entry))
+        .chain(pending.iter().map(|p| (&p.key, &p.entry)))
+        .find(|(_, other)| library::is_duplicate(entry, other))
+        .map(|(id, _)| id.clone())
+}
+
+/// Writes the selected entries to the project and returns the created file names.
+pub fn commit_import(
+    pending: &[PendingImport],
+    project_path: &str,
+    key_format: KeyFormat,
+) -> Result<Vec<String>> {
+    let config = config::load(project_path)?;
+    let mut taken = HashSet::new();
+    let mut file_names = Vec::new();
+
+    for import in pending.iter().filter(|p| p.selected) {
+        let key = sanitize_key(&import.key);
+        let file_name = add_entry_with_key(&import.entry, &key, project_path, &config, &taken, key_format)?;
+        taken.insert(file_name.trim_end_matches(".ris").to_string());
+        file_names.push(file_name);
     }
 
+    Ok(file_names)
+}
+
+/// File names `commit_import` would create for the selected entries.
+pub fn planned_file_names(pending: &[PendingImport], project_path: &str, key_format: KeyFormat) -> Result<Vec<String>> {
+    let on_disk = library::file_stems(project_path)?;
+    let config = config::load(project_path)?;
+    let mut taken = HashSet::new();
+    Ok(pending
+        .iter()
+        .filter(|p| p.selected)
+        .map(|import| {
+            let key = unique_key(&sanitize_key(&import.key), &on_disk, &taken, key_format);
+            let stem = sanitize_key(&config.file_stem(&key, &import.entry));
+            let stem = unique_key(&stem, &on_disk, &taken, key_format);
+            taken.insert(stem.clone());
+            format!("{}.ris", stem)
+        })
+        .collect())
+}
+
+fn add_entry_with_key(
+    entry: &RisEntry,
+    key: &str,
+    project_path: &str,
+    config: &ProjectConfig,
+    taken: &HashSet<String>,
+    key_format: KeyFormat,
+) -> Result<String> {
+    let on_disk = library::file_stems(project_path)?;
+    let key = unique_key(key, &on_disk, taken, key_format);
+    let stem = sanitize_key(&config.file_stem(&key, entry));
+    let stem = unique_key(&stem, &on_disk, taken, key_format);
+    let file_name = format!("{}.ris", stem);
+    let file_path = library::entry_path(project_path, config, &stem, entry);
+
+    let folder = file_path.parent().unwrap_or(Path::new(project_path));
+    fs::create_dir_all(folder).with_context(|| format!("Failed to create directory {}", folder.display()))?;
+
+    // Pin the assigned key in the entry, so it survives renames and later
+    // changes to the key scheme.
+    let mut entry = entry.clone();
+    entry.fields.insert(KEY_TAG.to_string(), vec![key]);
+
     // Write the RIS entry to the file
     fs::write(&file_path, entry.to_string())?;
 
     let commit_message = format!("Added {}", file_name);
-    repo::add_all(&project_path)?;
-    repo::commit(&project_path, &commit_message)?;
+    repo::add_all(project_path)?;
+    repo::commit(project_path, &commit_message)?;
+
+    Ok(file_name)
+}
+
+/// Replaces characters that are unsafe in file names or BibTeX keys.
+pub fn sanitize_key(key: &str) -> String {
+    let key = key
+        .trim()
+        .replace(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'), "_");
+    if key.is_empty() {
+        "unnamed".to_string()
+    } else {
+        key
+    }
+}
+
+/// Appends a disambiguation suffix until the key is neither the stem of a
+/// file on disk nor already taken: `_1`, `_2`, ... for refrs keys and `a`,
+/// `b`, ... like Better BibTeX does for its keys.
+fn unique_key(
+    key: &str,
+    on_disk: &HashSet<String>,
+    taken: &HashSet<String>,
+    key_format: KeyFormat,
+) -> String {
+    let is_free = |candidate: &str| !taken.contains(candidate) && !on_disk.contains(candidate);
+
+    disambiguate(key, key_format, is_free)
+}
+
+fn disambiguate(key: &str, key_format: KeyFormat, is_free: impl Fn(&str) -> bool) -> String {
+    let mut candidate = key.to_string();
+    let mut counter = 1;
+    while !is_free(&candidate) {
+        candidate = match key_format {
+            KeyFormat::Refrs => format!("{}_{}", key, counter),
+            KeyFormat::BetterBibtex | KeyFormat::Pattern => format!("{}{}", key, letter_suffix(counter)),
+        };
+        counter += 1;
+    }
+    candidate
+}
+
+/// New citation keys for the entries without a pinned key, or for all
+/// entries with `regenerate`, as `(entry index, key)`. Entries competing for
+/// a key are ordered by title, year and DOI, so the outcome does not depend
+/// on file names or the order of the library.
+pub fn assign_keys(
+    entries: &[LibraryEntry],
+    key_format: KeyFormat,
+    key_pattern: &str,
+    regenerate: bool,
+) -> Vec<(usize, String)> {
+    let pinned = |entry: &LibraryEntry| entry.entry.get_field(KEY_TAG).is_some_and(|key| !key.trim().is_empty());
+    let (pending, kept): (Vec<usize>, Vec<usize>) =
+        (0..entries.len()).partition(|&i| regenerate || !pinned(&entries[i]));
+    let mut taken: HashSet<String> = kept.iter().map(|&i| entries[i].key().to_string()).collect();
+
+    let base_keys: HashMap<usize, String> = pending
+        .iter()
+        .map(|&i| {
+            let entry = &entries[i].entry;
+            // The `ID` tag is what gets replaced, so unlike `citekey::generate`
+            // only keys pinned in notes are kept.
+            let key = match key_format {
+                KeyFormat::Refrs => citekey::refrs_key(entry),
+                KeyFormat::BetterBibtex => citekey::pinned_key(entry).unwrap_or_else(|| citekey::bbt_key(entry)),
+                KeyFormat::Pattern => {
+                    citekey::pinned_key(entry).unwrap_or_else(|| citekey::pattern_key(entry, key_pattern))
+                }
+            };
+            (i, sanitize_key(&key))
+        })
+        .collect();
+    let mut order = pending;
+    order.sort_by_key(|&i| (base_keys[&i].clone(), disambiguation_order(&entries[i].entry), entries[i].id.clone()));
+
+    let mut keys = Vec::new();
+    for i in order {
+        let key = disambiguate(&base_keys[&i], key_format, |candidate| !taken.contains(candidate));
+        taken.insert(key.clone());
+        keys.push((i, key));
+    }
+    keys.sort();
+    keys
+}
 
-    Ok(())
+/// 1 -> `a`, 26 -> `z`, 27 -> `aa`, ...
+pub fn letter_suffix(mut n: usize) -> String {
+    let mut suffix = Vec::new();
+    while n > 0 {
+        n -= 1;
+        suffix.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    suffix.reverse();
+    String::from_utf8(suffix).unwrap_or_default()
 }
 
-fn first_non_stopword(input: &str, stopwords: &[&str]) -> Option<String> {
-    // Convert the stopwords array into a HashSet for faster lookup
-    let stopwords_set: std::collections::HashSet<_> = stopwords.iter().copied().collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colliding_keys_do_not_depend_on_import_order() {
+        let project = std::env::temp_dir().join(format!("refrs-keys-{}", std::process::id()));
+        let project = project.to_string_lossy().to_string();
+        let entry = |title: &str| {
+            ris::parse_ris(&format!("TY  - JOUR\nAU  - Smith, A\nTI  - {}\nPY  - 2020\nER  -", title))
+                .unwrap()
+                .remove(0)
+        };
+
+        let keys = |titles: &[&str]| -> Vec<(String, String)> {
+            let entries = titles.iter().map(|t| entry(t)).collect();
+            prepare_import(entries, &project, KeyFormat::BetterBibtex, "")
+                .unwrap()
+                .into_iter()
+                .map(|p| (p.entry.get_field("TI").unwrap().clone(), p.key))
+                .collect()
+        };
+
+        let zebras = "Deep learning models of zebras";
+        let apes = "Deep learning models of apes";
+        let mut forward = keys(&[zebras, apes]);
+        let mut backward = keys(&[apes, zebras]);
+        forward.sort();
+        backward.sort();
+        assert_eq!(forward, backward);
+        assert_eq!(
+            forward,
+            vec![
+                (apes.to_string(), "smithDeepLearningModels2020".to_string()),
+                (zebras.to_string(), "smithDeepLearningModels2020a".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assigned_keys_skip_pinned_keys_deterministically() {
+        let entry = |id: &str, text: &str| LibraryEntry {
+            id: id.to_string(),
+            path: Path::new(id).with_extension("ris"),
+            entry: ris::parse_ris(text).unwrap().remove(0),
+        };
+        let cats = "TY  - JOUR\nAU  - Doe, Jane\nTI  - Comprehensive cats\nPY  - 2021\nER  -";
+        let dogs = "TY  - JOUR\nAU  - Doe, Jane\nTI  - Comprehensive dogs\nPY  - 2021\nER  -";
+        let pinned = "TY  - JOUR\nID  - doe2021comprehensive\nTI  - Other\nER  -";
+
+        let forward = vec![entry("a", cats), entry("b", dogs), entry("c", pinned)];
+        let backward = vec![entry("a", dogs), entry("b", cats), entry("c", pinned)];
+        let keys = |entries: &[LibraryEntry]| -> Vec<(String, String)> {
+            let mut keys: Vec<_> = assign_keys(entries, KeyFormat::Pattern, citekey::DEFAULT_KEY_PATTERN, false)
+                .into_iter()
+                .map(|(i, key)| (entries[i].entry.get_field("TI").unwrap().clone(), key))
+                .collect();
+            keys.sort();
+            keys
+        };
+
+        assert_eq!(keys(&forward), keys(&backward));
+        assert_eq!(
+            keys(&forward),
+            vec![
+                ("Comprehensive cats".to_string(), "doe2021comprehensivea".to_string()),
+                ("Comprehensive dogs".to_string(), "doe2021comprehensiveb".to_string()),
+            ]
+        );
+    }
 
-    // Split the input into words, filter out stopwords, and return the first non-stopword
-    input
-        .split_whitespace() // Split the string into words
-        .filter(|word| !stopwords_set.contains(*word)) // Remove stopwords
-        .next() // Get the first non-stopword
-        .map(|word| word.to_string()) // Convert it to a String
+    #[test]
+    fn test_bibtex_errors_do_not_hide_ris() {
+        let text = "TY  - JOUR\nTI  - Title\nAB  - Contact me@example.org {\nER  - \n";
+        match detect(text) {
+            (ParseResult::Parsed { format, entries }, Confidence::High) => {
+                assert_eq!(format, ImportFormat::Ris);
+                assert_eq!(entries.len(), 1);
+            }
+            _ => panic!("expected RIS to be detected"),
+        }
+    }
+
+    #[test]
+    fn test_bibtex_inside_prose_has_low_confidence() {
+        let text = "Cite as:\n@article{a, title={T}, year={2000}}\n";
+        let (result, confidence) = detect(text);
+        assert!(matches!(
+            result,
+            ParseResult::Parsed {
+                format: ImportFormat::Bibtex,
+                ..
+            }
+        ));
+        assert_eq!(confidence, Confidence::Low);
+    }
+
+    #[test]
+    fn test_falls_back_to_plain_text_citations() {
+        let text = "Doe, J., & Smith, J. (2020). Cats. Nature, 12(3), 45-67.\n";
+        let (result, confidence) = detect(text);
+        assert!(matches!(
+            result,
+            ParseResult::Parsed {
+                format: ImportFormat::Text,
+                ..
+            }
+        ));
+        assert_eq!(confidence, Confidence::Low);
+        assert!(parse("Just some words.").entries().is_none());
+    }
+
+    #[test]
+    fn test_detects_csl_json_before_bibtex() {
+        let text = r#"[{"id": "doe2020", "type": "article-journal", "title": "Cats",
+            "note": "@article{doe2020, title={Cats}}"}]"#;
+        let (result, confidence) = detect(text);
+        match result {
+            ParseResult::Parsed {
+                format: ImportFormat::CslJson,
+                entries,
+            } => assert_eq!(entries[0].get_field("TI").map(String::as_str), Some("Cats")),
+            _ => panic!("expected CSL-JSON"),
+        }
+        assert_eq!(confidence, Confidence::High);
+    }
 }
@@ -0,0 +1,163 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+
+use crate::services::http;
+use crate::state;
+
+const STYLES_REPOSITORY: &str =
+    "https://raw.githubusercontent.com/citation-style-language/styles/master";
+const LOCALES_REPOSITORY: &str =
+    "https://raw.githubusercontent.com/citation-style-language/locales/master";
+
+pub struct InstalledStyle {
+    pub name: String,
+    pub title: Option<String>,
+}
+
+pub fn styles_dir() -> PathBuf {
+    state::data_dir().join("styles")
+}
+
+pub fn locales_dir() -> PathBuf {
+    state::data_dir().join("locales")
+}
+
+fn is_url(name_or_url: &str) -> bool {
+    name_or_url.starts_with("http://") || name_or_url.starts_with("https://")
+}
+
+/// Whether `name` is safe as a file name in the styles or locales folder:
+/// not empty, without path separators and without `..`.
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
+}
+
+/// Downloads a CSL style, given either its name in the official repository
+/// (e.g. `apa`, `ieee`) or a URL, and returns the name it was installed under.
+pub fn install_style(name_or_url: &str) -> Result<String> {
+    let (name, url) = if is_url(name_or_url) {
+        let file_name = name_or_url
+            .rsplit('/')
+            .next()
+            .unwrap_or(name_or_url)
+            .trim_end_matches(".csl");
+        (file_name.to_string(), name_or_url.to_string())
+    } else {
+        let name = name_or_url.trim_end_matches(".csl");
+        (name.to_string(), format!("{}/{}.csl", STYLES_REPOSITORY, name))
+    };
+
+    if !is_plain_name(&name) {
+        return Err(anyhow!("{} is not a valid style name", name));
+    }
+
+    let content = http::get_text(&url)?;
+    if !content.contains("<style") {
+        return Err(anyhow!("{} is not a CSL style", url));
+    }
+
+    fs::create_dir_all(styles_dir())?;
+    fs::write(styles_dir().join(format!("{}.csl", name)), content)?;
+    Ok(name)
+}
+
+/// Downloads the CSL locale for a language code such as `en-US` or `nb-NO`.
+pub fn install_locale(code: &str) -> Result<String> {
+    if !is_plain_name(code) {
+        return Err(anyhow!("{} is not a valid locale code", code));
+    }
+    let url = format!("{}/locales-{}.xml", LOCALES_REPOSITORY, code);
+    let content = http::get_text(&url)?;
+    if !content.contains("<locale") {
+        return Err(anyhow!("{} is not a CSL locale", url));
+    }
+
+    fs::create_dir_all(locales_dir())?;
+    fs::write(locales_dir().join(format!("locales-{}.xml", code)), content)?;
+    Ok(code.to_string())
+}
+
+pub fn installed_styles() -> Result<Vec<InstalledStyle>> {
+    let mut styles = Vec::new();
+    if !styles_dir().exists() {
+        return Ok(styles);
+    }
+
+    for path in fs::read_dir(styles_dir())? {
+        let path = path?.path();
+        if path.extension().map(|ext| ext == "csl").unwrap_or(false) {
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            styles.push(InstalledStyle {
+                name: path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                title: element_text(&content, "title"),
+            });
+        }
+    }
+
+    styles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(styles)
+}
+
+pub fn installed_locales() -> Result<Vec<String>> {
+    let mut locales = Vec::new();
+    if !locales_dir().exists() {
+        return Ok(locales);
+    }
+
+    for path in fs::read_dir(locales_dir())? {
+        let file_name = path?.file_name().to_string_lossy().to_string();
+        if let Some(code) = file_name
+            .strip_prefix("locales-")
+            .and_then(|rest| rest.strip_suffix(".xml"))
+        {
+            locales.push(code.to_string());
+        }
+    }
+
+    locales.sort();
+    Ok(locales)
+}
+
+/// Path of an installed style, if there is one with that name.
+pub fn style_path(name: &str) -> Option<PathBuf> {
+    let name = name.trim_end_matches(".csl");
+    let path = styles_dir().join(format!("{}.csl", name));
+    (is_plain_name(name) && path.exists()).then_some(path)
+}
+
+pub fn remove_style(name: &str) -> Result<bool> {
+    match style_path(name) {
+        Some(path) => {
+            fs::remove_file(path)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Text of the first `<name>` element, which is enough to read a style's title.
+fn element_text(xml: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = xml[start..].find(&format!("</{}>", name))? + start;
+    Some(xml[start..end].trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_plain_name;
+
+    #[test]
+    fn rejects_names_leaving_the_folder() {
+        assert!(is_plain_name("apa"));
+        assert!(is_plain_name("nb-NO"));
+        assert!(!is_plain_name(""));
+        assert!(!is_plain_name("../../.bashrc"));
+        assert!(!is_plain_name("styles/apa"));
+        assert!(!is_plain_name("..\\apa"));
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+
+use crate::model::ris::RisEntry;
+use crate::services::library::LibraryEntry;
+
+/// Tags are stored as RIS keywords, so other reference managers see them too.
+pub const TAG_FIELD: &str = "KW";
+
+pub fn tags(entry: &RisEntry) -> &[String] {
+    entry.fields.get(TAG_FIELD).map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Whether the entry has the tag, ignoring case.
+pub fn has_tag(entry: &RisEntry, tag: &str) -> bool {
+    tags(entry).iter().any(|t| t.trim().eq_ignore_ascii_case(tag.trim()))
+}
+
+/// Adds the tag unless the entry already has it. Returns whether it was added.
+pub fn add_tag(entry: &mut RisEntry, tag: &str) -> bool {
+    let tag = tag.trim();
+    if tag.is_empty() || has_tag(entry, tag) {
+        return false;
+    }
+    entry.fields.entry(TAG_FIELD.to_string()).or_default().push(tag.to_string());
+    true
+}
+
+/// Removes the tag, ignoring case. Returns whether the entry had it.
+pub fn remove_tag(entry: &mut RisEntry, tag: &str) -> bool {
+    let Some(tags) = entry.fields.get_mut(TAG_FIELD) else {
+        return false;
+    };
+    let before = tags.len();
+    tags.retain(|t| !t.trim().eq_ignore_ascii_case(tag.trim()));
+    let removed = tags.len() < before;
+    if tags.is_empty() {
+        entry.fields.remove(TAG_FIELD);
+    }
+    removed
+}
+
+/// Every tag in the library with the number of entries using it. Tags
+/// differing only in case are counted together under the first spelling seen.
+pub fn tag_counts(entries: &[LibraryEntry]) -> BTreeMap<String, usize> {
+    let mut spellings: BTreeMap<String, String> = BTreeMap::new();
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in entries {
+        for tag in tags(&entry.entry) {
+            let tag = tag.trim();
+            let spelling = spellings
+                .entry(tag.to_lowercase())
+                .or_insert_with(|| tag.to_string());
+            *counts.entry(spelling.clone()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ris::parse_ris;
+
+    #[test]
+    fn adds_and_removes_tags_ignoring_case() {
+        let mut entry = parse_ris("TY  - JOUR\nTI  - T\nKW  - Graphs\nER  -").unwrap().remove(0);
+
+        assert!(!add_tag(&mut entry, "graphs"));
+        assert!(add_tag(&mut entry, "to-read"));
+        assert_eq!(tags(&entry), ["Graphs", "to-read"]);
+
+        assert!(remove_tag(&mut entry, "GRAPHS"));
+        assert!(remove_tag(&mut entry, "to-read"));
+        assert!(!entry.fields.contains_key(TAG_FIELD));
+    }
+}
@@ -0,0 +1,51 @@
+//! Client for Zotero's [translation-server], which runs Zotero's site
+//! translators on a page. They know publishers whose pages lack citation
+//! meta tags, so `import --url` asks the server first when one is set up.
+//!
+//! [translation-server]: https://github.com/zotero/translation-server
+
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+use crate::model::ris::RisEntry;
+use crate::services::{http, zotero};
+
+static ENDPOINT: OnceLock<String> = OnceLock::new();
+
+/// Sets the server `import --url` uses, e.g. `http://127.0.0.1:1969`.
+pub fn set_endpoint(endpoint: &str) {
+    let _ = ENDPOINT.set(endpoint.trim_end_matches('/').to_string());
+}
+
+/// The configured server, if any.
+pub fn endpoint() -> Option<&'static str> {
+    ENDPOINT.get().map(String::as_str)
+}
+
+/// Runs the translators of `endpoint` on `url`. A page listing several items,
+/// such as search results, gives all of them.
+pub fn translate(endpoint: &str, url: &str) -> Result<Vec<RisEntry>> {
+    let web = format!("{}/web", endpoint);
+    let request = || http::agent().post(&web);
+    let response = match request().set("Content-Type", "text/plain").send_string(url) {
+        Ok(response) => response,
+        Err(ureq::Error::Status(501, _)) => bail!("The translation server has no translator for {}", url),
+        Err(err) => return Err(err).with_context(|| format!("Request to {} failed", web)),
+    };
+
+    let response = if response.status() == 300 {
+        // Sending the choices back unchanged selects every item.
+        let choices: Value = response.into_json().context("Failed to parse the translation server's choices")?;
+        request()
+            .send_json(choices)
+            .with_context(|| format!("Request to {} failed", web))?
+    } else {
+        response
+    };
+    let items: Value = response
+        .into_json()
+        .context("Failed to parse JSON from the translation server")?;
+    Ok(zotero::parse_items(&items))
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// File in the project root mapping canonical venue names to their aliases.
+pub const ALIASES_FILE: &str = "venues.yaml";
+
+/// Canonical venue name → aliases, e.g.
+/// `Proceedings of the VLDB Endowment: [Proc. VLDB Endow., PVLDB]`.
+#[derive(Debug, Default)]
+pub struct VenueAliases {
+    pub table: BTreeMap<String, Vec<String>>,
+}
+
+fn aliases_path(project_path: &str) -> PathBuf {
+    Path::new(project_path).join(ALIASES_FILE)
+}
+
+/// Lowercase words without punctuation, so `Proc. VLDB Endow.` and
+/// `proc vldb endow` are the same alias.
+fn normalize(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl VenueAliases {
+    /// Reads the project's alias table. A missing file is an empty table.
+    pub fn load(project_path: &str) -> Result<Self> {
+        let path = aliases_path(project_path);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let table = serde_yaml::from_str(&text)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Self { table })
+    }
+
+    pub fn save(&self, project_path: &str) -> Result<()> {
+        let path = aliases_path(project_path);
+        fs::write(&path, serde_yaml::to_string(&self.table)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn add(&mut self, alias: &str, canonical: &str) {
+        let aliases = self.table.entry(canonical.trim().to_string()).or_default();
+        if !aliases.iter().any(|a| normalize(a) == normalize(alias)) {
+            aliases.push(alias.trim().to_string());
+        }
+    }
+
+    /// The canonical name for `venue`, if it is a known alias or a
+    /// differently written canonical name.
+    pub fn canonical(&self, venue: &str) -> Option<&str> {
+        let wanted = normalize(venue);
+        self.table
+            .iter()
+            .find(|(canonical, aliases)| {
+                normalize(canonical) == wanted || aliases.iter().any(|a| normalize(a) == wanted)
+            })
+            .map(|(canonical, _)| canonical.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_aliases_ignoring_case_and_punctuation() {
+        let mut aliases = VenueAliases::default();
+        aliases.add("Proc. VLDB Endow.", "Proceedings of the VLDB Endowment");
+        aliases.add("proc vldb endow", "Proceedings of the VLDB Endowment");
+
+        assert_eq!(aliases.table["Proceedings of the VLDB Endowment"].len(), 1);
+        assert_eq!(
+            aliases.canonical("PROC. VLDB ENDOW"),
+            Some("Proceedings of the VLDB Endowment")
+        );
+        assert_eq!(
+            aliases.canonical("proceedings of the vldb endowment"),
+            Some("Proceedings of the VLDB Endowment")
+        );
+        assert_eq!(aliases.canonical("VLDB Journal"), None);
+    }
+}
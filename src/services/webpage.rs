@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::model::ris::{ReferenceType, RisEntry};
+use crate::services::{doi, html, http};
+
+/// Fetches a landing page and reads its citation metadata, falling back to
+/// looking up the first DOI on the page.
+pub fn fetch(url: &str) -> Result<RisEntry> {
+    let page = http::get_text(url)?;
+    let tags = html::meta_tags(&page);
+    if let Some(entry) = parse_meta(&tags, url) {
+        return Ok(entry);
+    }
+
+    let found = tags
+        .iter()
+        .filter(|(name, _)| name.starts_with("dc.identifier") || name == "citation_doi")
+        .find_map(|(_, content)| doi::find(content))
+        .or_else(|| doi::find(&page));
+    match found {
+        Some(found) => {
+            let mut entry = doi::fetch(&found)?;
+            if entry.get_field("UR").is_none() {
+                entry.fields.insert("UR".to_string(), vec![url.to_string()]);
+            }
+            Ok(entry)
+        }
+        None => bail!("No citation metadata or DOI found on {}", url),
+    }
+}
+
+/// Builds an entry from Highwire Press (`citation_*`) tags, or Dublin Core
+/// (`DC.*`) tags when there are none. Needs at least a title.
+pub fn parse_meta(tags: &[(String, String)], url: &str) -> Option<RisEntry> {
+    let all = |names: &[&str]| -> Vec<&str> {
+        tags.iter()
+            .filter(|(name, content)| names.contains(&name.as_str()) && !content.is_empty())
+            .map(|(_, content)| content.as_str())
+            .collect()
+    };
+    let first = |names: &[&str]| all(names).first().map(|content| content.to_string());
+
+    let highwire = first(&["citation_title"]).is_some();
+    let title = first(&["citation_title"]).or_else(|| first(&["dc.title"]))?;
+
+    let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+    let mut add_field = |tag: &str, value: Option<String>| {
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            fields.entry(tag.to_string()).or_default().push(value);
+        }
+    };
+
+    add_field("TI", Some(title));
+    let ty = if highwire {
+        for author in all(&["citation_author"]) {
+            add_field("AU", Some(ris_name(author)));
+        }
+        let date = first(&["citation_publication_date", "citation_date", "citation_online_date"]);
+        add_field("PY", date.as_deref().and_then(year));
+        add_field("DA", date.as_deref().and_then(ris_date));
+        add_field(
+            "T2",
+            first(&[
+                "citation_journal_title",
+                "citation_conference_title",
+                "citation_book_title",
+                "citation_inbook_title",
+            ]),
+        );
+        add_field("J2", first(&["citation_journal_abbrev"]));
+        add_field("VL", first(&["citation_volume"]));
+        add_field("IS", first(&["citation_issue"]));
+        add_field("SP", first(&["citation_firstpage"]));
+        add_field("EP", first(&["citation_lastpage"]));
+        add_field("DO", first(&["citation_doi"]).and_then(|d| doi::find(&d)));
+        add_field("SN", first(&["citation_issn", "citation_isbn"]));
+        add_field(
+            "PB",
+            first(&[
+                "citation_publisher",
+                "citation_dissertation_institution",
+                "citation_technical_report_institution",
+            ]),
+        );
+        add_field("AB", first(&["citation_abstract"]));
+        add_field("LA", first(&["citation_language"]));
+        for keywords in all(&["citation_keywords"]) {
+            for keyword in keywords.split([';', ',']) {
+                add_field("KW", Some(keyword.trim().to_string()));
+            }
+        }
+
+        let has = |names: &[&str]| !all(names).is_empty();
+        if has(&["citation_journal_title"]) {
+            ReferenceType::Journal
+        } else if has(&["citation_conference_title"]) {
+            ReferenceType::ConferencePaper
+        } else if has(&["citation_book_title", "citation_inbook_title"]) {
+            ReferenceType::Chart
+        } else if has(&["citation_dissertation_institution"]) {
+            ReferenceType::Thesis
+        } else if has(&["citation_technical_report_institution"]) {
+            ReferenceType::Report
+        } else {
+            ReferenceType::ElectronicArticle
+        }
+    } else {
+        for author in all(&["dc.creator", "dc.contributor"]) {
+            add_field("AU", Some(ris_name(author)));
+        }
+        let date = first(&["dc.date", "dc.date.issued"]);
+        add_field("PY", date.as_deref().and_then(year));
+        add_field("DA", date.as_deref().and_then(ris_date));
+        add_field("PB", first(&["dc.publisher"]));
+        add_field("AB", first(&["dc.description"]));
+        add_field("LA", first(&["dc.language"]));
+        add_field("DO", all(&["dc.identifier"]).into_iter().find_map(doi::find));
+        for subject in all(&["dc.subject"]) {
+            add_field("KW", Some(subject.to_string()));
+        }
+        ReferenceType::ElectronicArticle
+    };
+    add_field("UR", Some(url.to_string()));
+
+    Some(RisEntry { ty, fields })
+}
+
+/// Pages give `Jane Doe` or `Doe, Jane`; RIS wants the latter.
+fn ris_name(name: &str) -> String {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.contains(',') {
+        return name;
+    }
+    match name.rsplit_once(' ') {
+        Some((first, last)) => format!("{}, {}", last, first),
+        None => name,
+    }
+}
+
+fn year(date: &str) -> Option<String> {
+    let year = date.get(..4)?;
+    year.chars().all(|c| c.is_ascii_digit()).then(|| year.to_string())
+}
+
+/// `2020/03/15` out of `2020-03-15` or `2020/03/15`, keeping what is given.
+fn ris_date(date: &str) -> Option<String> {
+    let parts: Vec<&str> = date
+        .split(['-', '/'])
+        .map(str::trim)
+        .take_while(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+        .collect();
+    (parts.len() > 1 && parts[0].len() == 4).then(|| parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_highwire_tags() {
+        let page = r#"<meta name="citation_title" content="Parametric query optimization">
+            <meta name="citation_author" content="Yannis E. Ioannidis">
+            <meta name="citation_author" content="Ng, Raymond T.">
+            <meta name="citation_journal_title" content="The VLDB Journal">
+            <meta name="citation_volume" content="6"><meta name="citation_firstpage" content="132">
+            <meta name="citation_publication_date" content="1997/05/01">
+            <meta name="citation_doi" content="doi:10.1007/s007780050037">
+            <meta name="DC.title" content="Ignored when Highwire tags exist">"#;
+        let entry = parse_meta(&html::meta_tags(page), "https://example.org/a").unwrap();
+
+        assert_eq!(entry.ty, ReferenceType::Journal);
+        assert_eq!(entry.fields["AU"], vec!["Ioannidis, Yannis E.", "Ng, Raymond T."]);
+        assert_eq!(entry.fields["TI"], vec!["Parametric query optimization"]);
+        assert_eq!(entry.fields["T2"], vec!["The VLDB Journal"]);
+        assert_eq!(entry.fields["DA"], vec!["1997/05/01"]);
+        assert_eq!(entry.fields["PY"], vec!["1997"]);
+        assert_eq!(entry.fields["DO"], vec!["10.1007/s007780050037"]);
+        assert_eq!(entry.fields["UR"], vec!["https://example.org/a"]);
+
+        let dublin_core = r#"<meta name="DC.title" content="Cats"><meta name="DC.creator" content="Doe, Jane">
+            <meta name="DC.date" content="2020-03-15"><meta name="DC.identifier" content="https://doi.org/10.1000/x">"#;
+        let entry = parse_meta(&html::meta_tags(dublin_core), "https://example.org/b").unwrap();
+        assert_eq!(entry.ty, ReferenceType::ElectronicArticle);
+        assert_eq!(entry.fields["DA"], vec!["2020/03/15"]);
+        assert_eq!(entry.fields["DO"], vec!["10.1000/x"]);
+
+        assert!(parse_meta(&html::meta_tags("<meta name=\"description\" content=\"x\">"), "u").is_none());
+    }
+}
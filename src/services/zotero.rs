@@ -0,0 +1,146 @@
+//! Zotero's item JSON, as its translation-server and Better BibTeX's JSON
+//! export give it.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::model::date::Date;
+use crate::model::ris::{ReferenceType, RisEntry};
+use crate::services::doi;
+
+/// Builds entries from Zotero's item JSON, skipping notes and attachments.
+pub fn parse_items(items: &Value) -> Vec<RisEntry> {
+    items
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|item| is_reference(item))
+        .map(item_to_entry)
+        .collect()
+}
+
+/// Whether `item` is a reference rather than a standalone note or attachment.
+pub fn is_reference(item: &Value) -> bool {
+    !matches!(item["itemType"].as_str(), Some("note" | "attachment") | None)
+}
+
+/// The entry of a single item.
+pub fn item_to_entry(item: &Value) -> RisEntry {
+    let ty = match item["itemType"].as_str().unwrap_or_default() {
+        "journalArticle" | "preprint" => ReferenceType::Journal,
+        "magazineArticle" => ReferenceType::MagazineArticle,
+        "newspaperArticle" => ReferenceType::Newspaper,
+        "book" => ReferenceType::Book,
+        "bookSection" => ReferenceType::Chart,
+        "conferencePaper" => ReferenceType::ConferencePaper,
+        "thesis" => ReferenceType::Thesis,
+        "report" => ReferenceType::Report,
+        "webpage" | "blogPost" | "forumPost" => ReferenceType::ElectronicArticle,
+        "dataset" => ReferenceType::Dataset,
+        "patent" => ReferenceType::Patent,
+        "manuscript" => ReferenceType::Manuscript,
+        "computerProgram" => ReferenceType::ComputerProgram,
+        "statute" => ReferenceType::Statute,
+        "case" => ReferenceType::Case,
+        "map" => ReferenceType::Map,
+        _ => ReferenceType::Generic,
+    };
+
+    let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+    let mut add_field = |tag: &str, value: Option<String>| {
+        if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+            fields.entry(tag.to_string()).or_default().push(value);
+        }
+    };
+    let text = |key: &str| item[key].as_str().map(str::to_string);
+
+    for creator in item["creators"].as_array().into_iter().flatten() {
+        let tag = match creator["creatorType"].as_str() {
+            Some("editor" | "seriesEditor") => "ED",
+            Some("author" | "inventor" | "programmer" | "presenter") | None => "AU",
+            _ => continue,
+        };
+        let name = match (creator["lastName"].as_str(), creator["firstName"].as_str()) {
+            (Some(last), Some(first)) if !first.is_empty() => Some(format!("{}, {}", last, first)),
+            (Some(last), _) => Some(last.to_string()),
+            _ => creator["name"].as_str().map(str::to_string),
+        };
+        add_field(tag, name);
+    }
+
+    add_field("ID", text("citationKey"));
+    add_field("TI", text("title"));
+    add_field(
+        "T2",
+        ["publicationTitle", "proceedingsTitle", "bookTitle", "websiteTitle", "blogTitle"]
+            .iter()
+            .find_map(|key| text(key).filter(|v| !v.is_empty())),
+    );
+    add_field("J2", text("journalAbbreviation"));
+    if let Some(date) = text("date").as_deref().and_then(Date::parse) {
+        add_field("PY", Some(date.year.to_string()));
+        add_field("DA", date.month.is_some().then(|| date.to_string()));
+    }
+    add_field("VL", text("volume"));
+    add_field("IS", text("issue"));
+    if let Some(pages) = text("pages") {
+        match pages.split_once(['-', '–']) {
+            Some((start, end)) => {
+                add_field("SP", Some(start.trim().to_string()));
+                add_field("EP", Some(end.trim().to_string()));
+            }
+            None => add_field("SP", Some(pages)),
+        }
+    }
+    add_field("PB", text("publisher").or_else(|| text("university")).or_else(|| text("institution")));
+    add_field("CY", text("place"));
+    add_field("DO", text("DOI").and_then(|d| doi::find(&d)));
+    add_field("SN", text("ISSN").or_else(|| text("ISBN")));
+    add_field("UR", text("url"));
+    add_field("AB", text("abstractNote"));
+    add_field("LA", text("language"));
+    for tag in item["tags"].as_array().into_iter().flatten() {
+        add_field("KW", tag["tag"].as_str().map(str::to_string));
+    }
+
+    RisEntry { ty, fields }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn maps_zotero_items() {
+        let items = json!([
+            {
+                "itemType": "journalArticle",
+                "title": "Skip lists",
+                "creators": [
+                    { "creatorType": "author", "firstName": "William", "lastName": "Pugh" },
+                    { "creatorType": "editor", "name": "ACM" },
+                    { "creatorType": "contributor", "firstName": "A", "lastName": "B" }
+                ],
+                "publicationTitle": "Communications of the ACM",
+                "date": "1990-06-01",
+                "pages": "668–676",
+                "DOI": "https://doi.org/10.1145/78973.78977",
+                "tags": [{ "tag": "data structures" }]
+            },
+            { "itemType": "note", "note": "<p>Skipped</p>" }
+        ]);
+        let entries = parse_items(&items);
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.ty, ReferenceType::Journal);
+        assert_eq!(entry.fields["AU"], vec!["Pugh, William"]);
+        assert_eq!(entry.fields["ED"], vec!["ACM"]);
+        assert_eq!(entry.fields["DA"], vec!["1990/06/01"]);
+        assert_eq!(entry.fields["PY"], vec!["1990"]);
+        assert_eq!((entry.fields["SP"][0].as_str(), entry.fields["EP"][0].as_str()), ("668", "676"));
+        assert_eq!(entry.fields["DO"], vec!["10.1145/78973.78977"]);
+        assert_eq!(entry.fields["KW"], vec!["data structures"]);
+    }
+}
@@ -1,13 +1,47 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use serde_yaml::{Mapping, Value};
+use crate::services::citekey::{KeyFormat, DEFAULT_KEY_PATTERN};
+use crate::services::export::ExportSort;
+use crate::i18n::Language;
+use crate::repo::GitAuth;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use std::{process, thread};
 
+/// A reference repository cloned into the workspace.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Project {
+    /// Set with `refrs workspace rename`; see [`Project::name`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     pub absolute_path: String,
+    /// The git remote it was cloned from.
     pub url: String,
+    /// Credentials for this project's remote, replacing `AppState::git_auth`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_auth: Option<GitAuth>,
 }
 
+impl Project {
+    /// The name given with `refrs workspace rename`, else the folder name.
+    pub fn name(&self) -> &str {
+        if let Some(name) = &self.name {
+            return name;
+        }
+        Path::new(&self.absolute_path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&self.absolute_path)
+    }
+}
+
+/// Projects and settings, stored as `state.yaml` in [`data_dir`] and, for
+/// the [`SETTINGS`], `config.yaml` in [`config_dir`].
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AppState {
     #[serde(default)]
@@ -18,6 +52,38 @@ pub struct AppState {
 
     #[serde(default)]
     pub current_project: String,
+
+    #[serde(default)]
+    pub key_format: KeyFormat,
+
+    /// Pattern of `KeyFormat::Pattern`, such as `[auth][year][shorttitle]`.
+    #[serde(default)]
+    pub key_pattern: Option<String>,
+
+    /// Base URL of the institution's OpenURL link resolver.
+    #[serde(default)]
+    pub openurl_resolver: Option<String>,
+
+    /// Folders the daemon imports dropped `.bib`/`.ris` files from.
+    #[serde(default)]
+    pub watch_folders: Vec<String>,
+
+    /// Entry order used by `refrs export` when `--sort` is not given.
+    #[serde(default)]
+    pub export_sort: ExportSort,
+
+    /// Language of CLI messages. Taken from the locale when not set.
+    #[serde(default)]
+    pub language: Option<Language>,
+
+    /// Commands opening links and attachments by kind (`doi`, `url`,
+    /// `library` or a file extension), instead of the system default.
+    #[serde(default)]
+    pub open_with: BTreeMap<String, String>,
+
+    /// Credentials for git remotes of projects without their own.
+    #[serde(default)]
+    pub git_auth: GitAuth,
 }
 
 impl Default for AppState {
@@ -26,36 +92,260 @@ impl Default for AppState {
             initialized: false,
             projects: Vec::new(),
             current_project: "".to_string(),
+            key_format: KeyFormat::default(),
+            key_pattern: None,
+            openurl_resolver: None,
+            watch_folders: Vec::new(),
+            export_sort: ExportSort::default(),
+            language: None,
+            open_with: BTreeMap::new(),
+            git_auth: GitAuth::default(),
         }
     }
 }
 
-fn get_state_file_path() -> PathBuf {
+impl AppState {
+    pub fn key_pattern(&self) -> &str {
+        self.key_pattern.as_deref().unwrap_or(DEFAULT_KEY_PATTERN)
+    }
+
+    /// Credentials for the project at `project_path`.
+    pub fn git_auth_for(&self, project_path: &str) -> &GitAuth {
+        self.projects
+            .iter()
+            .find(|p| p.absolute_path == project_path)
+            .and_then(|p| p.git_auth.as_ref())
+            .unwrap_or(&self.git_auth)
+    }
+}
+
+/// Directory holding the state file and other per-user refrs data:
+/// `$REFRS_HOME`, else `refrs` in the XDG data directory.
+pub fn data_dir() -> PathBuf {
+    if let Some(home) = refrs_home() {
+        return home;
+    }
     let mut path = dirs_next::data_local_dir().unwrap_or_else(std::env::temp_dir);
     path.push("refrs");
+    path
+}
+
+/// Directory holding the user's settings: `$REFRS_HOME`, else `refrs` in the
+/// XDG config directory.
+pub fn config_dir() -> PathBuf {
+    if let Some(home) = refrs_home() {
+        return home;
+    }
+    let mut path = dirs_next::config_dir().unwrap_or_else(data_dir);
+    path.push("refrs");
+    path
+}
+
+/// Keeps everything in one folder, for tests and portable setups.
+fn refrs_home() -> Option<PathBuf> {
+    std::env::var_os("REFRS_HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+fn get_state_file_path() -> PathBuf {
+    let mut path = data_dir();
     path.push("state.yaml");
     path
 }
 
+/// The settings file in [`config_dir`], holding the [`SETTINGS`] of
+/// [`AppState`] and those of `refrs config`.
+pub fn config_file_path() -> PathBuf {
+    config_dir().join("config.yaml")
+}
+
+/// Fields of [`AppState`] that are preferences rather than data, and are
+/// stored in the settings file instead of the state file.
+const SETTINGS: &[&str] = &[
+    "key_format",
+    "key_pattern",
+    "openurl_resolver",
+    "watch_folders",
+    "export_sort",
+    "language",
+    "open_with",
+    "git_auth",
+];
+
+fn is_setting(key: &Value) -> bool {
+    key.as_str().is_some_and(|key| SETTINGS.contains(&key))
+}
+
+/// The state file as this process last read or wrote it, which tells its
+/// own changes apart from those other refrs processes saved meanwhile.
+static LOADED: Mutex<Option<Value>> = Mutex::new(None);
+
+/// How long a save waits for another process to finish saving.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Age after which a lock is taken to be left behind by a killed process.
+const STALE_LOCK: Duration = Duration::from_secs(30);
+
 pub fn load_state() -> Result<AppState> {
-    let state_file = get_state_file_path();
-    if state_file.exists() {
-        let content = fs::read_to_string(&state_file).context("Failed to read state file")?;
-        let state: AppState =
-            serde_yaml::from_str(&content).context("Failed to parse state file")?;
-        Ok(state)
-    } else {
-        Ok(AppState::default())
+    let stored = read_stored_state()?;
+    let state: AppState =
+        serde_yaml::from_value(Value::Mapping(stored.clone())).context("Failed to parse state file")?;
+    *LOADED.lock().unwrap() = Some(Value::Mapping(stored));
+    Ok(state)
+}
+
+/// The state file with the settings from the settings file. Settings still
+/// in the state file, where older versions kept them, count until saved.
+fn read_stored_state() -> Result<Mapping> {
+    let mut stored = read_mapping(&get_state_file_path()).context("Failed to parse state file")?;
+    let settings = read_mapping(&config_file_path()).context("Failed to parse config file")?;
+    for (key, value) in settings {
+        if is_setting(&key) {
+            stored.insert(key, value);
+        }
     }
+    Ok(stored)
 }
 
+/// Writes the state, keeping settings another process saved since this one
+/// loaded it unless this process changed them too. Saves are serialized
+/// with a lock file and written atomically, so readers never see half a file.
 pub fn save_state(state: &AppState) -> Result<()> {
-    let state_file = get_state_file_path();
-    let parent_dir = state_file.parent().unwrap();
+    let _lock = StateLock::acquire()?;
 
-    fs::create_dir_all(parent_dir).context("Failed to create state directory")?;
+    let mut loaded = LOADED.lock().unwrap();
+    let base = match loaded.take() {
+        Some(base) => base,
+        None => serde_yaml::to_value(AppState::default())?,
+    };
+    let ours = serde_yaml::to_value(state).context("Failed to serialize state")?;
+    let value = merge_changes(&base, ours, Value::Mapping(read_stored_state()?));
 
-    let content = serde_yaml::to_string(state).context("Failed to serialize state")?;
-    fs::write(&state_file, content).context("Failed to write state file")?;
+    let mut data = Mapping::new();
+    let mut settings = read_mapping(&config_file_path()).context("Failed to parse config file")?;
+    settings.retain(|key, _| !is_setting(key));
+    if let Value::Mapping(merged) = &value {
+        for (key, value) in merged {
+            if is_setting(key) {
+                settings.insert(key.clone(), value.clone());
+            } else {
+                data.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    write_mapping(&config_file_path(), &settings).context("Failed to write config file")?;
+    write_mapping(&get_state_file_path(), &data).context("Failed to write state file")?;
+    *loaded = Some(value);
     Ok(())
 }
+
+/// Changes the settings file under the same lock as [`save_state`], keeping
+/// what `change` leaves alone.
+pub fn update_config_file(change: impl FnOnce(&mut Mapping)) -> Result<()> {
+    let _lock = StateLock::acquire()?;
+    let path = config_file_path();
+    let mut settings = read_mapping(&path).context("Failed to parse config file")?;
+    change(&mut settings);
+    write_mapping(&path, &settings).context("Failed to write config file")
+}
+
+/// A YAML file's top-level mapping, empty when the file doesn't exist.
+fn read_mapping(path: &Path) -> Result<Mapping> {
+    if !path.exists() {
+        return Ok(Mapping::new());
+    }
+    let content = fs::read_to_string(path)?;
+    match serde_yaml::from_str(&content)? {
+        Value::Mapping(mapping) => Ok(mapping),
+        Value::Null => Ok(Mapping::new()),
+        _ => bail!("{} is not a mapping", path.display()),
+    }
+}
+
+/// Writes through a temporary file, so the file is replaced in one step.
+fn write_mapping(path: &Path, mapping: &Mapping) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp_file = path.with_extension(format!("yaml.{}.tmp", process::id()));
+    fs::write(&temp_file, serde_yaml::to_string(mapping)?)?;
+    fs::rename(&temp_file, path)?;
+    Ok(())
+}
+
+/// `saved` with the top-level settings that `ours` changed from `base`.
+fn merge_changes(base: &Value, ours: Value, saved: Value) -> Value {
+    let (Value::Mapping(base), Value::Mapping(ours), Value::Mapping(mut merged)) = (base, &ours, saved) else {
+        return ours;
+    };
+    for (key, value) in ours {
+        if base.get(key) != Some(value) {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    // Settings left out when unset, which this process unset.
+    for key in base.keys() {
+        if !ours.contains_key(key) {
+            merged.remove(key);
+        }
+    }
+    Value::Mapping(merged)
+}
+
+/// Lock on the state and settings files, held by creating `state.yaml.lock`.
+struct StateLock(PathBuf);
+
+impl StateLock {
+    fn acquire() -> Result<StateLock> {
+        fs::create_dir_all(data_dir()).context("Failed to create state directory")?;
+        let path = get_state_file_path().with_extension("yaml.lock");
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(StateLock(path)),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let age = fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .map(|modified| modified.elapsed().unwrap_or_default());
+                    if age.is_ok_and(|age| age > STALE_LOCK) {
+                        let _ = fs::remove_file(&path);
+                    } else if start.elapsed() > LOCK_TIMEOUT {
+                        bail!(
+                            "The state file is locked by another refrs process. If none is running, remove {}",
+                            path.display()
+                        );
+                    } else {
+                        thread::sleep(Duration::from_millis(50));
+                    }
+                }
+                Err(e) => return Err(e).context("Failed to lock state file"),
+            }
+        }
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_settings_saved_by_another_process() {
+        let yaml = |text: &str| -> Value { serde_yaml::from_str(text).unwrap() };
+        let base = yaml("language: en\nkey_format: refrs\nkey_pattern: '[auth]'\n");
+        // This process changed the language and unset the pattern, another
+        // one changed the key format.
+        let ours = yaml("language: nb\nkey_format: refrs\n");
+        let saved = yaml("language: en\nkey_format: pattern\nkey_pattern: '[auth]'\n");
+        assert_eq!(
+            merge_changes(&base, ours, saved),
+            yaml("language: nb\nkey_format: pattern\n")
+        );
+    }
+}
@@ -1,48 +1,130 @@
-use std::fs;
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::OnceLock;
 
-use anyhow::anyhow;
 use anyhow::Result;
 use colored::Colorize;
+use serde::Serialize;
 
-use crate::model::ris::{parse_ris, RisEntry};
+use crate::model::ris::RisEntry;
+use crate::repo;
+use crate::services::diff;
+use crate::services::library::{self, LibraryEntry};
 
 pub fn print_not_initialized() {
     println!(
-        "{}{}{}",
-        "Warning: ".yellow().bold(),
-        "Rustrs not initialized. To initialize, run: ",
-        "rustrs init".bold()
+        "{}{}",
+        t!("warning").yellow().bold(),
+        t!("not-initialized", command = "refrs init".bold())
     );
 }
 
-pub fn read_ris_files_from_dir(dir: &str) -> Result<Vec<RisEntry>> {
-    let mut entries = Vec::new();
+pub fn print_no_project_selected() {
+    println!("{}", t!("no-project-selected").blue().bold());
+}
+
+/// Shortens `text` to at most `width` characters, marking the cut with an ellipsis.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut shortened: String = text.chars().take(width.saturating_sub(1)).collect();
+    shortened.push('…');
+    shortened
+}
+
+/// One-line summary of an entry, used in pickers and listings.
+pub fn describe_entry(entry: &RisEntry) -> String {
+    let author = entry.get_field("AU").map(String::as_str).unwrap_or("Unknown");
+    let year = entry.get_field("PY").map(String::as_str).unwrap_or("n.d.");
+    let title = entry.get_field("TI").map(String::as_str).unwrap_or("Untitled");
+    format!("{} ({}) {}", author, year, title)
+}
 
-    // Read the directory
-    let paths = fs::read_dir(dir).map_err(|e| anyhow!("Failed to read directory: {}", e))?;
+/// Looks up a reference by id, or lets the user fuzzy-search one when no id is given.
+/// Returns `None` when the id is unknown or the picker was cancelled.
+pub fn resolve_reference<'a>(
+    entries: &'a [LibraryEntry],
+    id: Option<&str>,
+) -> Result<Option<&'a LibraryEntry>> {
+    if let Some(id) = id {
+        let found = library::find_entry(entries, id);
+        if found.is_none() {
+            println!("{}{}", t!("error").red().bold(), t!("no-reference-with-id", id = id));
+        }
+        return Ok(found);
+    }
+
+    if entries.is_empty() {
+        println!("{}", t!("no-references").blue().bold());
+        return Ok(None);
+    }
 
-    for path in paths {
-        let path = path?.path();
+    let items: Vec<String> = entries
+        .iter()
+        .map(|e| format!("{}  {}", e.id, describe_entry(&e.entry)))
+        .collect();
 
-        // Check if the file has a `.ris` extension
-        if path.extension().map(|ext| ext == "ris").unwrap_or(false) {
-            // Read the file content
-            let content = fs::read_to_string(&path)
-                .map_err(|e| anyhow!("Failed to read file {:?}: {}", path, e))?;
+    let selected = dialoguer::FuzzySelect::new()
+        .with_prompt(t!("search-references"))
+        .items(&items)
+        .default(0)
+        .max_length(15)
+        .interact_opt()?;
 
-            // Parse the RIS content
-            let file_entries = parse_ris(&content).map_err(|e| {
-                anyhow!(
-                    "Failed to parse RIS content in file {:?}: {}",
-                    path.file_name().unwrap_or_default(),
-                    e
-                )
-            })?;
+    Ok(selected.map(|i| &entries[i]))
+}
 
-            // Append parsed entries to the result vector
-            entries.extend(file_entries);
+/// Prints the per-field differences between two versions of an entry,
+/// removed values in red and added values in green.
+pub fn print_entry_diff(old: &RisEntry, new: &RisEntry) {
+    let changes = diff::diff_entries(old, new);
+    if changes.is_empty() {
+        repo::status(t!("no-field-changes").dimmed());
+        return;
+    }
+
+    for change in changes {
+        for value in &change.old {
+            repo::status(format!("- {}  {}", change.tag, value).red());
+        }
+        for value in &change.new {
+            repo::status(format!("+ {}  {}", change.tag, value).green());
         }
     }
+}
 
-    Ok(entries)
+/// The real stdout once [`divert_stdout`] has pointed stdout at stderr.
+static JSON_OUT: OnceLock<File> = OnceLock::new();
+
+/// For `--json`: points stdout at stderr, so whatever else commands print
+/// along the way doesn't end up among the JSON that [`print_json`] writes to
+/// the real stdout.
+#[cfg(unix)]
+pub fn divert_stdout() -> Result<()> {
+    use std::os::fd::{AsFd, AsRawFd};
+
+    io::stdout().flush()?;
+    let out = io::stdout().as_fd().try_clone_to_owned()?;
+    // SAFETY: both are open descriptors of this process.
+    if unsafe { libc::dup2(io::stderr().as_raw_fd(), io::stdout().as_raw_fd()) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    let _ = JSON_OUT.set(File::from(out));
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn divert_stdout() -> Result<()> {
+    Ok(())
+}
+
+/// Prints `value` as pretty JSON to stdout, for `--json`.
+pub fn print_json(value: &impl Serialize) -> Result<()> {
+    let json = serde_json::to_string_pretty(value)?;
+    match JSON_OUT.get() {
+        Some(mut out) => writeln!(out, "{}", json)?,
+        None => println!("{}", json),
+    }
+    Ok(())
 }
@@ -0,0 +1,42 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_json_import_prints_only_json() {
+    let home = std::env::temp_dir().join(format!("refrs-import-json-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&home);
+    let project = home.join("project");
+    fs::create_dir_all(&project).unwrap();
+
+    let repo = git2::Repository::init(&project).unwrap();
+    let mut git_config = repo.config().unwrap();
+    git_config.set_str("user.name", "Test").unwrap();
+    git_config.set_str("user.email", "test@example.com").unwrap();
+
+    let project_path = project.to_str().unwrap();
+    fs::write(
+        home.join("state.yaml"),
+        format!(
+            "initialized: true\nprojects:\n- absolute_path: {0}\n  url: ''\ncurrent_project: {0}\n",
+            project_path
+        ),
+    )
+    .unwrap();
+    let file = home.join("import.ris");
+    fs::write(&file, "TY  - JOUR\nAU  - Doe, Jane\nTI  - Cats\nPY  - 2020\nER  - \n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_refrs"))
+        .args(["--json", "import", file.to_str().unwrap()])
+        .env("REFRS_HOME", &home)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["imported"].as_array().map(Vec::len), Some(1));
+    assert_eq!(report["dry_run"], false);
+    // The progress and git lines still reach the user.
+    assert!(!output.stderr.is_empty());
+
+    fs::remove_dir_all(&home).unwrap();
+}